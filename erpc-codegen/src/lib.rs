@@ -0,0 +1,185 @@
+//! Generates Rust bindings for `rust-erpc` from erpc IDL files.
+//!
+//! Each IDL `interface` becomes a module holding the service id, the function ids as erpcgen
+//! numbers them, a typed client calling through a `rust_erpc::rpc::Caller`, such as `rpc::Client` or
+//! `TransportArbitrator`, and, for the server side, a trait named
+//! after the interface as erpcgen names it (`I<interface>`) with a `Service` dispatching invocations to it.
+//! Enums and structs become Rust types with `write` and `read` methods encoding them through `Codec`.
+//!
+//! From a build script:
+//!
+//! ```no_run
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! erpc_codegen::compile(
+//!     "rpc_ble_api.erpc",
+//!     std::path::Path::new(&out_dir).join("rpc_ble_api.rs"),
+//!     &erpc_codegen::Options::default(),
+//! )
+//! .unwrap();
+//! ```
+//!
+//! The generated file is then included with `include!(concat!(env!("OUT_DIR"), "/rpc_ble_api.rs"));`.
+
+pub mod model;
+mod rust;
+
+use erpc_idl::ast::Document;
+use erpc_idl::Diagnostic;
+use model::Model;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Generate the client of each interface.
+    pub client: bool,
+    /// Generate the trait and the dispatcher implementing each interface.
+    pub server: bool,
+    /// Path of the `rust-erpc` crate in the generated code.
+    pub runtime: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            client: true,
+            server: true,
+            runtime: "::rust_erpc".to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    /// Parse or semantic errors, already rendered with their file names and source lines.
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            Error::Invalid(diagnostics) => write!(f, "{}", diagnostics.trim_end()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: String,
+    pub document: Document,
+}
+
+/// Reads and parses `path` and, recursively, the files it imports.
+///
+/// Imports are resolved relative to the importing file and each file is loaded once.
+/// Imported files come before the files importing them.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<SourceFile>, Error> {
+    let mut files = Vec::new();
+    let mut loading = Vec::new();
+    load_recursive(path.as_ref(), &mut files, &mut loading)?;
+    Ok(files)
+}
+
+fn load_recursive(
+    path: &Path,
+    files: &mut Vec<SourceFile>,
+    loading: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|err| Error::Io(path.to_path_buf(), err))?;
+    if loading.contains(&canonical) || files.iter().any(|file| file.path == canonical) {
+        return Ok(());
+    }
+    let source =
+        std::fs::read_to_string(&canonical).map_err(|err| Error::Io(path.to_path_buf(), err))?;
+    let document = erpc_idl::parse(&source)
+        .map_err(|diagnostics| Error::Invalid(render(path, &source, &diagnostics)))?;
+
+    loading.push(canonical.clone());
+    let directory = canonical.parent().unwrap_or_else(|| Path::new("."));
+    for import in document.imports() {
+        load_recursive(&directory.join(&import.path), files, loading)?;
+    }
+    loading.pop();
+
+    files.push(SourceFile {
+        path: canonical,
+        source,
+        document,
+    });
+    Ok(())
+}
+
+/// Builds the model of the loaded `files`.
+pub fn build_model(files: &[SourceFile]) -> Result<Model, Error> {
+    let documents: Vec<_> = files.iter().map(|file| &file.document).collect();
+    Model::build(&documents).map_err(|diagnostics| invalid(files, &diagnostics))
+}
+
+/// Generates Rust source from the loaded `files`.
+pub fn generate(files: &[SourceFile], options: &Options) -> Result<String, Error> {
+    rust::generate(&build_model(files)?, options)
+        .map_err(|diagnostics| invalid(files, &diagnostics))
+}
+
+fn invalid(files: &[SourceFile], diagnostics: &[(usize, Diagnostic)]) -> Error {
+    Error::Invalid(
+        diagnostics
+            .iter()
+            .map(|(index, diagnostic)| {
+                let file = &files[*index];
+                diagnostic.render(&file.path.display().to_string(), &file.source)
+            })
+            .collect(),
+    )
+}
+
+/// Generates Rust source from a single IDL file given as a string. Imports are not allowed.
+pub fn generate_str(source: &str, options: &Options) -> Result<String, Error> {
+    let path = Path::new("<input>");
+    let document = erpc_idl::parse(source)
+        .map_err(|diagnostics| Error::Invalid(render(path, source, &diagnostics)))?;
+    if let Some(import) = document.imports().next() {
+        let diagnostic = Diagnostic::error("imports require loading from a file", import.span);
+        return Err(Error::Invalid(render(path, source, &[diagnostic])));
+    }
+    generate(
+        &[SourceFile {
+            path: path.to_path_buf(),
+            source: source.to_string(),
+            document,
+        }],
+        options,
+    )
+}
+
+/// Generates Rust source from the IDL file `input` and writes it to `output`.
+///
+/// When called from a build script, the files read are reported to cargo so that the build script reruns when they change.
+pub fn compile(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    options: &Options,
+) -> Result<(), Error> {
+    let files = load(input)?;
+    if std::env::var_os("CARGO_MANIFEST_DIR").is_some() && std::env::var_os("OUT_DIR").is_some() {
+        for file in &files {
+            println!("cargo:rerun-if-changed={}", file.path.display());
+        }
+    }
+    let generated = generate(&files, options)?;
+    let output = output.as_ref();
+    std::fs::write(output, generated).map_err(|err| Error::Io(output.to_path_buf(), err))
+}
+
+fn render(path: &Path, source: &str, diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render(&path.display().to_string(), source))
+        .collect()
+}
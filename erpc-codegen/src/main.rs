@@ -0,0 +1,54 @@
+use erpc_codegen::Options;
+use std::process::exit;
+
+const USAGE: &str =
+    "usage: erpc-codegen [--runtime <path>] [--no-client] [--no-server] <input.erpc> [-o <output.rs>]";
+
+fn main() {
+    let mut options = Options::default();
+    let mut input = None;
+    let mut output = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = args.next(),
+            "--runtime" => match args.next() {
+                Some(runtime) => options.runtime = runtime,
+                None => usage(),
+            },
+            "--no-client" => options.client = false,
+            "--no-server" => options.server = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if input.is_none() && !arg.starts_with('-') => input = Some(arg),
+            _ => usage(),
+        }
+    }
+    let input = input.unwrap_or_else(|| usage());
+
+    let result =
+        erpc_codegen::load(&input).and_then(|files| erpc_codegen::generate(&files, &options));
+    let generated = match result {
+        Ok(generated) => generated,
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(1);
+        }
+    };
+    match output {
+        Some(output) => {
+            if let Err(err) = std::fs::write(&output, generated) {
+                eprintln!("{}: {}", output, err);
+                exit(1);
+            }
+        }
+        None => print!("{}", generated),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    exit(2);
+}
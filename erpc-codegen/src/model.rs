@@ -0,0 +1,991 @@
+use erpc_idl::ast::{self, find_annotation, BuiltinType, ExprKind, Span, TypeKind};
+use erpc_idl::Diagnostic;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Value of an evaluated constant expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i128),
+    Float(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ty {
+    Void,
+    Builtin(BuiltinType),
+    Enum(String),
+    Struct(String),
+    /// Alias declared with `typedef`, together with the aliased type.
+    Typedef(String, Box<Ty>),
+    Array(Box<Ty>, usize),
+    List(Box<Ty>),
+}
+
+impl Ty {
+    /// Strips typedefs.
+    pub fn resolved(&self) -> &Ty {
+        match self {
+            Ty::Typedef(_, ty) => ty.resolved(),
+            ty => ty,
+        }
+    }
+    /// Whether the encoded size depends on the value.
+    pub fn is_variable_length(&self, model: &Model) -> bool {
+        match self.resolved() {
+            Ty::Builtin(BuiltinType::String)
+            | Ty::Builtin(BuiltinType::UString)
+            | Ty::Builtin(BuiltinType::Binary)
+            | Ty::List(_) => true,
+            Ty::Array(element, _) => element.is_variable_length(model),
+            Ty::Struct(name) => model
+                .find_struct(name)
+                .map(|structure| {
+                    structure
+                        .members
+                        .iter()
+                        .any(|member| member.ty.is_variable_length(model))
+                })
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Const {
+    pub doc: Option<String>,
+    pub name: String,
+    pub ty: Ty,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    pub doc: Option<String>,
+    /// `None` for anonymous enums, whose members are plain constants.
+    pub name: Option<String>,
+    pub members: Vec<EnumMember>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumMember {
+    pub doc: Option<String>,
+    pub name: String,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    pub doc: Option<String>,
+    pub name: String,
+    pub members: Vec<StructMember>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructMember {
+    pub doc: Option<String>,
+    pub name: String,
+    pub ty: Ty,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Typedef {
+    pub doc: Option<String>,
+    pub name: String,
+    pub ty: Ty,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Declaration {
+    Const(Const),
+    Enum(Enum),
+    Struct(Struct),
+    Typedef(Typedef),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interface {
+    /// Index of the document declaring the interface.
+    pub document: usize,
+    pub doc: Option<String>,
+    pub name: String,
+    pub id: u32,
+    pub functions: Vec<Function>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub doc: Option<String>,
+    pub name: String,
+    pub id: u32,
+    pub is_oneway: bool,
+    pub params: Vec<Param>,
+    pub return_type: Ty,
+}
+
+impl Function {
+    /// Name of the constant holding the function id, as erpcgen names it.
+    pub fn id_name(&self) -> String {
+        format!("{}_ID", self.name.to_uppercase())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub direction: ast::Direction,
+    pub ty: Ty,
+    pub is_nullable: bool,
+    /// Value of `@max_length`, which bounds the length of a list, string or binary.
+    pub max_length: Option<usize>,
+    pub span: Span,
+}
+
+/// Declarations and interfaces of an IDL program with names resolved, constants evaluated and ids assigned.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Model {
+    pub program: Option<String>,
+    pub declarations: Vec<Declaration>,
+    pub interfaces: Vec<Interface>,
+}
+
+impl Model {
+    /// Builds the model from `documents`, which must be ordered so that imported files come first.
+    ///
+    /// Diagnostics are paired with the index of the document they refer to.
+    pub fn build(documents: &[&ast::Document]) -> Result<Model, Vec<(usize, Diagnostic)>> {
+        let mut builder = Builder::default();
+        for (index, document) in documents.iter().enumerate() {
+            builder.document = index;
+            builder.declare_types(document);
+        }
+        for (index, document) in documents.iter().enumerate() {
+            builder.document = index;
+            builder.build(document);
+        }
+        if builder.diagnostics.is_empty() {
+            Ok(builder.model)
+        } else {
+            Err(builder.diagnostics)
+        }
+    }
+
+    pub fn find_struct(&self, name: &str) -> Option<&Struct> {
+        self.declarations
+            .iter()
+            .find_map(|declaration| match declaration {
+                Declaration::Struct(structure) if structure.name == name => Some(structure),
+                _ => None,
+            })
+    }
+    pub fn find_enum(&self, name: &str) -> Option<&Enum> {
+        self.declarations
+            .iter()
+            .find_map(|declaration| match declaration {
+                Declaration::Enum(enumeration) if enumeration.name.as_deref() == Some(name) => {
+                    Some(enumeration)
+                }
+                _ => None,
+            })
+    }
+}
+
+/// Methods generated on the `Client` and `Service` of each interface besides its functions.
+const RESERVED_FUNCTION_NAMES: &[&str] = &["new", "with_scratch", "handler", "release"];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum TypeName {
+    Enum,
+    Struct,
+    Union,
+    Typedef,
+    Callback,
+}
+
+#[derive(Default)]
+struct Builder {
+    model: Model,
+    document: usize,
+    type_names: HashMap<String, TypeName>,
+    typedefs: HashMap<String, Ty>,
+    values: HashMap<String, Value>,
+    diagnostics: Vec<(usize, Diagnostic)>,
+}
+
+impl Builder {
+    fn error(&mut self, message: impl Into<String>, span: Span) {
+        self.diagnostics
+            .push((self.document, Diagnostic::error(message, span)));
+    }
+
+    fn declare_type(&mut self, name: &ast::Ident, kind: TypeName) {
+        if self.type_names.insert(name.name.clone(), kind).is_some() {
+            self.error(
+                format!("type `{}` is defined multiple times", name.name),
+                name.span,
+            );
+        }
+    }
+
+    fn declare_types(&mut self, document: &ast::Document) {
+        for item in &document.items {
+            match item {
+                ast::Item::Enum(ast::EnumDef {
+                    name: Some(name), ..
+                }) => self.declare_type(name, TypeName::Enum),
+                ast::Item::Struct(structure) => {
+                    self.declare_type(&structure.name, TypeName::Struct)
+                }
+                ast::Item::Union(union) => self.declare_type(&union.name, TypeName::Union),
+                ast::Item::Typedef(typedef) => self.declare_type(&typedef.name, TypeName::Typedef),
+                ast::Item::CallbackType(function) => {
+                    self.declare_type(&function.name, TypeName::Callback)
+                }
+                ast::Item::Interface(interface) => {
+                    for member in &interface.members {
+                        if let ast::InterfaceMember::CallbackType(function) = member {
+                            self.declare_type(&function.name, TypeName::Callback);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn build(&mut self, document: &ast::Document) {
+        // Imported files come first, so the main file determines the program name.
+        if let Some(program) = &document.program {
+            self.model.program = Some(program.name.name.clone());
+        }
+        for item in &document.items {
+            match item {
+                ast::Item::Import(_) => {}
+                ast::Item::Const(constant) => self.build_const(constant),
+                ast::Item::Enum(enumeration) => self.build_enum(enumeration),
+                ast::Item::Struct(structure) => self.build_struct(structure),
+                ast::Item::Union(union) => self.error(
+                    "non-encapsulated unions are not supported by the Rust generator",
+                    union.name.span,
+                ),
+                ast::Item::Typedef(typedef) => self.build_typedef(typedef),
+                ast::Item::Interface(interface) => self.build_interface(interface),
+                // Callback types only matter where they are used as parameters.
+                ast::Item::CallbackType(_) => {}
+            }
+        }
+    }
+
+    fn build_const(&mut self, constant: &ast::ConstDef) {
+        let ty = match self.resolve_type(&constant.ty) {
+            Some(ty) => ty,
+            None => return,
+        };
+        let value = match self.evaluate(&constant.value) {
+            Some(value) => value,
+            None => return,
+        };
+        let is_valid = match (ty.resolved(), &value) {
+            (Ty::Builtin(BuiltinType::String), Value::String(_)) => true,
+            (Ty::Builtin(BuiltinType::Float), Value::Float(_))
+            | (Ty::Builtin(BuiltinType::Double), Value::Float(_)) => true,
+            (Ty::Builtin(BuiltinType::Bool), Value::Integer(value)) => *value == 0 || *value == 1,
+            (Ty::Builtin(builtin), Value::Integer(value)) => integer_range(*builtin)
+                .map(|(min, max)| min <= *value && *value <= max)
+                .unwrap_or(false),
+            _ => false,
+        };
+        if !is_valid {
+            self.error(
+                format!(
+                    "the value of `{}` does not fit its type",
+                    constant.name.name
+                ),
+                constant.value.span,
+            );
+            return;
+        }
+        self.define_value(&constant.name, value.clone());
+        self.model.declarations.push(Declaration::Const(Const {
+            doc: constant.doc.clone(),
+            name: constant.name.name.clone(),
+            ty,
+            value,
+        }));
+    }
+
+    fn build_enum(&mut self, enumeration: &ast::EnumDef) {
+        let mut members = Vec::new();
+        let mut next_value = 0i128;
+        for member in &enumeration.members {
+            let value = match &member.value {
+                Some(expr) => match self.evaluate_integer(expr) {
+                    Some(value) => value,
+                    None => continue,
+                },
+                None => next_value,
+            };
+            let span = member
+                .value
+                .as_ref()
+                .map(|expr| expr.span)
+                .unwrap_or(member.name.span);
+            if value < i32::MIN as i128 || value > u32::MAX as i128 {
+                self.error(
+                    format!(
+                        "the value of `{}` does not fit in 32 bits",
+                        member.name.name
+                    ),
+                    span,
+                );
+                continue;
+            }
+            next_value = value + 1;
+            self.define_value(&member.name, Value::Integer(value));
+            members.push(EnumMember {
+                doc: member.doc.clone(),
+                name: member.name.name.clone(),
+                // Enums are encoded as int32, so values above `i32::MAX` wrap around.
+                value: value as i32,
+            });
+        }
+        if let Some(name) = &enumeration.name {
+            if members.is_empty() {
+                self.error(format!("enum `{}` has no members", name.name), name.span);
+                return;
+            }
+        }
+        self.model.declarations.push(Declaration::Enum(Enum {
+            doc: enumeration.doc.clone(),
+            name: enumeration.name.as_ref().map(|name| name.name.clone()),
+            members,
+        }));
+    }
+
+    fn build_struct(&mut self, structure: &ast::StructDef) {
+        let mut members: Vec<(&ast::StructMember, Ty)> = Vec::new();
+        for member in &structure.members {
+            if members
+                .iter()
+                .any(|(other, _)| other.name.name == member.name.name)
+            {
+                self.error(
+                    format!("member `{}` is defined multiple times", member.name.name),
+                    member.name.span,
+                );
+                continue;
+            }
+            if find_annotation(&member.annotations, "nullable").is_some() {
+                self.error(
+                    "nullable struct members are not supported by the Rust generator",
+                    member.span,
+                );
+                continue;
+            }
+            if let TypeKind::Union(_) = member.ty.kind {
+                self.error(
+                    "encapsulated unions are not supported by the Rust generator",
+                    member.ty.span,
+                );
+                continue;
+            }
+            let ty = match self.resolve_type(&member.ty) {
+                Some(ty) => ty,
+                None => continue,
+            };
+            if self.contains_struct(&ty, &structure.name.name) {
+                self.error(
+                    format!(
+                        "struct `{}` contains itself, which gives it an infinite size",
+                        structure.name.name
+                    ),
+                    member.ty.span,
+                );
+                continue;
+            }
+            members.push((member, ty));
+        }
+        let members = members
+            .into_iter()
+            .filter_map(|(member, ty)| {
+                if ty.is_variable_length(&self.model) {
+                    self.error(
+                        "variable-length struct members are not supported by the Rust generator",
+                        member.ty.span,
+                    );
+                    None
+                } else {
+                    Some(StructMember {
+                        doc: member.doc.clone(),
+                        name: member.name.name.clone(),
+                        ty,
+                    })
+                }
+            })
+            .collect();
+        self.model.declarations.push(Declaration::Struct(Struct {
+            doc: structure.doc.clone(),
+            name: structure.name.name.clone(),
+            members,
+        }));
+    }
+
+    /// Whether a value of type `ty` holds a struct `name` in place. Structs are checked as they are built,
+    /// so the structs already in the model never contain themselves.
+    fn contains_struct(&self, ty: &Ty, name: &str) -> bool {
+        match ty.resolved() {
+            Ty::Struct(other) if other == name => true,
+            Ty::Struct(other) => self.model.find_struct(other).is_some_and(|structure| {
+                structure
+                    .members
+                    .iter()
+                    .any(|member| self.contains_struct(&member.ty, name))
+            }),
+            Ty::Array(element, _) => self.contains_struct(element, name),
+            _ => false,
+        }
+    }
+
+    fn build_typedef(&mut self, typedef: &ast::TypedefDef) {
+        if let Some(ty) = self.resolve_type(&typedef.ty) {
+            self.typedefs.insert(typedef.name.name.clone(), ty.clone());
+            self.model.declarations.push(Declaration::Typedef(Typedef {
+                doc: typedef.doc.clone(),
+                name: typedef.name.name.clone(),
+                ty,
+            }));
+        }
+    }
+
+    fn build_interface(&mut self, interface: &ast::InterfaceDef) {
+        let id = match self.annotated_id(&interface.annotations) {
+            Some(id) => id,
+            None => self.model.interfaces.len() as u32 + 1,
+        };
+        if let Some(other) = self.model.interfaces.iter().find(|other| other.id == id) {
+            let message = format!("interface id {} is already used by `{}`", id, other.name);
+            self.error(message, interface.name.span);
+        }
+        let mut functions: Vec<Function> = Vec::new();
+        let mut counter = 0;
+        for member in &interface.members {
+            let (function, callback_type) = match member {
+                ast::InterfaceMember::Function(function) => (function, None),
+                ast::InterfaceMember::CallbackType(_) => continue,
+                ast::InterfaceMember::CallbackFunction(function) => {
+                    let callback = interface.members.iter().find_map(|member| match member {
+                        ast::InterfaceMember::CallbackType(callback)
+                            if callback.name.name == function.callback_type.name =>
+                        {
+                            Some(callback)
+                        }
+                        _ => None,
+                    });
+                    match callback {
+                        Some(callback) => (callback, Some(function)),
+                        None => {
+                            self.error(
+                                format!(
+                                    "cannot find callback type `{}` in this interface",
+                                    function.callback_type.name
+                                ),
+                                function.callback_type.span,
+                            );
+                            continue;
+                        }
+                    }
+                }
+            };
+            // erpcgen numbers functions in declaration order, `@id` only overrides the number of the annotated one.
+            counter += 1;
+            let (name, annotations, span) = match callback_type {
+                Some(callback) => (&callback.name, &callback.annotations, callback.span),
+                None => (&function.name, &function.annotations, function.span),
+            };
+            if RESERVED_FUNCTION_NAMES.contains(&name.name.as_str()) {
+                let message = format!(
+                    "function `{}` collides with the method of the same name generated on `Client` or `Service`",
+                    name.name
+                );
+                self.error(message, name.span);
+            }
+            if functions.iter().any(|other| other.name == name.name) {
+                let message = format!("function `{}` is defined multiple times", name.name);
+                self.error(message, name.span);
+            }
+            let id = self.annotated_id(annotations).unwrap_or(counter);
+            if let Some(other) = functions.iter().find(|other| other.id == id) {
+                let message = format!("function id {} is already used by `{}`", id, other.name);
+                self.error(message, name.span);
+            }
+            if let Some(function) = self.build_function(function, callback_type, id, span) {
+                functions.push(function);
+            }
+        }
+        self.model.interfaces.push(Interface {
+            document: self.document,
+            doc: interface.doc.clone(),
+            name: interface.name.name.clone(),
+            id,
+            functions,
+        });
+    }
+
+    fn build_function(
+        &mut self,
+        function: &ast::FunctionDef,
+        callback: Option<&ast::CallbackFunctionDef>,
+        id: u32,
+        span: Span,
+    ) -> Option<Function> {
+        if let Some(callback) = callback {
+            if callback.param_names.len() != function.params.len() {
+                self.error(
+                    format!(
+                        "`{}` names {} parameters but `{}` has {}",
+                        callback.name.name,
+                        callback.param_names.len(),
+                        function.name.name,
+                        function.params.len()
+                    ),
+                    span,
+                );
+                return None;
+            }
+        }
+        let mut params = Vec::new();
+        let mut is_valid = true;
+        let mut names: Vec<&str> = Vec::new();
+        for (index, param) in function.params.iter().enumerate() {
+            let name = match (callback, &param.name) {
+                (Some(callback), _) => &callback.param_names[index],
+                (None, Some(name)) => name,
+                (None, None) => {
+                    self.error("parameter name is missing", param.span);
+                    is_valid = false;
+                    continue;
+                }
+            };
+            if names.contains(&name.name.as_str()) {
+                self.error(
+                    format!("parameter `{}` is defined multiple times", name.name),
+                    name.span,
+                );
+                is_valid = false;
+                continue;
+            }
+            names.push(&name.name);
+            let name = name.name.clone();
+            let ty = match self.resolve_type(&param.ty) {
+                Some(ty) => ty,
+                None => {
+                    is_valid = false;
+                    continue;
+                }
+            };
+            let is_nullable = find_annotation(&param.annotations, "nullable").is_some();
+            let max_length = match find_annotation(&param.annotations, "max_length") {
+                Some(annotation) => match self.max_length(annotation, &ty) {
+                    Some(max_length) => Some(max_length),
+                    None => {
+                        is_valid = false;
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let error = if find_annotation(&param.annotations, "length").is_some() {
+                Some("`@length` parameters are not supported by the Rust generator")
+            } else if is_nullable && param.direction() != ast::Direction::In {
+                Some("only `in` parameters can be nullable")
+            } else if function.is_oneway && param.direction() != ast::Direction::In {
+                Some("oneway functions cannot have `out` or `inout` parameters")
+            } else if param.direction() == ast::Direction::InOut
+                && ty.is_variable_length(&self.model)
+            {
+                Some("variable-length `inout` parameters are not supported by the Rust generator")
+            } else if matches!(ty.resolved(), Ty::List(element) | Ty::Array(element, _) if element.is_variable_length(&self.model))
+            {
+                Some("lists and arrays of variable-length elements are not supported by the Rust generator")
+            } else if ty == Ty::Void {
+                Some("parameters cannot be `void`")
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                self.error(error, param.span);
+                is_valid = false;
+                continue;
+            }
+            params.push(Param {
+                name,
+                direction: param.direction(),
+                ty,
+                is_nullable,
+                max_length,
+                span: param.span,
+            });
+        }
+        let return_type = self.resolve_type(&function.return_type)?;
+        if return_type.is_variable_length(&self.model) {
+            self.error(
+                "variable-length return types are not supported by the Rust generator, use an `out` parameter instead",
+                function.return_type.span,
+            );
+            return None;
+        }
+        if !is_valid {
+            return None;
+        }
+        let name = callback
+            .map(|callback| &callback.name)
+            .unwrap_or(&function.name);
+        let doc = callback
+            .map(|callback| &callback.doc)
+            .unwrap_or(&function.doc);
+        Some(Function {
+            doc: doc.clone(),
+            name: name.name.clone(),
+            id,
+            is_oneway: function.is_oneway,
+            params,
+            return_type,
+        })
+    }
+
+    fn annotated_id(&mut self, annotations: &[ast::Annotation]) -> Option<u32> {
+        let annotation = find_annotation(annotations, "id")?;
+        let value = match &annotation.value {
+            Some(value) => self.evaluate_integer(value)?,
+            None => {
+                self.error("`@id` requires a value", annotation.span);
+                return None;
+            }
+        };
+        if value <= 0 || value > 0xff {
+            // Ids are encoded in 8 bits of the message header.
+            self.error("ids must be between 1 and 255", annotation.span);
+            return None;
+        }
+        Some(value as u32)
+    }
+
+    fn max_length(&mut self, annotation: &ast::Annotation, ty: &Ty) -> Option<usize> {
+        if !ty.is_variable_length(&self.model) || matches!(ty.resolved(), Ty::Array(_, _)) {
+            self.error(
+                "`@max_length` only applies to lists, strings and binaries",
+                annotation.span,
+            );
+            return None;
+        }
+        let value = match &annotation.value {
+            Some(value) => self.evaluate_integer(value)?,
+            None => {
+                self.error("`@max_length` requires a value", annotation.span);
+                return None;
+            }
+        };
+        if value <= 0 || value > u32::MAX as i128 {
+            self.error("`@max_length` must be positive", annotation.span);
+            return None;
+        }
+        Some(value as usize)
+    }
+
+    fn resolve_type(&mut self, ty: &ast::Type) -> Option<Ty> {
+        Some(match &ty.kind {
+            TypeKind::Void => Ty::Void,
+            TypeKind::Builtin(builtin) => Ty::Builtin(*builtin),
+            TypeKind::List(element) => Ty::List(Box::new(self.resolve_element(element)?)),
+            TypeKind::Array(element, length) => {
+                let element = self.resolve_element(element)?;
+                let length = self.evaluate_integer(length)?;
+                if length <= 0 || length > u32::MAX as i128 {
+                    self.error("array length must be positive", ty.span);
+                    return None;
+                }
+                Ty::Array(Box::new(element), length as usize)
+            }
+            TypeKind::Named(name) => match self.type_names.get(&name.name) {
+                Some(TypeName::Enum) => Ty::Enum(name.name.clone()),
+                Some(TypeName::Struct) => Ty::Struct(name.name.clone()),
+                Some(TypeName::Typedef) => match self.typedefs.get(&name.name) {
+                    Some(aliased) => Ty::Typedef(name.name.clone(), Box::new(aliased.clone())),
+                    None => {
+                        self.error(
+                            format!("typedef `{}` is used before its definition", name.name),
+                            name.span,
+                        );
+                        return None;
+                    }
+                },
+                Some(TypeName::Union) => {
+                    self.error(
+                        "non-encapsulated unions are not supported by the Rust generator",
+                        name.span,
+                    );
+                    return None;
+                }
+                Some(TypeName::Callback) => {
+                    self.error(
+                        "callback parameters are not supported by the Rust generator",
+                        name.span,
+                    );
+                    return None;
+                }
+                None => {
+                    self.error(format!("cannot find type `{}`", name.name), name.span);
+                    return None;
+                }
+            },
+            TypeKind::Union(_) => {
+                self.error(
+                    "encapsulated unions are not supported by the Rust generator",
+                    ty.span,
+                );
+                return None;
+            }
+        })
+    }
+
+    fn resolve_element(&mut self, element: &ast::Type) -> Option<Ty> {
+        let ty = self.resolve_type(element)?;
+        if ty == Ty::Void {
+            self.error("elements cannot be `void`", element.span);
+            return None;
+        }
+        Some(ty)
+    }
+
+    fn define_value(&mut self, name: &ast::Ident, value: Value) {
+        if self.values.insert(name.name.clone(), value).is_some() {
+            self.error(
+                format!("constant `{}` is defined multiple times", name.name),
+                name.span,
+            );
+        }
+    }
+
+    fn evaluate_integer(&mut self, expr: &ast::Expr) -> Option<i128> {
+        match self.evaluate(expr)? {
+            Value::Integer(value) => Some(value),
+            _ => {
+                self.error("expected an integer", expr.span);
+                None
+            }
+        }
+    }
+
+    fn evaluate(&mut self, expr: &ast::Expr) -> Option<Value> {
+        Some(match &expr.kind {
+            ExprKind::Integer(value) => Value::Integer(*value as i128),
+            ExprKind::Float(value) => Value::Float(*value),
+            ExprKind::String(value) => Value::String(value.clone()),
+            ExprKind::Ident(name) => match self.values.get(&name.name) {
+                Some(value) => value.clone(),
+                None => {
+                    self.error(format!("cannot find constant `{}`", name.name), name.span);
+                    return None;
+                }
+            },
+            ExprKind::Unary(op, operand) => match (op, self.evaluate(operand)?) {
+                (ast::UnaryOp::Plus, value @ Value::Integer(_))
+                | (ast::UnaryOp::Plus, value @ Value::Float(_)) => value,
+                (ast::UnaryOp::Negate, Value::Integer(value)) => Value::Integer(-value),
+                (ast::UnaryOp::Negate, Value::Float(value)) => Value::Float(-value),
+                (ast::UnaryOp::Not, Value::Integer(value)) => Value::Integer(!value),
+                _ => {
+                    self.error("invalid operand", operand.span);
+                    return None;
+                }
+            },
+            ExprKind::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (self.evaluate(lhs)?, self.evaluate(rhs)?);
+                match (lhs, rhs) {
+                    (Value::Integer(lhs), Value::Integer(rhs)) => {
+                        match evaluate_integer_op(*op, lhs, rhs) {
+                            Some(value) => Value::Integer(value),
+                            None => {
+                                self.error("invalid constant expression", expr.span);
+                                return None;
+                            }
+                        }
+                    }
+                    (Value::String(_), _) | (_, Value::String(_)) => {
+                        self.error("strings cannot be used in arithmetic", expr.span);
+                        return None;
+                    }
+                    (lhs, rhs) => {
+                        let as_float = |value: Value| match value {
+                            Value::Integer(value) => value as f64,
+                            Value::Float(value) => value,
+                            Value::String(_) => unreachable!(),
+                        };
+                        let (lhs, rhs) = (as_float(lhs), as_float(rhs));
+                        Value::Float(match op {
+                            ast::BinaryOp::Add => lhs + rhs,
+                            ast::BinaryOp::Subtract => lhs - rhs,
+                            ast::BinaryOp::Multiply => lhs * rhs,
+                            ast::BinaryOp::Divide => lhs / rhs,
+                            _ => {
+                                self.error("bitwise operators require integer operands", expr.span);
+                                return None;
+                            }
+                        })
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn evaluate_integer_op(op: ast::BinaryOp, lhs: i128, rhs: i128) -> Option<i128> {
+    match op {
+        ast::BinaryOp::Add => lhs.checked_add(rhs),
+        ast::BinaryOp::Subtract => lhs.checked_sub(rhs),
+        ast::BinaryOp::Multiply => lhs.checked_mul(rhs),
+        ast::BinaryOp::Divide => lhs.checked_div(rhs),
+        ast::BinaryOp::Modulo => lhs.checked_rem(rhs),
+        ast::BinaryOp::ShiftLeft => lhs.checked_shl(u32::try_from(rhs).ok()?),
+        ast::BinaryOp::ShiftRight => lhs.checked_shr(u32::try_from(rhs).ok()?),
+        ast::BinaryOp::And => Some(lhs & rhs),
+        ast::BinaryOp::Or => Some(lhs | rhs),
+        ast::BinaryOp::Xor => Some(lhs ^ rhs),
+    }
+}
+
+fn integer_range(builtin: BuiltinType) -> Option<(i128, i128)> {
+    Some(match builtin {
+        BuiltinType::Int8 => (i8::MIN as i128, i8::MAX as i128),
+        BuiltinType::Int16 => (i16::MIN as i128, i16::MAX as i128),
+        BuiltinType::Int32 => (i32::MIN as i128, i32::MAX as i128),
+        BuiltinType::Int64 => (i64::MIN as i128, i64::MAX as i128),
+        BuiltinType::UInt8 => (0, u8::MAX as i128),
+        BuiltinType::UInt16 => (0, u16::MAX as i128),
+        BuiltinType::UInt32 => (0, u32::MAX as i128),
+        BuiltinType::UInt64 => (0, u64::MAX as i128),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(source: &str) -> Result<Model, Vec<String>> {
+        let document = erpc_idl::parse(source).unwrap();
+        Model::build(&[&document]).map_err(|diagnostics| {
+            diagnostics
+                .into_iter()
+                .map(|(_, diagnostic)| diagnostic.message)
+                .collect()
+        })
+    }
+
+    #[test]
+    fn assign_ids_like_erpcgen() {
+        let model = build(
+            "interface a { f() -> void g() -> void @id(10) h() -> void }
+             @id(5) interface b { type cb_t(int32 x) -> void cb_t on_x(value) }
+             interface c { f() -> void }",
+        )
+        .unwrap();
+        let ids: Vec<Vec<u32>> = model
+            .interfaces
+            .iter()
+            .map(|interface| {
+                interface
+                    .functions
+                    .iter()
+                    .map(|function| function.id)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(ids, [vec![1, 10, 3], vec![1], vec![1]]);
+        let interface_ids: Vec<u32> = model
+            .interfaces
+            .iter()
+            .map(|interface| interface.id)
+            .collect();
+        assert_eq!(interface_ids, [1, 5, 3]);
+        let callback = &model.interfaces[1].functions[0];
+        assert_eq!(callback.name, "on_x");
+        assert_eq!(callback.params[0].name, "value");
+        assert_eq!(callback.id_name(), "ON_X_ID");
+    }
+
+    #[test]
+    fn evaluate_constants_and_enums() {
+        let model = build(
+            "const int32 A = 2 * (3 + 4)
+             enum e { X = A, Y, Z = ~0 }
+             struct s { uint8[Y - X + 1] data }",
+        )
+        .unwrap();
+        assert_eq!(
+            model.declarations[0],
+            Declaration::Const(Const {
+                doc: None,
+                name: "A".to_string(),
+                ty: Ty::Builtin(BuiltinType::Int32),
+                value: Value::Integer(14),
+            })
+        );
+        let values: Vec<i32> = model
+            .find_enum("e")
+            .unwrap()
+            .members
+            .iter()
+            .map(|member| member.value)
+            .collect();
+        assert_eq!(values, [14, 15, -1]);
+        assert_eq!(
+            model.find_struct("s").unwrap().members[0].ty,
+            Ty::Array(Box::new(Ty::Builtin(BuiltinType::UInt8)), 2)
+        );
+    }
+
+    #[test]
+    fn report_unsupported_and_invalid_declarations() {
+        let errors = build(
+            "const uint8 BIG = 256
+             struct s { binary data; unknown_t value }
+             struct r { int32 a; r[2] self_array; int32 a }
+             struct p { q value } struct q { p value }
+             interface i {
+                 f(out string a) -> void @id(1)
+                 g() -> void @id(1)
+                 oneway h(out int32 x)
+                 k() -> binary
+                 m(@max_length(0) list<int32> x, @max_length(2) int32 y) -> void
+                 n(int32 a, int32 a) -> void
+                 new() -> void
+                 handler() -> void
+                 g() -> void
+             }",
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                "the value of `BIG` does not fit its type",
+                "cannot find type `unknown_t`",
+                "variable-length struct members are not supported by the Rust generator",
+                "struct `r` contains itself, which gives it an infinite size",
+                "member `a` is defined multiple times",
+                "struct `q` contains itself, which gives it an infinite size",
+                "function id 1 is already used by `f`",
+                "oneway functions cannot have `out` or `inout` parameters",
+                "variable-length return types are not supported by the Rust generator, use an `out` parameter instead",
+                "`@max_length` must be positive",
+                "`@max_length` only applies to lists, strings and binaries",
+                "parameter `a` is defined multiple times",
+                "function `new` collides with the method of the same name generated on `Client` or `Service`",
+                "function `handler` collides with the method of the same name generated on `Client` or `Service`",
+                "function `g` is defined multiple times",
+            ]
+        );
+    }
+}
@@ -0,0 +1,1255 @@
+use crate::model::{
+    Const, Declaration, Enum, Function, Interface, Model, Param, Struct, Ty, Value,
+};
+use crate::Options;
+use erpc_idl::ast::{BuiltinType, Direction};
+use erpc_idl::Diagnostic;
+
+/// Generates the bindings of `model`, or diagnostics paired with the index of the document they refer to.
+pub fn generate(model: &Model, options: &Options) -> Result<String, Vec<(usize, Diagnostic)>> {
+    let mut generator = Generator {
+        model,
+        runtime: &options.runtime,
+        out: Output::default(),
+        diagnostics: Vec::new(),
+    };
+    generator.header();
+    for declaration in &model.declarations {
+        match declaration {
+            Declaration::Const(constant) => generator.constant(constant),
+            Declaration::Enum(enumeration) => generator.enumeration(enumeration),
+            Declaration::Struct(structure) => generator.structure(structure),
+            Declaration::Typedef(typedef) => {
+                generator.out.blank();
+                generator.doc(&typedef.doc);
+                generator.out.line("#[allow(non_camel_case_types)]");
+                let line = format!(
+                    "pub type {} = {};",
+                    escape(&typedef.name),
+                    generator.value_type(&typedef.ty)
+                );
+                generator.out.line(&line);
+            }
+        }
+    }
+    for interface in &model.interfaces {
+        generator.interface(interface, options);
+    }
+    if generator.diagnostics.is_empty() {
+        Ok(generator.out.text)
+    } else {
+        Err(generator.diagnostics)
+    }
+}
+
+#[derive(Default)]
+struct Output {
+    text: String,
+    indent: usize,
+}
+
+impl Output {
+    /// Appends `text`, which may span multiple lines, at the current indentation.
+    fn line(&mut self, text: &str) {
+        for line in text.lines() {
+            if !line.is_empty() {
+                for _ in 0..self.indent {
+                    self.text.push_str("    ");
+                }
+            }
+            self.text.push_str(line);
+            self.text.push('\n');
+        }
+    }
+    fn open(&mut self, text: &str) {
+        self.line(text);
+        self.indent += 1;
+    }
+    fn close(&mut self, text: &str) {
+        self.indent -= 1;
+        self.line(text);
+    }
+    fn blank(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with("{\n") && !self.text.ends_with("\n\n") {
+            self.text.push('\n');
+        }
+    }
+}
+
+/// Expression of a value to encode, either the value itself or a reference to it.
+#[derive(Copy, Clone)]
+struct Place<'a> {
+    expr: &'a str,
+    is_ref: bool,
+}
+
+impl<'a> Place<'a> {
+    fn value(expr: &'a str) -> Self {
+        Self {
+            expr,
+            is_ref: false,
+        }
+    }
+    fn reference(expr: &'a str) -> Self {
+        Self { expr, is_ref: true }
+    }
+    fn copied(self) -> String {
+        if self.is_ref {
+            format!("*{}", self.expr)
+        } else {
+            self.expr.to_string()
+        }
+    }
+    fn borrowed(self) -> String {
+        if self.is_ref {
+            self.expr.to_string()
+        } else {
+            format!("&{}", self.expr)
+        }
+    }
+}
+
+/// How a parameter is passed to and returned from a client method.
+struct ParamShape {
+    /// Type of the method argument, if the parameter is passed in.
+    argument: Option<String>,
+    /// Type of the value returned to the caller, if the parameter is passed out.
+    output: Option<String>,
+    /// Lifetime of the caller supplied buffer which receives variable-length out parameters.
+    lifetime: Option<String>,
+}
+
+/// Method signature shared by a client method and the corresponding handler method.
+struct Signature {
+    names: Vec<String>,
+    shapes: Vec<ParamShape>,
+    result_name: String,
+    lifetimes: Vec<String>,
+    arguments: Vec<String>,
+    /// Names and types of the out parameters and the return value.
+    outputs: Vec<(String, String)>,
+    output_type: String,
+    /// Pattern binding the outputs.
+    output_values: String,
+}
+
+struct Generator<'a> {
+    model: &'a Model,
+    runtime: &'a str,
+    out: Output,
+    diagnostics: Vec<(usize, Diagnostic)>,
+}
+
+impl<'a> Generator<'a> {
+    fn header(&mut self) {
+        let source = match &self.model.program {
+            Some(program) => format!(" from program `{}`", program),
+            None => String::new(),
+        };
+        self.out.line(&format!(
+            "// Generated by erpc-codegen{}. Do not edit.",
+            source
+        ));
+    }
+
+    fn doc(&mut self, doc: &Option<String>) {
+        if let Some(doc) = doc {
+            for line in doc.lines() {
+                if line.is_empty() {
+                    self.out.line("///");
+                } else {
+                    self.out.line(&format!("/// {}", line));
+                }
+            }
+        }
+    }
+
+    fn constant(&mut self, constant: &Const) {
+        self.out.blank();
+        self.doc(&constant.doc);
+        allow_non_upper_case(&mut self.out, &constant.name);
+        let ty = match constant.ty.resolved() {
+            Ty::Builtin(BuiltinType::String) => "&str".to_string(),
+            _ => self.value_type(&constant.ty),
+        };
+        let value = match (&constant.value, constant.ty.resolved()) {
+            (Value::Integer(value), Ty::Builtin(BuiltinType::Bool)) => (*value != 0).to_string(),
+            (Value::Integer(value), _) => value.to_string(),
+            (Value::Float(value), _) => format!("{:?}", value),
+            (Value::String(value), _) => format!("{:?}", value),
+        };
+        self.out.line(&format!(
+            "pub const {}: {} = {};",
+            escape(&constant.name),
+            ty,
+            value
+        ));
+    }
+
+    fn enumeration(&mut self, enumeration: &Enum) {
+        let name = match &enumeration.name {
+            Some(name) => escape(name),
+            None => {
+                // Members of anonymous enums are plain integer constants.
+                for member in &enumeration.members {
+                    self.out.blank();
+                    self.doc(&member.doc);
+                    allow_non_upper_case(&mut self.out, &member.name);
+                    self.out.line(&format!(
+                        "pub const {}: i32 = {};",
+                        escape(&member.name),
+                        member.value
+                    ));
+                }
+                return;
+            }
+        };
+        let (variants, aliases): (Vec<_>, Vec<_>) = enumeration
+            .members
+            .iter()
+            .enumerate()
+            .partition(|(index, member)| {
+                !enumeration.members[..*index]
+                    .iter()
+                    .any(|other| other.value == member.value)
+            });
+
+        self.out.blank();
+        self.doc(&enumeration.doc);
+        self.out.line("#[allow(non_camel_case_types)]");
+        self.out
+            .line("#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]");
+        self.out.line("#[repr(i32)]");
+        self.out.open(&format!("pub enum {} {{", name));
+        for (index, (_, member)) in variants.iter().enumerate() {
+            self.doc(&member.doc);
+            if index == 0 {
+                self.out.line("#[default]");
+            }
+            self.out
+                .line(&format!("{} = {},", escape(&member.name), member.value));
+        }
+        self.out.close("}");
+        self.out.blank();
+        self.out.open(&format!("impl {} {{", name));
+        for (_, member) in &aliases {
+            let original = enumeration
+                .members
+                .iter()
+                .find(|other| other.value == member.value)
+                .unwrap();
+            self.doc(&member.doc);
+            allow_non_upper_case(&mut self.out, &member.name);
+            self.out.line(&format!(
+                "pub const {}: Self = Self::{};",
+                escape(&member.name),
+                escape(&original.name)
+            ));
+        }
+        self.out
+            .open("pub fn from_i32(value: i32) -> Option<Self> {");
+        self.out.open("match value {");
+        for (_, member) in &variants {
+            self.out.line(&format!(
+                "{} => Some(Self::{}),",
+                member.value,
+                escape(&member.name)
+            ));
+        }
+        self.out.line("_ => None,");
+        self.out.close("}");
+        self.out.close("}");
+        let runtime = self.runtime;
+        self.codec_method(
+            "write",
+            "self, codec: &mut CodecType",
+            "()",
+            "codec.write_i32(self as i32)",
+        );
+        self.codec_method(
+            "read",
+            "codec: &mut CodecType",
+            "Self",
+            &format!(
+                "Self::from_i32(codec.read_i32()?).ok_or({}::codec::CodecError::InvalidEnumValue)",
+                runtime
+            ),
+        );
+        self.out.close("}");
+    }
+
+    fn codec_method(&mut self, name: &str, params: &str, output: &str, body: &str) {
+        let runtime = self.runtime;
+        self.out.blank();
+        self.out.line(&format!(
+            "pub fn {}<CursorType, CodecType>({}) -> Result<{}, {}::codec::CodecError>",
+            name, params, output, runtime
+        ));
+        self.out.line("where");
+        self.out.line(&format!(
+            "    CursorType: {}::cursor::Cursor,\n    CodecType: {}::codec::Codec<CursorType>,",
+            runtime, runtime
+        ));
+        self.out.open("{");
+        self.out.line(body);
+        self.out.close("}");
+    }
+
+    fn structure(&mut self, structure: &Struct) {
+        let name = escape(&structure.name);
+        // `Default` is only implemented for arrays of up to 32 elements.
+        let is_default_derivable = structure
+            .members
+            .iter()
+            .all(|member| self.is_default_derivable(&member.ty));
+
+        self.out.blank();
+        self.doc(&structure.doc);
+        self.out.line("#[allow(non_camel_case_types)]");
+        self.out.line(&format!(
+            "#[derive(Debug, Copy, Clone, {}PartialEq)]",
+            if is_default_derivable {
+                "Default, "
+            } else {
+                ""
+            }
+        ));
+        if structure.members.is_empty() {
+            self.out.line(&format!("pub struct {} {{}}", name));
+        } else {
+            self.out.open(&format!("pub struct {} {{", name));
+            for member in &structure.members {
+                self.doc(&member.doc);
+                let line = format!(
+                    "pub {}: {},",
+                    escape(&member.name),
+                    self.value_type(&member.ty)
+                );
+                self.out.line(&line);
+            }
+            self.out.close("}");
+        }
+
+        if !is_default_derivable {
+            self.out.blank();
+            self.out.open(&format!("impl Default for {} {{", name));
+            self.out.open("fn default() -> Self {");
+            self.out.open("Self {");
+            for member in &structure.members {
+                let line = format!("{}: {},", escape(&member.name), self.zero(&member.ty));
+                self.out.line(&line);
+            }
+            self.out.close("}");
+            self.out.close("}");
+            self.out.close("}");
+        }
+
+        self.out.blank();
+        self.out.open(&format!("impl {} {{", name));
+        let codec = if structure.members.is_empty() {
+            "_codec"
+        } else {
+            "codec"
+        };
+        let mut body = String::new();
+        for member in &structure.members {
+            let place = format!("self.{}", escape(&member.name));
+            body.push_str(&self.write(&member.ty, Place::value(&place)));
+            body.push('\n');
+        }
+        body.push_str("Ok(())");
+        self.codec_method(
+            "write",
+            &format!("&self, {}: &mut CodecType", codec),
+            "()",
+            &body,
+        );
+        let body = if structure.members.is_empty() {
+            "Ok(Self {})".to_string()
+        } else {
+            let mut body = "Ok(Self {\n".to_string();
+            for member in &structure.members {
+                let value = indent_rest(&self.read(&member.ty), 1);
+                body.push_str(&format!("    {}: {},\n", escape(&member.name), value));
+            }
+            body.push_str("})");
+            body
+        };
+        self.codec_method("read", &format!("{}: &mut CodecType", codec), "Self", &body);
+        self.out.close("}");
+    }
+
+    fn is_default_derivable(&self, ty: &Ty) -> bool {
+        match ty.resolved() {
+            Ty::Array(element, length) => *length <= 32 && self.is_default_derivable(element),
+            _ => true,
+        }
+    }
+
+    /// Type of a value stored in a struct, constant or typedef.
+    fn value_type(&self, ty: &Ty) -> String {
+        match ty {
+            Ty::Void => "()".to_string(),
+            Ty::Builtin(builtin) => builtin_type(*builtin).to_string(),
+            Ty::Enum(name) | Ty::Struct(name) | Ty::Typedef(name, _) => escape(name),
+            Ty::Array(element, length) => format!("[{}; {}]", self.value_type(element), length),
+            Ty::List(element) => format!("[{}]", self.value_type(element)),
+        }
+    }
+
+    /// Expression of the default value of a fixed-length type.
+    fn zero(&self, ty: &Ty) -> String {
+        match ty.resolved() {
+            Ty::Builtin(BuiltinType::Bool) => "false".to_string(),
+            Ty::Builtin(BuiltinType::Float) | Ty::Builtin(BuiltinType::Double) => "0.0".to_string(),
+            Ty::Builtin(_) => "0".to_string(),
+            Ty::Array(element, length) => format!("[{}; {}]", self.zero(element), length),
+            _ => format!("{}::default()", self.value_type(ty)),
+        }
+    }
+
+    /// Statements encoding the value at `place` to `codec`.
+    fn write(&self, ty: &Ty, place: Place) -> String {
+        match ty.resolved() {
+            Ty::Builtin(BuiltinType::String) | Ty::Builtin(BuiltinType::UString) => {
+                format!("codec.write_str({})?;", place.borrowed())
+            }
+            Ty::Builtin(BuiltinType::Binary) => {
+                format!("codec.write_binary({})?;", place.borrowed())
+            }
+            Ty::Builtin(builtin) => format!(
+                "codec.write_{}({})?;",
+                builtin_type(*builtin),
+                place.copied()
+            ),
+            Ty::Enum(_) | Ty::Struct(_) => format!("{}.write(codec)?;", place.expr),
+            Ty::Array(element, _) => format!(
+                "for element in {}.iter() {{\n{}\n}}",
+                place.expr,
+                indent(&self.write(element, Place::reference("element")))
+            ),
+            Ty::List(element) => format!(
+                "codec.start_write_list({}.len())?;\nfor element in {}.iter() {{\n{}\n}}",
+                place.expr,
+                place.expr,
+                indent(&self.write(element, Place::reference("element")))
+            ),
+            Ty::Void | Ty::Typedef(_, _) => unreachable!(),
+        }
+    }
+
+    /// Expression decoding a fixed-length value from `codec`.
+    fn read(&self, ty: &Ty) -> String {
+        match ty.resolved() {
+            Ty::Builtin(builtin) => format!("codec.read_{}()?", builtin_type(*builtin)),
+            Ty::Enum(name) | Ty::Struct(name) => format!("{}::read(codec)?", escape(name)),
+            Ty::Array(element, _) => format!(
+                "{{\n    let mut array = {};\n    for element in array.iter_mut() {{\n        *element = {};\n    }}\n    array\n}}",
+                self.zero(ty),
+                indent_rest(&self.read(element), 2)
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    fn param_shape(&self, param: &Param) -> ParamShape {
+        let lifetime = format!("'{}", param.name);
+        let lifetime =
+            if is_keyword(&param.name) || param.name == "buffer" || param.name == "client" {
+                format!("{}_", lifetime)
+            } else {
+                lifetime
+            };
+        let ty = param.ty.resolved();
+        let is_variable_length = param.ty.is_variable_length(self.model);
+        let argument = match param.direction {
+            Direction::Out => None,
+            _ => {
+                let argument = match ty {
+                    Ty::Builtin(BuiltinType::String) | Ty::Builtin(BuiltinType::UString) => {
+                        "&str".to_string()
+                    }
+                    Ty::Builtin(BuiltinType::Binary) => "&[u8]".to_string(),
+                    Ty::Builtin(_) | Ty::Enum(_) => self.value_type(&param.ty),
+                    _ => format!("&{}", self.value_type(&param.ty)),
+                };
+                Some(if param.is_nullable {
+                    format!("Option<{}>", argument)
+                } else {
+                    argument
+                })
+            }
+        };
+        let (argument, output, lifetime) = match (param.direction, is_variable_length) {
+            (Direction::In, _) => (argument, None, None),
+            (Direction::Out, true) => {
+                let (buffer, output) = match ty {
+                    Ty::Builtin(BuiltinType::Binary) => ("[u8]".to_string(), "[u8]".to_string()),
+                    Ty::List(element) => {
+                        let element = self.value_type(element);
+                        (format!("[{}]", element), format!("[{}]", element))
+                    }
+                    _ => ("[u8]".to_string(), "str".to_string()),
+                };
+                (
+                    Some(format!("&{} mut {}", lifetime, buffer)),
+                    Some(format!("&{} {}", lifetime, output)),
+                    Some(lifetime),
+                )
+            }
+            _ => (argument, Some(self.value_type(&param.ty)), None),
+        };
+        ParamShape {
+            argument,
+            output,
+            lifetime,
+        }
+    }
+
+    fn interface(&mut self, interface: &Interface, options: &Options) {
+        let runtime = self.runtime;
+        self.out.blank();
+        self.doc(&interface.doc);
+        self.out
+            .open(&format!("pub mod {} {{", escape(&interface.name)));
+        self.out
+            .line("#![allow(non_camel_case_types, non_snake_case, unused_imports)]");
+        self.out.line("use super::*;");
+        self.out.line(&format!(
+            "use {}::codec::{{Codec, CodecError, CodecFactory}};",
+            runtime
+        ));
+        self.out.line(&format!(
+            "use {}::cursor::{{BufferCursor, Cursor, CursorError}};",
+            runtime
+        ));
+        self.out.line(&format!(
+            "use {}::framed_transport::FramedTransport;",
+            runtime
+        ));
+        self.out
+            .line(&format!("use {}::request::RequestResponseError;", runtime));
+        self.out.line(&format!("use {}::rpc;", runtime));
+        self.out.blank();
+        self.out
+            .line(&format!("pub const SERVICE_ID: u32 = {};", interface.id));
+        for function in &interface.functions {
+            self.out.line(&format!(
+                "pub const {}: u32 = {};",
+                function.id_name(),
+                function.id
+            ));
+        }
+        if options.client {
+            self.client(interface);
+        }
+        if options.server {
+            self.server(interface);
+        }
+        self.out.close("}");
+    }
+
+    fn client(&mut self, interface: &Interface) {
+        self.out.blank();
+        self.out.line(&format!(
+            "/// Client of `{}` calling through an [`rpc::Caller`], such as an [`rpc::Client`].",
+            interface.name
+        ));
+        self.out.open("pub struct Client<'client, CallerType> {");
+        self.out.line("caller: &'client mut CallerType,");
+        self.out.close("}");
+        self.out.blank();
+        self.out
+            .open("impl<'client, CallerType> Client<'client, CallerType> {");
+        self.out
+            .open("pub fn new(caller: &'client mut CallerType) -> Self {");
+        self.out.line("Self { caller }");
+        self.out.close("}");
+        for function in &interface.functions {
+            self.client_function(function);
+        }
+        self.out.close("}");
+    }
+
+    fn server(&mut self, interface: &Interface) {
+        let handler = format!("I{}", interface.name);
+        self.out.blank();
+        self.out.line(&format!(
+            "/// Implementation of `{}`, called by [`Service`].",
+            interface.name
+        ));
+        self.out.open(&format!("pub trait {} {{", handler));
+        for function in &interface.functions {
+            self.out.blank();
+            self.doc(&function.doc);
+            self.handler_function(function, None);
+        }
+        self.out.close("}");
+
+        // Lets one handler be shared by several services, as long as they are not used at the same time.
+        self.out.blank();
+        self.out.open(&format!(
+            "impl<Handler: {0} + ?Sized> {0} for &mut Handler {{",
+            handler
+        ));
+        for function in &interface.functions {
+            self.out.blank();
+            self.handler_function(function, Some("(**self)"));
+        }
+        self.out.close("}");
+
+        // Lets one handler be registered as several services at once, each call borrowing it in turn.
+        self.out.blank();
+        self.out.open(&format!(
+            "impl<Handler: {0} + ?Sized> {0} for &core::cell::RefCell<Handler> {{",
+            handler
+        ));
+        for function in &interface.functions {
+            self.out.blank();
+            self.handler_function(function, Some("self.borrow_mut()"));
+        }
+        self.out.close("}");
+
+        let arms: Vec<_> = interface
+            .functions
+            .iter()
+            .filter_map(|function| self.dispatch(interface, function))
+            .collect();
+        let uses_in_codec = interface.functions.iter().any(|function| {
+            function
+                .params
+                .iter()
+                .any(|param| param.direction != Direction::Out)
+        });
+        let uses_out_codec = interface.functions.iter().any(|function| {
+            function.return_type != Ty::Void
+                || function
+                    .params
+                    .iter()
+                    .any(|param| param.direction != Direction::In)
+        });
+
+        self.out.blank();
+        self.out.line(&format!(
+            "/// Dispatches invocations of `{}` to an [`{}`] implementation.",
+            interface.name, handler
+        ));
+        self.out.line("///");
+        self.out.line(
+            "/// Strings and binaries passed in are decoded into the scratch buffer. The rest of the scratch buffer is split",
+        );
+        self.out.line(
+            "/// evenly into the buffers receiving variable-length out parameters, lists use arrays of their `@max_length`.",
+        );
+        self.out.open("pub struct Service<'scratch, Handler> {");
+        self.out.line("handler: Handler,");
+        self.out.line("scratch: &'scratch mut [u8],");
+        self.out.close("}");
+        self.out.blank();
+        self.out
+            .open("impl<'scratch, Handler> Service<'scratch, Handler> {");
+        self.out.line("/// Creates a service without a scratch buffer, enough for functions without strings and binaries.");
+        self.out.open("pub fn new(handler: Handler) -> Self {");
+        self.out.line("Self::with_scratch(handler, &mut [])");
+        self.out.close("}");
+        self.out
+            .open("pub fn with_scratch(handler: Handler, scratch: &'scratch mut [u8]) -> Self {");
+        self.out.line("Self { handler, scratch }");
+        self.out.close("}");
+        self.out.open("pub fn handler(&mut self) -> &mut Handler {");
+        self.out.line("&mut self.handler");
+        self.out.close("}");
+        self.out
+            .open("pub fn release(self) -> (Handler, &'scratch mut [u8]) {");
+        self.out.line("(self.handler, self.scratch)");
+        self.out.close("}");
+        self.out.close("}");
+        self.out.blank();
+        self.out.line(
+            "impl<'scratch, CursorType, CodecType, Handler> rpc::Service<CursorType, CodecType>",
+        );
+        self.out.line("    for Service<'scratch, Handler>");
+        self.out.line("where");
+        self.out.line("    CursorType: Cursor,");
+        self.out.line("    CodecType: Codec<CursorType>,");
+        self.out.line(&format!("    Handler: {},", handler));
+        self.out.open("{");
+        self.out.open("fn get_service_id(&self) -> u32 {");
+        self.out.line("SERVICE_ID");
+        self.out.close("}");
+        self.out.blank();
+        self.out.line("fn handle_invocation(");
+        self.out.line("    &mut self,");
+        self.out.line("    method_id: u32,");
+        self.out.line(&format!(
+            "    {}: &mut CodecType,",
+            if uses_in_codec {
+                "in_codec"
+            } else {
+                "_in_codec"
+            }
+        ));
+        self.out.line(&format!(
+            "    {}: &mut CodecType,",
+            if uses_out_codec {
+                "out_codec"
+            } else {
+                "_out_codec"
+            }
+        ));
+        self.out.line(") -> Result<(), rpc::ServiceError> {");
+        self.out.indent += 1;
+        self.out.open("match method_id {");
+        for (function, body) in &arms {
+            if body.is_empty() {
+                self.out.line(&format!("{} => {{}}", function));
+            } else {
+                self.out.open(&format!("{} => {{", function));
+                self.out.line(body);
+                self.out.close("}");
+            }
+        }
+        self.out
+            .line("_ => return Err(rpc::ServiceError::UnknownMethod),");
+        self.out.close("}");
+        self.out.line("Ok(())");
+        self.out.close("}");
+        self.out.close("}");
+    }
+
+    /// Returns the id constant of `function` and the statements decoding its arguments, calling the handler
+    /// and encoding the reply.
+    fn dispatch(&mut self, interface: &Interface, function: &Function) -> Option<(String, String)> {
+        let Signature {
+            names,
+            output_values,
+            outputs,
+            ..
+        } = self.signature(function);
+        let scratch = unique_name("scratch", &function.params);
+        let is_bytes = |param: &Param| {
+            matches!(
+                param.ty.resolved(),
+                Ty::Builtin(BuiltinType::String)
+                    | Ty::Builtin(BuiltinType::UString)
+                    | Ty::Builtin(BuiltinType::Binary)
+            )
+        };
+        let out_buffers: Vec<_> = function
+            .params
+            .iter()
+            .zip(&names)
+            .filter(|(param, _)| param.direction == Direction::Out && is_bytes(param))
+            .map(|(_, name)| name)
+            .collect();
+        let mut scratch_users = function
+            .params
+            .iter()
+            .filter(|param| param.direction == Direction::In && is_bytes(param))
+            .count()
+            + if out_buffers.is_empty() { 0 } else { 1 };
+
+        let mut body = String::new();
+        if function
+            .params
+            .iter()
+            .any(|param| param.direction != Direction::Out)
+        {
+            body.push_str("let codec = in_codec;\n");
+        }
+        if scratch_users > 0 {
+            body.push_str(&format!("let {} = &mut *self.scratch;\n", scratch));
+        }
+        let mut arguments = Vec::new();
+        let mut is_valid = true;
+        for (param, name) in function.params.iter().zip(&names) {
+            let ty = param.ty.resolved();
+            if let Ty::List(element) = ty {
+                let max_length = match param.max_length {
+                    Some(max_length) => max_length,
+                    None => {
+                        self.diagnostics.push((
+                            interface.document,
+                            Diagnostic::error(
+                                "list parameters require `@max_length` to generate the server",
+                                param.span,
+                            ),
+                        ));
+                        is_valid = false;
+                        continue;
+                    }
+                };
+                body.push_str(&format!(
+                    "let mut {} = [{}; {}];\n",
+                    name,
+                    self.zero(element),
+                    max_length
+                ));
+                if param.direction == Direction::Out {
+                    arguments.push(format!("&mut {}", name));
+                    continue;
+                }
+                let list = format!(
+                    "{{\n    let length = codec.start_read_list()?;\n    let list = {}\n        .get_mut(..length)\n        .ok_or(CodecError::LengthExceedsBuffer)?;\n    for element in list.iter_mut() {{\n        *element = {};\n    }}\n    &*list\n}}",
+                    name,
+                    indent_rest(&self.read(element), 2)
+                );
+                body.push_str(&format!(
+                    "let {} = {};\n",
+                    name,
+                    self.nullable(param, &list)
+                ));
+                arguments.push(name.clone());
+                continue;
+            }
+            if param.direction == Direction::Out {
+                if is_bytes(param) {
+                    arguments.push(name.clone());
+                }
+                continue;
+            }
+            if is_bytes(param) {
+                scratch_users -= 1;
+                let rest = if scratch_users > 0 {
+                    scratch.as_str()
+                } else {
+                    "_"
+                };
+                let mut decode = format!(
+                    "let ({}, {}) = {{\n    let length = codec.read_binary({})?.len();\n    {}.split_at_mut(length)\n}};\n",
+                    name,
+                    if param.is_nullable { &scratch } else { rest },
+                    scratch,
+                    scratch
+                );
+                let value = if let Ty::Builtin(BuiltinType::Binary) = ty {
+                    format!("&*{}", name)
+                } else {
+                    decode.push_str(&format!(
+                        "let {} = core::str::from_utf8({}).map_err(CodecError::from)?;\n",
+                        name, name
+                    ));
+                    name.clone()
+                };
+                if param.is_nullable {
+                    body.push_str(&format!(
+                        "let ({}, {}) = if codec.read_null_flag()? {{\n    (None, {})\n}} else {{\n{}\n    (Some({}), {})\n}};\n",
+                        name,
+                        rest,
+                        scratch,
+                        indent(decode.trim_end()),
+                        value,
+                        scratch
+                    ));
+                } else {
+                    body.push_str(&decode);
+                }
+                arguments.push(name.clone());
+                continue;
+            }
+            body.push_str(&format!(
+                "let {} = {};\n",
+                name,
+                self.nullable(param, &self.read(&param.ty))
+            ));
+            arguments.push(match (ty, param.is_nullable) {
+                (Ty::Builtin(_), _) | (Ty::Enum(_), _) => name.clone(),
+                (_, false) => format!("&{}", name),
+                (_, true) => format!("{}.as_ref()", name),
+            });
+        }
+        if !is_valid {
+            return None;
+        }
+        for (index, name) in out_buffers.iter().enumerate() {
+            let remaining = out_buffers.len() - index;
+            if remaining == 1 {
+                body.push_str(&format!("let {} = {};\n", name, scratch));
+            } else {
+                body.push_str(&format!(
+                    "let ({}, {}) = {}.split_at_mut({}.len() / {});\n",
+                    name, scratch, scratch, scratch, remaining
+                ));
+            }
+        }
+
+        let call = format!(
+            "self.handler.{}({})",
+            escape(&function.name),
+            arguments.join(", ")
+        );
+        if outputs.is_empty() {
+            body.push_str(&format!("{};\n", call));
+        } else {
+            body.push_str(&format!("let {} = {};\n", output_values, call));
+            body.push_str("let codec = out_codec;\n");
+            for (param, name) in function.params.iter().zip(&names) {
+                if param.direction == Direction::In {
+                    continue;
+                }
+                let place = Place {
+                    expr: name,
+                    is_ref: param.ty.is_variable_length(self.model),
+                };
+                body.push_str(&self.write(&param.ty, place));
+                body.push('\n');
+            }
+            if function.return_type != Ty::Void {
+                let result = &outputs.last().unwrap().0;
+                body.push_str(&self.write(&function.return_type, Place::value(result)));
+                body.push('\n');
+            }
+        }
+        Some((function.id_name(), body.trim_end().to_string()))
+    }
+
+    /// Wraps the expression decoding `param` to first decode the null flag if the parameter is nullable.
+    fn nullable(&self, param: &Param, value: &str) -> String {
+        if param.is_nullable {
+            format!(
+                "if codec.read_null_flag()? {{\n    None\n}} else {{\n    Some({})\n}}",
+                indent_rest(value, 1)
+            )
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Arguments, without the receiver, and outputs of the method implementing `function`.
+    ///
+    /// Clients and handlers share the shape, the dispatcher supplies the buffers the client caller would.
+    fn signature(&self, function: &Function) -> Signature {
+        let shapes: Vec<_> = function
+            .params
+            .iter()
+            .map(|param| self.param_shape(param))
+            .collect();
+        let names: Vec<_> = function
+            .params
+            .iter()
+            .map(|param| escape(&param.name))
+            .collect();
+        let result_name = unique_name("result", &function.params);
+
+        let lifetimes = shapes
+            .iter()
+            .filter_map(|shape| shape.lifetime.clone())
+            .collect();
+        let mut arguments = Vec::new();
+        for (name, shape) in names.iter().zip(&shapes) {
+            if let Some(argument) = &shape.argument {
+                arguments.push(format!("{}: {}", name, argument));
+            }
+        }
+        let mut outputs: Vec<(String, String)> = names
+            .iter()
+            .zip(&shapes)
+            .filter_map(|(name, shape)| {
+                shape
+                    .output
+                    .as_ref()
+                    .map(|output| (name.clone(), output.clone()))
+            })
+            .collect();
+        if function.return_type != Ty::Void {
+            outputs.push((result_name.clone(), self.value_type(&function.return_type)));
+        }
+        let output_type = match outputs.len() {
+            0 => "()".to_string(),
+            1 => outputs[0].1.clone(),
+            _ => format!(
+                "({})",
+                outputs
+                    .iter()
+                    .map(|(_, ty)| ty.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let output_values = match outputs.len() {
+            1 => outputs[0].0.clone(),
+            _ => format!(
+                "({})",
+                outputs
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        Signature {
+            names,
+            shapes,
+            result_name,
+            lifetimes,
+            arguments,
+            outputs,
+            output_type,
+            output_values,
+        }
+    }
+
+    /// Writes the declaration of `function` in the handler trait, or with `forward` its definition calling the
+    /// handler `forward` evaluates to.
+    fn handler_function(&mut self, function: &Function, forward: Option<&str>) {
+        let signature = self.signature(function);
+        if signature.arguments.len() >= 7 {
+            self.out.line("#[allow(clippy::too_many_arguments)]");
+        }
+        let generics = if signature.lifetimes.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", signature.lifetimes.join(", "))
+        };
+        let name = escape(&function.name);
+        self.out.line(&format!("fn {}{}(", name, generics));
+        self.out.line("    &mut self,");
+        for argument in &signature.arguments {
+            self.out.line(&format!("    {},", argument));
+        }
+        let end = if forward.is_some() { " {" } else { ";" };
+        if signature.outputs.is_empty() {
+            self.out.line(&format!("){}", end));
+        } else {
+            self.out
+                .line(&format!(") -> {}{}", signature.output_type, end));
+        }
+        if let Some(handler) = forward {
+            let names: Vec<_> = signature
+                .arguments
+                .iter()
+                .map(|argument| argument.split(':').next().unwrap())
+                .collect();
+            self.out.indent += 1;
+            self.out
+                .line(&format!("{}.{}({})", handler, name, names.join(", ")));
+            self.out.close("}");
+        }
+    }
+
+    fn client_function(&mut self, function: &Function) {
+        let Signature {
+            names,
+            shapes,
+            result_name,
+            lifetimes,
+            arguments: params,
+            outputs,
+            output_type,
+            output_values,
+        } = self.signature(function);
+        let mut generics = lifetimes;
+        generics.push("Error".to_string());
+        generics.push("CursorType".to_string());
+        generics.push("CodecType".to_string());
+        let mut arguments = vec!["&mut self".to_string()];
+        arguments.extend(params);
+
+        self.out.blank();
+        self.doc(&function.doc);
+        if arguments.len() > 7 {
+            self.out.line("#[allow(clippy::too_many_arguments)]");
+        }
+        self.out.line(&format!(
+            "pub fn {}<{}>(",
+            escape(&function.name),
+            generics.join(", ")
+        ));
+        for argument in &arguments {
+            self.out.line(&format!("    {},", argument));
+        }
+        self.out.line(&format!(
+            ") -> Result<{}, RequestResponseError<Error>>",
+            output_type
+        ));
+        self.out.line("where");
+        self.out
+            .line("    CallerType: rpc::Caller<Error, CursorType, CodecType>,");
+        self.out.line("    CursorType: Cursor,");
+        self.out.line("    CodecType: Codec<CursorType>,");
+        self.out.open("{");
+
+        let mut constructor = String::new();
+        for (param, name) in function.params.iter().zip(&names) {
+            if param.direction == Direction::Out {
+                continue;
+            }
+            let is_ref = match param.ty.resolved() {
+                Ty::Builtin(BuiltinType::String)
+                | Ty::Builtin(BuiltinType::UString)
+                | Ty::Builtin(BuiltinType::Binary) => true,
+                Ty::Builtin(_) | Ty::Enum(_) => false,
+                _ => true,
+            };
+            if param.is_nullable {
+                let place = Place { expr: name, is_ref };
+                constructor.push_str(&format!(
+                    "match {} {{\n    Some({}) => {{\n        codec.write_null_flag(false)?;\n{}\n    }}\n    None => codec.write_null_flag(true)?,\n}}\n",
+                    name,
+                    name,
+                    indent(&indent(&self.write(&param.ty, place)))
+                ));
+            } else {
+                let place = Place { expr: name, is_ref };
+                constructor.push_str(&self.write(&param.ty, place));
+                constructor.push('\n');
+            }
+        }
+        let constructor = if constructor.is_empty() {
+            "|_| Ok(())".to_string()
+        } else {
+            format!(
+                "|codec| {{\n{}\n    Ok(())\n}}",
+                indent(constructor.trim_end())
+            )
+        };
+
+        let mut parser = String::new();
+        let mut captures_buffer = false;
+        for ((param, name), shape) in function.params.iter().zip(&names).zip(&shapes) {
+            if param.direction == Direction::In {
+                continue;
+            }
+            let value = if shape.lifetime.is_some() {
+                captures_buffer = true;
+                match param.ty.resolved() {
+                    Ty::Builtin(BuiltinType::Binary) => format!("codec.read_binary({})?", name),
+                    Ty::List(element) => format!(
+                        "{{\n    let length = codec.start_read_list()?;\n    let list = {}\n        .get_mut(..length)\n        .ok_or(CodecError::LengthExceedsBuffer)?;\n    for element in list.iter_mut() {{\n        *element = {};\n    }}\n    list\n}}",
+                        name,
+                        indent_rest(&self.read(element), 2)
+                    ),
+                    _ => format!("codec.read_str({})?", name),
+                }
+            } else {
+                self.read(&param.ty)
+            };
+            let binding = match param.ty.resolved() {
+                // The decoded list is a mutable borrow of the caller's buffer, which is handed back immutably.
+                Ty::List(element) if shape.lifetime.is_some() => format!(
+                    "let {}: &[{}] = {};\n",
+                    name,
+                    self.value_type(element),
+                    value
+                ),
+                _ => format!("let {} = {};\n", name, value),
+            };
+            parser.push_str(&binding);
+        }
+        if function.return_type != Ty::Void {
+            parser.push_str(&format!(
+                "let {} = {};\n",
+                result_name,
+                self.read(&function.return_type)
+            ));
+        }
+        let parser = if outputs.is_empty() {
+            "|_| Ok(())".to_string()
+        } else {
+            format!(
+                "{}|codec| {{\n{}\n    Ok({})\n}}",
+                if captures_buffer { "move " } else { "" },
+                indent(parser.trim_end()),
+                output_values
+            )
+        };
+
+        let id = function.id_name();
+        if function.is_oneway {
+            self.out.line(&format!(
+                "self.caller.call_oneway(\n    SERVICE_ID,\n    {},\n{},\n)",
+                id,
+                indent(&constructor)
+            ));
+        } else {
+            self.out.line(&format!(
+                "self.caller.call(\n    SERVICE_ID,\n    {},\n{},\n{},\n)",
+                id,
+                indent(&constructor),
+                indent(&parser)
+            ));
+        }
+        self.out.close("}");
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("    {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Indents all lines but the first, which continues an already indented line.
+fn indent_rest(text: &str, levels: usize) -> String {
+    let mut lines = text.lines();
+    let mut result = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        result.push('\n');
+        if !line.is_empty() {
+            result.push_str(&"    ".repeat(levels));
+        }
+        result.push_str(line);
+    }
+    result
+}
+
+fn builtin_type(builtin: BuiltinType) -> &'static str {
+    match builtin {
+        BuiltinType::Bool => "bool",
+        BuiltinType::Int8 => "i8",
+        BuiltinType::Int16 => "i16",
+        BuiltinType::Int32 => "i32",
+        BuiltinType::Int64 => "i64",
+        BuiltinType::UInt8 => "u8",
+        BuiltinType::UInt16 => "u16",
+        BuiltinType::UInt32 => "u32",
+        BuiltinType::UInt64 => "u64",
+        BuiltinType::Float => "f32",
+        BuiltinType::Double => "f64",
+        BuiltinType::String | BuiltinType::UString => "str",
+        BuiltinType::Binary => "[u8]",
+    }
+}
+
+fn allow_non_upper_case(out: &mut Output, name: &str) {
+    if name.chars().any(|c| c.is_ascii_lowercase()) {
+        out.line("#[allow(non_upper_case_globals)]");
+    }
+}
+
+/// Returns `base`, or `base` with underscores appended if a parameter already uses the name.
+fn unique_name(base: &str, params: &[Param]) -> String {
+    let mut name = base.to_string();
+    while params.iter().any(|param| param.name == name) {
+        name.push('_');
+    }
+    name
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name)
+}
+
+/// Turns an IDL identifier into a Rust identifier.
+fn escape(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        name if is_keyword(name) => format!("r#{}", name),
+        name => name.to_string(),
+    }
+}
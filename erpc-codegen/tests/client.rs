@@ -0,0 +1,211 @@
+#[allow(dead_code)]
+mod example {
+    include!("generated/example.rs");
+}
+
+use example::*;
+use rust_erpc::arbitrator::TransportArbitrator;
+use rust_erpc::codec::{BasicCodecFactory, CodecError};
+use rust_erpc::framed_transport::{FramedTransport, FramedTransportError};
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc;
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// Records sent frames and returns prepared replies.
+#[derive(Default)]
+struct MockTransport {
+    sent: Vec<Vec<u8>>,
+    replies: VecDeque<Vec<u8>>,
+}
+
+impl FramedTransport<()> for MockTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sent.push(data.to_vec());
+        Ok(())
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        let reply = self
+            .replies
+            .pop_front()
+            .ok_or(FramedTransportError::UnderlyingError(()))?;
+        buffer[..reply.len()].copy_from_slice(&reply);
+        Ok(&buffer[..reply.len()])
+    }
+}
+
+fn message(message_type: u32, request: u32, sequence: u32, payload: &[u8]) -> Vec<u8> {
+    let header = (1u32 << 24) | (7 << 16) | (request << 8) | message_type;
+    let mut message = header.to_le_bytes().to_vec();
+    message.extend_from_slice(&sequence.to_le_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+fn device() -> device_t {
+    device_t {
+        addr: [1, 2, 3, 4, 5, 6],
+        handle: 0x1234,
+        status: status_t::STATUS_BUSY,
+        bonded: true,
+    }
+}
+
+const DEVICE_BYTES: [u8; 13] = [1, 2, 3, 4, 5, 6, 0x34, 0x12, 1, 0, 0, 0, 1];
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let idl = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/idl/example.erpc");
+    let files = erpc_codegen::load(idl).unwrap();
+    let generated = erpc_codegen::generate(&files, &erpc_codegen::Options::default()).unwrap();
+    assert_eq!(generated, include_str!("generated/example.rs"));
+}
+
+#[test]
+fn ids_match_erpcgen() {
+    assert_eq!(example_service::SERVICE_ID, 7);
+    assert_eq!(example_service::ADD_ID, 1);
+    assert_eq!(example_service::SET_DEVICE_ID, 2);
+    assert_eq!(example_service::GET_DEVICE_ID, 20);
+    // `@id` does not shift the numbering of the following functions.
+    assert_eq!(example_service::READ_DATA_ID, 4);
+    assert_eq!(status_t::STATUS_ERROR, status_t::STATUS_FAILED);
+    assert_eq!(FLAGS, 0x13);
+}
+
+#[test]
+fn call_encodes_arguments_and_decodes_results() {
+    let mut transport = MockTransport::default();
+    transport.replies.push_back(message(2, 20, 1, &{
+        let mut payload = DEVICE_BYTES.to_vec();
+        payload.extend_from_slice(&0u32.to_le_bytes());
+        payload
+    }));
+    transport.replies.push_back(message(2, 2, 2, &[0xff; 4]));
+    let mut buffer = [0u8; 256];
+    let mut client = rpc::Client::new(transport, &mut buffer, BasicCodecFactory::new());
+
+    let mut service = example_service::Client::new(&mut client);
+    assert_eq!(
+        service.get_device(0x1234),
+        Ok((device(), status_t::STATUS_OK))
+    );
+    assert_eq!(
+        service.set_device(&device(), &[0xaa, 0xbb]),
+        Ok(status_t::STATUS_FAILED)
+    );
+
+    let (transport, _, _) = client.release();
+    assert_eq!(transport.sent[0], message(0, 20, 1, &[0x34, 0x12]));
+    let mut payload = DEVICE_BYTES.to_vec();
+    payload.extend_from_slice(&[2, 0, 0, 0, 0xaa, 0xbb]);
+    assert_eq!(transport.sent[1], message(0, 2, 2, &payload));
+}
+
+#[test]
+fn out_parameters_use_caller_buffers() {
+    let mut transport = MockTransport::default();
+    transport.replies.push_back(message(
+        2,
+        4,
+        1,
+        &[3, 0, 0, 0, 9, 8, 7, 2, 0, 0, 0, b'o', b'k', 1],
+    ));
+    transport
+        .replies
+        .push_back(message(2, 5, 2, &[2, 0, 0, 0, 1, 0, 2, 0]));
+    transport
+        .replies
+        .push_back(message(2, 5, 3, &[3, 0, 0, 0, 1, 0, 2, 0, 3, 0]));
+    let mut buffer = [0u8; 256];
+    let mut client = rpc::Client::new(transport, &mut buffer, BasicCodecFactory::new());
+    let mut service = example_service::Client::new(&mut client);
+
+    let mut data = [0u8; 8];
+    let mut name = [0u8; 8];
+    let (data, name, result) = service.read_data(1, &mut data, &mut name).unwrap();
+    assert_eq!((data, name, result), (&[9u8, 8, 7][..], "ok", true));
+
+    let mut handles = [0u16; 2];
+    assert_eq!(service.read_handles(&mut handles), Ok(&[1u16, 2][..]));
+    assert_eq!(
+        service.read_handles(&mut handles),
+        Err(RequestResponseError::CodecError(
+            CodecError::LengthExceedsBuffer
+        ))
+    );
+}
+
+#[test]
+fn inout_nullable_and_oneway() {
+    let mut transport = MockTransport::default();
+    transport
+        .replies
+        .push_back(message(2, 7, 1, &[4, 3, 2, 1, 6, 5, 4, 3, 2, 1]));
+    transport.replies.push_back(message(2, 8, 2, &[0; 4]));
+    transport.replies.push_back(message(2, 8, 4, &[5, 0, 0, 0]));
+    let mut buffer = [0u8; 256];
+    let mut client = rpc::Client::new(transport, &mut buffer, BasicCodecFactory::new());
+    let mut service = example_service::Client::new(&mut client);
+
+    assert_eq!(
+        service.swap(0x01020304, &[1, 2, 3, 4, 5, 6]),
+        Ok((0x01020304, [6, 5, 4, 3, 2, 1]))
+    );
+    assert_eq!(service.find(None, Some(&device())), Ok(status_t::STATUS_OK));
+    assert_eq!(service.notify(status_t::STATUS_BUSY), Ok(()));
+    assert_eq!(
+        service.find(Some("a"), None),
+        Err(RequestResponseError::CodecError(
+            CodecError::InvalidEnumValue
+        ))
+    );
+
+    let (transport, _, _) = client.release();
+    assert_eq!(
+        transport.sent[0],
+        message(0, 7, 1, &[4, 3, 2, 1, 1, 2, 3, 4, 5, 6])
+    );
+    let mut payload = vec![1, 0];
+    payload.extend_from_slice(&DEVICE_BYTES);
+    assert_eq!(transport.sent[1], message(0, 8, 2, &payload));
+    assert_eq!(transport.sent[2], message(1, 9, 3, &[1, 0, 0, 0]));
+    assert_eq!(
+        transport.sent[3],
+        message(0, 8, 4, &[0, 1, 0, 0, 0, b'a', 1])
+    );
+}
+
+#[test]
+fn calls_through_an_arbitrator() {
+    let mut transport = MockTransport::default();
+    // An invocation of the peer, which no registered service handles, arrives before the reply.
+    transport.replies.push_back(message(0, 1, 9, &[1, 0, 0, 0]));
+    transport
+        .replies
+        .push_back(message(2, 1, 1, &5i32.to_le_bytes()));
+    let mut receive_buffer = [0u8; 256];
+    let mut send_buffer = [0u8; 256];
+    let mut arbitrator: TransportArbitrator<_, _, _, 1> = TransportArbitrator::new(
+        transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        BasicCodecFactory::new(),
+    );
+
+    assert_eq!(
+        example_service::Client::new(&mut arbitrator).add(2, 3),
+        Ok(5)
+    );
+    let (transport, _, _, _) = arbitrator.release();
+    assert_eq!(
+        transport.sent,
+        [message(0, 1, 1, &[2, 0, 0, 0, 3, 0, 0, 0])]
+    );
+}
@@ -0,0 +1,985 @@
+// Generated by erpc-codegen from program `example`. Do not edit.
+
+pub const ADDR_LEN: u8 = 6;
+
+pub const GREETING: &str = "hello";
+
+pub const RATIO: f32 = 1.5;
+
+pub const FLAGS: u32 = 19;
+
+/// Result of an operation.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[repr(i32)]
+pub enum status_t {
+    /// Succeeded.
+    #[default]
+    STATUS_OK = 0,
+    /// Try again later.
+    STATUS_BUSY = 1,
+    STATUS_FAILED = -1,
+}
+
+impl status_t {
+    pub const STATUS_ERROR: Self = Self::STATUS_FAILED;
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::STATUS_OK),
+            1 => Some(Self::STATUS_BUSY),
+            -1 => Some(Self::STATUS_FAILED),
+            _ => None,
+        }
+    }
+
+    pub fn write<CursorType, CodecType>(self, codec: &mut CodecType) -> Result<(), ::rust_erpc::codec::CodecError>
+    where
+        CursorType: ::rust_erpc::cursor::Cursor,
+        CodecType: ::rust_erpc::codec::Codec<CursorType>,
+    {
+        codec.write_i32(self as i32)
+    }
+
+    pub fn read<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, ::rust_erpc::codec::CodecError>
+    where
+        CursorType: ::rust_erpc::cursor::Cursor,
+        CodecType: ::rust_erpc::codec::Codec<CursorType>,
+    {
+        Self::from_i32(codec.read_i32()?).ok_or(::rust_erpc::codec::CodecError::InvalidEnumValue)
+    }
+}
+
+pub const ANONYMOUS_A: i32 = 16;
+
+pub const ANONYMOUS_B: i32 = 17;
+
+#[allow(non_camel_case_types)]
+pub type handle_t = u16;
+
+/// Remote device.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct device_t {
+    /// Device address.
+    pub addr: [u8; 6],
+    pub handle: handle_t,
+    pub status: status_t,
+    pub bonded: bool,
+}
+
+impl device_t {
+    pub fn write<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), ::rust_erpc::codec::CodecError>
+    where
+        CursorType: ::rust_erpc::cursor::Cursor,
+        CodecType: ::rust_erpc::codec::Codec<CursorType>,
+    {
+        for element in self.addr.iter() {
+            codec.write_u8(*element)?;
+        }
+        codec.write_u16(self.handle)?;
+        self.status.write(codec)?;
+        codec.write_bool(self.bonded)?;
+        Ok(())
+    }
+
+    pub fn read<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, ::rust_erpc::codec::CodecError>
+    where
+        CursorType: ::rust_erpc::cursor::Cursor,
+        CodecType: ::rust_erpc::codec::Codec<CursorType>,
+    {
+        Ok(Self {
+            addr: {
+                let mut array = [0; 6];
+                for element in array.iter_mut() {
+                    *element = codec.read_u8()?;
+                }
+                array
+            },
+            handle: codec.read_u16()?,
+            status: status_t::read(codec)?,
+            bonded: codec.read_bool()?,
+        })
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct table_t {
+    pub name: [u8; 40],
+    pub devices: [device_t; 2],
+    pub matrix: [[i16; 3]; 2],
+    pub scale: f64,
+}
+
+impl Default for table_t {
+    fn default() -> Self {
+        Self {
+            name: [0; 40],
+            devices: [device_t::default(); 2],
+            matrix: [[0; 3]; 2],
+            scale: 0.0,
+        }
+    }
+}
+
+impl table_t {
+    pub fn write<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), ::rust_erpc::codec::CodecError>
+    where
+        CursorType: ::rust_erpc::cursor::Cursor,
+        CodecType: ::rust_erpc::codec::Codec<CursorType>,
+    {
+        for element in self.name.iter() {
+            codec.write_u8(*element)?;
+        }
+        for element in self.devices.iter() {
+            element.write(codec)?;
+        }
+        for element in self.matrix.iter() {
+            for element in element.iter() {
+                codec.write_i16(*element)?;
+            }
+        }
+        codec.write_f64(self.scale)?;
+        Ok(())
+    }
+
+    pub fn read<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, ::rust_erpc::codec::CodecError>
+    where
+        CursorType: ::rust_erpc::cursor::Cursor,
+        CodecType: ::rust_erpc::codec::Codec<CursorType>,
+    {
+        Ok(Self {
+            name: {
+                let mut array = [0; 40];
+                for element in array.iter_mut() {
+                    *element = codec.read_u8()?;
+                }
+                array
+            },
+            devices: {
+                let mut array = [device_t::default(); 2];
+                for element in array.iter_mut() {
+                    *element = device_t::read(codec)?;
+                }
+                array
+            },
+            matrix: {
+                let mut array = [[0; 3]; 2];
+                for element in array.iter_mut() {
+                    *element = {
+                        let mut array = [0; 3];
+                        for element in array.iter_mut() {
+                            *element = codec.read_i16()?;
+                        }
+                        array
+                    };
+                }
+                array
+            },
+            scale: codec.read_f64()?,
+        })
+    }
+}
+
+pub mod example_service {
+    #![allow(non_camel_case_types, non_snake_case, unused_imports)]
+    use super::*;
+    use ::rust_erpc::codec::{Codec, CodecError, CodecFactory};
+    use ::rust_erpc::cursor::{BufferCursor, Cursor, CursorError};
+    use ::rust_erpc::framed_transport::FramedTransport;
+    use ::rust_erpc::request::RequestResponseError;
+    use ::rust_erpc::rpc;
+
+    pub const SERVICE_ID: u32 = 7;
+    pub const ADD_ID: u32 = 1;
+    pub const SET_DEVICE_ID: u32 = 2;
+    pub const GET_DEVICE_ID: u32 = 20;
+    pub const READ_DATA_ID: u32 = 4;
+    pub const READ_HANDLES_ID: u32 = 5;
+    pub const WRITE_HANDLES_ID: u32 = 6;
+    pub const SWAP_ID: u32 = 7;
+    pub const FIND_ID: u32 = 8;
+    pub const NOTIFY_ID: u32 = 9;
+    pub const GET_TABLE_ID: u32 = 10;
+    pub const MANY_ID: u32 = 11;
+    pub const PING_ID: u32 = 12;
+
+    /// Client of `example_service` calling through an [`rpc::Caller`], such as an [`rpc::Client`].
+    pub struct Client<'client, CallerType> {
+        caller: &'client mut CallerType,
+    }
+
+    impl<'client, CallerType> Client<'client, CallerType> {
+        pub fn new(caller: &'client mut CallerType) -> Self {
+            Self { caller }
+        }
+
+        /// Adds two numbers.
+        pub fn add<Error, CursorType, CodecType>(
+            &mut self,
+            a: i32,
+            b: i32,
+        ) -> Result<i32, RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                ADD_ID,
+                |codec| {
+                    codec.write_i32(a)?;
+                    codec.write_i32(b)?;
+                    Ok(())
+                },
+                |codec| {
+                    let result = codec.read_i32()?;
+                    Ok(result)
+                },
+            )
+        }
+
+        pub fn set_device<Error, CursorType, CodecType>(
+            &mut self,
+            device: &device_t,
+            data: &[u8],
+        ) -> Result<status_t, RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                SET_DEVICE_ID,
+                |codec| {
+                    device.write(codec)?;
+                    codec.write_binary(data)?;
+                    Ok(())
+                },
+                |codec| {
+                    let result = status_t::read(codec)?;
+                    Ok(result)
+                },
+            )
+        }
+
+        pub fn get_device<Error, CursorType, CodecType>(
+            &mut self,
+            handle: handle_t,
+        ) -> Result<(device_t, status_t), RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                GET_DEVICE_ID,
+                |codec| {
+                    codec.write_u16(handle)?;
+                    Ok(())
+                },
+                |codec| {
+                    let device = device_t::read(codec)?;
+                    let result = status_t::read(codec)?;
+                    Ok((device, result))
+                },
+            )
+        }
+
+        pub fn read_data<'data, 'name, Error, CursorType, CodecType>(
+            &mut self,
+            handle: handle_t,
+            data: &'data mut [u8],
+            name: &'name mut [u8],
+        ) -> Result<(&'data [u8], &'name str, bool), RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                READ_DATA_ID,
+                |codec| {
+                    codec.write_u16(handle)?;
+                    Ok(())
+                },
+                move |codec| {
+                    let data = codec.read_binary(data)?;
+                    let name = codec.read_str(name)?;
+                    let result = codec.read_bool()?;
+                    Ok((data, name, result))
+                },
+            )
+        }
+
+        pub fn read_handles<'handles, Error, CursorType, CodecType>(
+            &mut self,
+            handles: &'handles mut [handle_t],
+        ) -> Result<&'handles [handle_t], RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                READ_HANDLES_ID,
+                |_| Ok(()),
+                move |codec| {
+                    let handles: &[handle_t] = {
+                        let length = codec.start_read_list()?;
+                        let list = handles
+                            .get_mut(..length)
+                            .ok_or(CodecError::LengthExceedsBuffer)?;
+                        for element in list.iter_mut() {
+                            *element = codec.read_u16()?;
+                        }
+                        list
+                    };
+                    Ok(handles)
+                },
+            )
+        }
+
+        pub fn write_handles<Error, CursorType, CodecType>(
+            &mut self,
+            handles: &[handle_t],
+            name: &str,
+        ) -> Result<(), RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                WRITE_HANDLES_ID,
+                |codec| {
+                    codec.start_write_list(handles.len())?;
+                    for element in handles.iter() {
+                        codec.write_u16(*element)?;
+                    }
+                    codec.write_str(name)?;
+                    Ok(())
+                },
+                |_| Ok(()),
+            )
+        }
+
+        pub fn swap<Error, CursorType, CodecType>(
+            &mut self,
+            value: u32,
+            addr: &[u8; 6],
+        ) -> Result<(u32, [u8; 6]), RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                SWAP_ID,
+                |codec| {
+                    codec.write_u32(value)?;
+                    for element in addr.iter() {
+                        codec.write_u8(*element)?;
+                    }
+                    Ok(())
+                },
+                |codec| {
+                    let value = codec.read_u32()?;
+                    let addr = {
+                        let mut array = [0; 6];
+                        for element in array.iter_mut() {
+                            *element = codec.read_u8()?;
+                        }
+                        array
+                    };
+                    Ok((value, addr))
+                },
+            )
+        }
+
+        pub fn find<Error, CursorType, CodecType>(
+            &mut self,
+            name: Option<&str>,
+            hint: Option<&device_t>,
+        ) -> Result<status_t, RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                FIND_ID,
+                |codec| {
+                    match name {
+                        Some(name) => {
+                            codec.write_null_flag(false)?;
+                            codec.write_str(name)?;
+                        }
+                        None => codec.write_null_flag(true)?,
+                    }
+                    match hint {
+                        Some(hint) => {
+                            codec.write_null_flag(false)?;
+                            hint.write(codec)?;
+                        }
+                        None => codec.write_null_flag(true)?,
+                    }
+                    Ok(())
+                },
+                |codec| {
+                    let result = status_t::read(codec)?;
+                    Ok(result)
+                },
+            )
+        }
+
+        pub fn notify<Error, CursorType, CodecType>(
+            &mut self,
+            status: status_t,
+        ) -> Result<(), RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call_oneway(
+                SERVICE_ID,
+                NOTIFY_ID,
+                |codec| {
+                    status.write(codec)?;
+                    Ok(())
+                },
+            )
+        }
+
+        pub fn get_table<Error, CursorType, CodecType>(
+            &mut self,
+        ) -> Result<table_t, RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                GET_TABLE_ID,
+                |_| Ok(()),
+                |codec| {
+                    let result = table_t::read(codec)?;
+                    Ok(result)
+                },
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn many<Error, CursorType, CodecType>(
+            &mut self,
+            a: u8,
+            b: u8,
+            c: u8,
+            d: u8,
+            e: u8,
+            f: u8,
+            g: u8,
+        ) -> Result<(), RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                MANY_ID,
+                |codec| {
+                    codec.write_u8(a)?;
+                    codec.write_u8(b)?;
+                    codec.write_u8(c)?;
+                    codec.write_u8(d)?;
+                    codec.write_u8(e)?;
+                    codec.write_u8(f)?;
+                    codec.write_u8(g)?;
+                    Ok(())
+                },
+                |_| Ok(()),
+            )
+        }
+
+        pub fn ping<Error, CursorType, CodecType>(
+            &mut self,
+        ) -> Result<(), RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            self.caller.call(
+                SERVICE_ID,
+                PING_ID,
+                |_| Ok(()),
+                |_| Ok(()),
+            )
+        }
+    }
+
+    /// Implementation of `example_service`, called by [`Service`].
+    pub trait Iexample_service {
+        /// Adds two numbers.
+        fn add(
+            &mut self,
+            a: i32,
+            b: i32,
+        ) -> i32;
+
+        fn set_device(
+            &mut self,
+            device: &device_t,
+            data: &[u8],
+        ) -> status_t;
+
+        fn get_device(
+            &mut self,
+            handle: handle_t,
+        ) -> (device_t, status_t);
+
+        fn read_data<'data, 'name>(
+            &mut self,
+            handle: handle_t,
+            data: &'data mut [u8],
+            name: &'name mut [u8],
+        ) -> (&'data [u8], &'name str, bool);
+
+        fn read_handles<'handles>(
+            &mut self,
+            handles: &'handles mut [handle_t],
+        ) -> &'handles [handle_t];
+
+        fn write_handles(
+            &mut self,
+            handles: &[handle_t],
+            name: &str,
+        );
+
+        fn swap(
+            &mut self,
+            value: u32,
+            addr: &[u8; 6],
+        ) -> (u32, [u8; 6]);
+
+        fn find(
+            &mut self,
+            name: Option<&str>,
+            hint: Option<&device_t>,
+        ) -> status_t;
+
+        fn notify(
+            &mut self,
+            status: status_t,
+        );
+
+        fn get_table(
+            &mut self,
+        ) -> table_t;
+
+        #[allow(clippy::too_many_arguments)]
+        fn many(
+            &mut self,
+            a: u8,
+            b: u8,
+            c: u8,
+            d: u8,
+            e: u8,
+            f: u8,
+            g: u8,
+        );
+
+        fn ping(
+            &mut self,
+        );
+    }
+
+    impl<Handler: Iexample_service + ?Sized> Iexample_service for &mut Handler {
+        fn add(
+            &mut self,
+            a: i32,
+            b: i32,
+        ) -> i32 {
+            (**self).add(a, b)
+        }
+
+        fn set_device(
+            &mut self,
+            device: &device_t,
+            data: &[u8],
+        ) -> status_t {
+            (**self).set_device(device, data)
+        }
+
+        fn get_device(
+            &mut self,
+            handle: handle_t,
+        ) -> (device_t, status_t) {
+            (**self).get_device(handle)
+        }
+
+        fn read_data<'data, 'name>(
+            &mut self,
+            handle: handle_t,
+            data: &'data mut [u8],
+            name: &'name mut [u8],
+        ) -> (&'data [u8], &'name str, bool) {
+            (**self).read_data(handle, data, name)
+        }
+
+        fn read_handles<'handles>(
+            &mut self,
+            handles: &'handles mut [handle_t],
+        ) -> &'handles [handle_t] {
+            (**self).read_handles(handles)
+        }
+
+        fn write_handles(
+            &mut self,
+            handles: &[handle_t],
+            name: &str,
+        ) {
+            (**self).write_handles(handles, name)
+        }
+
+        fn swap(
+            &mut self,
+            value: u32,
+            addr: &[u8; 6],
+        ) -> (u32, [u8; 6]) {
+            (**self).swap(value, addr)
+        }
+
+        fn find(
+            &mut self,
+            name: Option<&str>,
+            hint: Option<&device_t>,
+        ) -> status_t {
+            (**self).find(name, hint)
+        }
+
+        fn notify(
+            &mut self,
+            status: status_t,
+        ) {
+            (**self).notify(status)
+        }
+
+        fn get_table(
+            &mut self,
+        ) -> table_t {
+            (**self).get_table()
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn many(
+            &mut self,
+            a: u8,
+            b: u8,
+            c: u8,
+            d: u8,
+            e: u8,
+            f: u8,
+            g: u8,
+        ) {
+            (**self).many(a, b, c, d, e, f, g)
+        }
+
+        fn ping(
+            &mut self,
+        ) {
+            (**self).ping()
+        }
+    }
+
+    impl<Handler: Iexample_service + ?Sized> Iexample_service for &core::cell::RefCell<Handler> {
+        fn add(
+            &mut self,
+            a: i32,
+            b: i32,
+        ) -> i32 {
+            self.borrow_mut().add(a, b)
+        }
+
+        fn set_device(
+            &mut self,
+            device: &device_t,
+            data: &[u8],
+        ) -> status_t {
+            self.borrow_mut().set_device(device, data)
+        }
+
+        fn get_device(
+            &mut self,
+            handle: handle_t,
+        ) -> (device_t, status_t) {
+            self.borrow_mut().get_device(handle)
+        }
+
+        fn read_data<'data, 'name>(
+            &mut self,
+            handle: handle_t,
+            data: &'data mut [u8],
+            name: &'name mut [u8],
+        ) -> (&'data [u8], &'name str, bool) {
+            self.borrow_mut().read_data(handle, data, name)
+        }
+
+        fn read_handles<'handles>(
+            &mut self,
+            handles: &'handles mut [handle_t],
+        ) -> &'handles [handle_t] {
+            self.borrow_mut().read_handles(handles)
+        }
+
+        fn write_handles(
+            &mut self,
+            handles: &[handle_t],
+            name: &str,
+        ) {
+            self.borrow_mut().write_handles(handles, name)
+        }
+
+        fn swap(
+            &mut self,
+            value: u32,
+            addr: &[u8; 6],
+        ) -> (u32, [u8; 6]) {
+            self.borrow_mut().swap(value, addr)
+        }
+
+        fn find(
+            &mut self,
+            name: Option<&str>,
+            hint: Option<&device_t>,
+        ) -> status_t {
+            self.borrow_mut().find(name, hint)
+        }
+
+        fn notify(
+            &mut self,
+            status: status_t,
+        ) {
+            self.borrow_mut().notify(status)
+        }
+
+        fn get_table(
+            &mut self,
+        ) -> table_t {
+            self.borrow_mut().get_table()
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn many(
+            &mut self,
+            a: u8,
+            b: u8,
+            c: u8,
+            d: u8,
+            e: u8,
+            f: u8,
+            g: u8,
+        ) {
+            self.borrow_mut().many(a, b, c, d, e, f, g)
+        }
+
+        fn ping(
+            &mut self,
+        ) {
+            self.borrow_mut().ping()
+        }
+    }
+
+    /// Dispatches invocations of `example_service` to an [`Iexample_service`] implementation.
+    ///
+    /// Strings and binaries passed in are decoded into the scratch buffer. The rest of the scratch buffer is split
+    /// evenly into the buffers receiving variable-length out parameters, lists use arrays of their `@max_length`.
+    pub struct Service<'scratch, Handler> {
+        handler: Handler,
+        scratch: &'scratch mut [u8],
+    }
+
+    impl<'scratch, Handler> Service<'scratch, Handler> {
+        /// Creates a service without a scratch buffer, enough for functions without strings and binaries.
+        pub fn new(handler: Handler) -> Self {
+            Self::with_scratch(handler, &mut [])
+        }
+        pub fn with_scratch(handler: Handler, scratch: &'scratch mut [u8]) -> Self {
+            Self { handler, scratch }
+        }
+        pub fn handler(&mut self) -> &mut Handler {
+            &mut self.handler
+        }
+        pub fn release(self) -> (Handler, &'scratch mut [u8]) {
+            (self.handler, self.scratch)
+        }
+    }
+
+    impl<'scratch, CursorType, CodecType, Handler> rpc::Service<CursorType, CodecType>
+        for Service<'scratch, Handler>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+        Handler: Iexample_service,
+    {
+        fn get_service_id(&self) -> u32 {
+            SERVICE_ID
+        }
+
+        fn handle_invocation(
+            &mut self,
+            method_id: u32,
+            in_codec: &mut CodecType,
+            out_codec: &mut CodecType,
+        ) -> Result<(), rpc::ServiceError> {
+            match method_id {
+                ADD_ID => {
+                    let codec = in_codec;
+                    let a = codec.read_i32()?;
+                    let b = codec.read_i32()?;
+                    let result = self.handler.add(a, b);
+                    let codec = out_codec;
+                    codec.write_i32(result)?;
+                }
+                SET_DEVICE_ID => {
+                    let codec = in_codec;
+                    let scratch = &mut *self.scratch;
+                    let device = device_t::read(codec)?;
+                    let (data, _) = {
+                        let length = codec.read_binary(scratch)?.len();
+                        scratch.split_at_mut(length)
+                    };
+                    let result = self.handler.set_device(&device, data);
+                    let codec = out_codec;
+                    result.write(codec)?;
+                }
+                GET_DEVICE_ID => {
+                    let codec = in_codec;
+                    let handle = codec.read_u16()?;
+                    let (device, result) = self.handler.get_device(handle);
+                    let codec = out_codec;
+                    device.write(codec)?;
+                    result.write(codec)?;
+                }
+                READ_DATA_ID => {
+                    let codec = in_codec;
+                    let scratch = &mut *self.scratch;
+                    let handle = codec.read_u16()?;
+                    let (data, scratch) = scratch.split_at_mut(scratch.len() / 2);
+                    let name = scratch;
+                    let (data, name, result) = self.handler.read_data(handle, data, name);
+                    let codec = out_codec;
+                    codec.write_binary(data)?;
+                    codec.write_str(name)?;
+                    codec.write_bool(result)?;
+                }
+                READ_HANDLES_ID => {
+                    let mut handles = [0; 4];
+                    let handles = self.handler.read_handles(&mut handles);
+                    let codec = out_codec;
+                    codec.start_write_list(handles.len())?;
+                    for element in handles.iter() {
+                        codec.write_u16(*element)?;
+                    }
+                }
+                WRITE_HANDLES_ID => {
+                    let codec = in_codec;
+                    let scratch = &mut *self.scratch;
+                    let mut handles = [0; 4];
+                    let handles = {
+                        let length = codec.start_read_list()?;
+                        let list = handles
+                            .get_mut(..length)
+                            .ok_or(CodecError::LengthExceedsBuffer)?;
+                        for element in list.iter_mut() {
+                            *element = codec.read_u16()?;
+                        }
+                        &*list
+                    };
+                    let (name, _) = {
+                        let length = codec.read_binary(scratch)?.len();
+                        scratch.split_at_mut(length)
+                    };
+                    let name = core::str::from_utf8(name).map_err(CodecError::from)?;
+                    self.handler.write_handles(handles, name);
+                }
+                SWAP_ID => {
+                    let codec = in_codec;
+                    let value = codec.read_u32()?;
+                    let addr = {
+                        let mut array = [0; 6];
+                        for element in array.iter_mut() {
+                            *element = codec.read_u8()?;
+                        }
+                        array
+                    };
+                    let (value, addr) = self.handler.swap(value, &addr);
+                    let codec = out_codec;
+                    codec.write_u32(value)?;
+                    for element in addr.iter() {
+                        codec.write_u8(*element)?;
+                    }
+                }
+                FIND_ID => {
+                    let codec = in_codec;
+                    let scratch = &mut *self.scratch;
+                    let (name, _) = if codec.read_null_flag()? {
+                        (None, scratch)
+                    } else {
+                        let (name, scratch) = {
+                            let length = codec.read_binary(scratch)?.len();
+                            scratch.split_at_mut(length)
+                        };
+                        let name = core::str::from_utf8(name).map_err(CodecError::from)?;
+                        (Some(name), scratch)
+                    };
+                    let hint = if codec.read_null_flag()? {
+                        None
+                    } else {
+                        Some(device_t::read(codec)?)
+                    };
+                    let result = self.handler.find(name, hint.as_ref());
+                    let codec = out_codec;
+                    result.write(codec)?;
+                }
+                NOTIFY_ID => {
+                    let codec = in_codec;
+                    let status = status_t::read(codec)?;
+                    self.handler.notify(status);
+                }
+                GET_TABLE_ID => {
+                    let result = self.handler.get_table();
+                    let codec = out_codec;
+                    result.write(codec)?;
+                }
+                MANY_ID => {
+                    let codec = in_codec;
+                    let a = codec.read_u8()?;
+                    let b = codec.read_u8()?;
+                    let c = codec.read_u8()?;
+                    let d = codec.read_u8()?;
+                    let e = codec.read_u8()?;
+                    let f = codec.read_u8()?;
+                    let g = codec.read_u8()?;
+                    self.handler.many(a, b, c, d, e, f, g);
+                }
+                PING_ID => {
+                    self.handler.ping();
+                }
+                _ => return Err(rpc::ServiceError::UnknownMethod),
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,282 @@
+#[allow(dead_code)]
+mod example {
+    include!("generated/example.rs");
+}
+
+use example::example_service::Iexample_service;
+use example::*;
+use rust_erpc::codec::{BasicCodecFactory, CodecError};
+use rust_erpc::framed_transport::{FramedTransport, FramedTransportError};
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc::SimpleServer;
+use std::collections::VecDeque;
+
+/// Returns prepared requests and records sent replies.
+#[derive(Default)]
+struct MockTransport {
+    requests: VecDeque<Vec<u8>>,
+    sent: Vec<Vec<u8>>,
+}
+
+impl FramedTransport<()> for MockTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sent.push(data.to_vec());
+        Ok(())
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        let request = self
+            .requests
+            .pop_front()
+            .ok_or(FramedTransportError::UnderlyingError(()))?;
+        buffer[..request.len()].copy_from_slice(&request);
+        Ok(&buffer[..request.len()])
+    }
+}
+
+fn message(message_type: u32, request: u32, sequence: u32, payload: &[u8]) -> Vec<u8> {
+    let header = (1u32 << 24) | (7 << 16) | (request << 8) | message_type;
+    let mut message = header.to_le_bytes().to_vec();
+    message.extend_from_slice(&sequence.to_le_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Handler keeping one device and recording the calls which return nothing.
+#[derive(Default)]
+struct Handler {
+    device: device_t,
+    data: Vec<u8>,
+    handles: Vec<handle_t>,
+    calls: Vec<String>,
+}
+
+impl Iexample_service for Handler {
+    fn add(&mut self, a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn set_device(&mut self, device: &device_t, data: &[u8]) -> status_t {
+        self.device = *device;
+        self.data = data.to_vec();
+        status_t::STATUS_OK
+    }
+
+    fn get_device(&mut self, handle: handle_t) -> (device_t, status_t) {
+        if handle == self.device.handle {
+            (self.device, status_t::STATUS_OK)
+        } else {
+            (device_t::default(), status_t::STATUS_FAILED)
+        }
+    }
+
+    fn read_data<'data, 'name>(
+        &mut self,
+        _handle: handle_t,
+        data: &'data mut [u8],
+        _name: &'name mut [u8],
+    ) -> (&'data [u8], &'name str, bool) {
+        data[..self.data.len()].copy_from_slice(&self.data);
+        (&data[..self.data.len()], "ok", true)
+    }
+
+    fn read_handles<'handles>(
+        &mut self,
+        handles: &'handles mut [handle_t],
+    ) -> &'handles [handle_t] {
+        handles[..self.handles.len()].copy_from_slice(&self.handles);
+        &handles[..self.handles.len()]
+    }
+
+    fn write_handles(&mut self, handles: &[handle_t], name: &str) {
+        self.handles = handles.to_vec();
+        self.calls.push(format!("write_handles {}", name));
+    }
+
+    fn swap(&mut self, value: u32, addr: &[u8; 6]) -> (u32, [u8; 6]) {
+        let mut reversed = *addr;
+        reversed.reverse();
+        (value.swap_bytes(), reversed)
+    }
+
+    fn find(&mut self, name: Option<&str>, hint: Option<&device_t>) -> status_t {
+        self.calls.push(format!(
+            "find {:?} {:?}",
+            name,
+            hint.map(|hint| hint.handle)
+        ));
+        status_t::STATUS_BUSY
+    }
+
+    fn notify(&mut self, status: status_t) {
+        self.calls.push(format!("notify {:?}", status));
+    }
+
+    fn get_table(&mut self) -> table_t {
+        table_t::default()
+    }
+
+    fn many(&mut self, a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8) {
+        self.calls
+            .push(format!("many {}", [a, b, c, d, e, f, g].iter().sum::<u8>()));
+    }
+
+    fn ping(&mut self) {
+        self.calls.push("ping".to_string());
+    }
+}
+
+type RunResult = Result<(), RequestResponseError<()>>;
+
+/// Serves `requests` one by one and returns the handler, the replies sent and the results of `run_once`.
+fn serve(scratch: &mut [u8], requests: Vec<Vec<u8>>) -> (Handler, Vec<Vec<u8>>, Vec<RunResult>) {
+    let count = requests.len();
+    let transport = MockTransport {
+        requests: requests.into(),
+        sent: Vec::new(),
+    };
+    let mut service = example_service::Service::with_scratch(Handler::default(), scratch);
+    let mut receive_buffer = [0u8; 256];
+    let mut send_buffer = [0u8; 256];
+    let mut server: SimpleServer<_, _, _, 1> = SimpleServer::new(
+        transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        BasicCodecFactory::new(),
+    );
+    assert!(server.add_service(&mut service).is_ok());
+    let results = (0..count).map(|_| server.run_once()).collect();
+    let (transport, _, _, _) = server.release();
+    (service.release().0, transport.sent, results)
+}
+
+const DEVICE_BYTES: [u8; 13] = [1, 2, 3, 4, 5, 6, 0x34, 0x12, 1, 0, 0, 0, 1];
+
+#[test]
+fn dispatch_decodes_arguments_and_encodes_replies() {
+    let mut set_device = DEVICE_BYTES.to_vec();
+    set_device.extend_from_slice(&[3, 0, 0, 0, 9, 8, 7]);
+    let mut scratch = [0u8; 16];
+    let (handler, sent, results) = serve(
+        &mut scratch,
+        vec![
+            message(0, 1, 1, &[2, 0, 0, 0, 3, 0, 0, 0]),
+            message(0, 2, 2, &set_device),
+            message(0, 20, 3, &[0x34, 0x12]),
+            message(0, 4, 4, &[0x34, 0x12]),
+            message(0, 7, 5, &[1, 2, 3, 4, 1, 2, 3, 4, 5, 6]),
+        ],
+    );
+    assert!(results.iter().all(Result::is_ok));
+
+    assert_eq!(sent[0], message(2, 1, 1, &[5, 0, 0, 0]));
+    assert_eq!(sent[1], message(2, 2, 2, &[0, 0, 0, 0]));
+    let mut device = DEVICE_BYTES.to_vec();
+    device.extend_from_slice(&[0, 0, 0, 0]);
+    assert_eq!(sent[2], message(2, 20, 3, &device));
+    assert_eq!(
+        sent[3],
+        message(2, 4, 4, &[3, 0, 0, 0, 9, 8, 7, 2, 0, 0, 0, b'o', b'k', 1])
+    );
+    assert_eq!(sent[4], message(2, 7, 5, &[4, 3, 2, 1, 6, 5, 4, 3, 2, 1]));
+    assert_eq!(handler.data, [9, 8, 7]);
+}
+
+#[test]
+fn lists_nullable_oneway_and_errors() {
+    let mut scratch = [0u8; 4];
+    let mut find = vec![1, 0];
+    find.extend_from_slice(&DEVICE_BYTES);
+    let (handler, sent, results) = serve(
+        &mut scratch,
+        vec![
+            message(0, 6, 1, &[2, 0, 0, 0, 1, 0, 2, 0, 1, 0, 0, 0, b'x']),
+            message(0, 5, 2, &[]),
+            message(0, 8, 3, &find),
+            message(0, 8, 4, &[0, 2, 0, 0, 0, b'a', b'b', 1]),
+            message(1, 9, 5, &[1, 0, 0, 0]),
+            message(0, 12, 6, &[]),
+            // Longer than `@max_length(4)`.
+            message(0, 6, 7, &[5, 0, 0, 0]),
+            message(0, 30, 8, &[]),
+            // `name` is longer than the scratch buffer.
+            message(
+                0,
+                6,
+                9,
+                &[0, 0, 0, 0, 5, 0, 0, 0, b'a', b'b', b'c', b'd', b'e'],
+            ),
+        ],
+    );
+    assert_eq!(
+        handler.calls,
+        [
+            "write_handles x",
+            "find None Some(4660)",
+            "find Some(\"ab\") None",
+            "notify STATUS_BUSY",
+            "ping",
+        ]
+    );
+    assert_eq!(sent.len(), 5);
+    assert_eq!(sent[0], message(2, 6, 1, &[]));
+    assert_eq!(sent[1], message(2, 5, 2, &[2, 0, 0, 0, 1, 0, 2, 0]));
+    assert_eq!(sent[2], message(2, 8, 3, &[1, 0, 0, 0]));
+    assert_eq!(sent[4], message(2, 12, 6, &[]));
+    assert!(results[..6].iter().all(Result::is_ok));
+    assert_eq!(
+        results[6],
+        Err(RequestResponseError::CodecError(
+            CodecError::LengthExceedsBuffer
+        ))
+    );
+    assert_eq!(results[7], Err(RequestResponseError::InvalidRequest));
+    assert_eq!(
+        results[8],
+        Err(RequestResponseError::CodecError(
+            CodecError::LengthExceedsBuffer
+        ))
+    );
+}
+
+#[test]
+fn lists_require_max_length() {
+    let source = "interface i { f(list<int32> values) -> void }";
+    let err = erpc_codegen::generate_str(source, &erpc_codegen::Options::default()).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("list parameters require `@max_length` to generate the server"));
+    let options = erpc_codegen::Options {
+        server: false,
+        ..Default::default()
+    };
+    assert!(erpc_codegen::generate_str(source, &options).is_ok());
+}
+
+#[test]
+fn handlers_can_be_borrowed() {
+    let mut handler = Handler::default();
+    let transport = MockTransport {
+        requests: vec![message(0, 12, 1, &[])].into(),
+        sent: Vec::new(),
+    };
+    let mut service = example_service::Service::new(&mut handler);
+    let mut receive_buffer = [0u8; 64];
+    let mut send_buffer = [0u8; 64];
+    let mut server: SimpleServer<_, _, _, 1> = SimpleServer::new(
+        transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        BasicCodecFactory::new(),
+    );
+    assert!(server.add_service(&mut service).is_ok());
+    assert_eq!(server.run_once(), Ok(()));
+    let (transport, _, _, _) = server.release();
+    assert_eq!(transport.sent, [message(2, 12, 1, &[])]);
+    assert_eq!(handler.calls, ["ping"]);
+}
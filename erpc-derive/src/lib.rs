@@ -0,0 +1,620 @@
+//! `#[derive(ErpcEncode, ErpcDecode)]` for `rust_erpc::encode::{Encode, Decode}`,
+//! and `#[derive(ErpcMaxEncodedSize)]` for `rust_erpc::encode::MaxEncodedSize`.
+//!
+//! Structs encode their fields in declaration order like erpcgen encodes struct members.
+//! Enums without fields encode as `int32`, and enums whose variants are marked with `#[erpc(case = value)]`
+//! are unions, encoded as the discriminator followed by the fields of the variant.
+//! See the documentation of `rust_erpc::encode` for the field attributes.
+//!
+//! `#[service(id = value)]` generates a client proxy and an `rpc::Service` dispatcher for a trait, see `rust_erpc::service`.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DataEnum, DeriveInput, Error, Expr, Fields,
+    Ident, ItemTrait, Member, Path, Type,
+};
+
+mod service;
+
+#[proc_macro_derive(ErpcEncode, attributes(erpc))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, Trait::Encode)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ErpcDecode, attributes(erpc))]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, Trait::Decode)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(ErpcMaxEncodedSize, attributes(erpc))]
+pub fn derive_max_encoded_size(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_max_encoded_size(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_attribute]
+pub fn service(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemTrait);
+    service::expand(args.into(), item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Trait {
+    Encode,
+    Decode,
+}
+
+/// How a field is encoded.
+enum Kind {
+    Plain,
+    /// `Option<T>` preceded by the null flag.
+    Nullable,
+    /// Array whose first elements, as many as the named field holds, are encoded as a list.
+    Length(Ident),
+    /// Union whose discriminator is stored in the named field.
+    Discriminator(Ident),
+}
+
+struct Field {
+    member: Member,
+    /// Name of the local variable bound to the field.
+    binding: Ident,
+    ty: Type,
+    kind: Kind,
+    /// Whether the field is encoded by the field linking to it rather than in place.
+    is_linked: bool,
+}
+
+fn expand(input: &DeriveInput, which: Trait) -> syn::Result<TokenStream2> {
+    let runtime = runtime_path(&input.attrs)?;
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    let bound: Path = match which {
+        Trait::Encode => parse_quote!(#runtime::encode::Encode),
+        Trait::Decode => parse_quote!(#runtime::encode::Decode),
+    };
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = fields(&data.fields)?;
+            match which {
+                Trait::Encode => {
+                    let pattern = pattern(quote!(Self), &data.fields, &fields);
+                    let encode = encode_fields(&runtime, &fields);
+                    quote! {
+                        let #pattern = self;
+                        #encode
+                        Ok(())
+                    }
+                }
+                Trait::Decode => {
+                    let decode = decode_fields(&runtime, &fields);
+                    let pattern = pattern(quote!(Self), &data.fields, &fields);
+                    quote! {
+                        #decode
+                        Ok(#pattern)
+                    }
+                }
+            }
+        }
+        Data::Enum(data) => {
+            let cases = cases(data)?;
+            if cases.iter().all(Option::is_none) {
+                return expand_enum(
+                    input,
+                    which,
+                    &runtime,
+                    &impl_generics,
+                    &ty_generics,
+                    where_clause,
+                );
+            }
+            let mut variants = Vec::new();
+            for (variant, case) in data.variants.iter().zip(cases) {
+                let case = case.ok_or_else(|| {
+                    Error::new(
+                        variant.span(),
+                        "all variants of a union need `#[erpc(case = value)]`",
+                    )
+                })?;
+                let fields = fields(&variant.fields)?;
+                let ident = &variant.ident;
+                let pattern = pattern(quote!(Self::#ident), &variant.fields, &fields);
+                variants.push((case, pattern, fields));
+            }
+            match which {
+                Trait::Encode => {
+                    let discriminators = variants
+                        .iter()
+                        .map(|(case, pattern, _)| quote!(#pattern => #case,));
+                    let arms = variants.iter().map(|(_, pattern, fields)| {
+                        let encode = encode_fields(&runtime, fields);
+                        quote!(#pattern => { #encode })
+                    });
+                    let encode = wrap(
+                        which,
+                        &runtime,
+                        name,
+                        &impl_generics,
+                        &ty_generics,
+                        where_clause,
+                        quote!(codec.write_union(self)),
+                    );
+                    return Ok(quote! {
+                        impl #impl_generics #runtime::encode::Union for #name #ty_generics #where_clause {
+                            #[allow(unused_variables)]
+                            fn discriminator(&self) -> i32 {
+                                match self {
+                                    #(#discriminators)*
+                                }
+                            }
+
+                            #[allow(unused_variables)]
+                            fn encode_case<CursorType, CodecType>(
+                                &self,
+                                codec: &mut CodecType,
+                            ) -> ::core::result::Result<(), #runtime::codec::CodecError>
+                            where
+                                CursorType: #runtime::cursor::Cursor,
+                                CodecType: #runtime::codec::Codec<CursorType>,
+                            {
+                                match self {
+                                    #(#arms)*
+                                }
+                                Ok(())
+                            }
+                        }
+
+                        #encode
+                    });
+                }
+                Trait::Decode => {
+                    let arms = variants.iter().map(|(case, pattern, fields)| {
+                        let decode = decode_fields(&runtime, fields);
+                        quote! {
+                            if discriminator == #case {
+                                #decode
+                                return Ok(#pattern);
+                            }
+                        }
+                    });
+                    let decode = wrap(
+                        which,
+                        &runtime,
+                        name,
+                        &impl_generics,
+                        &ty_generics,
+                        where_clause,
+                        quote!(codec.read_union()),
+                    );
+                    return Ok(quote! {
+                        impl #impl_generics #runtime::encode::DecodeUnion for #name #ty_generics #where_clause {
+                            fn decode_case<CursorType, CodecType>(
+                                discriminator: i32,
+                                codec: &mut CodecType,
+                            ) -> ::core::result::Result<Self, #runtime::codec::CodecError>
+                            where
+                                CursorType: #runtime::cursor::Cursor,
+                                CodecType: #runtime::codec::Codec<CursorType>,
+                            {
+                                #(#arms)*
+                                Err(#runtime::codec::CodecError::InvalidEnumValue)
+                            }
+                        }
+
+                        #decode
+                    });
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "Rust unions are not supported, use an enum with `#[erpc(case = value)]` variants",
+            ))
+        }
+    };
+    Ok(wrap(
+        which,
+        &runtime,
+        name,
+        &impl_generics,
+        &ty_generics,
+        where_clause,
+        body,
+    ))
+}
+
+/// The `#[erpc(case = value)]` of each variant.
+fn cases(data: &DataEnum) -> syn::Result<Vec<Option<Expr>>> {
+    let mut cases = Vec::new();
+    for variant in &data.variants {
+        let mut case = None;
+        for attr in erpc_attributes(&variant.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("case") {
+                    case = Some(meta.value()?.parse::<Expr>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown erpc variant attribute"))
+                }
+            })?;
+        }
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+/// Expands `MaxEncodedSize`: the sum of the fields of a struct, 4 bytes for an enum,
+/// and the discriminator followed by the largest case for a union.
+fn expand_max_encoded_size(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let runtime = runtime_path(&input.attrs)?;
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(#runtime::encode::MaxEncodedSize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let size = match &input.data {
+        Data::Struct(data) => fields_size(&runtime, &fields(&data.fields)?),
+        Data::Enum(data) => {
+            let cases = cases(data)?;
+            if cases.iter().all(Option::is_none) {
+                if let Some(variant) = data
+                    .variants
+                    .iter()
+                    .find(|variant| !matches!(variant.fields, Fields::Unit))
+                {
+                    return Err(Error::new(
+                        variant.span(),
+                        "variants with fields need `#[erpc(case = value)]` to make the enum a union",
+                    ));
+                }
+                quote!(4)
+            } else {
+                let mut sizes = Vec::new();
+                for (variant, case) in data.variants.iter().zip(cases) {
+                    if case.is_none() {
+                        return Err(Error::new(
+                            variant.span(),
+                            "all variants of a union need `#[erpc(case = value)]`",
+                        ));
+                    }
+                    sizes.push(fields_size(&runtime, &fields(&variant.fields)?));
+                }
+                quote! {{
+                    let mut largest = 0;
+                    #(
+                        let size = #sizes;
+                        if size > largest {
+                            largest = size;
+                        }
+                    )*
+                    4 + largest
+                }}
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "Rust unions are not supported, use an enum with `#[erpc(case = value)]` variants",
+            ))
+        }
+    };
+    Ok(quote! {
+        impl #impl_generics #runtime::encode::MaxEncodedSize for #name #ty_generics #where_clause {
+            const MAX_ENCODED_SIZE: usize = #size;
+        }
+    })
+}
+
+/// Sum of the maximum encoded sizes of `fields`.
+fn fields_size(runtime: &Path, fields: &[Field]) -> TokenStream2 {
+    let sizes = fields.iter().filter(|field| !field.is_linked).map(|field| {
+        let ty = &field.ty;
+        let size = quote!(<#ty as #runtime::encode::MaxEncodedSize>::MAX_ENCODED_SIZE);
+        match field.kind {
+            // The list length precedes the elements.
+            Kind::Length(_) => quote!(4 + #size),
+            Kind::Plain | Kind::Nullable | Kind::Discriminator(_) => size,
+        }
+    });
+    quote!(0 #(+ #sizes)*)
+}
+
+/// Expands an enum without fields, encoded as `int32` like erpc enums.
+fn expand_enum(
+    input: &DeriveInput,
+    which: Trait,
+    runtime: &Path,
+    impl_generics: &syn::ImplGenerics,
+    ty_generics: &syn::TypeGenerics,
+    where_clause: Option<&syn::WhereClause>,
+) -> syn::Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => unreachable!(),
+    };
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new(
+                variant.span(),
+                "variants with fields need `#[erpc(case = value)]` to make the enum a union",
+            ));
+        }
+        variants.push(&variant.ident);
+    }
+    let body = match which {
+        Trait::Encode => quote! {
+            let value = match self {
+                #(Self::#variants => Self::#variants as i32,)*
+            };
+            codec.write_i32(value)
+        },
+        Trait::Decode => quote! {
+            let value = codec.read_i32()?;
+            #(
+                if value == Self::#variants as i32 {
+                    return Ok(Self::#variants);
+                }
+            )*
+            Err(#runtime::codec::CodecError::InvalidEnumValue)
+        },
+    };
+    Ok(wrap(
+        which,
+        runtime,
+        &input.ident,
+        impl_generics,
+        ty_generics,
+        where_clause,
+        body,
+    ))
+}
+
+fn wrap(
+    which: Trait,
+    runtime: &Path,
+    name: &Ident,
+    impl_generics: &syn::ImplGenerics,
+    ty_generics: &syn::TypeGenerics,
+    where_clause: Option<&syn::WhereClause>,
+    body: TokenStream2,
+) -> TokenStream2 {
+    match which {
+        Trait::Encode => quote! {
+            impl #impl_generics #runtime::encode::Encode for #name #ty_generics #where_clause {
+                #[allow(unused_variables)]
+                fn encode<CursorType, CodecType>(
+                    &self,
+                    codec: &mut CodecType,
+                ) -> ::core::result::Result<(), #runtime::codec::CodecError>
+                where
+                    CursorType: #runtime::cursor::Cursor,
+                    CodecType: #runtime::codec::Codec<CursorType>,
+                {
+                    #body
+                }
+            }
+        },
+        Trait::Decode => quote! {
+            impl #impl_generics #runtime::encode::Decode for #name #ty_generics #where_clause {
+                fn decode<CursorType, CodecType>(
+                    codec: &mut CodecType,
+                ) -> ::core::result::Result<Self, #runtime::codec::CodecError>
+                where
+                    CursorType: #runtime::cursor::Cursor,
+                    CodecType: #runtime::codec::Codec<CursorType>,
+                {
+                    #body
+                }
+            }
+        },
+    }
+}
+
+fn erpc_attributes(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("erpc"))
+}
+
+/// Path of the `rust-erpc` crate, `::rust_erpc` unless overridden with `#[erpc(crate = path)]`.
+fn runtime_path(attrs: &[Attribute]) -> syn::Result<Path> {
+    let mut runtime = parse_quote!(::rust_erpc);
+    for attr in erpc_attributes(attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                runtime = meta.value()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unknown erpc container attribute"))
+            }
+        })?;
+    }
+    Ok(runtime)
+}
+
+fn fields(fields: &Fields) -> syn::Result<Vec<Field>> {
+    let mut result = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let (member, binding) = match &field.ident {
+            Some(ident) => (
+                Member::Named(ident.clone()),
+                format_ident!("field_{}", ident),
+            ),
+            None => (
+                Member::Unnamed(index.into()),
+                format_ident!("field_{}", index),
+            ),
+        };
+        let mut kind = Kind::Plain;
+        for attr in erpc_attributes(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if !matches!(kind, Kind::Plain) {
+                    return Err(meta.error("a field takes only one erpc attribute"));
+                }
+                if meta.path.is_ident("nullable") {
+                    if !is_option(&field.ty) {
+                        return Err(meta.error("`nullable` requires an `Option` field"));
+                    }
+                    kind = Kind::Nullable;
+                } else if meta.path.is_ident("length") {
+                    if !matches!(field.ty, Type::Array(_)) {
+                        return Err(meta.error("`length` requires an array field"));
+                    }
+                    kind = Kind::Length(meta.value()?.parse()?);
+                } else if meta.path.is_ident("discriminator") {
+                    kind = Kind::Discriminator(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown erpc field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        result.push(Field {
+            member,
+            binding,
+            ty: field.ty.clone(),
+            kind,
+            is_linked: false,
+        });
+    }
+    for index in 0..result.len() {
+        let target = match &result[index].kind {
+            Kind::Length(target) | Kind::Discriminator(target) => target.clone(),
+            _ => continue,
+        };
+        let linked = result
+            .iter()
+            .position(|field| matches!(&field.member, Member::Named(ident) if *ident == target))
+            .ok_or_else(|| Error::new(target.span(), format!("cannot find field `{}`", target)))?;
+        if linked == index
+            || !matches!(result[linked].kind, Kind::Plain)
+            || result[linked].is_linked
+        {
+            return Err(Error::new(
+                target.span(),
+                "the linked field must be another field without erpc attributes, linked only once",
+            ));
+        }
+        result[linked].is_linked = true;
+    }
+    Ok(result)
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Pattern destructuring `path` into the bindings of `fields`, also used to construct it.
+fn pattern(path: TokenStream2, shape: &Fields, fields: &[Field]) -> TokenStream2 {
+    let bindings = fields.iter().map(|field| &field.binding);
+    match shape {
+        Fields::Named(_) => {
+            let members = fields.iter().map(|field| &field.member);
+            quote!(#path { #(#members: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(#bindings),*)),
+        Fields::Unit => path,
+    }
+}
+
+fn find_binding<'a>(fields: &'a [Field], target: &Ident) -> &'a Ident {
+    &fields
+        .iter()
+        .find(|field| matches!(&field.member, Member::Named(ident) if ident == target))
+        .unwrap()
+        .binding
+}
+
+/// Statements encoding `fields`, whose bindings are references to the values.
+fn encode_fields(runtime: &Path, fields: &[Field]) -> TokenStream2 {
+    let statements = fields.iter().filter(|field| !field.is_linked).map(|field| {
+        let binding = &field.binding;
+        match &field.kind {
+            Kind::Plain | Kind::Discriminator(_) => quote! {
+                #runtime::encode::Encode::encode(#binding, codec)?;
+            },
+            Kind::Nullable => quote! {
+                codec.write_optional(#binding.as_ref())?;
+            },
+            Kind::Length(target) => {
+                let length = find_binding(fields, target);
+                quote! {
+                    let length = <usize as ::core::convert::TryFrom<_>>::try_from(*#length).map_err(|_| {
+                        #runtime::codec::CodecError::Cursor(#runtime::cursor::CursorError::InsufficientBuffer)
+                    })?;
+                    #runtime::encode::encode_prefix(&#binding[..], length, codec)?;
+                }
+            }
+        }
+    });
+    quote!(#(#statements)*)
+}
+
+/// Statements decoding `fields` into their bindings.
+fn decode_fields(runtime: &Path, fields: &[Field]) -> TokenStream2 {
+    let statements = fields.iter().filter(|field| !field.is_linked).map(|field| {
+        let binding = &field.binding;
+        let ty = &field.ty;
+        match &field.kind {
+            Kind::Plain => quote! {
+                let #binding: #ty = #runtime::encode::Decode::decode(codec)?;
+            },
+            Kind::Nullable => quote! {
+                let #binding: #ty = codec.read_optional()?;
+            },
+            Kind::Length(target) => {
+                let length = find_binding(fields, target);
+                quote! {
+                    let mut #binding: #ty = ::core::array::from_fn(|_| ::core::default::Default::default());
+                    let #length = ::core::convert::TryFrom::try_from(
+                        #runtime::encode::decode_prefix(&mut #binding[..], codec)?,
+                    )
+                    .map_err(|_| #runtime::codec::CodecError::LengthExceedsBuffer)?;
+                }
+            }
+            Kind::Discriminator(target) => {
+                let discriminator = find_binding(fields, target);
+                quote! {
+                    let #binding: #ty = #runtime::encode::Decode::decode(codec)?;
+                    let #discriminator = ::core::convert::TryFrom::try_from(
+                        #runtime::encode::Union::discriminator(&#binding),
+                    )
+                    .map_err(|_| #runtime::codec::CodecError::InvalidEnumValue)?;
+                }
+            }
+        }
+    });
+    quote!(#(#statements)*)
+}
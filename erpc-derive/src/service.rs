@@ -0,0 +1,365 @@
+//! `#[service]`: client proxy and `rpc::Service` dispatcher for a trait annotated with service and method ids.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::Parser;
+use syn::spanned::Spanned;
+use syn::{
+    Error, FnArg, Ident, ItemTrait, LitInt, Pat, Path, ReturnType, TraitItem, TraitItemFn, Type,
+};
+
+use crate::erpc_attributes;
+
+struct Method {
+    ident: Ident,
+    /// Name of the method id constant.
+    id_name: Ident,
+    id: u32,
+    is_oneway: bool,
+    arguments: Vec<(Ident, Type)>,
+    output: Option<Type>,
+    docs: Vec<syn::Attribute>,
+}
+
+pub fn expand(args: TokenStream2, mut item: ItemTrait) -> syn::Result<TokenStream2> {
+    let mut runtime: Path = syn::parse_quote!(::rust_erpc);
+    let mut service_id = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("id") {
+            service_id = Some(parse_id(&meta.value()?.parse()?)?);
+            Ok(())
+        } else if meta.path.is_ident("crate") {
+            runtime = meta.value()?.parse()?;
+            Ok(())
+        } else {
+            Err(meta.error("unknown erpc service attribute"))
+        }
+    });
+    parser.parse2(args)?;
+    let service_id = service_id.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "services need an id: `#[service(id = value)]`",
+        )
+    })?;
+    if !item.generics.params.is_empty() {
+        return Err(Error::new(
+            item.generics.span(),
+            "generic services are not supported",
+        ));
+    }
+
+    let mut methods: Vec<Method> = Vec::new();
+    // erpcgen numbers functions in declaration order, `@id` only overrides the number of the annotated one.
+    let mut counter = 0;
+    for trait_item in &mut item.items {
+        let function = match trait_item {
+            TraitItem::Fn(function) => function,
+            other => {
+                return Err(Error::new(
+                    other.span(),
+                    "services may only contain methods",
+                ))
+            }
+        };
+        counter += 1;
+        let method = method(function, counter)?;
+        if let Some(other) = methods.iter().find(|other| other.id == method.id) {
+            let message = format!(
+                "method id {} is already used by `{}`",
+                method.id, other.ident
+            );
+            return Err(Error::new(method.ident.span(), message));
+        }
+        function.attrs.retain(|attr| !attr.path().is_ident("erpc"));
+        methods.push(method);
+    }
+
+    let vis = &item.vis;
+    let name = &item.ident;
+    let module = format_ident!("{}", snake_case(&name.to_string()));
+    let ids = methods.iter().map(|method| {
+        let id_name = &method.id_name;
+        let id = method.id;
+        quote!(pub const #id_name: u32 = #id;)
+    });
+    let client_methods = methods.iter().map(|method| client_method(&runtime, method));
+    let dispatch_arms = methods.iter().map(|method| dispatch_arm(&runtime, method));
+    let client_doc = format!(
+        "Client proxy of [`{}`] calling through an [`rpc::Caller`], such as an [`rpc::Client`].",
+        name
+    );
+    let service_doc = format!(
+        "Dispatches invocations of [`{}`] to the handler, registered with a server such as [`rpc::SimpleServer`].",
+        name
+    );
+
+    Ok(quote! {
+        #item
+
+        #[allow(clippy::too_many_arguments)]
+        #vis mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+            use #runtime::codec::Codec;
+            use #runtime::cursor::Cursor;
+            use #runtime::request::RequestResponseError;
+            use #runtime::rpc;
+
+            pub const SERVICE_ID: u32 = #service_id;
+            #(#ids)*
+
+            #[doc = #client_doc]
+            pub struct Client<'client, CallerType> {
+                caller: &'client mut CallerType,
+            }
+
+            impl<'client, CallerType> Client<'client, CallerType> {
+                pub fn new(caller: &'client mut CallerType) -> Self {
+                    Self { caller }
+                }
+
+                #(#client_methods)*
+            }
+
+            #[doc = #service_doc]
+            pub struct Service<Handler> {
+                handler: Handler,
+            }
+
+            impl<Handler> Service<Handler> {
+                pub fn new(handler: Handler) -> Self {
+                    Self { handler }
+                }
+
+                pub fn handler(&mut self) -> &mut Handler {
+                    &mut self.handler
+                }
+
+                pub fn release(self) -> Handler {
+                    self.handler
+                }
+            }
+
+            impl<CursorType, CodecType, Handler> rpc::Service<CursorType, CodecType> for Service<Handler>
+            where
+                CursorType: Cursor,
+                CodecType: Codec<CursorType>,
+                Handler: #name,
+            {
+                fn get_service_id(&self) -> u32 {
+                    SERVICE_ID
+                }
+
+                fn handle_invocation(
+                    &mut self,
+                    method_id: u32,
+                    in_codec: &mut CodecType,
+                    out_codec: &mut CodecType,
+                ) -> Result<(), rpc::ServiceError> {
+                    match method_id {
+                        #(#dispatch_arms)*
+                        _ => return Err(rpc::ServiceError::UnknownMethod),
+                    }
+                    Ok(())
+                }
+            }
+        }
+    })
+}
+
+fn method(function: &TraitItemFn, counter: u32) -> syn::Result<Method> {
+    let signature = &function.sig;
+    let mut id = counter;
+    let mut is_oneway = false;
+    for attr in erpc_attributes(&function.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = parse_id(&meta.value()?.parse()?)?;
+                Ok(())
+            } else if meta.path.is_ident("oneway") {
+                is_oneway = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown erpc method attribute"))
+            }
+        })?;
+    }
+    if !signature.generics.params.is_empty() || signature.asyncness.is_some() {
+        return Err(Error::new(
+            signature.span(),
+            "service methods cannot be generic or async",
+        ));
+    }
+
+    let mut inputs = signature.inputs.iter();
+    match inputs.next() {
+        Some(FnArg::Receiver(receiver))
+            if receiver.reference.is_some() && receiver.mutability.is_some() => {}
+        _ => {
+            return Err(Error::new(
+                signature.span(),
+                "service methods must take `&mut self`",
+            ))
+        }
+    }
+    let mut arguments = Vec::new();
+    for input in inputs {
+        let argument = match input {
+            FnArg::Typed(argument) => argument,
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(receiver.span(), "unexpected receiver"))
+            }
+        };
+        let ident = match &*argument.pat {
+            Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat.ident.clone(),
+            pat => {
+                return Err(Error::new(
+                    pat.span(),
+                    "service method arguments must be plain identifiers",
+                ))
+            }
+        };
+        if let Type::Reference(_) = &*argument.ty {
+            return Err(Error::new(
+                argument.ty.span(),
+                "service method arguments must be owned to be decoded by the server",
+            ));
+        }
+        arguments.push((ident, (*argument.ty).clone()));
+    }
+    let output = match &signature.output {
+        ReturnType::Default => None,
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(tuple) if tuple.elems.is_empty() => None,
+            ty => Some(ty.clone()),
+        },
+    };
+    if is_oneway && output.is_some() {
+        return Err(Error::new(
+            signature.output.span(),
+            "oneway methods cannot return a value",
+        ));
+    }
+
+    let ident = signature.ident.clone();
+    Ok(Method {
+        id_name: format_ident!("{}_ID", ident.to_string().to_uppercase()),
+        ident,
+        id,
+        is_oneway,
+        arguments,
+        output,
+        docs: function
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .cloned()
+            .collect(),
+    })
+}
+
+fn parse_id(value: &LitInt) -> syn::Result<u32> {
+    match value.base10_parse::<u32>() {
+        // Ids are encoded in 8 bits of the message header.
+        Ok(id) if (1..=0xff).contains(&id) => Ok(id),
+        _ => Err(Error::new(value.span(), "ids must be between 1 and 255")),
+    }
+}
+
+fn client_method(runtime: &Path, method: &Method) -> TokenStream2 {
+    let Method {
+        ident,
+        id_name,
+        is_oneway,
+        arguments,
+        output,
+        docs,
+        ..
+    } = method;
+    let parameters = arguments.iter().map(|(name, ty)| quote!(#name: #ty));
+    let names = arguments.iter().map(|(name, _)| name);
+    let output_type = match output {
+        Some(ty) => quote!(#ty),
+        None => quote!(()),
+    };
+    let constructor = quote! {
+        |codec| {
+            #(#runtime::encode::Encode::encode(&#names, codec)?;)*
+            Ok(())
+        }
+    };
+    let call = if *is_oneway {
+        quote!(self.caller.call_oneway(SERVICE_ID, #id_name, #constructor))
+    } else {
+        let parser = match output {
+            Some(_) => quote!(|codec| #runtime::encode::Decode::decode(codec)),
+            None => quote!(|_| Ok(())),
+        };
+        quote!(self.caller.call(SERVICE_ID, #id_name, #constructor, #parser))
+    };
+    quote! {
+        #(#docs)*
+        #[allow(unused_variables)]
+        pub fn #ident<Error, CursorType, CodecType>(
+            &mut self,
+            #(#parameters),*
+        ) -> Result<#output_type, RequestResponseError<Error>>
+        where
+            CallerType: rpc::Caller<Error, CursorType, CodecType>,
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            #call
+        }
+    }
+}
+
+fn dispatch_arm(runtime: &Path, method: &Method) -> TokenStream2 {
+    let Method {
+        ident,
+        id_name,
+        arguments,
+        output,
+        ..
+    } = method;
+    let decode = arguments
+        .iter()
+        .map(|(name, ty)| quote!(let #name: #ty = #runtime::encode::Decode::decode(in_codec)?;));
+    let names = arguments.iter().map(|(name, _)| name);
+    match output {
+        Some(_) => quote! {
+            #id_name => {
+                #(#decode)*
+                let result = self.handler.#ident(#(#names),*);
+                #runtime::encode::Encode::encode(&result, out_codec)?;
+            }
+        },
+        None => quote! {
+            #id_name => {
+                #(#decode)*
+                self.handler.#ident(#(#names),*);
+            }
+        },
+    }
+}
+
+/// Converts a trait name such as `BleHost` to the name of its module, `ble_host`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
@@ -0,0 +1,187 @@
+use rust_erpc::codec::{BasicCodec, Codec, CodecError};
+use rust_erpc::cursor::SliceCursor;
+use rust_erpc::encode::{
+    Decode, Encode, ErpcDecode, ErpcEncode, ErpcMaxEncodedSize, MaxEncodedSize, Union,
+};
+use std::convert::TryFrom;
+
+/// `RPC_T_GAP_ROLE` of `rpc_ble_api`.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, ErpcEncode, ErpcDecode, ErpcMaxEncodedSize)]
+enum RPC_T_GAP_ROLE {
+    #[default]
+    RPC_GAP_LINK_ROLE_UNDEFINED,
+    RPC_GAP_LINK_ROLE_MASTER,
+    RPC_GAP_LINK_ROLE_SLAVE,
+}
+
+/// `RPC_T_LE_REMOTE_BD` of `rpc_ble_api`.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, ErpcEncode, ErpcDecode, ErpcMaxEncodedSize)]
+struct RPC_T_LE_REMOTE_BD {
+    addr: [u8; 6],
+    remote_bd_type: u8,
+    bond_flags: u8,
+}
+
+/// `RPC_T_LE_KEY_ENTRY` of `rpc_ble_api`.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, ErpcEncode, ErpcDecode, ErpcMaxEncodedSize)]
+struct RPC_T_LE_KEY_ENTRY {
+    is_used: bool,
+    idx: u8,
+    flags: u16,
+    local_bd_type: u8,
+    app_data: u8,
+    reserved: [u8; 2],
+    remote_bd: RPC_T_LE_REMOTE_BD,
+    resolved_remote_bd: RPC_T_LE_REMOTE_BD,
+}
+
+#[derive(Debug, Clone, PartialEq, ErpcEncode, ErpcDecode, ErpcMaxEncodedSize)]
+enum Shape {
+    #[erpc(case = 1)]
+    Circle { radius: f32 },
+    #[erpc(case = 2)]
+    Rectangle(u16, u16),
+    #[erpc(case = RPC_T_GAP_ROLE::RPC_GAP_LINK_ROLE_SLAVE as i32)]
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, ErpcEncode, ErpcDecode, ErpcMaxEncodedSize)]
+struct Record {
+    role: RPC_T_GAP_ROLE,
+    #[erpc(nullable)]
+    name: Option<heapless::String<8>>,
+    #[erpc(nullable)]
+    entry: Option<RPC_T_LE_REMOTE_BD>,
+    #[erpc(length = key_len)]
+    key: [u8; 4],
+    key_len: u8,
+    kind: u32,
+    values: heapless::Vec<i16, 4>,
+    #[erpc(discriminator = kind)]
+    shape: Shape,
+}
+
+#[derive(Debug, PartialEq, ErpcEncode, ErpcDecode, ErpcMaxEncodedSize)]
+struct Pair<T>(T, T);
+
+fn encode<T: Encode>(value: &T) -> Vec<u8> {
+    let mut buffer = [0u8; 128];
+    let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+    value.encode(&mut codec).unwrap();
+    let length = codec.detach().get_position();
+    buffer[..length].to_vec()
+}
+
+fn decode<T: Decode>(bytes: &[u8]) -> Result<T, CodecError> {
+    let mut buffer = bytes.to_vec();
+    let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+    T::decode(&mut codec)
+}
+
+#[test]
+fn encode_structs_like_erpcgen() {
+    let entry = RPC_T_LE_KEY_ENTRY {
+        is_used: true,
+        idx: 3,
+        flags: 0x0102,
+        local_bd_type: 1,
+        app_data: 0xaa,
+        reserved: [0, 0],
+        remote_bd: RPC_T_LE_REMOTE_BD {
+            addr: [1, 2, 3, 4, 5, 6],
+            remote_bd_type: 2,
+            bond_flags: 0x10,
+        },
+        resolved_remote_bd: RPC_T_LE_REMOTE_BD::default(),
+    };
+    // The byte sequence written by `RPC_T_LE_KEY_ENTRY._write` of the Python shim.
+    let bytes = [
+        1, 3, 0x02, 0x01, 1, 0xaa, 0, 0, 1, 2, 3, 4, 5, 6, 2, 0x10, 0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+    assert_eq!(encode(&entry), bytes);
+    assert_eq!(decode::<RPC_T_LE_KEY_ENTRY>(&bytes), Ok(entry));
+
+    assert_eq!(
+        encode(&RPC_T_GAP_ROLE::RPC_GAP_LINK_ROLE_SLAVE),
+        [2, 0, 0, 0]
+    );
+    assert_eq!(
+        decode::<RPC_T_GAP_ROLE>(&[3, 0, 0, 0]),
+        Err(CodecError::InvalidEnumValue)
+    );
+    assert_eq!(encode(&Pair(1u16, 2u16)), [1, 0, 2, 0]);
+}
+
+#[test]
+fn encode_nullable_length_and_union_fields() {
+    let record = Record {
+        role: RPC_T_GAP_ROLE::RPC_GAP_LINK_ROLE_MASTER,
+        name: Some(heapless::String::try_from("ab").unwrap()),
+        entry: None,
+        key: [9, 8, 0, 0],
+        key_len: 2,
+        kind: 2,
+        values: heapless::Vec::from_slice(&[-1]).unwrap(),
+        shape: Shape::Rectangle(3, 4),
+    };
+    let bytes = [
+        1, 0, 0, 0, // role
+        0, 2, 0, 0, 0, b'a', b'b', // name
+        1,    // entry
+        2, 0, 0, 0, 9, 8, // key, carrying key_len
+        // kind is encoded as the discriminator of shape
+        1, 0, 0, 0, 0xff, 0xff, // values
+        2, 0, 0, 0, 3, 0, 4, 0, // shape
+    ];
+    assert_eq!(encode(&record), bytes);
+    assert_eq!(decode::<Record>(&bytes), Ok(record));
+
+    assert_eq!(Shape::Empty.discriminator(), 2);
+    assert_eq!(
+        encode(&Shape::Circle { radius: 1.0 }),
+        [1, 0, 0, 0, 0, 0, 0x80, 0x3f]
+    );
+    assert_eq!(
+        decode::<Shape>(&[3, 0, 0, 0]),
+        Err(CodecError::InvalidEnumValue)
+    );
+}
+
+#[test]
+fn reject_lengths_beyond_arrays() {
+    let mut bytes = vec![0, 0, 0, 0, 1];
+    bytes.extend_from_slice(&[1, 5, 0, 0, 0]);
+    assert_eq!(
+        decode::<Record>(&bytes),
+        Err(CodecError::LengthExceedsBuffer)
+    );
+}
+
+#[test]
+fn max_encoded_size_covers_every_case() {
+    assert_eq!(RPC_T_GAP_ROLE::MAX_ENCODED_SIZE, 4);
+    assert_eq!(RPC_T_LE_KEY_ENTRY::MAX_ENCODED_SIZE, 24);
+    assert_eq!(Pair::<u16>::MAX_ENCODED_SIZE, 4);
+    // The discriminator and the largest case.
+    assert_eq!(Shape::MAX_ENCODED_SIZE, 8);
+    // The linked key_len and kind are not counted.
+    assert_eq!(
+        Record::MAX_ENCODED_SIZE,
+        4 + (1 + 12) + (1 + 8) + (4 + 4) + (4 + 8) + 8
+    );
+
+    let record = Record {
+        role: RPC_T_GAP_ROLE::RPC_GAP_LINK_ROLE_MASTER,
+        name: Some(heapless::String::try_from("12345678").unwrap()),
+        entry: Some(RPC_T_LE_REMOTE_BD::default()),
+        key: [1, 2, 3, 4],
+        key_len: 4,
+        kind: 1,
+        values: heapless::Vec::from_slice(&[1, 2, 3, 4]).unwrap(),
+        shape: Shape::Circle { radius: 1.0 },
+    };
+    assert_eq!(encode(&record).len(), Record::MAX_ENCODED_SIZE);
+}
@@ -0,0 +1,248 @@
+use rust_erpc::codec::BasicCodecFactory;
+use rust_erpc::encode::{ErpcDecode, ErpcEncode};
+use rust_erpc::framed_transport::{FramedTransport, FramedTransportError};
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc;
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+#[derive(Debug, Default, Clone, PartialEq, ErpcEncode, ErpcDecode)]
+pub struct Point {
+    x: i16,
+    y: i16,
+}
+
+#[rust_erpc::service(id = 3)]
+pub trait Geometry {
+    /// Moves `point` by `offset`.
+    fn translate(&mut self, point: Point, offset: i16) -> Point;
+    fn name(&mut self, index: u8) -> heapless::String<8>;
+    #[erpc(id = 10, oneway)]
+    fn reset(&mut self, points: heapless::Vec<Point, 4>);
+    fn ping(&mut self);
+}
+
+#[derive(Default)]
+struct Handler {
+    calls: Vec<String>,
+}
+
+impl Geometry for Handler {
+    fn translate(&mut self, point: Point, offset: i16) -> Point {
+        Point {
+            x: point.x + offset,
+            y: point.y + offset,
+        }
+    }
+
+    fn name(&mut self, index: u8) -> heapless::String<8> {
+        let mut name = heapless::String::new();
+        let _ = name.push_str(["zero", "one"][index as usize]);
+        name
+    }
+
+    fn reset(&mut self, points: heapless::Vec<Point, 4>) {
+        self.calls.push(format!("reset {:?}", points));
+    }
+
+    fn ping(&mut self) {
+        self.calls.push("ping".to_string());
+    }
+}
+
+/// Returns prepared messages and records sent ones.
+#[derive(Default)]
+struct MockTransport {
+    received: VecDeque<Vec<u8>>,
+    sent: Vec<Vec<u8>>,
+}
+
+impl FramedTransport<()> for MockTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sent.push(data.to_vec());
+        Ok(())
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        let message = self
+            .received
+            .pop_front()
+            .ok_or(FramedTransportError::UnderlyingError(()))?;
+        buffer[..message.len()].copy_from_slice(&message);
+        Ok(&buffer[..message.len()])
+    }
+}
+
+/// Transport exchanging messages with another thread.
+struct ChannelTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl FramedTransport<()> for ChannelTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sender
+            .send(data.to_vec())
+            .map_err(|_| FramedTransportError::UnderlyingError(()))
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        let message = self
+            .receiver
+            .recv()
+            .map_err(|_| FramedTransportError::UnderlyingError(()))?;
+        buffer[..message.len()].copy_from_slice(&message);
+        Ok(&buffer[..message.len()])
+    }
+}
+
+fn message(message_type: u32, request: u32, sequence: u32, payload: &[u8]) -> Vec<u8> {
+    let header = (1u32 << 24) | (3 << 16) | (request << 8) | message_type;
+    let mut message = header.to_le_bytes().to_vec();
+    message.extend_from_slice(&sequence.to_le_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+#[test]
+fn ids_follow_erpcgen_numbering() {
+    assert_eq!(geometry::SERVICE_ID, 3);
+    assert_eq!(geometry::TRANSLATE_ID, 1);
+    assert_eq!(geometry::NAME_ID, 2);
+    assert_eq!(geometry::RESET_ID, 10);
+    assert_eq!(geometry::PING_ID, 4);
+}
+
+#[test]
+fn client_encodes_requests_like_erpcgen() {
+    let transport = MockTransport {
+        received: vec![
+            message(2, 1, 1, &[5, 0, 6, 0]),
+            // Late reply to the first request.
+            message(2, 1, 1, &[]),
+            message(2, 2, 2, &[3, 0, 0, 0, b'o', b'n', b'e']),
+            message(2, 4, 4, &[]),
+            message(2, 1, 5, &[]),
+        ]
+        .into(),
+        sent: Vec::new(),
+    };
+    let mut buffer = [0u8; 64];
+    let mut caller = rpc::Client::new(transport, &mut buffer, BasicCodecFactory::new());
+    let mut client = geometry::Client::new(&mut caller);
+
+    assert_eq!(
+        client.translate(Point { x: 1, y: 2 }, 4),
+        Ok(Point { x: 5, y: 6 })
+    );
+    assert_eq!(client.name(1).unwrap().as_str(), "one");
+    let mut points = heapless::Vec::new();
+    let _ = points.push(Point { x: -1, y: 1 });
+    assert_eq!(client.reset(points), Ok(()));
+    assert_eq!(client.ping(), Ok(()));
+    assert_eq!(client.ping(), Err(RequestResponseError::InvalidResponse));
+
+    let (transport, _, _) = caller.release();
+    assert_eq!(transport.sent[0], message(0, 1, 1, &[1, 0, 2, 0, 4, 0]));
+    assert_eq!(transport.sent[1], message(0, 2, 2, &[1]));
+    assert_eq!(
+        transport.sent[2],
+        message(1, 10, 3, &[1, 0, 0, 0, 0xff, 0xff, 1, 0])
+    );
+    assert_eq!(transport.sent[3], message(0, 4, 4, &[]));
+}
+
+#[test]
+fn server_dispatches_to_handler() {
+    let transport = MockTransport {
+        received: vec![
+            message(0, 1, 1, &[1, 0, 2, 0, 4, 0]),
+            message(1, 10, 2, &[1, 0, 0, 0, 0xff, 0xff, 1, 0]),
+            message(0, 4, 3, &[]),
+            message(0, 2, 4, &[0]),
+            message(0, 7, 5, &[]),
+        ]
+        .into(),
+        sent: Vec::new(),
+    };
+    let mut service = geometry::Service::new(Handler::default());
+    let mut receive_buffer = [0u8; 64];
+    let mut send_buffer = [0u8; 64];
+    let mut server: rpc::SimpleServer<_, _, _, 1> = rpc::SimpleServer::new(
+        transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        BasicCodecFactory::new(),
+    );
+    assert!(server.add_service(&mut service).is_ok());
+    for _ in 0..4 {
+        assert_eq!(server.run_once(), Ok(()));
+    }
+    assert_eq!(server.run_once(), Err(RequestResponseError::InvalidRequest));
+
+    let (transport, _, _, _) = server.release();
+    assert_eq!(
+        service.release().calls,
+        ["reset [Point { x: -1, y: 1 }]", "ping"]
+    );
+    assert_eq!(
+        transport.sent,
+        [
+            message(2, 1, 1, &[5, 0, 6, 0]),
+            message(2, 4, 3, &[]),
+            message(2, 2, 4, &[4, 0, 0, 0, b'z', b'e', b'r', b'o']),
+        ]
+    );
+}
+
+#[test]
+fn client_and_server_round_trip() {
+    let (client_sender, server_receiver) = channel();
+    let (server_sender, client_receiver) = channel();
+    let server = std::thread::spawn(move || {
+        let transport = ChannelTransport {
+            sender: server_sender,
+            receiver: server_receiver,
+        };
+        let mut service = geometry::Service::new(Handler::default());
+        let mut receive_buffer = [0u8; 64];
+        let mut send_buffer = [0u8; 64];
+        let mut server: rpc::SimpleServer<_, _, _, 1> = rpc::SimpleServer::new(
+            transport,
+            &mut receive_buffer,
+            &mut send_buffer,
+            BasicCodecFactory::new(),
+        );
+        assert!(server.add_service(&mut service).is_ok());
+        for _ in 0..3 {
+            server.run_once().unwrap();
+        }
+        drop(server);
+        service.release().calls
+    });
+
+    let transport = ChannelTransport {
+        sender: client_sender,
+        receiver: client_receiver,
+    };
+    let mut buffer = [0u8; 64];
+    let mut caller = rpc::Client::new(transport, &mut buffer, BasicCodecFactory::new());
+    let mut client = geometry::Client::new(&mut caller);
+    assert_eq!(
+        client.translate(Point { x: -3, y: 7 }, 3),
+        Ok(Point { x: 0, y: 10 })
+    );
+    assert_eq!(client.reset(heapless::Vec::new()), Ok(()));
+    assert_eq!(client.ping(), Ok(()));
+    assert_eq!(server.join().unwrap(), ["reset []", "ping"]);
+}
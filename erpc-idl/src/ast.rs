@@ -0,0 +1,371 @@
+/// Byte range in the source text.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+    pub fn merge(self, other: Span) -> Span {
+        Span::new(
+            core::cmp::min(self.start, other.start),
+            core::cmp::max(self.end, other.end),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub name: Ident,
+    pub value: Option<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub program: Option<ProgramDecl>,
+    pub items: Vec<Item>,
+    pub span: Span,
+}
+
+impl Document {
+    pub fn imports(&self) -> impl Iterator<Item = &Import> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Import(import) => Some(import),
+            _ => None,
+        })
+    }
+    pub fn interfaces(&self) -> impl Iterator<Item = &InterfaceDef> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Interface(interface) => Some(interface),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramDecl {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub name: Ident,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Import(Import),
+    Const(ConstDef),
+    Enum(EnumDef),
+    Struct(StructDef),
+    Union(UnionDef),
+    Typedef(TypedefDef),
+    Interface(InterfaceDef),
+    CallbackType(FunctionDef),
+}
+
+impl Item {
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Import(item) => item.span,
+            Item::Const(item) => item.span,
+            Item::Enum(item) => item.span,
+            Item::Struct(item) => item.span,
+            Item::Union(item) => item.span,
+            Item::Typedef(item) => item.span,
+            Item::Interface(item) => item.span,
+            Item::CallbackType(item) => item.span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub path: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub ty: Type,
+    pub name: Ident,
+    pub value: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    /// Anonymous enums only introduce their members as constants.
+    pub name: Option<Ident>,
+    pub members: Vec<EnumMember>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumMember {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub name: Ident,
+    pub value: Option<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub name: Ident,
+    pub members: Vec<StructMember>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructMember {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub is_byref: bool,
+    pub ty: Type,
+    pub name: Ident,
+    pub span: Span,
+}
+
+/// A non-encapsulated union declared at the top level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnionDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub name: Ident,
+    pub cases: Vec<UnionCase>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnionCase {
+    pub labels: Vec<CaseLabel>,
+    pub members: Vec<StructMember>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseLabel {
+    Value(Expr),
+    Default(Span),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedefDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub ty: Type,
+    pub name: Ident,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub name: Ident,
+    pub members: Vec<InterfaceMember>,
+    pub span: Span,
+}
+
+impl InterfaceDef {
+    pub fn functions(&self) -> impl Iterator<Item = &FunctionDef> {
+        self.members.iter().filter_map(|member| match member {
+            InterfaceMember::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterfaceMember {
+    Function(FunctionDef),
+    /// `type name(params) -> return_type` declares a callback type.
+    CallbackType(FunctionDef),
+    /// `callback_type name(param_names)` declares a function implementing a callback type.
+    CallbackFunction(CallbackFunctionDef),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub is_oneway: bool,
+    pub name: Ident,
+    pub params: Vec<Param>,
+    pub return_type: Type,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallbackFunctionDef {
+    pub doc: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub callback_type: Ident,
+    pub name: Ident,
+    pub param_names: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Direction {
+    In,
+    Out,
+    InOut,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub annotations: Vec<Annotation>,
+    /// `None` when the direction is omitted, which erpcgen treats as `in`.
+    pub direction: Option<Direction>,
+    pub is_byref: bool,
+    pub ty: Type,
+    pub name: Option<Ident>,
+    pub span: Span,
+}
+
+impl Param {
+    pub fn direction(&self) -> Direction {
+        self.direction.unwrap_or(Direction::In)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeKind {
+    Void,
+    Builtin(BuiltinType),
+    List(Box<Type>),
+    Array(Box<Type>, Expr),
+    /// Reference to a struct, enum, union, typedef or callback type.
+    Named(Ident),
+    /// Encapsulated union inside a struct, discriminated by another member.
+    Union(InlineUnion),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineUnion {
+    pub discriminator: Ident,
+    pub cases: Vec<UnionCase>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum BuiltinType {
+    Bool,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float,
+    Double,
+    String,
+    UString,
+    Binary,
+}
+
+impl BuiltinType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => BuiltinType::Bool,
+            "int8" => BuiltinType::Int8,
+            "int16" => BuiltinType::Int16,
+            "int32" => BuiltinType::Int32,
+            "int64" => BuiltinType::Int64,
+            "uint8" => BuiltinType::UInt8,
+            "uint16" => BuiltinType::UInt16,
+            "uint32" => BuiltinType::UInt32,
+            "uint64" => BuiltinType::UInt64,
+            "float" => BuiltinType::Float,
+            "double" => BuiltinType::Double,
+            "string" => BuiltinType::String,
+            "ustring" => BuiltinType::UString,
+            "binary" => BuiltinType::Binary,
+            _ => return None,
+        })
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinType::Bool => "bool",
+            BuiltinType::Int8 => "int8",
+            BuiltinType::Int16 => "int16",
+            BuiltinType::Int32 => "int32",
+            BuiltinType::Int64 => "int64",
+            BuiltinType::UInt8 => "uint8",
+            BuiltinType::UInt16 => "uint16",
+            BuiltinType::UInt32 => "uint32",
+            BuiltinType::UInt64 => "uint64",
+            BuiltinType::Float => "float",
+            BuiltinType::Double => "double",
+            BuiltinType::String => "string",
+            BuiltinType::UString => "ustring",
+            BuiltinType::Binary => "binary",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Integer(u64),
+    Float(f64),
+    String(String),
+    Ident(Ident),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnaryOp {
+    Plus,
+    Negate,
+    Not,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Or,
+    Xor,
+}
+
+pub fn find_annotation<'a>(annotations: &'a [Annotation], name: &str) -> Option<&'a Annotation> {
+    annotations
+        .iter()
+        .find(|annotation| annotation.name.name == name)
+}
@@ -0,0 +1,124 @@
+use crate::ast::Span;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+        }
+    }
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Formats the diagnostic with its location and the offending source line, e.g.
+    ///
+    /// ```text
+    /// error: expected `;`, found `}`
+    ///  --> rpc_ble_api.erpc:3:5
+    ///   |
+    /// 3 |     int32 a }
+    ///   |             ^
+    /// ```
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let index = LineIndex::new(source);
+        let (line, column) = index.line_col(self.span.start);
+        let line_text = index.line_text(source, line);
+        let number = (line + 1).to_string();
+        let gutter = " ".repeat(number.len());
+        let underline_start = column;
+        let underline_length = if self.span.end > self.span.start {
+            core::cmp::min(
+                self.span.end - self.span.start,
+                line_text.len().saturating_sub(underline_start),
+            )
+        } else {
+            0
+        };
+        format!(
+            "{}: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}\n",
+            self.severity,
+            self.message,
+            gutter,
+            file_name,
+            line + 1,
+            column + 1,
+            gutter,
+            number,
+            line_text,
+            gutter,
+            " ".repeat(underline_start),
+            "^".repeat(core::cmp::max(underline_length, 1)),
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Maps byte offsets to zero-based line and column numbers.
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .char_indices()
+                .filter(|(_, c)| *c == '\n')
+                .map(|(index, _)| index + 1),
+        );
+        Self { line_starts }
+    }
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        (line, offset - self.line_starts[line])
+    }
+    pub fn line_text<'a>(&self, source: &'a str, line: usize) -> &'a str {
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map(|next| next - 1)
+            .unwrap_or_else(|| source.len());
+        source[start..end].trim_end_matches('\r')
+    }
+}
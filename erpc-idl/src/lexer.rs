@@ -0,0 +1,349 @@
+use crate::ast::Span;
+use crate::diagnostic::Diagnostic;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Punct {
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Lt,
+    Gt,
+    Semicolon,
+    Comma,
+    Colon,
+    Eq,
+    At,
+    Arrow,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Amp,
+    Pipe,
+    Caret,
+    Tilde,
+}
+
+impl Punct {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Punct::LBrace => "{",
+            Punct::RBrace => "}",
+            Punct::LParen => "(",
+            Punct::RParen => ")",
+            Punct::LBracket => "[",
+            Punct::RBracket => "]",
+            Punct::Lt => "<",
+            Punct::Gt => ">",
+            Punct::Semicolon => ";",
+            Punct::Comma => ",",
+            Punct::Colon => ":",
+            Punct::Eq => "=",
+            Punct::At => "@",
+            Punct::Arrow => "->",
+            Punct::Plus => "+",
+            Punct::Minus => "-",
+            Punct::Star => "*",
+            Punct::Slash => "/",
+            Punct::Percent => "%",
+            Punct::Amp => "&",
+            Punct::Pipe => "|",
+            Punct::Caret => "^",
+            Punct::Tilde => "~",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Ident(String),
+    Integer(u64),
+    Float(f64),
+    String(String),
+    Punct(Punct),
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(name) => write!(f, "`{}`", name),
+            TokenKind::Integer(value) => write!(f, "`{}`", value),
+            TokenKind::Float(value) => write!(f, "`{}`", value),
+            TokenKind::String(value) => write!(f, "{:?}", value),
+            TokenKind::Punct(punct) => write!(f, "`{}`", punct.as_str()),
+            TokenKind::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+    /// Doc comment (`/*! */`, `/** */`, `//!` or `///`) preceding the token.
+    pub doc: Option<String>,
+    /// Trailing doc comment (`//!<`, `///<`, `/*!< */` or `/**< */`) following the token.
+    pub trailing_doc: Option<String>,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>, Diagnostic> {
+    Lexer {
+        source,
+        bytes: source.as_bytes(),
+        position: 0,
+        tokens: Vec::new(),
+        pending_doc: None,
+    }
+    .run()
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    position: usize,
+    tokens: Vec<Token>,
+    pending_doc: Option<String>,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self, offset: usize) -> u8 {
+        self.bytes.get(self.position + offset).copied().unwrap_or(0)
+    }
+
+    fn run(mut self) -> Result<Vec<Token>, Diagnostic> {
+        loop {
+            self.skip_whitespace_and_comments()?;
+            let start = self.position;
+            if start >= self.bytes.len() {
+                self.push(TokenKind::Eof, start);
+                return Ok(self.tokens);
+            }
+            let c = self.peek(0);
+            let kind = if c.is_ascii_alphabetic() || c == b'_' {
+                while self.peek(0).is_ascii_alphanumeric() || self.peek(0) == b'_' {
+                    self.position += 1;
+                }
+                TokenKind::Ident(self.source[start..self.position].to_string())
+            } else if c.is_ascii_digit() || (c == b'.' && self.peek(1).is_ascii_digit()) {
+                self.number()?
+            } else if c == b'"' {
+                self.string()?
+            } else {
+                let (punct, length) = match (c, self.peek(1)) {
+                    (b'-', b'>') => (Punct::Arrow, 2),
+                    (b'{', _) => (Punct::LBrace, 1),
+                    (b'}', _) => (Punct::RBrace, 1),
+                    (b'(', _) => (Punct::LParen, 1),
+                    (b')', _) => (Punct::RParen, 1),
+                    (b'[', _) => (Punct::LBracket, 1),
+                    (b']', _) => (Punct::RBracket, 1),
+                    (b'<', _) => (Punct::Lt, 1),
+                    (b'>', _) => (Punct::Gt, 1),
+                    (b';', _) => (Punct::Semicolon, 1),
+                    (b',', _) => (Punct::Comma, 1),
+                    (b':', _) => (Punct::Colon, 1),
+                    (b'=', _) => (Punct::Eq, 1),
+                    (b'@', _) => (Punct::At, 1),
+                    (b'+', _) => (Punct::Plus, 1),
+                    (b'-', _) => (Punct::Minus, 1),
+                    (b'*', _) => (Punct::Star, 1),
+                    (b'/', _) => (Punct::Slash, 1),
+                    (b'%', _) => (Punct::Percent, 1),
+                    (b'&', _) => (Punct::Amp, 1),
+                    (b'|', _) => (Punct::Pipe, 1),
+                    (b'^', _) => (Punct::Caret, 1),
+                    (b'~', _) => (Punct::Tilde, 1),
+                    _ => {
+                        let ch = self.source[start..].chars().next().unwrap_or('\0');
+                        return Err(Diagnostic::error(
+                            format!("unexpected character `{}`", ch),
+                            Span::new(start, start + ch.len_utf8()),
+                        ));
+                    }
+                };
+                self.position += length;
+                TokenKind::Punct(punct)
+            };
+            self.push(kind, start);
+        }
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize) {
+        self.tokens.push(Token {
+            kind,
+            span: Span::new(start, self.position),
+            doc: self.pending_doc.take(),
+            trailing_doc: None,
+        });
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), Diagnostic> {
+        loop {
+            let c = self.peek(0);
+            if c.is_ascii_whitespace() {
+                self.position += 1;
+            } else if c == b'/' && self.peek(1) == b'/' {
+                let start = self.position;
+                while self.position < self.bytes.len() && self.peek(0) != b'\n' {
+                    self.position += 1;
+                }
+                self.comment(&self.source[start..self.position]);
+            } else if c == b'/' && self.peek(1) == b'*' {
+                let start = self.position;
+                self.position += 2;
+                loop {
+                    if self.position >= self.bytes.len() {
+                        return Err(Diagnostic::error(
+                            "unterminated block comment",
+                            Span::new(start, start + 2),
+                        ));
+                    }
+                    if self.peek(0) == b'*' && self.peek(1) == b'/' {
+                        self.position += 2;
+                        break;
+                    }
+                    self.position += 1;
+                }
+                self.comment(&self.source[start..self.position]);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn comment(&mut self, text: &str) {
+        let (body, is_block) = if let Some(body) = text.strip_prefix("/*") {
+            (body.strip_suffix("*/").unwrap_or(body), true)
+        } else {
+            (&text[2..], false)
+        };
+        let marker = match body.chars().next() {
+            Some('!') => 1,
+            Some('*') if is_block && !body.starts_with("*/") && body != "*" => 1,
+            Some('/') if !is_block => 1,
+            _ => return,
+        };
+        let body = &body[marker..];
+        let (body, is_trailing) = match body.strip_prefix('<') {
+            Some(body) => (body, true),
+            None => (body, false),
+        };
+        let text = clean_doc(body, is_block);
+        if is_trailing {
+            if let Some(token) = self.tokens.last_mut() {
+                append_doc(&mut token.trailing_doc, text);
+            }
+        } else {
+            append_doc(&mut self.pending_doc, text);
+        }
+    }
+
+    fn number(&mut self) -> Result<TokenKind, Diagnostic> {
+        let start = self.position;
+        let (radix, prefix) = match (self.peek(0), self.peek(1) | 0x20) {
+            (b'0', b'x') => (16, 2),
+            (b'0', b'b') if self.peek(2).is_ascii_digit() => (2, 2),
+            _ => (10, 0),
+        };
+        self.position += prefix;
+        let mut is_float = false;
+        loop {
+            let c = self.peek(0);
+            if c.is_ascii_hexdigit() && (radix == 16 || c.is_ascii_digit()) {
+                self.position += 1;
+            } else if radix == 10 && c == b'.' && !is_float {
+                is_float = true;
+                self.position += 1;
+            } else if radix == 10 && (c == b'e' || c == b'E') {
+                is_float = true;
+                self.position += 1;
+                if self.peek(0) == b'+' || self.peek(0) == b'-' {
+                    self.position += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let digits_end = self.position;
+        // Accept C style integer suffixes such as `10u` or `0x10UL`.
+        while matches!(self.peek(0), b'u' | b'U' | b'l' | b'L') && !is_float {
+            self.position += 1;
+        }
+        let span = Span::new(start, self.position);
+        let text = &self.source[start + prefix..digits_end];
+        if is_float {
+            text.parse::<f64>()
+                .map(TokenKind::Float)
+                .map_err(|_| Diagnostic::error("invalid floating point literal", span))
+        } else {
+            u64::from_str_radix(text, radix)
+                .map(TokenKind::Integer)
+                .map_err(|_| Diagnostic::error("invalid integer literal", span))
+        }
+    }
+
+    fn string(&mut self) -> Result<TokenKind, Diagnostic> {
+        let start = self.position;
+        self.position += 1;
+        let mut value = String::new();
+        loop {
+            let rest = &self.source[self.position..];
+            let c = match rest.chars().next() {
+                Some(c) if c != '\n' => c,
+                _ => {
+                    return Err(Diagnostic::error(
+                        "unterminated string literal",
+                        Span::new(start, self.position),
+                    ))
+                }
+            };
+            self.position += c.len_utf8();
+            match c {
+                '"' => return Ok(TokenKind::String(value)),
+                '\\' => {
+                    let escaped = self.source[self.position..].chars().next().unwrap_or('\0');
+                    self.position += escaped.len_utf8();
+                    value.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        '0' => '\0',
+                        other => other,
+                    });
+                }
+                c => value.push(c),
+            }
+        }
+    }
+}
+
+fn clean_doc(body: &str, is_block: bool) -> String {
+    let lines: Vec<&str> = body
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            if is_block {
+                line.strip_prefix('*').map(str::trim_start).unwrap_or(line)
+            } else {
+                line
+            }
+        })
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+fn append_doc(doc: &mut Option<String>, text: String) {
+    match doc {
+        Some(doc) => {
+            doc.push('\n');
+            doc.push_str(&text);
+        }
+        None => *doc = Some(text),
+    }
+}
@@ -0,0 +1,24 @@
+//! Parser for the erpc interface definition language (`.erpc` files) used by erpcgen.
+//!
+//! ```
+//! let document = erpc_idl::parse(
+//!     r#"
+//!     program rpc_ble_api
+//!
+//!     @id(2)
+//!     interface rpc_gap {
+//!         rpc_gap_set_param(in uint32 param, in binary value) -> uint32
+//!     }
+//!     "#,
+//! )
+//! .unwrap();
+//! assert_eq!(document.program.unwrap().name.name, "rpc_ble_api");
+//! ```
+
+pub mod ast;
+pub mod diagnostic;
+pub mod lexer;
+pub mod parser;
+
+pub use diagnostic::{Diagnostic, Severity};
+pub use parser::parse;
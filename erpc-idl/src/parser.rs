@@ -0,0 +1,1030 @@
+use crate::ast::*;
+use crate::diagnostic::Diagnostic;
+use crate::lexer::{tokenize, Punct, Token, TokenKind};
+
+type ParseResult<T> = Result<T, Diagnostic>;
+
+/// Parses an erpc IDL source into a [`Document`].
+///
+/// The parser recovers at declaration boundaries, so all syntax errors of the file are reported at once.
+pub fn parse(source: &str) -> Result<Document, Vec<Diagnostic>> {
+    let tokens = tokenize(source).map_err(|diagnostic| vec![diagnostic])?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        diagnostics: Vec::new(),
+    };
+    let document = parser.document();
+    if parser.diagnostics.is_empty() {
+        Ok(document)
+    } else {
+        Err(parser.diagnostics)
+    }
+}
+
+const ITEM_KEYWORDS: &[&str] = &[
+    "program",
+    "import",
+    "const",
+    "enum",
+    "struct",
+    "union",
+    "typedef",
+    "interface",
+    "type",
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        self.peek_nth(0)
+    }
+    fn peek_nth(&self, n: usize) -> &Token {
+        let index = core::cmp::min(self.position + n, self.tokens.len() - 1);
+        &self.tokens[index]
+    }
+    fn bump(&mut self) -> Token {
+        let token = self.peek().clone();
+        if self.position < self.tokens.len() - 1 {
+            self.position += 1;
+        }
+        token
+    }
+    fn previous_span(&self) -> Span {
+        if self.position == 0 {
+            Span::default()
+        } else {
+            self.tokens[self.position - 1].span
+        }
+    }
+
+    fn is_punct(&self, punct: Punct) -> bool {
+        self.peek().kind == TokenKind::Punct(punct)
+    }
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.peek_keyword(0, keyword)
+    }
+    fn peek_keyword(&self, n: usize, keyword: &str) -> bool {
+        matches!(&self.peek_nth(n).kind, TokenKind::Ident(name) if name == keyword)
+    }
+    fn eat_punct(&mut self, punct: Punct) -> bool {
+        if self.is_punct(punct) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        let token = self.peek();
+        Diagnostic::error(
+            format!("expected {}, found {}", expected, token.kind),
+            token.span,
+        )
+    }
+    fn expect_punct(&mut self, punct: Punct) -> ParseResult<Span> {
+        if self.is_punct(punct) {
+            Ok(self.bump().span)
+        } else {
+            Err(self.unexpected(&format!("`{}`", punct.as_str())))
+        }
+    }
+    fn expect_ident(&mut self, what: &str) -> ParseResult<Ident> {
+        match &self.peek().kind {
+            TokenKind::Ident(name) => {
+                let ident = Ident {
+                    name: name.clone(),
+                    span: self.peek().span,
+                };
+                self.bump();
+                Ok(ident)
+            }
+            _ => Err(self.unexpected(what)),
+        }
+    }
+    fn eat_terminator(&mut self) {
+        self.eat_punct(Punct::Semicolon);
+    }
+    /// Annotations following a top-level declaration belong to it only when a `;` terminates the declaration.
+    /// Otherwise they are left to annotate the next declaration.
+    fn trailing_item_annotations(&mut self) -> Vec<Annotation> {
+        let position = self.position;
+        match self.annotations() {
+            Ok(annotations) if self.peek().kind == TokenKind::Punct(Punct::Semicolon) => {
+                annotations
+            }
+            _ => {
+                self.position = position;
+                Vec::new()
+            }
+        }
+    }
+
+    fn leading_doc(&self) -> Option<String> {
+        self.peek().doc.clone()
+    }
+    /// Collects trailing doc comments attached to the tokens in `start..self.position`.
+    fn trailing_doc(&self, start: usize) -> Option<String> {
+        let docs: Vec<&str> = self.tokens[start..self.position]
+            .iter()
+            .filter_map(|token| token.trailing_doc.as_deref())
+            .collect();
+        if docs.is_empty() {
+            None
+        } else {
+            Some(docs.join("\n"))
+        }
+    }
+    fn member_doc(&self, leading: Option<String>, start: usize) -> Option<String> {
+        leading.or_else(|| self.trailing_doc(start))
+    }
+
+    /// Skips tokens until the start of the next top level declaration.
+    fn recover_item(&mut self, start: usize) {
+        let mut depth = self.tokens[start..self.position]
+            .iter()
+            .fold(0isize, |depth, token| match token.kind {
+                TokenKind::Punct(Punct::LBrace) => depth + 1,
+                TokenKind::Punct(Punct::RBrace) => depth - 1,
+                _ => depth,
+            });
+        loop {
+            match &self.peek().kind {
+                TokenKind::Eof => return,
+                TokenKind::Punct(Punct::LBrace) => depth += 1,
+                TokenKind::Punct(Punct::RBrace) => {
+                    depth -= 1;
+                    if depth <= 0 {
+                        self.bump();
+                        self.eat_terminator();
+                        return;
+                    }
+                }
+                TokenKind::Punct(Punct::Semicolon) if depth <= 0 => {
+                    self.bump();
+                    return;
+                }
+                TokenKind::Punct(Punct::At) if depth <= 0 && self.position != start => return,
+                TokenKind::Ident(name)
+                    if depth <= 0
+                        && self.position != start
+                        && ITEM_KEYWORDS.contains(&name.as_str()) =>
+                {
+                    return
+                }
+                _ => {}
+            }
+            self.bump();
+        }
+    }
+
+    fn document(&mut self) -> Document {
+        let mut program = None;
+        let mut items = Vec::new();
+        while self.peek().kind != TokenKind::Eof {
+            let start = self.position;
+            match self.item() {
+                Ok(ItemOrProgram::Program(decl)) => {
+                    if program.is_some() {
+                        self.diagnostics.push(Diagnostic::error(
+                            "duplicate `program` declaration",
+                            decl.span,
+                        ));
+                    } else {
+                        program = Some(decl);
+                    }
+                }
+                Ok(ItemOrProgram::Item(item)) => items.push(item),
+                Err(diagnostic) => {
+                    self.diagnostics.push(diagnostic);
+                    self.recover_item(start);
+                }
+            }
+        }
+        Document {
+            program,
+            items,
+            span: Span::new(0, self.peek().span.end),
+        }
+    }
+
+    fn item(&mut self) -> ParseResult<ItemOrProgram> {
+        let doc = self.leading_doc();
+        let start_span = self.peek().span;
+        let annotations = self.annotations()?;
+        let keyword = match &self.peek().kind {
+            TokenKind::Ident(name) => name.clone(),
+            _ => return Err(self.unexpected("a declaration")),
+        };
+        let item = match keyword.as_str() {
+            "program" => {
+                self.bump();
+                let name = self.expect_ident("a program name")?;
+                let mut annotations = annotations;
+                annotations.extend(self.trailing_item_annotations());
+                self.eat_terminator();
+                return Ok(ItemOrProgram::Program(ProgramDecl {
+                    doc,
+                    annotations,
+                    name,
+                    span: start_span.merge(self.previous_span()),
+                }));
+            }
+            "import" => {
+                self.bump();
+                let path = match &self.peek().kind {
+                    TokenKind::String(path) => path.clone(),
+                    _ => return Err(self.unexpected("an import path string")),
+                };
+                self.bump();
+                self.eat_terminator();
+                Item::Import(Import {
+                    path,
+                    span: start_span.merge(self.previous_span()),
+                })
+            }
+            "const" => {
+                self.bump();
+                let ty = self.type_spec()?;
+                let name = self.expect_ident("a constant name")?;
+                self.expect_punct(Punct::Eq)?;
+                let value = self.expr()?;
+                let mut annotations = annotations;
+                annotations.extend(self.trailing_item_annotations());
+                self.eat_terminator();
+                Item::Const(ConstDef {
+                    doc,
+                    annotations,
+                    ty,
+                    name,
+                    value,
+                    span: start_span.merge(self.previous_span()),
+                })
+            }
+            "enum" => {
+                self.bump();
+                let name = match &self.peek().kind {
+                    TokenKind::Ident(_) => Some(self.expect_ident("an enum name")?),
+                    _ => None,
+                };
+                let mut annotations = annotations;
+                annotations.extend(self.annotations()?);
+                let members = self.enum_members()?;
+                annotations.extend(self.trailing_item_annotations());
+                self.eat_terminator();
+                Item::Enum(EnumDef {
+                    doc,
+                    annotations,
+                    name,
+                    members,
+                    span: start_span.merge(self.previous_span()),
+                })
+            }
+            "struct" => {
+                self.bump();
+                let name = self.expect_ident("a struct name")?;
+                let mut annotations = annotations;
+                annotations.extend(self.annotations()?);
+                self.expect_punct(Punct::LBrace)?;
+                let mut members = Vec::new();
+                while !self.eat_punct(Punct::RBrace) {
+                    members.push(self.struct_member()?);
+                }
+                annotations.extend(self.trailing_item_annotations());
+                self.eat_terminator();
+                Item::Struct(StructDef {
+                    doc,
+                    annotations,
+                    name,
+                    members,
+                    span: start_span.merge(self.previous_span()),
+                })
+            }
+            "union" => {
+                self.bump();
+                let name = self.expect_ident("a union name")?;
+                let mut annotations = annotations;
+                annotations.extend(self.annotations()?);
+                let cases = self.union_body()?;
+                annotations.extend(self.trailing_item_annotations());
+                self.eat_terminator();
+                Item::Union(UnionDef {
+                    doc,
+                    annotations,
+                    name,
+                    cases,
+                    span: start_span.merge(self.previous_span()),
+                })
+            }
+            "typedef" => {
+                self.bump();
+                let ty = self.type_spec()?;
+                let name = self.expect_ident("a type name")?;
+                let mut annotations = annotations;
+                annotations.extend(self.trailing_item_annotations());
+                self.eat_terminator();
+                Item::Typedef(TypedefDef {
+                    doc,
+                    annotations,
+                    ty,
+                    name,
+                    span: start_span.merge(self.previous_span()),
+                })
+            }
+            "interface" => {
+                self.bump();
+                let name = self.expect_ident("an interface name")?;
+                let mut annotations = annotations;
+                annotations.extend(self.annotations()?);
+                self.expect_punct(Punct::LBrace)?;
+                let mut members = Vec::new();
+                while !self.eat_punct(Punct::RBrace) {
+                    members.push(self.interface_member()?);
+                }
+                annotations.extend(self.trailing_item_annotations());
+                self.eat_terminator();
+                Item::Interface(InterfaceDef {
+                    doc,
+                    annotations,
+                    name,
+                    members,
+                    span: start_span.merge(self.previous_span()),
+                })
+            }
+            "type" => {
+                self.bump();
+                let function = self.function(doc, annotations, false, start_span)?;
+                Item::CallbackType(function)
+            }
+            _ => return Err(self.unexpected("a declaration")),
+        };
+        Ok(ItemOrProgram::Item(item))
+    }
+
+    fn annotations(&mut self) -> ParseResult<Vec<Annotation>> {
+        let mut annotations = Vec::new();
+        while self.is_punct(Punct::At) {
+            let start = self.bump().span;
+            let name = self.expect_ident("an annotation name")?;
+            let value = if self.eat_punct(Punct::LParen) {
+                if self.eat_punct(Punct::RParen) {
+                    None
+                } else {
+                    let value = self.expr()?;
+                    self.expect_punct(Punct::RParen)?;
+                    Some(value)
+                }
+            } else {
+                None
+            };
+            annotations.push(Annotation {
+                name,
+                value,
+                span: start.merge(self.previous_span()),
+            });
+        }
+        Ok(annotations)
+    }
+
+    fn enum_members(&mut self) -> ParseResult<Vec<EnumMember>> {
+        self.expect_punct(Punct::LBrace)?;
+        let mut members = Vec::new();
+        while !self.eat_punct(Punct::RBrace) {
+            let doc = self.leading_doc();
+            let start = self.position;
+            let mut annotations = self.annotations()?;
+            let name = self.expect_ident("an enum member name")?;
+            let value = if self.eat_punct(Punct::Eq) {
+                Some(self.expr()?)
+            } else {
+                None
+            };
+            annotations.extend(self.annotations()?);
+            let span = name.span.merge(self.previous_span());
+            if !self.eat_punct(Punct::Comma) && !self.is_punct(Punct::RBrace) {
+                return Err(self.unexpected("`,` or `}`"));
+            }
+            members.push(EnumMember {
+                doc: self.member_doc(doc, start),
+                annotations,
+                name,
+                value,
+                span,
+            });
+        }
+        Ok(members)
+    }
+
+    fn struct_member(&mut self) -> ParseResult<StructMember> {
+        let doc = self.leading_doc();
+        let start = self.position;
+        let start_span = self.peek().span;
+        let mut annotations = self.annotations()?;
+        let is_byref = self.eat_keyword("byref");
+        let ty = self.type_spec()?;
+        let name = self.expect_ident("a member name")?;
+        annotations.extend(self.annotations()?);
+        let span = start_span.merge(self.previous_span());
+        self.eat_terminator();
+        Ok(StructMember {
+            doc: self.member_doc(doc, start),
+            annotations,
+            is_byref,
+            ty,
+            name,
+            span,
+        })
+    }
+
+    fn union_body(&mut self) -> ParseResult<Vec<UnionCase>> {
+        self.expect_punct(Punct::LBrace)?;
+        let mut cases = Vec::new();
+        while !self.eat_punct(Punct::RBrace) {
+            let start_span = self.peek().span;
+            let mut labels = Vec::new();
+            if self.eat_keyword("default") {
+                labels.push(CaseLabel::Default(self.previous_span()));
+            } else if self.eat_keyword("case") {
+                loop {
+                    labels.push(CaseLabel::Value(self.expr()?));
+                    if !self.eat_punct(Punct::Comma) {
+                        break;
+                    }
+                }
+            } else {
+                return Err(self.unexpected("`case` or `default`"));
+            }
+            self.expect_punct(Punct::Colon)?;
+            let mut members = Vec::new();
+            if self.eat_keyword("void") {
+                self.eat_terminator();
+            } else {
+                while !self.is_keyword("case")
+                    && !self.is_keyword("default")
+                    && !self.is_punct(Punct::RBrace)
+                {
+                    members.push(self.struct_member()?);
+                }
+            }
+            cases.push(UnionCase {
+                labels,
+                members,
+                span: start_span.merge(self.previous_span()),
+            });
+        }
+        Ok(cases)
+    }
+
+    fn interface_member(&mut self) -> ParseResult<InterfaceMember> {
+        let doc = self.leading_doc();
+        let start_span = self.peek().span;
+        let annotations = self.annotations()?;
+        if self.eat_keyword("type") {
+            let function = self.function(doc, annotations, false, start_span)?;
+            return Ok(InterfaceMember::CallbackType(function));
+        }
+        let is_oneway = self.eat_keyword("oneway");
+        if !is_oneway {
+            if let (TokenKind::Ident(_), TokenKind::Ident(_)) =
+                (&self.peek().kind, &self.peek_nth(1).kind)
+            {
+                return self.callback_function(doc, annotations, start_span);
+            }
+        }
+        let function = self.function(doc, annotations, is_oneway, start_span)?;
+        Ok(InterfaceMember::Function(function))
+    }
+
+    fn callback_function(
+        &mut self,
+        doc: Option<String>,
+        mut annotations: Vec<Annotation>,
+        start_span: Span,
+    ) -> ParseResult<InterfaceMember> {
+        let callback_type = self.expect_ident("a callback type name")?;
+        let name = self.expect_ident("a function name")?;
+        let mut param_names = Vec::new();
+        if self.eat_punct(Punct::LParen) {
+            while !self.eat_punct(Punct::RParen) {
+                param_names.push(self.expect_ident("a parameter name")?);
+                if !self.eat_punct(Punct::Comma) && !self.is_punct(Punct::RParen) {
+                    return Err(self.unexpected("`,` or `)`"));
+                }
+            }
+        }
+        annotations.extend(self.annotations()?);
+        let span = start_span.merge(self.previous_span());
+        self.eat_terminator();
+        Ok(InterfaceMember::CallbackFunction(CallbackFunctionDef {
+            doc,
+            annotations,
+            callback_type,
+            name,
+            param_names,
+            span,
+        }))
+    }
+
+    fn function(
+        &mut self,
+        doc: Option<String>,
+        mut annotations: Vec<Annotation>,
+        is_oneway: bool,
+        start_span: Span,
+    ) -> ParseResult<FunctionDef> {
+        let name = self.expect_ident("a function name")?;
+        self.expect_punct(Punct::LParen)?;
+        let mut params = Vec::new();
+        if self.is_keyword("void") && self.peek_nth(1).kind == TokenKind::Punct(Punct::RParen) {
+            self.bump();
+        }
+        while !self.eat_punct(Punct::RParen) {
+            params.push(self.param()?);
+            if !self.eat_punct(Punct::Comma) && !self.is_punct(Punct::RParen) {
+                return Err(self.unexpected("`,` or `)`"));
+            }
+        }
+        let return_type = if self.eat_punct(Punct::Arrow) {
+            if is_oneway {
+                return Err(Diagnostic::error(
+                    "oneway functions cannot declare a return type",
+                    self.previous_span(),
+                ));
+            }
+            self.type_spec()?
+        } else {
+            Type {
+                kind: TypeKind::Void,
+                span: self.previous_span(),
+            }
+        };
+        annotations.extend(self.annotations()?);
+        let span = start_span.merge(self.previous_span());
+        self.eat_terminator();
+        Ok(FunctionDef {
+            doc,
+            annotations,
+            is_oneway,
+            name,
+            params,
+            return_type,
+            span,
+        })
+    }
+
+    fn param(&mut self) -> ParseResult<Param> {
+        let start_span = self.peek().span;
+        let mut annotations = self.annotations()?;
+        let direction = if self.eat_keyword("in") {
+            Some(Direction::In)
+        } else if self.eat_keyword("out") {
+            Some(Direction::Out)
+        } else if self.eat_keyword("inout") {
+            Some(Direction::InOut)
+        } else {
+            None
+        };
+        let is_byref = self.eat_keyword("byref");
+        let ty = self.type_spec()?;
+        let name = match &self.peek().kind {
+            TokenKind::Ident(_) => Some(self.expect_ident("a parameter name")?),
+            _ => None,
+        };
+        annotations.extend(self.annotations()?);
+        Ok(Param {
+            annotations,
+            direction,
+            is_byref,
+            ty,
+            name,
+            span: start_span.merge(self.previous_span()),
+        })
+    }
+
+    fn type_spec(&mut self) -> ParseResult<Type> {
+        let start = self.peek().span;
+        let name = self.expect_ident("a type")?;
+        let kind = if name.name == "void" {
+            TypeKind::Void
+        } else if name.name == "list" {
+            self.expect_punct(Punct::Lt)?;
+            let element = self.type_spec()?;
+            self.expect_punct(Punct::Gt)?;
+            TypeKind::List(Box::new(element))
+        } else if name.name == "union" {
+            self.expect_punct(Punct::LParen)?;
+            let discriminator = self.expect_ident("a discriminator member name")?;
+            self.expect_punct(Punct::RParen)?;
+            let cases = self.union_body()?;
+            TypeKind::Union(InlineUnion {
+                discriminator,
+                cases,
+            })
+        } else if let Some(builtin) = BuiltinType::from_name(&name.name) {
+            TypeKind::Builtin(builtin)
+        } else {
+            TypeKind::Named(name)
+        };
+        let mut ty = Type {
+            kind,
+            span: start.merge(self.previous_span()),
+        };
+        // `int32[2][3]` is an array of two `int32[3]`, as in erpcgen.
+        let mut dimensions = Vec::new();
+        while self.eat_punct(Punct::LBracket) {
+            dimensions.push(self.expr()?);
+            self.expect_punct(Punct::RBracket)?;
+        }
+        for dimension in dimensions.into_iter().rev() {
+            ty = Type {
+                kind: TypeKind::Array(Box::new(ty), dimension),
+                span: start.merge(self.previous_span()),
+            };
+        }
+        Ok(ty)
+    }
+
+    fn expr(&mut self) -> ParseResult<Expr> {
+        self.binary_expr(0)
+    }
+
+    /// Returns the binary operator at the current position, its precedence and its token length.
+    fn peek_binary_op(&self) -> Option<(BinaryOp, u8, usize)> {
+        let adjacent = |punct: Punct| {
+            self.peek_nth(1).kind == TokenKind::Punct(punct)
+                && self.peek_nth(1).span.start == self.peek().span.end
+        };
+        let op = match &self.peek().kind {
+            TokenKind::Punct(Punct::Pipe) => (BinaryOp::Or, 1, 1),
+            TokenKind::Punct(Punct::Caret) => (BinaryOp::Xor, 2, 1),
+            TokenKind::Punct(Punct::Amp) => (BinaryOp::And, 3, 1),
+            TokenKind::Punct(Punct::Lt) if adjacent(Punct::Lt) => (BinaryOp::ShiftLeft, 4, 2),
+            TokenKind::Punct(Punct::Gt) if adjacent(Punct::Gt) => (BinaryOp::ShiftRight, 4, 2),
+            TokenKind::Punct(Punct::Plus) => (BinaryOp::Add, 5, 1),
+            TokenKind::Punct(Punct::Minus) => (BinaryOp::Subtract, 5, 1),
+            TokenKind::Punct(Punct::Star) => (BinaryOp::Multiply, 6, 1),
+            TokenKind::Punct(Punct::Slash) => (BinaryOp::Divide, 6, 1),
+            TokenKind::Punct(Punct::Percent) => (BinaryOp::Modulo, 6, 1),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn binary_expr(&mut self, min_precedence: u8) -> ParseResult<Expr> {
+        let mut lhs = self.unary_expr()?;
+        while let Some((op, precedence, length)) = self.peek_binary_op() {
+            if precedence <= min_precedence {
+                break;
+            }
+            for _ in 0..length {
+                self.bump();
+            }
+            let rhs = self.binary_expr(precedence)?;
+            let span = lhs.span.merge(rhs.span);
+            lhs = Expr {
+                kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+                span,
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn unary_expr(&mut self) -> ParseResult<Expr> {
+        let op = match &self.peek().kind {
+            TokenKind::Punct(Punct::Plus) => Some(UnaryOp::Plus),
+            TokenKind::Punct(Punct::Minus) => Some(UnaryOp::Negate),
+            TokenKind::Punct(Punct::Tilde) => Some(UnaryOp::Not),
+            _ => None,
+        };
+        if let Some(op) = op {
+            let start = self.bump().span;
+            let operand = self.unary_expr()?;
+            let span = start.merge(operand.span);
+            return Ok(Expr {
+                kind: ExprKind::Unary(op, Box::new(operand)),
+                span,
+            });
+        }
+        self.primary_expr()
+    }
+
+    fn primary_expr(&mut self) -> ParseResult<Expr> {
+        let token = self.peek().clone();
+        let kind = match token.kind {
+            TokenKind::Integer(value) => ExprKind::Integer(value),
+            TokenKind::Float(value) => ExprKind::Float(value),
+            TokenKind::String(value) => ExprKind::String(value),
+            TokenKind::Ident(name) => ExprKind::Ident(Ident {
+                name,
+                span: token.span,
+            }),
+            TokenKind::Punct(Punct::LParen) => {
+                self.bump();
+                let inner = self.expr()?;
+                let end = self.expect_punct(Punct::RParen)?;
+                return Ok(Expr {
+                    kind: inner.kind,
+                    span: token.span.merge(end),
+                });
+            }
+            _ => return Err(self.unexpected("an expression")),
+        };
+        self.bump();
+        Ok(Expr {
+            kind,
+            span: token.span,
+        })
+    }
+}
+
+enum ItemOrProgram {
+    Program(ProgramDecl),
+    Item(Item),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+/*! BLE API */
+@output_dir("erpc_shim/")
+program rpc_ble_api;
+
+import "rpc_ble_common.erpc"
+
+const uint8 GAP_BD_ADDR_LEN = 6;
+const int32 MASK = (1 << 4) | 0x0f & ~2;
+
+/*! @brief GAP Cause List */
+enum RPC_T_GAP_CAUSE {
+    RPC_GAP_CAUSE_SUCCESS = 0, //!< Operation success.
+    RPC_GAP_CAUSE_ALREADY_IN_REQ, //!< Operation already in progress.
+    RPC_GAP_CAUSE_ERROR_UNKNOWN = 0xFF //!< Unknown error.
+}
+
+enum { ANONYMOUS_A = -1, ANONYMOUS_B }
+
+struct RPC_T_GAP_CONN_INFO {
+    RPC_T_GAP_CONN_STATE conn_state; //!< Connection state.
+    uint8[GAP_BD_ADDR_LEN] remote_bd;
+    list<uint16> handles @length(handle_count);
+    uint32 handle_count
+    byref RPC_T_LE_REMOTE_BD remote @nullable;
+    int32 kind;
+    union(kind) {
+        case 0, 1:
+            int32 a;
+        case ANONYMOUS_B:
+            void
+        default:
+            float b;
+            float c;
+    } data;
+    uint8[2][3] matrix;
+}
+
+union Value {
+    case 1: uint8 small;
+    default: uint32 large;
+}
+
+typedef list<binary> binary_list_t;
+
+type notify_t(in uint8 event) -> void
+
+@id(2)
+interface rpc_gap {
+    rpc_gap_set_param(in RPC_T_GAP_PARAM_TYPE param, in binary value) -> RPC_T_GAP_CAUSE
+    rpc_gap_get_param(in RPC_T_GAP_PARAM_TYPE param, out binary value) -> RPC_T_GAP_CAUSE @id(5);
+    oneway rpc_gap_notify(uint8 event, inout byref RPC_T_GAP_CONN_INFO info @nullable)
+    type callback_t(int32, out string) -> bool
+    callback_t on_event(code, message)
+    rpc_gap_set_pairable_mode(void) -> RPC_T_GAP_CAUSE
+}
+"#;
+
+    #[test]
+    fn parse_all_constructs() {
+        let document = parse(SOURCE).unwrap_or_else(|diagnostics| {
+            panic!(
+                "{}",
+                diagnostics
+                    .iter()
+                    .map(|d| d.render("test.erpc", SOURCE))
+                    .collect::<String>()
+            )
+        });
+
+        let program = document.program.as_ref().unwrap();
+        assert_eq!(program.name.name, "rpc_ble_api");
+        assert_eq!(program.doc.as_deref(), Some("BLE API"));
+        assert_eq!(program.annotations[0].name.name, "output_dir");
+        assert_eq!(
+            program.annotations[0].value.as_ref().unwrap().kind,
+            ExprKind::String("erpc_shim/".to_string())
+        );
+        assert_eq!(
+            &SOURCE[program.name.span.start..program.name.span.end],
+            "rpc_ble_api"
+        );
+
+        let imports: Vec<_> = document.imports().collect();
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].path, "rpc_ble_common.erpc");
+
+        let mut items = document.items.iter().skip(1);
+        match items.next().unwrap() {
+            Item::Const(constant) => {
+                assert_eq!(constant.name.name, "GAP_BD_ADDR_LEN");
+                assert_eq!(constant.ty.kind, TypeKind::Builtin(BuiltinType::UInt8));
+                assert_eq!(constant.value.kind, ExprKind::Integer(6));
+            }
+            item => panic!("unexpected item {:?}", item),
+        }
+        match items.next().unwrap() {
+            Item::Const(constant) => match &constant.value.kind {
+                ExprKind::Binary(BinaryOp::Or, lhs, rhs) => {
+                    assert!(matches!(
+                        lhs.kind,
+                        ExprKind::Binary(BinaryOp::ShiftLeft, _, _)
+                    ));
+                    assert!(matches!(rhs.kind, ExprKind::Binary(BinaryOp::And, _, _)));
+                }
+                kind => panic!("unexpected expression {:?}", kind),
+            },
+            item => panic!("unexpected item {:?}", item),
+        }
+        match items.next().unwrap() {
+            Item::Enum(enumeration) => {
+                assert_eq!(enumeration.name.as_ref().unwrap().name, "RPC_T_GAP_CAUSE");
+                assert_eq!(enumeration.doc.as_deref(), Some("@brief GAP Cause List"));
+                let docs: Vec<_> = enumeration
+                    .members
+                    .iter()
+                    .map(|member| member.doc.as_deref().unwrap())
+                    .collect();
+                assert_eq!(
+                    docs,
+                    [
+                        "Operation success.",
+                        "Operation already in progress.",
+                        "Unknown error."
+                    ]
+                );
+                assert_eq!(enumeration.members[1].value, None);
+                assert_eq!(
+                    enumeration.members[2].value.as_ref().unwrap().kind,
+                    ExprKind::Integer(0xff)
+                );
+            }
+            item => panic!("unexpected item {:?}", item),
+        }
+        match items.next().unwrap() {
+            Item::Enum(enumeration) => {
+                assert!(enumeration.name.is_none());
+                assert!(matches!(
+                    enumeration.members[0].value.as_ref().unwrap().kind,
+                    ExprKind::Unary(UnaryOp::Negate, _)
+                ));
+            }
+            item => panic!("unexpected item {:?}", item),
+        }
+        match items.next().unwrap() {
+            Item::Struct(structure) => {
+                let members = &structure.members;
+                assert_eq!(members.len(), 8);
+                assert_eq!(members[0].doc.as_deref(), Some("Connection state."));
+                match &members[1].ty.kind {
+                    TypeKind::Array(element, length) => {
+                        assert_eq!(element.kind, TypeKind::Builtin(BuiltinType::UInt8));
+                        assert!(
+                            matches!(&length.kind, ExprKind::Ident(id) if id.name == "GAP_BD_ADDR_LEN")
+                        );
+                    }
+                    kind => panic!("unexpected type {:?}", kind),
+                }
+                assert!(matches!(members[2].ty.kind, TypeKind::List(_)));
+                assert!(find_annotation(&members[2].annotations, "length").is_some());
+                assert!(members[4].is_byref);
+                assert!(find_annotation(&members[4].annotations, "nullable").is_some());
+                match &members[6].ty.kind {
+                    TypeKind::Union(union) => {
+                        assert_eq!(union.discriminator.name, "kind");
+                        assert_eq!(union.cases.len(), 3);
+                        assert_eq!(union.cases[0].labels.len(), 2);
+                        assert!(union.cases[1].members.is_empty());
+                        assert!(matches!(union.cases[2].labels[0], CaseLabel::Default(_)));
+                        assert_eq!(union.cases[2].members.len(), 2);
+                    }
+                    kind => panic!("unexpected type {:?}", kind),
+                }
+                match &members[7].ty.kind {
+                    TypeKind::Array(outer, length) => {
+                        assert_eq!(length.kind, ExprKind::Integer(2));
+                        assert!(
+                            matches!(&outer.kind, TypeKind::Array(_, length) if length.kind == ExprKind::Integer(3))
+                        );
+                    }
+                    kind => panic!("unexpected type {:?}", kind),
+                }
+            }
+            item => panic!("unexpected item {:?}", item),
+        }
+        assert!(matches!(items.next().unwrap(), Item::Union(union) if union.cases.len() == 2));
+        match items.next().unwrap() {
+            Item::Typedef(typedef) => assert!(
+                matches!(&typedef.ty.kind, TypeKind::List(element) if element.kind == TypeKind::Builtin(BuiltinType::Binary))
+            ),
+            item => panic!("unexpected item {:?}", item),
+        }
+        assert!(
+            matches!(items.next().unwrap(), Item::CallbackType(callback) if callback.name.name == "notify_t")
+        );
+        match items.next().unwrap() {
+            Item::Interface(interface) => {
+                assert_eq!(interface.name.name, "rpc_gap");
+                assert_eq!(interface.members.len(), 6);
+                let functions: Vec<_> = interface.functions().collect();
+                assert_eq!(functions.len(), 4);
+                assert_eq!(functions[0].params[1].direction(), Direction::In);
+                assert_eq!(functions[1].params[1].direction, Some(Direction::Out));
+                assert!(find_annotation(&functions[1].annotations, "id").is_some());
+                assert!(functions[2].is_oneway);
+                assert_eq!(functions[2].return_type.kind, TypeKind::Void);
+                assert!(functions[2].params[1].is_byref);
+                assert!(functions[3].params.is_empty());
+                match &interface.members[3] {
+                    InterfaceMember::CallbackType(callback) => {
+                        assert!(callback.params[0].name.is_none());
+                        assert_eq!(callback.params[1].direction(), Direction::Out);
+                    }
+                    member => panic!("unexpected member {:?}", member),
+                }
+                match &interface.members[4] {
+                    InterfaceMember::CallbackFunction(function) => {
+                        assert_eq!(function.callback_type.name, "callback_t");
+                        assert_eq!(function.name.name, "on_event");
+                        assert_eq!(function.param_names.len(), 2);
+                    }
+                    member => panic!("unexpected member {:?}", member),
+                }
+            }
+            item => panic!("unexpected item {:?}", item),
+        }
+        assert!(items.next().is_none());
+    }
+
+    #[test]
+    fn attach_annotations_between_items() {
+        let source = "struct a { int32 x } @external;\nstruct b { int32 y }\n@id(3) interface c { f() -> void }";
+        let document = parse(source).unwrap();
+        let annotations: Vec<_> = document
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Struct(structure) => &structure.annotations,
+                Item::Interface(interface) => &interface.annotations,
+                item => panic!("unexpected item {:?}", item),
+            })
+            .collect();
+        assert_eq!(annotations[0][0].name.name, "external");
+        assert!(annotations[1].is_empty());
+        assert_eq!(annotations[2][0].name.name, "id");
+    }
+
+    #[test]
+    fn report_errors_with_spans() {
+        let source = "struct A {\n    int32 a\n    int32 = 5;\n}\nenum B { X Y }\ninterface C { f(in int32 a) -> int32 }\n";
+        let diagnostics = parse(source).unwrap_err();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "expected a member name, found `=`");
+        assert_eq!(
+            diagnostics[0].render("test.erpc", source),
+            "error: expected a member name, found `=`\n --> test.erpc:3:11\n  |\n3 |     int32 = 5;\n  |           ^\n"
+        );
+        assert_eq!(diagnostics[1].message, "expected `,` or `}`, found `Y`");
+    }
+
+    #[test]
+    fn report_lexer_errors() {
+        let diagnostics = parse("const int32 a = 1;\nconst string s = \"abc").unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unterminated string literal");
+        assert_eq!(diagnostics[0].span.start, 36);
+    }
+}
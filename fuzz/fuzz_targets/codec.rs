@@ -0,0 +1,30 @@
+//! Decodes arbitrary data with reads chosen by the data itself. Every read must fail instead of panicking.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_erpc::codec::{BasicCodec, Codec};
+use rust_erpc::cursor::SliceCursor;
+use rust_erpc::encode::Decode;
+
+fuzz_target!(|data: &[u8]| {
+    let mut data = data.to_vec();
+    let mut codec = BasicCodec::new(SliceCursor::new(&mut data));
+    let mut buffer = [0u8; 64];
+    while let Ok(operation) = codec.read_u8() {
+        let _ = match operation % 12 {
+            0 => codec.read_bool().map(drop),
+            1 => codec.read_i16().map(drop),
+            2 => codec.read_u32().map(drop),
+            3 => codec.read_f64().map(drop),
+            4 => codec.read_str(&mut buffer).map(drop),
+            5 => codec.read_binary(&mut buffer).map(drop),
+            6 => codec.start_read_list().map(drop),
+            7 => codec.start_read_union().map(drop),
+            8 => codec.read_callback(&[1, 2, 3]).map(drop),
+            9 => codec.start_read_message().map(drop),
+            10 => heapless::Vec::<u16, 8>::decode(&mut codec).map(drop),
+            _ => heapless::String::<8>::decode(&mut codec).map(drop),
+        };
+    }
+});
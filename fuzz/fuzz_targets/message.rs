@@ -0,0 +1,26 @@
+//! Receives arbitrary bytes as framed requests and replies, as a server and a client would.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_erpc::codec::{BasicCodecFactory, Codec};
+use rust_erpc::cursor::SliceCursor;
+use rust_erpc::framed_transport::BasicFramedTransport;
+use rust_erpc::request::{Request, Response};
+
+fuzz_target!(|data: &[u8]| {
+    let mut data = data.to_vec();
+    let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut data));
+    let mut buffer = [0u8; 128];
+    let mut binary = [0u8; 16];
+    if let Ok((_, mut codec)) =
+        Request::receive_request(&mut transport, &mut buffer, BasicCodecFactory::new())
+    {
+        while codec.read_binary(&mut binary).is_ok() {}
+    }
+    if let Ok((_, mut codec)) =
+        Response::receive_response(&mut transport, &mut buffer, BasicCodecFactory::new())
+    {
+        while codec.read_u32().is_ok() {}
+    }
+});
@@ -0,0 +1,11 @@
+use std::path::Path;
+
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    erpc_codegen::compile(
+        "idl/rpc_ble_api.erpc",
+        Path::new(&out_dir).join("rpc_ble_api.rs"),
+        &erpc_codegen::Options::default(),
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
+}
@@ -0,0 +1,97 @@
+//! Server receiving the `rpc_ble_callback` events the RTL8720 calls on the host.
+//!
+//! [`CallbackServer`] dispatches each request to the generated [`rpc_ble_callback::Service`] with a
+//! `rust_erpc::rpc::SimpleServer`, so the binaries passed to the handler are decoded into the scratch buffer.
+//! It serves a link dedicated to the callbacks. On a link also used to call the RTL8720, register
+//! [`rpc_ble_callback::Service`] with a `rust_erpc::arbitrator::TransportArbitrator` instead;
+//! both dispatch to the same [`Irpc_ble_callback`] handler.
+
+use crate::rpc_ble_api::rpc_ble_callback::{self, Irpc_ble_callback};
+use rust_erpc::codec::BasicCodecFactory;
+use rust_erpc::framed_transport::FramedTransport;
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc::SimpleServer;
+
+pub struct CallbackServer<'buffer, Transport, Handler> {
+    transport: Transport,
+    receive_buffer: &'buffer mut [u8],
+    send_buffer: &'buffer mut [u8],
+    service: rpc_ble_callback::Service<'buffer, Handler>,
+}
+
+impl<'buffer, Transport, Handler: Irpc_ble_callback> CallbackServer<'buffer, Transport, Handler> {
+    /// Creates a server receiving requests into `receive_buffer` and sending replies from `send_buffer`.
+    ///
+    /// `scratch` receives the binaries passed to the handler, and its remainder is passed as `read_cb_data`,
+    /// to hold the value of a local attribute being read.
+    pub fn new(
+        transport: Transport,
+        receive_buffer: &'buffer mut [u8],
+        send_buffer: &'buffer mut [u8],
+        scratch: &'buffer mut [u8],
+        handler: Handler,
+    ) -> Self {
+        Self {
+            transport,
+            receive_buffer,
+            send_buffer,
+            service: rpc_ble_callback::Service::with_scratch(handler, scratch),
+        }
+    }
+
+    pub fn handler(&mut self) -> &mut Handler {
+        self.service.handler()
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn release(
+        self,
+    ) -> (
+        Transport,
+        &'buffer mut [u8],
+        &'buffer mut [u8],
+        &'buffer mut [u8],
+        Handler,
+    ) {
+        let (handler, scratch) = self.service.release();
+        (
+            self.transport,
+            self.receive_buffer,
+            self.send_buffer,
+            scratch,
+            handler,
+        )
+    }
+
+    /// Receives one callback, calls the handler and sends its result back unless the request is oneway,
+    /// as `SimpleServer::run_once`.
+    ///
+    /// Requests to other services or to unknown methods are dropped and rejected with `InvalidRequest`.
+    pub fn run_once<Error>(&mut self) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        let mut server: SimpleServer<_, _, _, 1> = SimpleServer::new(
+            &mut self.transport,
+            &mut *self.receive_buffer,
+            &mut *self.send_buffer,
+            BasicCodecFactory::new(),
+        );
+        // The only service always fits.
+        let _ = server.add_service(&mut self.service);
+        server.run_once()
+    }
+
+    /// Runs `run_once` repeatedly, dropping the requests which fail, until the underlying transport fails.
+    pub fn run<Error>(&mut self) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        loop {
+            match self.run_once() {
+                Err(err) if err.is_underlying_error() => return Err(err),
+                _ => {}
+            }
+        }
+    }
+}
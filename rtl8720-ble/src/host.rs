@@ -0,0 +1,569 @@
+//! Typed BLE operations over the [`rpc_ble_api`](crate::rpc_ble_api) bindings.
+//!
+//! [`BleHost`] encodes the values of the `rpc_*_set_param` functions as the BLE stack of the RTL8720 expects them,
+//! and turns the `RPC_T_GAP_CAUSE` and boolean results into [`BleError`].
+//! Operations such as scanning, connecting or discovering services complete asynchronously:
+//! their results are reported through `rpc_ble_callback`, see [`crate::callback`].
+
+use crate::rpc_ble_api::*;
+use rust_erpc::codec::Codec;
+use rust_erpc::cursor::Cursor;
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc;
+
+/// Maximum length of the device name, without its terminating NUL.
+pub const MAX_DEVICE_NAME_LENGTH: usize = 39;
+/// Maximum length of the advertising and scan response data.
+pub const MAX_ADVERTISING_DATA_LENGTH: usize = 31;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BleError<TransportError> {
+    /// The BLE stack rejected the operation.
+    Gap(RPC_T_GAP_CAUSE),
+    /// The BLE stack reported a failure without a cause.
+    Failed,
+    /// An argument does not fit in its parameter, such as a device name longer than [`MAX_DEVICE_NAME_LENGTH`].
+    InvalidArgument,
+    Rpc(RequestResponseError<TransportError>),
+}
+
+impl<TransportError> From<RequestResponseError<TransportError>> for BleError<TransportError> {
+    fn from(err: RequestResponseError<TransportError>) -> Self {
+        Self::Rpc(err)
+    }
+}
+
+fn check<TransportError>(cause: RPC_T_GAP_CAUSE) -> Result<(), BleError<TransportError>> {
+    match cause {
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS => Ok(()),
+        cause => Err(BleError::Gap(cause)),
+    }
+}
+
+fn check_bool<TransportError>(succeeded: bool) -> Result<(), BleError<TransportError>> {
+    if succeeded {
+        Ok(())
+    } else {
+        Err(BleError::Failed)
+    }
+}
+
+/// Advertising PDU type, `T_GAP_ADTYPE` of the BLE stack.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AdvertisingType {
+    ConnectableUndirected = 0,
+    ConnectableHighDutyDirected = 1,
+    ScannableUndirected = 2,
+    NonConnectableUndirected = 3,
+    ConnectableLowDutyDirected = 4,
+}
+
+/// Parameters written by [`BleHost::set_advertising_parameters`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AdvertisingParameters {
+    pub advertising_type: AdvertisingType,
+    /// Minimum interval in units of 0.625 ms, from 0x0020 to 0x4000.
+    pub interval_min: u16,
+    /// Maximum interval in units of 0.625 ms, from 0x0020 to 0x4000.
+    pub interval_max: u16,
+    /// Bit mask of the advertising channels 37, 38 and 39.
+    pub channel_map: u8,
+    /// `T_GAP_ADV_FILTER_POLICY` of the BLE stack.
+    pub filter_policy: u8,
+}
+
+impl Default for AdvertisingParameters {
+    /// Connectable undirected advertising every 100 ms on all channels, without filter.
+    fn default() -> Self {
+        Self {
+            advertising_type: AdvertisingType::ConnectableUndirected,
+            interval_min: 0x00a0,
+            interval_max: 0x00a0,
+            channel_map: 0x07,
+            filter_policy: 0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ScanMode {
+    Passive = 0,
+    Active = 1,
+}
+
+/// Parameters written by [`BleHost::set_scan_parameters`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ScanParameters {
+    pub mode: ScanMode,
+    /// Interval in units of 0.625 ms, from 0x0004 to 0x4000.
+    pub interval: u16,
+    /// Window in units of 0.625 ms, at most `interval`.
+    pub window: u16,
+    /// `T_GAP_SCAN_FILTER_POLICY` of the BLE stack.
+    pub filter_policy: u8,
+    /// Report each advertiser once per scan.
+    pub filter_duplicates: bool,
+}
+
+impl Default for ScanParameters {
+    /// The defaults of the BLE stack: active scan with an interval and a window of 10 ms.
+    fn default() -> Self {
+        Self {
+            mode: ScanMode::Active,
+            interval: 0x0010,
+            window: 0x0010,
+            filter_policy: 0,
+            filter_duplicates: false,
+        }
+    }
+}
+
+/// BLE host driving the RTL8720 through any [`rpc::Caller`].
+///
+/// Over a link dedicated to the calls, the caller is an `rpc::Client`. When the events of `rpc_ble_callback`
+/// arrive on the same link, it is a `TransportArbitrator` with [`rpc_ble_callback::Service`] registered, which
+/// handles the events received while waiting for a reply.
+/// The functions of the bindings not covered here remain available through [`BleHost::caller`].
+pub struct BleHost<CallerType> {
+    caller: CallerType,
+}
+
+impl<CallerType> BleHost<CallerType> {
+    pub fn new(caller: CallerType) -> Self {
+        Self { caller }
+    }
+
+    pub fn release(self) -> CallerType {
+        self.caller
+    }
+
+    /// The caller to call the bindings directly, as in `rpc_gap_conn::Client::new(host.caller())`.
+    pub fn caller(&mut self) -> &mut CallerType {
+        &mut self.caller
+    }
+
+    /// Initializes the BLE stack. Call before any other operation.
+    pub fn init<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check_bool(rpc_ble_host::Client::new(&mut self.caller).rpc_ble_init()?)
+    }
+
+    /// Starts the BLE stack once its parameters are set.
+    pub fn start<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        Ok(rpc_ble_host::Client::new(&mut self.caller).rpc_ble_start()?)
+    }
+
+    pub fn deinit<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        Ok(rpc_ble_host::Client::new(&mut self.caller).rpc_ble_deinit()?)
+    }
+
+    fn set_gap_param<Error, CursorType, CodecType>(
+        &mut self,
+        param: RPC_T_GAP_LE_PARAM_TYPE,
+        value: &[u8],
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_le::Client::new(&mut self.caller).rpc_le_set_gap_param(param, value)?)
+    }
+
+    fn set_adv_param<Error, CursorType, CodecType>(
+        &mut self,
+        param: RPC_T_LE_ADV_PARAM_TYPE,
+        value: &[u8],
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_adv::Client::new(&mut self.caller).rpc_le_adv_set_param(param, value)?)
+    }
+
+    fn set_scan_param<Error, CursorType, CodecType>(
+        &mut self,
+        param: RPC_T_LE_SCAN_PARAM_TYPE,
+        value: &[u8],
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_scan::Client::new(&mut self.caller).rpc_le_scan_set_param(param, value)?)
+    }
+
+    /// Sets the name of the local device, as found in its GAP service.
+    pub fn set_device_name<Error, CursorType, CodecType>(
+        &mut self,
+        name: &str,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        if name.len() > MAX_DEVICE_NAME_LENGTH {
+            return Err(BleError::InvalidArgument);
+        }
+        let mut value = [0u8; MAX_DEVICE_NAME_LENGTH + 1];
+        value[..name.len()].copy_from_slice(name.as_bytes());
+        self.set_gap_param(
+            RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_DEVICE_NAME,
+            &value[..name.len() + 1],
+        )
+    }
+
+    /// Sets the appearance of the local device, one of the values assigned by the Bluetooth SIG.
+    pub fn set_appearance<Error, CursorType, CodecType>(
+        &mut self,
+        appearance: u16,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        self.set_gap_param(
+            RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_APPEARANCE,
+            &appearance.to_le_bytes(),
+        )
+    }
+
+    /// Reads the public address of the local device.
+    pub fn address<Error, CursorType, CodecType>(&mut self) -> Result<[u8; 6], BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        let mut value = [0u8; 6];
+        let (value, cause) = rpc_gap::Client::new(&mut self.caller)
+            .rpc_gap_get_param(RPC_T_GAP_PARAM_TYPE::RPC_GAP_PARAM_BD_ADDR, &mut value)?;
+        check(cause)?;
+        let mut address = [0u8; 6];
+        if value.len() != address.len() {
+            return Err(BleError::Rpc(RequestResponseError::InvalidResponse));
+        }
+        address.copy_from_slice(value);
+        Ok(address)
+    }
+
+    pub fn set_advertising_parameters<Error, CursorType, CodecType>(
+        &mut self,
+        parameters: &AdvertisingParameters,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        use RPC_T_LE_ADV_PARAM_TYPE::*;
+        self.set_adv_param(
+            RPC_GAP_PARAM_ADV_EVENT_TYPE,
+            &[parameters.advertising_type as u8],
+        )?;
+        self.set_adv_param(RPC_GAP_PARAM_ADV_CHANNEL_MAP, &[parameters.channel_map])?;
+        self.set_adv_param(RPC_GAP_PARAM_ADV_FILTER_POLICY, &[parameters.filter_policy])?;
+        self.set_adv_param(
+            RPC_GAP_PARAM_ADV_INTERVAL_MIN,
+            &parameters.interval_min.to_le_bytes(),
+        )?;
+        self.set_adv_param(
+            RPC_GAP_PARAM_ADV_INTERVAL_MAX,
+            &parameters.interval_max.to_le_bytes(),
+        )
+    }
+
+    /// Sets the advertising data, a sequence of AD structures of at most [`MAX_ADVERTISING_DATA_LENGTH`] bytes.
+    pub fn set_advertising_data<Error, CursorType, CodecType>(
+        &mut self,
+        data: &[u8],
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        if data.len() > MAX_ADVERTISING_DATA_LENGTH {
+            return Err(BleError::InvalidArgument);
+        }
+        self.set_adv_param(RPC_T_LE_ADV_PARAM_TYPE::RPC_GAP_PARAM_ADV_DATA, data)
+    }
+
+    /// Sets the data sent in reply to scan requests, at most [`MAX_ADVERTISING_DATA_LENGTH`] bytes.
+    pub fn set_scan_response_data<Error, CursorType, CodecType>(
+        &mut self,
+        data: &[u8],
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        if data.len() > MAX_ADVERTISING_DATA_LENGTH {
+            return Err(BleError::InvalidArgument);
+        }
+        self.set_adv_param(RPC_T_LE_ADV_PARAM_TYPE::RPC_GAP_PARAM_SCAN_RSP_DATA, data)
+    }
+
+    pub fn start_advertising<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_adv::Client::new(&mut self.caller).rpc_le_adv_start()?)
+    }
+
+    pub fn stop_advertising<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_adv::Client::new(&mut self.caller).rpc_le_adv_stop()?)
+    }
+
+    pub fn set_scan_parameters<Error, CursorType, CodecType>(
+        &mut self,
+        parameters: &ScanParameters,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        use RPC_T_LE_SCAN_PARAM_TYPE::*;
+        self.set_scan_param(RPC_GAP_PARAM_SCAN_MODE, &[parameters.mode as u8])?;
+        self.set_scan_param(
+            RPC_GAP_PARAM_SCAN_INTERVAL,
+            &parameters.interval.to_le_bytes(),
+        )?;
+        self.set_scan_param(RPC_GAP_PARAM_SCAN_WINDOW, &parameters.window.to_le_bytes())?;
+        self.set_scan_param(
+            RPC_GAP_PARAM_SCAN_FILTER_POLICY,
+            &[parameters.filter_policy],
+        )?;
+        self.set_scan_param(
+            RPC_GAP_PARAM_SCAN_FILTER_DUPLICATES,
+            &[parameters.filter_duplicates as u8],
+        )
+    }
+
+    /// Starts scanning. Advertising reports are delivered through `rpc_ble_gap_callback`.
+    pub fn start_scan<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_scan::Client::new(&mut self.caller).rpc_le_scan_start()?)
+    }
+
+    pub fn stop_scan<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_scan::Client::new(&mut self.caller).rpc_le_scan_stop()?)
+    }
+
+    /// Connects to a peripheral on the LE 1M PHY, giving up after `scan_timeout` in units of 10 ms.
+    ///
+    /// The connection id is reported by `rpc_ble_handle_gap_msg` once the connection is established.
+    pub fn connect<Error, CursorType, CodecType>(
+        &mut self,
+        address: &[u8; 6],
+        address_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+        parameters: &RPC_T_GAP_LE_CONN_REQ_PARAM,
+        scan_timeout: u16,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        /// `GAP_PHYS_CONN_INIT_1M_BIT` of the BLE stack.
+        const INIT_PHYS_1M: u8 = 0x01;
+        let mut connection = rpc_gap_conn::Client::new(&mut self.caller);
+        check(connection.rpc_le_set_conn_param(
+            RPC_T_GAP_CONN_PARAM_TYPE::RPC_GAP_CONN_PARAM_1M,
+            parameters,
+        )?)?;
+        check(connection.rpc_le_connect(
+            INIT_PHYS_1M,
+            address,
+            address_type,
+            RPC_T_GAP_LOCAL_ADDR_TYPE::RPC_GAP_LOCAL_ADDR_LE_PUBLIC,
+            scan_timeout,
+        )?)
+    }
+
+    pub fn disconnect<Error, CursorType, CodecType>(
+        &mut self,
+        conn_id: u8,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_conn::Client::new(&mut self.caller).rpc_le_disconnect(conn_id)?)
+    }
+
+    /// State, role and peer of the connection `conn_id`. Fails if the connection does not exist.
+    pub fn connection_info<Error, CursorType, CodecType>(
+        &mut self,
+        conn_id: u8,
+    ) -> Result<RPC_T_GAP_CONN_INFO, BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        let (info, found) =
+            rpc_gap_conn::Client::new(&mut self.caller).rpc_le_get_conn_info(conn_id)?;
+        check_bool(found)?;
+        Ok(info)
+    }
+
+    /// Starts pairing on the connection `conn_id`, bonding if the peer agrees.
+    pub fn pair<Error, CursorType, CodecType>(&mut self, conn_id: u8) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(rpc_gap_bone::Client::new(&mut self.caller).rpc_le_bond_pair(conn_id)?)
+    }
+
+    pub fn security_level<Error, CursorType, CodecType>(
+        &mut self,
+        conn_id: u8,
+    ) -> Result<RPC_T_GAP_SEC_LEVEL, BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        let (level, cause) =
+            rpc_gap_bone::Client::new(&mut self.caller).rpc_le_bond_get_sec_level(conn_id)?;
+        check(cause)?;
+        Ok(level)
+    }
+
+    pub fn bonded_device_count<Error, CursorType, CodecType>(
+        &mut self,
+    ) -> Result<u8, BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        Ok(rpc_gap_storage::Client::new(&mut self.caller).rpc_le_get_bond_dev_num()?)
+    }
+
+    /// Deletes the keys of every bonded device.
+    pub fn delete_bonds<Error, CursorType, CodecType>(&mut self) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        Ok(rpc_gap_bone::Client::new(&mut self.caller).rpc_le_bond_clear_all_keys()?)
+    }
+
+    /// Registers the GATT client `app_id` for up to `link_count` connections, returning its client id.
+    pub fn add_gatt_client<Error, CursorType, CodecType>(
+        &mut self,
+        app_id: u8,
+        link_count: u8,
+    ) -> Result<u8, BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        Ok(
+            rpc_gatt_client::Client::new(&mut self.caller)
+                .rpc_ble_add_client(app_id, link_count)?,
+        )
+    }
+
+    /// Discovers the primary services of the peer. Each service is reported through `rpc_ble_gattc_callback`.
+    pub fn discover_services<Error, CursorType, CodecType>(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(
+            rpc_gatt_client::Client::new(&mut self.caller)
+                .rpc_client_all_primary_srv_discovery(conn_id, client_id)?,
+        )
+    }
+
+    /// Discovers the primary services of the peer with the 16-bit UUID `uuid16`.
+    pub fn discover_services_by_uuid16<Error, CursorType, CodecType>(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        uuid16: u16,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(
+            rpc_gatt_client::Client::new(&mut self.caller)
+                .rpc_client_by_uuid_srv_discovery(conn_id, client_id, uuid16)?,
+        )
+    }
+
+    /// Discovers the characteristics between the handles `start_handle` and `end_handle`, those of a service.
+    pub fn discover_characteristics<Error, CursorType, CodecType>(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        start_handle: u16,
+        end_handle: u16,
+    ) -> Result<(), BleError<Error>>
+    where
+        CallerType: rpc::Caller<Error, CursorType, CodecType>,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        check(
+            rpc_gatt_client::Client::new(&mut self.caller).rpc_client_all_char_discovery(
+                conn_id,
+                client_id,
+                start_handle,
+                end_handle,
+            )?,
+        )
+    }
+}
@@ -0,0 +1,44 @@
+//! Bindings of the erpc interfaces of the RTL8720 BLE co-processor of the Wio Terminal.
+//!
+//! [`rpc_ble_api`] is generated by `erpc-codegen` from `idl/rpc_ble_api.erpc`, which follows the erpcgen output
+//! in `test/erpc_shim/rpc_ble_api` and `test/erpc_shim/rpc_ble_callback`. Each interface is a module holding the service and function ids,
+//! a typed `Client` calling through a `rust_erpc::rpc::Caller`, and the trait and dispatcher of the server side.
+//! The `RPC_T_*` enums and structs are at the root of the module.
+//!
+//! The RTL8720 reports events by calling the host through `rpc_ble_callback`, which the host serves
+//! with a [`callback::CallbackServer`] or by registering the generated service with a `TransportArbitrator`.
+//!
+//! [`host::BleHost`] wraps the most common functions in typed operations, such as setting the device name,
+//! advertising, scanning and connecting.
+//!
+//! [`simulator::Simulator`] plays the RTL8720 side of the services, to test host code over a loopback without
+//! the hardware.
+//!
+//! ```no_run
+//! # use rust_erpc::codec::BasicCodecFactory;
+//! # use rust_erpc::cursor::SliceCursor;
+//! # use rust_erpc::framed_transport::BasicFramedTransport;
+//! use rtl8720_ble::rpc_ble_api::{rpc_gap_le, RPC_T_GAP_CAUSE, RPC_T_GAP_LE_PARAM_TYPE};
+//!
+//! # let mut stream = [0u8; 64];
+//! let mut buffer = [0u8; 256];
+//! let mut client = rust_erpc::rpc::Client::new(
+//!     BasicFramedTransport::new(SliceCursor::new(&mut stream)),
+//!     &mut buffer,
+//!     BasicCodecFactory::new(),
+//! );
+//! let cause = rpc_gap_le::Client::new(&mut client)
+//!     .rpc_le_set_gap_param(RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_DEVICE_NAME, b"Wio Terminal\0")
+//!     .unwrap();
+//! assert_eq!(cause, RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS);
+//! ```
+
+#![cfg_attr(not(test), no_std)]
+
+pub mod callback;
+pub mod host;
+pub mod simulator;
+
+pub mod rpc_ble_api {
+    include!(concat!(env!("OUT_DIR"), "/rpc_ble_api.rs"));
+}
@@ -0,0 +1,2386 @@
+//! Simulator of the RTL8720 serving the [`rpc_ble_api`](crate::rpc_ble_api) services, to test host code without the hardware.
+//!
+//! [`Simulator`] implements the handler of every service. It keeps the GAP state, the parameters set through the
+//! `rpc_*_set_param` functions, the connections, the bonded devices and the GATT services created by the host.
+//! It also plays the remote devices: the [`Peer`]s found while scanning and accepting connections, which all expose
+//! the same fake GATT database.
+//! What the RTL8720 reports asynchronously is queued as [`Event`]s.
+//!
+//! [`SimulatorServices`] are the generated services sharing one simulator in a `RefCell`, to register with the
+//! servers of `rust_erpc`. [`deliver_events`] delivers the queued events by calling `rpc_ble_callback`, and is
+//! called after each request served. The simulator runs over any `FramedTransport`, such as a TCP loopback or an
+//! in-memory pipe, in either of the two setups of the RTL8720:
+//!
+//! * Two links: a `SimpleServer` serves the services, and the events are delivered through an `rpc::Client` over a
+//!   second link, which the host serves with a [`CallbackServer`](crate::callback::CallbackServer).
+//! * One link: a `TransportArbitrator` serves the services and delivers the events, serving the requests of the
+//!   host while a callback is pending. The host calls through its own arbitrator, serving `rpc_ble_callback`.
+//!
+//! ```
+//! use core::cell::RefCell;
+//! use rtl8720_ble::simulator::{deliver_events, Simulator, SimulatorServices, SERVICE_COUNT};
+//! use rust_erpc::arbitrator::TransportArbitrator;
+//! use rust_erpc::codec::BasicCodecFactory;
+//! use rust_erpc::framed_transport::FramedTransport;
+//! use rust_erpc::request::RequestResponseError;
+//!
+//! fn serve<Error>(transport: impl FramedTransport<Error>) -> RequestResponseError<Error> {
+//!     let simulator = RefCell::new(Simulator::new([0x11, 0x22, 0x33, 0x44, 0x55, 0x66]));
+//!     let mut scratch = [0u8; 1024];
+//!     let mut services = SimulatorServices::new(&simulator, &mut scratch);
+//!     let (mut receive_buffer, mut send_buffer) = ([0u8; 256], [0u8; 256]);
+//!     let mut arbitrator = TransportArbitrator::<_, _, _, SERVICE_COUNT>::new(
+//!         transport,
+//!         &mut receive_buffer,
+//!         &mut send_buffer,
+//!         BasicCodecFactory::new(),
+//!     );
+//!     for service in services.services() {
+//!         arbitrator.add_service(service).ok();
+//!     }
+//!     loop {
+//!         let result = arbitrator
+//!             .run_once()
+//!             .and_then(|()| deliver_events(&simulator, &mut arbitrator));
+//!         match result {
+//!             Err(err) if err.is_underlying_error() => return err,
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! The events are passed to the callbacks with the following layouts, all integers in little endian.
+//!
+//! * `rpc_ble_handle_gap_msg`: a `T_IO_MSG` of 8 bytes, the type `IO_MSG_TYPE_BT_STATUS`, the subtype
+//!   [`GAP_MSG_LE_DEV_STATE_CHANGE`], [`GAP_MSG_LE_CONN_STATE_CHANGE`] or [`GAP_MSG_LE_AUTHEN_STATE_CHANGE`] as `u16`,
+//!   then 4 bytes holding respectively the state byte of [`DeviceState`], a padding byte and the cause,
+//!   or the connection id, the new state and the cause.
+//! * `rpc_ble_gap_callback` with [`GAP_MSG_LE_SCAN_INFO`]: the address, its type, the advertising event type,
+//!   the RSSI, the length of the data and 31 bytes of data.
+//! * `rpc_ble_gattc_callback`: `gatt_if` is the client id, `cb_data` starts with one of the `GATTC_*` kinds,
+//!   followed by the fields of the corresponding [`Event`] as `u16`. Values are passed in `extra_data`.
+//! * `rpc_ble_gatts_callback`: `gatt_if` is the service id. The value written, or the CCCD bits, are passed in
+//!   `write_cb_data`, and the value returned by the host for a read is stored in the attribute.
+
+use crate::host::{
+    AdvertisingParameters, AdvertisingType, ScanMode, ScanParameters, MAX_ADVERTISING_DATA_LENGTH,
+    MAX_DEVICE_NAME_LENGTH,
+};
+use crate::rpc_ble_api::*;
+use core::cell::RefCell;
+use heapless::{Deque, Vec};
+use rust_erpc::codec::Codec;
+use rust_erpc::cursor::Cursor;
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc::{self, Caller};
+
+/// Number of simultaneous connections.
+pub const MAX_LINKS: usize = 4;
+/// Number of bonded devices kept before the one with the lowest priority is replaced.
+pub const MAX_BONDS: usize = 4;
+pub const MAX_PEERS: usize = 4;
+/// Number of services of the GATT server of the host, and of the database of the peers.
+pub const MAX_SERVICES: usize = 4;
+/// Number of characteristics of a peer service, and of attributes of a local service.
+pub const MAX_ATTRIBUTES: usize = 8;
+/// Length of attribute values, the payload of the default ATT MTU.
+pub const MAX_VALUE_LENGTH: usize = 20;
+/// Number of events pending delivery, and of data sent by the host kept for inspection.
+pub const MAX_EVENTS: usize = 16;
+/// Number of parameters stored by the `rpc_*_set_param` functions.
+pub const MAX_PARAMS: usize = 48;
+/// Length of a stored parameter, enough for the device name and its terminating NUL.
+pub const MAX_PARAM_LENGTH: usize = MAX_DEVICE_NAME_LENGTH + 1;
+
+/// `IO_MSG_TYPE_BT_STATUS`, the type of the messages passed to `rpc_ble_handle_gap_msg`.
+pub const IO_MSG_TYPE_BT_STATUS: u16 = 0x00;
+pub const GAP_MSG_LE_DEV_STATE_CHANGE: u16 = 0x01;
+pub const GAP_MSG_LE_CONN_STATE_CHANGE: u16 = 0x02;
+pub const GAP_MSG_LE_AUTHEN_STATE_CHANGE: u16 = 0x05;
+/// `cb_type` of the advertising reports passed to `rpc_ble_gap_callback`.
+pub const GAP_MSG_LE_SCAN_INFO: u8 = 0x30;
+/// `GAP_AUTHEN_STATE_STARTED`, state of [`Event::AuthenticationState`].
+pub const GAP_AUTHEN_STATE_STARTED: u8 = 0x00;
+/// `GAP_AUTHEN_STATE_COMPLETE`, state of [`Event::AuthenticationState`].
+pub const GAP_AUTHEN_STATE_COMPLETE: u8 = 0x01;
+
+/// Kinds of the events passed to `rpc_ble_gattc_callback`, the first byte of `cb_data`.
+pub const GATTC_SERVICE_FOUND: u8 = 0x01;
+pub const GATTC_CHARACTERISTIC_FOUND: u8 = 0x02;
+pub const GATTC_DISCOVERY_COMPLETE: u8 = 0x03;
+pub const GATTC_READ_RESULT: u8 = 0x04;
+pub const GATTC_WRITE_RESULT: u8 = 0x05;
+pub const GATTC_NOTIFICATION: u8 = 0x06;
+
+/// Disconnection causes, HCI error codes with the `HCI_ERR` flag of the BLE stack.
+pub const DISCONNECT_CAUSE_CONNECTION_TIMEOUT: u16 = 0x0108;
+pub const DISCONNECT_CAUSE_REMOTE_USER_TERMINATED: u16 = 0x0113;
+pub const DISCONNECT_CAUSE_LOCAL_HOST_TERMINATED: u16 = 0x0116;
+
+/// Value returned by `rpc_ble_add_client` and `rpc_ble_create_service` when no more can be registered.
+const INVALID_ID: u8 = 0xff;
+/// Value returned by `rpc_ble_create_char` and `rpc_ble_create_desc` when the attribute cannot be created.
+const INVALID_INDEX: u16 = 0xffff;
+/// ATT MTU of a new connection.
+const DEFAULT_MTU: u16 = 23;
+/// `GAP_PHYS_1M` of the BLE stack.
+const PHY_1M: u8 = 0x01;
+
+/// GAP device state, `T_GAP_DEV_STATE` of the BLE stack.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct DeviceState {
+    /// The stack is started, `GAP_INIT_STATE_STACK_READY`.
+    pub ready: bool,
+    pub advertising: bool,
+    pub scanning: bool,
+    /// A connection to a peripheral is being established.
+    pub connecting: bool,
+}
+
+impl DeviceState {
+    /// The bit fields of `T_GAP_DEV_STATE`: the init state in bit 0, the advertising state in bits 2-3,
+    /// the scan state in bits 4-5 and the connection state in bits 6-7.
+    pub fn to_byte(self) -> u8 {
+        /// `GAP_ADV_STATE_ADVERTISING` and `GAP_SCAN_STATE_SCANNING`.
+        const ACTIVE: u8 = 2;
+        (self.ready as u8)
+            | if self.advertising { ACTIVE << 2 } else { 0 }
+            | if self.scanning { ACTIVE << 4 } else { 0 }
+            | (self.connecting as u8) << 6
+    }
+}
+
+/// State of a connection, indexed by its connection id.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Link {
+    pub state: RPC_T_GAP_CONN_STATE,
+    pub role: RPC_T_GAP_ROLE,
+    pub remote_bd: [u8; 6],
+    pub remote_bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+    pub sec_level: RPC_T_GAP_SEC_LEVEL,
+    /// Connection interval in units of 1.25 ms.
+    pub interval: u16,
+    pub latency: u16,
+    /// Supervision timeout in units of 10 ms.
+    pub supervision_timeout: u16,
+    pub mtu: u16,
+}
+
+impl Link {
+    fn is_connected(&self) -> bool {
+        self.state == RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_CONNECTED
+    }
+}
+
+/// Remote device reported while scanning, which accepts connections if it is `connectable`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Peer {
+    pub address: [u8; 6],
+    pub address_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+    pub rssi: i8,
+    pub advertising_data: Vec<u8, MAX_ADVERTISING_DATA_LENGTH>,
+    pub connectable: bool,
+}
+
+impl Peer {
+    /// A connectable peer with a public address. `advertising_data` is truncated to [`MAX_ADVERTISING_DATA_LENGTH`].
+    pub fn new(address: [u8; 6], advertising_data: &[u8]) -> Self {
+        Self {
+            address,
+            address_type: RPC_T_GAP_REMOTE_ADDR_TYPE::RPC_GAP_REMOTE_ADDR_LE_PUBLIC,
+            rssi: -60,
+            advertising_data: truncated(advertising_data),
+            connectable: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PeerCharacteristic {
+    pub uuid16: u16,
+    pub properties: u8,
+    /// Handle of the value, following the handle of the declaration.
+    pub value_handle: u16,
+    pub value: Vec<u8, MAX_VALUE_LENGTH>,
+}
+
+/// Primary service of the GATT database of the peers.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PeerService {
+    pub uuid16: u16,
+    pub start_handle: u16,
+    pub end_handle: u16,
+    pub characteristics: Vec<PeerCharacteristic, MAX_ATTRIBUTES>,
+}
+
+/// Characteristic or descriptor of a service created by the host, indexed by its attribute index.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LocalAttribute {
+    pub uuid: [u8; 16],
+    pub uuid_length: u8,
+    /// Properties of a characteristic, or flags of a descriptor.
+    pub properties: u8,
+    pub permissions: u32,
+    pub value: Vec<u8, MAX_VALUE_LENGTH>,
+}
+
+/// Service created by the host with `rpc_ble_create_service`, indexed by its service id.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LocalService {
+    pub uuid: [u8; 16],
+    pub uuid_length: u8,
+    pub is_primary: bool,
+    pub started: bool,
+    pub attributes: Vec<LocalAttribute, MAX_ATTRIBUTES>,
+}
+
+/// Data sent by the host to a peer with `rpc_server_send_data`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SentData {
+    pub conn_id: u8,
+    pub service_id: u8,
+    pub attrib_index: u16,
+    pub pdu_type: RPC_T_GATT_PDU_TYPE,
+    pub data: Vec<u8, MAX_VALUE_LENGTH>,
+}
+
+/// Event reported to the host through `rpc_ble_callback`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// `GAP_MSG_LE_DEV_STATE_CHANGE` passed to `rpc_ble_handle_gap_msg`.
+    DeviceState {
+        state: DeviceState,
+        cause: u16,
+    },
+    /// `GAP_MSG_LE_CONN_STATE_CHANGE` passed to `rpc_ble_handle_gap_msg`.
+    ConnectionState {
+        conn_id: u8,
+        state: RPC_T_GAP_CONN_STATE,
+        disc_cause: u16,
+    },
+    /// `GAP_MSG_LE_AUTHEN_STATE_CHANGE` passed to `rpc_ble_handle_gap_msg`.
+    AuthenticationState {
+        conn_id: u8,
+        state: u8,
+        cause: u16,
+    },
+    /// `GAP_MSG_LE_SCAN_INFO` passed to `rpc_ble_gap_callback`, for each peer found while scanning.
+    ScanInfo {
+        address: [u8; 6],
+        address_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+        rssi: i8,
+        data: Vec<u8, MAX_ADVERTISING_DATA_LENGTH>,
+    },
+    ServiceFound {
+        conn_id: u8,
+        client_id: u8,
+        start_handle: u16,
+        end_handle: u16,
+        uuid16: u16,
+    },
+    CharacteristicFound {
+        conn_id: u8,
+        client_id: u8,
+        declaration_handle: u16,
+        properties: u8,
+        value_handle: u16,
+        uuid16: u16,
+    },
+    DiscoveryComplete {
+        conn_id: u8,
+        client_id: u8,
+    },
+    ReadResult {
+        conn_id: u8,
+        client_id: u8,
+        handle: u16,
+        cause: RPC_T_APP_RESULT,
+        value: Vec<u8, MAX_VALUE_LENGTH>,
+    },
+    WriteResult {
+        conn_id: u8,
+        client_id: u8,
+        handle: u16,
+        cause: RPC_T_APP_RESULT,
+    },
+    /// Notification or indication of a peer characteristic.
+    Notification {
+        conn_id: u8,
+        client_id: u8,
+        handle: u16,
+        value: Vec<u8, MAX_VALUE_LENGTH>,
+    },
+    /// A peer wrote the client characteristic configuration of a local characteristic.
+    CccdWritten {
+        conn_id: u8,
+        service_id: u8,
+        attrib_index: u16,
+        cccd: u16,
+    },
+    /// A peer reads a local attribute, whose value is returned by the host.
+    AttributeRead {
+        conn_id: u8,
+        service_id: u8,
+        attrib_index: u16,
+    },
+    AttributeWritten {
+        conn_id: u8,
+        service_id: u8,
+        attrib_index: u16,
+        value: Vec<u8, MAX_VALUE_LENGTH>,
+    },
+}
+
+/// Simulated RTL8720, see the [module documentation](self).
+pub struct Simulator {
+    address: [u8; 6],
+    random_address: [u8; 6],
+    state: DeviceState,
+    params: Vec<(u32, Vec<u8, MAX_PARAM_LENGTH>), MAX_PARAMS>,
+    conn_request: RPC_T_GAP_LE_CONN_REQ_PARAM,
+    links: [Link; MAX_LINKS],
+    white_list: Vec<([u8; 6], RPC_T_GAP_REMOTE_ADDR_TYPE), MAX_PEERS>,
+    bonds: [RPC_T_LE_KEY_ENTRY; MAX_BONDS],
+    /// Indices of the used bond entries, from the highest priority to the lowest.
+    bond_priorities: Vec<u8, MAX_BONDS>,
+    local_name: RPC_T_LOCAL_NAME,
+    local_appearance: RPC_T_LOCAL_APPEARANCE,
+    peers: Vec<Peer, MAX_PEERS>,
+    peer_services: Vec<PeerService, MAX_SERVICES>,
+    client_count: u8,
+    local_services: Vec<Option<LocalService>, MAX_SERVICES>,
+    sent_data: Deque<SentData, MAX_EVENTS>,
+    events: Deque<Event, MAX_EVENTS>,
+}
+
+impl Simulator {
+    /// Creates a simulator with the public address `address`, its stack not started yet.
+    pub fn new(address: [u8; 6]) -> Self {
+        let mut simulator = Self {
+            address,
+            random_address: [0; 6],
+            state: DeviceState::default(),
+            params: Vec::new(),
+            conn_request: RPC_T_GAP_LE_CONN_REQ_PARAM::default(),
+            links: [Link::default(); MAX_LINKS],
+            white_list: Vec::new(),
+            bonds: [RPC_T_LE_KEY_ENTRY::default(); MAX_BONDS],
+            bond_priorities: Vec::new(),
+            local_name: RPC_T_LOCAL_NAME::default(),
+            local_appearance: RPC_T_LOCAL_APPEARANCE::default(),
+            peers: Vec::new(),
+            peer_services: Vec::new(),
+            client_count: 0,
+            local_services: Vec::new(),
+            sent_data: Deque::new(),
+            events: Deque::new(),
+        };
+        simulator.reset_params();
+        simulator
+    }
+
+    /// Sets the defaults of the parameters the simulator interprets.
+    fn reset_params(&mut self) {
+        use RPC_T_GAP_LE_PARAM_TYPE::*;
+        use RPC_T_LE_ADV_PARAM_TYPE::*;
+        use RPC_T_LE_SCAN_PARAM_TYPE::*;
+        self.params.clear();
+        let advertising = AdvertisingParameters::default();
+        let scan = ScanParameters::default();
+        let defaults: [(u32, &[u8]); 13] = [
+            (RPC_GAP_PARAM_DEVICE_NAME as u32, &[0]),
+            (RPC_GAP_PARAM_APPEARANCE as u32, &[0, 0]),
+            (
+                RPC_GAP_PARAM_ADV_EVENT_TYPE as u32,
+                &[advertising.advertising_type as u8],
+            ),
+            (
+                RPC_GAP_PARAM_ADV_CHANNEL_MAP as u32,
+                &[advertising.channel_map],
+            ),
+            (
+                RPC_GAP_PARAM_ADV_FILTER_POLICY as u32,
+                &[advertising.filter_policy],
+            ),
+            (
+                RPC_GAP_PARAM_ADV_INTERVAL_MIN as u32,
+                &advertising.interval_min.to_le_bytes(),
+            ),
+            (
+                RPC_GAP_PARAM_ADV_INTERVAL_MAX as u32,
+                &advertising.interval_max.to_le_bytes(),
+            ),
+            // Limited discoverable, as the BLE stack.
+            (RPC_GAP_PARAM_ADV_DATA as u32, &[0x02, 0x01, 0x05]),
+            (RPC_GAP_PARAM_SCAN_MODE as u32, &[scan.mode as u8]),
+            (
+                RPC_GAP_PARAM_SCAN_INTERVAL as u32,
+                &scan.interval.to_le_bytes(),
+            ),
+            (RPC_GAP_PARAM_SCAN_WINDOW as u32, &scan.window.to_le_bytes()),
+            (
+                RPC_GAP_PARAM_SCAN_FILTER_POLICY as u32,
+                &[scan.filter_policy],
+            ),
+            (
+                RPC_GAP_PARAM_SCAN_FILTER_DUPLICATES as u32,
+                &[scan.filter_duplicates as u8],
+            ),
+        ];
+        for (param, value) in defaults.iter() {
+            self.store_param(*param, value);
+        }
+    }
+
+    /// Adds a device reported while scanning. The peer is handed back if [`MAX_PEERS`] peers already exist.
+    pub fn add_peer(&mut self, peer: Peer) -> Result<(), Peer> {
+        self.peers.push(peer)
+    }
+
+    /// Adds a primary service to the GATT database of the peers and returns its start handle.
+    pub fn add_peer_service(&mut self, uuid16: u16) -> Option<u16> {
+        let start_handle = self
+            .peer_services
+            .last()
+            .map_or(1, |service| service.end_handle + 1);
+        self.peer_services
+            .push(PeerService {
+                uuid16,
+                start_handle,
+                end_handle: start_handle,
+                characteristics: Vec::new(),
+            })
+            .ok()?;
+        Some(start_handle)
+    }
+
+    /// Adds a characteristic to the last peer service and returns the handle of its value.
+    pub fn add_peer_characteristic(
+        &mut self,
+        uuid16: u16,
+        properties: u8,
+        value: &[u8],
+    ) -> Option<u16> {
+        let service = self.peer_services.last_mut()?;
+        let value_handle = service.end_handle + 2;
+        service
+            .characteristics
+            .push(PeerCharacteristic {
+                uuid16,
+                properties,
+                value_handle,
+                value: truncated(value),
+            })
+            .ok()?;
+        service.end_handle = value_handle;
+        Some(value_handle)
+    }
+
+    /// Connects the advertising peer `peer` to the host, returning the connection id.
+    ///
+    /// Fails if the simulator is not advertising or no link is free. Advertising stops once connected.
+    pub fn accept_connection(&mut self, peer: usize) -> Option<u8> {
+        let peer = self.peers.get(peer)?;
+        if !self.state.advertising {
+            return None;
+        }
+        let (address, address_type) = (peer.address, peer.address_type);
+        let conn_id = self.free_link()?;
+        self.links[conn_id as usize] = Link {
+            state: RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_CONNECTED,
+            role: RPC_T_GAP_ROLE::RPC_GAP_LINK_ROLE_SLAVE,
+            ..self.new_link(address, address_type)
+        };
+        self.state.advertising = false;
+        self.emit_state();
+        self.emit_connection_state(conn_id, 0);
+        Some(conn_id)
+    }
+
+    /// The peer of the connection `conn_id` disconnects.
+    pub fn peer_disconnect(&mut self, conn_id: u8) -> bool {
+        self.drop_link(conn_id, DISCONNECT_CAUSE_REMOTE_USER_TERMINATED)
+    }
+
+    /// The peer of the connection `conn_id` writes the local attribute `attrib_index` of the service `service_id`.
+    pub fn peer_write(
+        &mut self,
+        conn_id: u8,
+        service_id: u8,
+        attrib_index: u16,
+        value: &[u8],
+    ) -> bool {
+        if !self.is_connected(conn_id) {
+            return false;
+        }
+        match self.local_attribute(service_id, attrib_index) {
+            Some(attribute) => attribute.value = truncated(value),
+            None => return false,
+        }
+        self.emit(Event::AttributeWritten {
+            conn_id,
+            service_id,
+            attrib_index,
+            value: truncated(value),
+        });
+        true
+    }
+
+    /// The peer of the connection `conn_id` reads the local attribute `attrib_index`, whose value is then
+    /// the one returned by the host.
+    pub fn peer_read(&mut self, conn_id: u8, service_id: u8, attrib_index: u16) -> bool {
+        if !self.is_connected(conn_id) || self.local_attribute(service_id, attrib_index).is_none() {
+            return false;
+        }
+        self.emit(Event::AttributeRead {
+            conn_id,
+            service_id,
+            attrib_index,
+        });
+        true
+    }
+
+    /// The peer of the connection `conn_id` writes `cccd` to the client characteristic configuration of the
+    /// local characteristic `attrib_index`.
+    pub fn peer_subscribe(
+        &mut self,
+        conn_id: u8,
+        service_id: u8,
+        attrib_index: u16,
+        cccd: u16,
+    ) -> bool {
+        if !self.is_connected(conn_id) || self.local_attribute(service_id, attrib_index).is_none() {
+            return false;
+        }
+        self.emit(Event::CccdWritten {
+            conn_id,
+            service_id,
+            attrib_index,
+            cccd,
+        });
+        true
+    }
+
+    /// The peer of the connection `conn_id` notifies the GATT client `client_id` of the value of `handle`.
+    pub fn peer_notify(&mut self, conn_id: u8, client_id: u8, handle: u16, value: &[u8]) -> bool {
+        if !self.is_connected(conn_id) {
+            return false;
+        }
+        match self.peer_characteristic(handle) {
+            Some(characteristic) => characteristic.value = truncated(value),
+            None => return false,
+        }
+        self.emit(Event::Notification {
+            conn_id,
+            client_id,
+            handle,
+            value: truncated(value),
+        });
+        true
+    }
+
+    pub fn address(&self) -> [u8; 6] {
+        self.address
+    }
+
+    pub fn device_state(&self) -> DeviceState {
+        self.state
+    }
+
+    /// Value of a parameter set through any of the `rpc_*_set_param` functions.
+    pub fn param(&self, param: u32) -> Option<&[u8]> {
+        self.params
+            .iter()
+            .find(|(stored, _)| *stored == param)
+            .map(|(_, value)| &value[..])
+    }
+
+    /// The device name, without its terminating NUL.
+    pub fn device_name(&self) -> &[u8] {
+        let name = self
+            .param(RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_DEVICE_NAME as u32)
+            .unwrap_or(&[]);
+        let length = name
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(name.len());
+        &name[..length]
+    }
+
+    pub fn appearance(&self) -> u16 {
+        self.param_u16(RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_APPEARANCE as u32)
+            .unwrap_or(0)
+    }
+
+    /// The advertising parameters, or `None` if the advertising type set is not one of [`AdvertisingType`].
+    pub fn advertising_parameters(&self) -> Option<AdvertisingParameters> {
+        use RPC_T_LE_ADV_PARAM_TYPE::*;
+        let advertising_type = match self.param_u8(RPC_GAP_PARAM_ADV_EVENT_TYPE as u32)? {
+            0 => AdvertisingType::ConnectableUndirected,
+            1 => AdvertisingType::ConnectableHighDutyDirected,
+            2 => AdvertisingType::ScannableUndirected,
+            3 => AdvertisingType::NonConnectableUndirected,
+            4 => AdvertisingType::ConnectableLowDutyDirected,
+            _ => return None,
+        };
+        Some(AdvertisingParameters {
+            advertising_type,
+            interval_min: self.param_u16(RPC_GAP_PARAM_ADV_INTERVAL_MIN as u32)?,
+            interval_max: self.param_u16(RPC_GAP_PARAM_ADV_INTERVAL_MAX as u32)?,
+            channel_map: self.param_u8(RPC_GAP_PARAM_ADV_CHANNEL_MAP as u32)?,
+            filter_policy: self.param_u8(RPC_GAP_PARAM_ADV_FILTER_POLICY as u32)?,
+        })
+    }
+
+    pub fn advertising_data(&self) -> &[u8] {
+        self.param(RPC_T_LE_ADV_PARAM_TYPE::RPC_GAP_PARAM_ADV_DATA as u32)
+            .unwrap_or(&[])
+    }
+
+    pub fn scan_response_data(&self) -> &[u8] {
+        self.param(RPC_T_LE_ADV_PARAM_TYPE::RPC_GAP_PARAM_SCAN_RSP_DATA as u32)
+            .unwrap_or(&[])
+    }
+
+    /// The scan parameters, or `None` if the scan mode set is not one of [`ScanMode`].
+    pub fn scan_parameters(&self) -> Option<ScanParameters> {
+        use RPC_T_LE_SCAN_PARAM_TYPE::*;
+        let mode = match self.param_u8(RPC_GAP_PARAM_SCAN_MODE as u32)? {
+            0 => ScanMode::Passive,
+            1 => ScanMode::Active,
+            _ => return None,
+        };
+        Some(ScanParameters {
+            mode,
+            interval: self.param_u16(RPC_GAP_PARAM_SCAN_INTERVAL as u32)?,
+            window: self.param_u16(RPC_GAP_PARAM_SCAN_WINDOW as u32)?,
+            filter_policy: self.param_u8(RPC_GAP_PARAM_SCAN_FILTER_POLICY as u32)?,
+            filter_duplicates: self.param_u8(RPC_GAP_PARAM_SCAN_FILTER_DUPLICATES as u32)? != 0,
+        })
+    }
+
+    pub fn link(&self, conn_id: u8) -> Option<&Link> {
+        self.links.get(conn_id as usize)
+    }
+
+    /// The used bond entries, from the highest priority to the lowest.
+    pub fn bonds(&self) -> impl Iterator<Item = &RPC_T_LE_KEY_ENTRY> {
+        self.bond_priorities
+            .iter()
+            .map(move |&idx| &self.bonds[idx as usize])
+    }
+
+    pub fn peer_services(&self) -> &[PeerService] {
+        &self.peer_services
+    }
+
+    pub fn local_service(&self, service_id: u8) -> Option<&LocalService> {
+        self.local_services.get(service_id as usize)?.as_ref()
+    }
+
+    /// Data sent by the host, the oldest first. Only the last [`MAX_EVENTS`] are kept.
+    pub fn sent_data(&self) -> impl Iterator<Item = &SentData> {
+        self.sent_data.iter()
+    }
+
+    /// Takes the oldest event pending delivery.
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Queues `event`, dropping the oldest pending event if [`MAX_EVENTS`] are pending.
+    fn emit(&mut self, event: Event) {
+        if self.events.is_full() {
+            self.events.pop_front();
+        }
+        let _ = self.events.push_back(event);
+    }
+
+    fn emit_state(&mut self) {
+        self.emit(Event::DeviceState {
+            state: self.state,
+            cause: 0,
+        });
+    }
+
+    fn emit_connection_state(&mut self, conn_id: u8, disc_cause: u16) {
+        self.emit(Event::ConnectionState {
+            conn_id,
+            state: self.links[conn_id as usize].state,
+            disc_cause,
+        });
+    }
+
+    fn store_param(&mut self, param: u32, value: &[u8]) -> RPC_T_GAP_CAUSE {
+        let value = match Vec::from_slice(value) {
+            Ok(value) => value,
+            Err(()) => return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM,
+        };
+        if let Some((_, stored)) = self.params.iter_mut().find(|(stored, _)| *stored == param) {
+            *stored = value;
+        } else if self.params.push((param, value)).is_err() {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NO_RESOURCE;
+        }
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    /// Stores `value` if its length is one of the lengths of the parameters the simulator interprets.
+    fn set_param(&mut self, param: u32, value: &[u8]) -> RPC_T_GAP_CAUSE {
+        use RPC_T_GAP_LE_PARAM_TYPE::*;
+        use RPC_T_LE_ADV_PARAM_TYPE::*;
+        use RPC_T_LE_SCAN_PARAM_TYPE::*;
+        let valid = match param {
+            param if param == RPC_GAP_PARAM_DEVICE_NAME as u32 => value.len() <= MAX_PARAM_LENGTH,
+            param
+                if param == RPC_GAP_PARAM_ADV_DATA as u32
+                    || param == RPC_GAP_PARAM_SCAN_RSP_DATA as u32 =>
+            {
+                value.len() <= MAX_ADVERTISING_DATA_LENGTH
+            }
+            param
+                if param == RPC_GAP_PARAM_APPEARANCE as u32
+                    || param == RPC_GAP_PARAM_ADV_INTERVAL_MIN as u32
+                    || param == RPC_GAP_PARAM_ADV_INTERVAL_MAX as u32
+                    || param == RPC_GAP_PARAM_SCAN_INTERVAL as u32
+                    || param == RPC_GAP_PARAM_SCAN_WINDOW as u32 =>
+            {
+                value.len() == 2
+            }
+            param
+                if param == RPC_GAP_PARAM_ADV_EVENT_TYPE as u32
+                    || param == RPC_GAP_PARAM_ADV_CHANNEL_MAP as u32
+                    || param == RPC_GAP_PARAM_ADV_FILTER_POLICY as u32
+                    || param == RPC_GAP_PARAM_SCAN_MODE as u32
+                    || param == RPC_GAP_PARAM_SCAN_FILTER_POLICY as u32
+                    || param == RPC_GAP_PARAM_SCAN_FILTER_DUPLICATES as u32 =>
+            {
+                value.len() == 1
+            }
+            _ => true,
+        };
+        if !valid {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM;
+        }
+        self.store_param(param, value)
+    }
+
+    fn get_param<'value>(
+        &self,
+        param: u32,
+        value: &'value mut [u8],
+    ) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+        match self.param(param) {
+            Some(stored) => copy_value(stored, value),
+            None => (&[], RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM),
+        }
+    }
+
+    fn param_u8(&self, param: u32) -> Option<u8> {
+        match self.param(param)? {
+            [value] => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn param_u16(&self, param: u32) -> Option<u16> {
+        match self.param(param)? {
+            [low, high] => Some(u16::from_le_bytes([*low, *high])),
+            _ => None,
+        }
+    }
+
+    fn is_connected(&self, conn_id: u8) -> bool {
+        self.link(conn_id).is_some_and(Link::is_connected)
+    }
+
+    /// `RPC_GAP_CAUSE_SUCCESS` if the connection `conn_id` is established, `RPC_GAP_CAUSE_NON_CONN` otherwise.
+    fn check_connected(&self, conn_id: u8) -> RPC_T_GAP_CAUSE {
+        if self.is_connected(conn_id) {
+            RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+        } else {
+            RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NON_CONN
+        }
+    }
+
+    fn free_link(&self) -> Option<u8> {
+        self.links
+            .iter()
+            .position(|link| link.state == RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_DISCONNECTED)
+            .map(|conn_id| conn_id as u8)
+    }
+
+    fn new_link(&self, remote_bd: [u8; 6], remote_bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE) -> Link {
+        Link {
+            remote_bd,
+            remote_bd_type,
+            interval: self.conn_request.conn_interval_max,
+            latency: self.conn_request.conn_latency,
+            supervision_timeout: self.conn_request.supv_tout,
+            mtu: DEFAULT_MTU,
+            ..Link::default()
+        }
+    }
+
+    /// Disconnects `conn_id`, reporting `disc_cause`.
+    fn drop_link(&mut self, conn_id: u8, disc_cause: u16) -> bool {
+        if !self.is_connected(conn_id) {
+            return false;
+        }
+        let link = &mut self.links[conn_id as usize];
+        link.state = RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_DISCONNECTING;
+        self.emit_connection_state(conn_id, 0);
+        self.links[conn_id as usize] = Link::default();
+        self.emit_connection_state(conn_id, disc_cause);
+        true
+    }
+
+    fn find_bond(&self, address: &[u8; 6], address_type: RPC_T_GAP_REMOTE_ADDR_TYPE) -> Option<u8> {
+        self.bond_priorities.iter().copied().find(|&idx| {
+            let remote_bd = &self.bonds[idx as usize].remote_bd;
+            remote_bd.addr == *address && remote_bd.remote_bd_type == address_type as u8
+        })
+    }
+
+    /// Bonds with `address`, replacing the bond with the lowest priority if all entries are used.
+    fn add_bond(
+        &mut self,
+        address: &[u8; 6],
+        address_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+        local_bd_type: RPC_T_GAP_LOCAL_ADDR_TYPE,
+    ) -> RPC_T_LE_KEY_ENTRY {
+        let idx = match self.find_bond(address, address_type) {
+            Some(idx) => idx,
+            None => match self.bonds.iter().position(|entry| !entry.is_used) {
+                Some(idx) => idx as u8,
+                None => self.bond_priorities.pop().unwrap(),
+            },
+        };
+        self.bond_priorities.retain(|&used| used != idx);
+        // The new bond has the highest priority.
+        let _ = self.bond_priorities.insert(0, idx);
+        let remote_bd = RPC_T_LE_REMOTE_BD {
+            addr: *address,
+            remote_bd_type: address_type as u8,
+            bond_flags: 0,
+        };
+        self.bonds[idx as usize] = RPC_T_LE_KEY_ENTRY {
+            is_used: true,
+            idx,
+            local_bd_type: local_bd_type as u8,
+            remote_bd,
+            resolved_remote_bd: remote_bd,
+            ..RPC_T_LE_KEY_ENTRY::default()
+        };
+        self.bonds[idx as usize]
+    }
+
+    fn delete_bond(&mut self, idx: u8) -> RPC_T_GAP_CAUSE {
+        match self.bonds.get_mut(idx as usize) {
+            Some(entry) if entry.is_used => {
+                *entry = RPC_T_LE_KEY_ENTRY::default();
+                self.bond_priorities.retain(|&used| used != idx);
+                RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+            }
+            _ => RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NOT_FIND,
+        }
+    }
+
+    fn bond_entry(&self, idx: Option<u8>) -> RPC_T_LE_KEY_ENTRY {
+        idx.map_or_else(RPC_T_LE_KEY_ENTRY::default, |idx| self.bonds[idx as usize])
+    }
+
+    fn local_attribute(
+        &mut self,
+        service_id: u8,
+        attrib_index: u16,
+    ) -> Option<&mut LocalAttribute> {
+        self.local_services
+            .get_mut(service_id as usize)?
+            .as_mut()?
+            .attributes
+            .get_mut(attrib_index as usize)
+    }
+
+    fn peer_characteristic(&mut self, handle: u16) -> Option<&mut PeerCharacteristic> {
+        self.peer_services
+            .iter_mut()
+            .flat_map(|service| service.characteristics.iter_mut())
+            .find(|characteristic| characteristic.value_handle == handle)
+    }
+
+    /// Checks that a GATT client procedure can run on `conn_id` for `client_id`.
+    fn check_client(&self, conn_id: u8, client_id: u8) -> RPC_T_GAP_CAUSE {
+        if client_id >= self.client_count {
+            RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM
+        } else {
+            self.check_connected(conn_id)
+        }
+    }
+
+    /// Reports the peer services accepted by `filter`, then the end of the discovery.
+    fn discover_services(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        filter: impl Fn(&PeerService) -> bool,
+    ) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_client(conn_id, client_id);
+        if cause != RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            return cause;
+        }
+        for index in 0..self.peer_services.len() {
+            let service = &self.peer_services[index];
+            if filter(service) {
+                let event = Event::ServiceFound {
+                    conn_id,
+                    client_id,
+                    start_handle: service.start_handle,
+                    end_handle: service.end_handle,
+                    uuid16: service.uuid16,
+                };
+                self.emit(event);
+            }
+        }
+        self.emit(Event::DiscoveryComplete { conn_id, client_id });
+        cause
+    }
+
+    /// Reports the peer characteristics between `start_handle` and `end_handle` accepted by `filter`,
+    /// then the end of the discovery.
+    fn discover_characteristics(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        start_handle: u16,
+        end_handle: u16,
+        filter: impl Fn(&PeerCharacteristic) -> bool,
+    ) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_client(conn_id, client_id);
+        if cause != RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            return cause;
+        }
+        let mut found: Vec<Event, MAX_EVENTS> = Vec::new();
+        for characteristic in self
+            .peer_services
+            .iter()
+            .flat_map(|service| service.characteristics.iter())
+        {
+            let declaration_handle = characteristic.value_handle - 1;
+            if declaration_handle >= start_handle
+                && characteristic.value_handle <= end_handle
+                && filter(characteristic)
+            {
+                let _ = found.push(Event::CharacteristicFound {
+                    conn_id,
+                    client_id,
+                    declaration_handle,
+                    properties: characteristic.properties,
+                    value_handle: characteristic.value_handle,
+                    uuid16: characteristic.uuid16,
+                });
+            }
+        }
+        for event in found {
+            self.emit(event);
+        }
+        self.emit(Event::DiscoveryComplete { conn_id, client_id });
+        cause
+    }
+
+    /// Reports the end of a discovery which finds nothing, as the database has no 128-bit UUIDs nor descriptors.
+    fn discover_nothing(&mut self, conn_id: u8, client_id: u8) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_client(conn_id, client_id);
+        if cause == RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            self.emit(Event::DiscoveryComplete { conn_id, client_id });
+        }
+        cause
+    }
+
+    fn read_result(&mut self, conn_id: u8, client_id: u8, handle: Option<u16>) {
+        let characteristic = handle.and_then(|handle| self.peer_characteristic(handle));
+        let (cause, value) = match characteristic {
+            Some(characteristic) => (
+                RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS,
+                characteristic.value.clone(),
+            ),
+            None => (RPC_T_APP_RESULT::RPC_APP_RESULT_ATTR_NOT_FOUND, Vec::new()),
+        };
+        self.emit(Event::ReadResult {
+            conn_id,
+            client_id,
+            handle: handle.unwrap_or(0),
+            cause,
+            value,
+        });
+    }
+}
+
+/// Copies `value` at most `MAX` bytes long.
+fn truncated<const MAX: usize>(value: &[u8]) -> Vec<u8, MAX> {
+    Vec::from_slice(&value[..value.len().min(MAX)]).unwrap()
+}
+
+/// Copies `stored` to `value`, failing with `RPC_GAP_CAUSE_INVALID_PARAM` if it does not fit.
+fn copy_value<'value>(stored: &[u8], value: &'value mut [u8]) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+    match value.get_mut(..stored.len()) {
+        Some(value) => {
+            value.copy_from_slice(stored);
+            (value, RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS)
+        }
+        None => (&[], RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM),
+    }
+}
+
+impl rpc_ble_host::Irpc_ble_host for Simulator {
+    fn rpc_ble_init(&mut self) -> bool {
+        true
+    }
+
+    fn rpc_ble_start(&mut self) {
+        self.state.ready = true;
+        self.emit_state();
+    }
+
+    /// Stops the stack, dropping the connections, the GATT clients and services and the parameters.
+    /// The bonds and the peers are kept.
+    fn rpc_ble_deinit(&mut self) {
+        self.state = DeviceState::default();
+        self.links = [Link::default(); MAX_LINKS];
+        self.client_count = 0;
+        self.local_services.clear();
+        self.reset_params();
+        self.events.clear();
+    }
+}
+
+impl rpc_gap::Irpc_gap for Simulator {
+    fn rpc_gap_set_param(&mut self, param: RPC_T_GAP_PARAM_TYPE, value: &[u8]) -> RPC_T_GAP_CAUSE {
+        match param {
+            RPC_T_GAP_PARAM_TYPE::RPC_GAP_PARAM_BD_ADDR => {
+                RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM
+            }
+            param => self.set_param(param as u32, value),
+        }
+    }
+
+    fn rpc_gap_get_param<'value>(
+        &mut self,
+        param: RPC_T_GAP_PARAM_TYPE,
+        value: &'value mut [u8],
+    ) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+        match param {
+            RPC_T_GAP_PARAM_TYPE::RPC_GAP_PARAM_BD_ADDR => copy_value(&self.address, value),
+            param => self.get_param(param as u32, value),
+        }
+    }
+
+    fn rpc_gap_set_pairable_mode(&mut self) -> RPC_T_GAP_CAUSE {
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+}
+
+impl rpc_gap_bone::Irpc_gap_bone for Simulator {
+    fn rpc_le_bond_set_param(
+        &mut self,
+        param: RPC_T_LE_BOND_PARAM_TYPE,
+        value: &[u8],
+    ) -> RPC_T_GAP_CAUSE {
+        self.set_param(param as u32, value)
+    }
+
+    fn rpc_le_bond_get_param<'value>(
+        &mut self,
+        param: RPC_T_LE_BOND_PARAM_TYPE,
+        value: &'value mut [u8],
+    ) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+        self.get_param(param as u32, value)
+    }
+
+    /// Pairs with just works and bonds with the peer at once.
+    fn rpc_le_bond_pair(&mut self, conn_id: u8) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_connected(conn_id);
+        if cause != RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            return cause;
+        }
+        let link = &mut self.links[conn_id as usize];
+        link.sec_level = RPC_T_GAP_SEC_LEVEL::RPC_GAP_SEC_LEVEL_UNAUTHEN;
+        let (remote_bd, remote_bd_type) = (link.remote_bd, link.remote_bd_type);
+        self.add_bond(
+            &remote_bd,
+            remote_bd_type,
+            RPC_T_GAP_LOCAL_ADDR_TYPE::RPC_GAP_LOCAL_ADDR_LE_PUBLIC,
+        );
+        for &state in [GAP_AUTHEN_STATE_STARTED, GAP_AUTHEN_STATE_COMPLETE].iter() {
+            self.emit(Event::AuthenticationState {
+                conn_id,
+                state,
+                cause: 0,
+            });
+        }
+        cause
+    }
+
+    fn rpc_le_bond_get_display_key(&mut self, conn_id: u8) -> (u32, RPC_T_GAP_CAUSE) {
+        (0, self.check_connected(conn_id))
+    }
+
+    fn rpc_le_bond_passkey_input_confirm(
+        &mut self,
+        conn_id: u8,
+        _passcode: u32,
+        _cause: RPC_T_GAP_CFM_CAUSE,
+    ) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_bond_oob_input_confirm(
+        &mut self,
+        conn_id: u8,
+        _cause: RPC_T_GAP_CFM_CAUSE,
+    ) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_bond_just_work_confirm(
+        &mut self,
+        conn_id: u8,
+        _cause: RPC_T_GAP_CFM_CAUSE,
+    ) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_bond_passkey_display_confirm(
+        &mut self,
+        conn_id: u8,
+        _cause: RPC_T_GAP_CFM_CAUSE,
+    ) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_bond_user_confirm(
+        &mut self,
+        conn_id: u8,
+        _cause: RPC_T_GAP_CFM_CAUSE,
+    ) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_bond_cfg_local_key_distribute(
+        &mut self,
+        _init_dist: u8,
+        _rsp_dist: u8,
+    ) -> RPC_T_GAP_CAUSE {
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_bond_clear_all_keys(&mut self) {
+        self.bonds = [RPC_T_LE_KEY_ENTRY::default(); MAX_BONDS];
+        self.bond_priorities.clear();
+    }
+
+    fn rpc_le_bond_delete_by_idx(&mut self, idx: u8) -> RPC_T_GAP_CAUSE {
+        self.delete_bond(idx)
+    }
+
+    fn rpc_le_bond_delete_by_bd(
+        &mut self,
+        bd_addr: &[u8; 6],
+        bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+    ) -> RPC_T_GAP_CAUSE {
+        match self.find_bond(bd_addr, bd_type) {
+            Some(idx) => self.delete_bond(idx),
+            None => RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NOT_FIND,
+        }
+    }
+
+    fn rpc_le_bond_get_sec_level(&mut self, conn_id: u8) -> (RPC_T_GAP_SEC_LEVEL, RPC_T_GAP_CAUSE) {
+        let cause = self.check_connected(conn_id);
+        let level = match self.link(conn_id) {
+            Some(link) if link.is_connected() => link.sec_level,
+            _ => RPC_T_GAP_SEC_LEVEL::default(),
+        };
+        (level, cause)
+    }
+}
+
+impl rpc_gap_le::Irpc_gap_le for Simulator {
+    fn rpc_le_gap_init(&mut self, link_num: u8) -> bool {
+        link_num as usize <= MAX_LINKS
+    }
+
+    fn rpc_le_gap_msg_info_way(&mut self, _use_msg: bool) {}
+
+    fn rpc_le_get_max_link_num(&mut self) -> u8 {
+        MAX_LINKS as u8
+    }
+
+    fn rpc_le_set_gap_param(
+        &mut self,
+        param: RPC_T_GAP_LE_PARAM_TYPE,
+        value: &[u8],
+    ) -> RPC_T_GAP_CAUSE {
+        match param {
+            RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_RANDOM_ADDR => match value {
+                [_, _, _, _, _, _] => self.rpc_le_set_rand_addr(&[
+                    value[0], value[1], value[2], value[3], value[4], value[5],
+                ]),
+                _ => RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM,
+            },
+            param => self.set_param(param as u32, value),
+        }
+    }
+
+    fn rpc_le_get_gap_param<'value>(
+        &mut self,
+        param: RPC_T_GAP_LE_PARAM_TYPE,
+        value: &'value mut [u8],
+    ) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+        match param {
+            RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_DEV_STATE => {
+                copy_value(&[self.state.to_byte()], value)
+            }
+            RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_MAX_WL_SIZE => {
+                copy_value(&(MAX_PEERS as u16).to_le_bytes(), value)
+            }
+            param => self.get_param(param as u32, value),
+        }
+    }
+
+    fn rpc_le_modify_white_list(
+        &mut self,
+        operation: RPC_T_GAP_WHITE_LIST_OP,
+        bd_addr: &[u8; 6],
+        bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+    ) -> RPC_T_GAP_CAUSE {
+        match operation {
+            RPC_T_GAP_WHITE_LIST_OP::RPC_GAP_WHITE_LIST_OP_CLEAR => self.white_list.clear(),
+            RPC_T_GAP_WHITE_LIST_OP::RPC_GAP_WHITE_LIST_OP_ADD => {
+                if !self.white_list.contains(&(*bd_addr, bd_type))
+                    && self.white_list.push((*bd_addr, bd_type)).is_err()
+                {
+                    return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NO_RESOURCE;
+                }
+            }
+            RPC_T_GAP_WHITE_LIST_OP::RPC_GAP_WHITE_LIST_OP_REMOVE => self
+                .white_list
+                .retain(|entry| *entry != (*bd_addr, bd_type)),
+        }
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    /// Derives the address from the public address, with the two most significant bits of the type requested.
+    fn rpc_le_gen_rand_addr(
+        &mut self,
+        rand_addr_type: RPC_T_GAP_RAND_ADDR_TYPE,
+    ) -> ([u8; 6], RPC_T_GAP_CAUSE) {
+        let mut address = self.address;
+        let type_bits = match rand_addr_type {
+            RPC_T_GAP_RAND_ADDR_TYPE::RPC_GAP_RAND_ADDR_STATIC => 0xc0,
+            RPC_T_GAP_RAND_ADDR_TYPE::RPC_GAP_RAND_ADDR_NON_RESOLVABLE => 0x00,
+            RPC_T_GAP_RAND_ADDR_TYPE::RPC_GAP_RAND_ADDR_RESOLVABLE => 0x40,
+        };
+        address[5] = (address[5] & 0x3f) | type_bits;
+        (address, RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS)
+    }
+
+    fn rpc_le_set_rand_addr(&mut self, random_bd: &[u8; 6]) -> RPC_T_GAP_CAUSE {
+        self.random_address = *random_bd;
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_cfg_local_identity_address(
+        &mut self,
+        _addr: &[u8; 6],
+        _ident_addr_type: RPC_T_GAP_IDENT_ADDR_TYPE,
+    ) -> RPC_T_GAP_CAUSE {
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_set_host_chann_classif(&mut self, _p_channel_map: u8) -> RPC_T_GAP_CAUSE {
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_write_default_data_len(&mut self, _tx_octets: u16, _tx_time: u16) -> RPC_T_GAP_CAUSE {
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+}
+
+/// The configuration is accepted and ignored.
+impl rpc_gap_config::Irpc_gap_config for Simulator {
+    fn rpc_gap_config_cccd_not_check(
+        &mut self,
+        _cccd_not_check_flag: RPC_T_GAP_CONFIG_GATT_CCCD_NOT_CHECK,
+    ) {
+    }
+
+    fn rpc_gap_config_ccc_bits_count(
+        &mut self,
+        _gatt_server_ccc_bits_count: u8,
+        _gatt_storage_ccc_bits_count: u8,
+    ) {
+    }
+
+    fn rpc_gap_config_max_attribute_table_count(&mut self, _gatt_max_attribute_table_count: u8) {}
+
+    fn rpc_gap_config_max_mtu_size(&mut self, _att_max_mtu_size: u16) {}
+
+    fn rpc_gap_config_bte_pool_size(&mut self, _bte_pool_size: u8) {}
+
+    fn rpc_gap_config_bt_report_buf_num(&mut self, _bt_report_buf_num: u8) {}
+
+    fn rpc_gap_config_le_key_storage_flag(&mut self, _le_key_storage_flag: u16) {}
+
+    fn rpc_gap_config_max_le_paired_device(&mut self, _max_le_paired_device: u8) {}
+
+    fn rpc_gap_config_max_le_link_num(&mut self, _le_link_num: u8) {}
+}
+
+impl rpc_gap_adv::Irpc_gap_adv for Simulator {
+    fn rpc_le_adv_set_param(
+        &mut self,
+        param: RPC_T_LE_ADV_PARAM_TYPE,
+        value: &[u8],
+    ) -> RPC_T_GAP_CAUSE {
+        self.set_param(param as u32, value)
+    }
+
+    fn rpc_le_adv_get_param<'value>(
+        &mut self,
+        param: RPC_T_LE_ADV_PARAM_TYPE,
+        value: &'value mut [u8],
+    ) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+        self.get_param(param as u32, value)
+    }
+
+    fn rpc_le_adv_start(&mut self) -> RPC_T_GAP_CAUSE {
+        if !self.state.ready || self.state.advertising {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_STATE;
+        }
+        self.state.advertising = true;
+        self.emit_state();
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_adv_stop(&mut self) -> RPC_T_GAP_CAUSE {
+        if !self.state.advertising {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_STATE;
+        }
+        self.state.advertising = false;
+        self.emit_state();
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_adv_update_param(&mut self) -> RPC_T_GAP_CAUSE {
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+}
+
+impl rpc_gap_scan::Irpc_gap_scan for Simulator {
+    fn rpc_le_scan_set_param(
+        &mut self,
+        param: RPC_T_LE_SCAN_PARAM_TYPE,
+        value: &[u8],
+    ) -> RPC_T_GAP_CAUSE {
+        self.set_param(param as u32, value)
+    }
+
+    fn rpc_le_scan_get_param<'value>(
+        &mut self,
+        param: RPC_T_LE_SCAN_PARAM_TYPE,
+        value: &'value mut [u8],
+    ) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+        self.get_param(param as u32, value)
+    }
+
+    /// Starts scanning and reports each peer once.
+    fn rpc_le_scan_start(&mut self) -> RPC_T_GAP_CAUSE {
+        if !self.state.ready || self.state.scanning {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_STATE;
+        }
+        self.state.scanning = true;
+        self.emit_state();
+        for index in 0..self.peers.len() {
+            let peer = &self.peers[index];
+            let event = Event::ScanInfo {
+                address: peer.address,
+                address_type: peer.address_type,
+                rssi: peer.rssi,
+                data: peer.advertising_data.clone(),
+            };
+            self.emit(event);
+        }
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    /// Scans as `rpc_le_scan_start`, the simulator does not keep time.
+    fn rpc_le_scan_timer_start(&mut self, _tick: u32) -> RPC_T_GAP_CAUSE {
+        self.rpc_le_scan_start()
+    }
+
+    fn rpc_le_scan_stop(&mut self) -> RPC_T_GAP_CAUSE {
+        if !self.state.scanning {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_STATE;
+        }
+        self.state.scanning = false;
+        self.emit_state();
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_scan_info_filter(
+        &mut self,
+        _enable: bool,
+        _offset: u8,
+        _len: u8,
+        _p_filter: &[u8; 31],
+    ) -> bool {
+        true
+    }
+}
+
+impl rpc_gap_conn::Irpc_gap_conn for Simulator {
+    fn rpc_le_get_conn_param<'value>(
+        &mut self,
+        param: RPC_T_LE_CONN_PARAM_TYPE,
+        value: &'value mut [u8],
+        conn_id: u8,
+    ) -> (&'value [u8], RPC_T_GAP_CAUSE) {
+        use RPC_T_LE_CONN_PARAM_TYPE::*;
+        let link = match self.link(conn_id) {
+            Some(link) if link.is_connected() => *link,
+            _ => return (&[], RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NON_CONN),
+        };
+        match param {
+            RPC_GAP_PARAM_CONN_BD_ADDR => copy_value(&link.remote_bd, value),
+            RPC_GAP_PARAM_CONN_BD_ADDR_TYPE => copy_value(&[link.remote_bd_type as u8], value),
+            RPC_GAP_PARAM_CONN_INTERVAL => copy_value(&link.interval.to_le_bytes(), value),
+            RPC_GAP_PARAM_CONN_LATENCY => copy_value(&link.latency.to_le_bytes(), value),
+            RPC_GAP_PARAM_CONN_TIMEOUT => {
+                copy_value(&link.supervision_timeout.to_le_bytes(), value)
+            }
+            RPC_GAP_PARAM_CONN_MTU_SIZE => copy_value(&link.mtu.to_le_bytes(), value),
+            RPC_GAP_PARAM_CONN_LOCAL_BD_TYPE => copy_value(&[0], value),
+            RPC_GAP_PARAM_CONN_RX_PHY_TYPE | RPC_GAP_PARAM_CONN_TX_PHY_TYPE => {
+                copy_value(&[PHY_1M], value)
+            }
+            RPC_GAP_PARAM_CONN_REMOTE_FEATURES => copy_value(&[0; 8], value),
+            RPC_GAP_PARAM_CONN_HANDLE => copy_value(&u16::from(conn_id).to_le_bytes(), value),
+        }
+    }
+
+    fn rpc_le_get_conn_info(&mut self, conn_id: u8) -> (RPC_T_GAP_CONN_INFO, bool) {
+        match self.link(conn_id) {
+            Some(link) if link.state != RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_DISCONNECTED => (
+                RPC_T_GAP_CONN_INFO {
+                    conn_state: link.state,
+                    role: link.role,
+                    remote_bd: link.remote_bd,
+                    remote_bd_type: link.remote_bd_type as u8,
+                },
+                true,
+            ),
+            _ => (RPC_T_GAP_CONN_INFO::default(), false),
+        }
+    }
+
+    fn rpc_le_get_conn_addr(&mut self, conn_id: u8) -> ([u8; 6], u8, bool) {
+        match self.link(conn_id) {
+            Some(link) if link.is_connected() => (link.remote_bd, link.remote_bd_type as u8, true),
+            _ => ([0; 6], 0, false),
+        }
+    }
+
+    fn rpc_le_get_conn_id(&mut self, bd_addr: &[u8; 6], bd_type: u8) -> (u8, bool) {
+        let conn_id = self.links.iter().position(|link| {
+            link.is_connected()
+                && link.remote_bd == *bd_addr
+                && link.remote_bd_type as u8 == bd_type
+        });
+        match conn_id {
+            Some(conn_id) => (conn_id as u8, true),
+            None => (0, false),
+        }
+    }
+
+    fn rpc_le_get_active_link_num(&mut self) -> u8 {
+        self.links.iter().filter(|link| link.is_connected()).count() as u8
+    }
+
+    fn rpc_le_get_idle_link_num(&mut self) -> u8 {
+        MAX_LINKS as u8 - self.rpc_le_get_active_link_num()
+    }
+
+    fn rpc_le_disconnect(&mut self, conn_id: u8) -> RPC_T_GAP_CAUSE {
+        if self.drop_link(conn_id, DISCONNECT_CAUSE_LOCAL_HOST_TERMINATED) {
+            RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+        } else {
+            RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NON_CONN
+        }
+    }
+
+    fn rpc_le_read_rssi(&mut self, conn_id: u8) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_set_data_len(
+        &mut self,
+        conn_id: u8,
+        _tx_octets: u16,
+        _tx_time: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_set_phy(
+        &mut self,
+        conn_id: u8,
+        _all_phys: u8,
+        _tx_phys: u8,
+        _rx_phys: u8,
+        _phy_options: RPC_T_GAP_PHYS_OPTIONS,
+    ) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+
+    fn rpc_le_set_conn_param(
+        &mut self,
+        _conn_type: RPC_T_GAP_CONN_PARAM_TYPE,
+        p_conn_param: &RPC_T_GAP_LE_CONN_REQ_PARAM,
+    ) -> RPC_T_GAP_CAUSE {
+        self.conn_request = *p_conn_param;
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    /// Connects at once if `remote_bd` is a connectable peer, otherwise reports a connection timeout.
+    fn rpc_le_connect(
+        &mut self,
+        _init_phys: u8,
+        remote_bd: &[u8; 6],
+        remote_bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+        _local_bd_type: RPC_T_GAP_LOCAL_ADDR_TYPE,
+        _scan_timeout: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        if !self.state.ready || self.state.connecting {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_STATE;
+        }
+        let conn_id = match self.free_link() {
+            Some(conn_id) => conn_id,
+            None => return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_CONN_LIMIT,
+        };
+        let connectable = self.peers.iter().any(|peer| {
+            peer.connectable && peer.address == *remote_bd && peer.address_type == remote_bd_type
+        });
+        self.links[conn_id as usize] = Link {
+            state: RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_CONNECTING,
+            role: RPC_T_GAP_ROLE::RPC_GAP_LINK_ROLE_MASTER,
+            ..self.new_link(*remote_bd, remote_bd_type)
+        };
+        self.state.connecting = true;
+        self.emit_state();
+        self.emit_connection_state(conn_id, 0);
+        let disc_cause = if connectable {
+            self.links[conn_id as usize].state = RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_CONNECTED;
+            0
+        } else {
+            self.links[conn_id as usize] = Link::default();
+            DISCONNECT_CAUSE_CONNECTION_TIMEOUT
+        };
+        self.state.connecting = false;
+        self.emit_state();
+        self.emit_connection_state(conn_id, disc_cause);
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    }
+
+    fn rpc_le_update_conn_param(
+        &mut self,
+        conn_id: u8,
+        _conn_interval_min: u16,
+        conn_interval_max: u16,
+        conn_latency: u16,
+        supervision_timeout: u16,
+        _ce_length_min: u16,
+        _ce_length_max: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_connected(conn_id);
+        if cause == RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            let link = &mut self.links[conn_id as usize];
+            link.interval = conn_interval_max;
+            link.latency = conn_latency;
+            link.supervision_timeout = supervision_timeout;
+        }
+        cause
+    }
+}
+
+impl rpc_gap_storage::Irpc_gap_storage for Simulator {
+    fn rpc_flash_save_local_name(&mut self, p_data: &RPC_T_LOCAL_NAME) -> u32 {
+        self.local_name = *p_data;
+        0
+    }
+
+    fn rpc_flash_load_local_name(&mut self) -> (RPC_T_LOCAL_NAME, u32) {
+        (self.local_name, 0)
+    }
+
+    fn rpc_flash_save_local_appearance(&mut self, p_data: &RPC_T_LOCAL_APPEARANCE) -> u32 {
+        self.local_appearance = *p_data;
+        0
+    }
+
+    fn rpc_flash_load_local_appearance(&mut self) -> (RPC_T_LOCAL_APPEARANCE, u32) {
+        (self.local_appearance, 0)
+    }
+
+    fn rpc_le_find_key_entry(
+        &mut self,
+        bd_addr: &[u8; 6],
+        bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+    ) -> RPC_T_LE_KEY_ENTRY {
+        self.bond_entry(self.find_bond(bd_addr, bd_type))
+    }
+
+    fn rpc_le_find_key_entry_by_idx(&mut self, idx: u8) -> RPC_T_LE_KEY_ENTRY {
+        match self.bonds.get(idx as usize) {
+            Some(entry) if entry.is_used => *entry,
+            _ => RPC_T_LE_KEY_ENTRY::default(),
+        }
+    }
+
+    fn rpc_le_get_bond_dev_num(&mut self) -> u8 {
+        self.bond_priorities.len() as u8
+    }
+
+    fn rpc_le_get_low_priority_bond(&mut self) -> RPC_T_LE_KEY_ENTRY {
+        self.bond_entry(self.bond_priorities.last().copied())
+    }
+
+    fn rpc_le_get_high_priority_bond(&mut self) -> RPC_T_LE_KEY_ENTRY {
+        self.bond_entry(self.bond_priorities.first().copied())
+    }
+
+    fn rpc_le_set_high_priority_bond(
+        &mut self,
+        bd_addr: &[u8; 6],
+        bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+    ) -> bool {
+        match self.find_bond(bd_addr, bd_type) {
+            Some(idx) => {
+                self.bond_priorities.retain(|&used| used != idx);
+                let _ = self.bond_priorities.insert(0, idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Never resolves, as the simulator has no identity resolving keys.
+    fn rpc_le_resolve_random_address(
+        &mut self,
+        _unresolved_addr: &[u8; 6],
+        resolved_addr: &[u8; 6],
+        resolved_addr_type: RPC_T_GAP_IDENT_ADDR_TYPE,
+    ) -> ([u8; 6], RPC_T_GAP_IDENT_ADDR_TYPE, bool) {
+        (*resolved_addr, resolved_addr_type, false)
+    }
+
+    fn rpc_le_get_cccd_data(&mut self, p_entry: &RPC_T_LE_KEY_ENTRY) -> (RPC_T_LE_CCCD, bool) {
+        let used = self
+            .bonds
+            .get(p_entry.idx as usize)
+            .is_some_and(|entry| entry.is_used);
+        (RPC_T_LE_CCCD::default(), used)
+    }
+
+    fn rpc_le_gen_bond_dev(
+        &mut self,
+        bd_addr: &[u8; 6],
+        bd_type: RPC_T_GAP_REMOTE_ADDR_TYPE,
+        local_bd_type: RPC_T_GAP_LOCAL_ADDR_TYPE,
+        _local_ltk: &[u8],
+        _key_type: RPC_T_LE_KEY_TYPE,
+        _p_cccd: &RPC_T_LE_CCCD,
+    ) -> bool {
+        self.add_bond(bd_addr, bd_type, local_bd_type);
+        true
+    }
+
+    /// The bond information is the address of the peer followed by its type.
+    fn rpc_le_get_dev_bond_info_len(&mut self) -> u16 {
+        7
+    }
+
+    fn rpc_le_set_dev_bond_info(&mut self, p_data: &[u8]) -> (bool, RPC_T_LE_KEY_ENTRY) {
+        let address_type = match p_data {
+            [_, _, _, _, _, _, 0] => RPC_T_GAP_REMOTE_ADDR_TYPE::RPC_GAP_REMOTE_ADDR_LE_PUBLIC,
+            [_, _, _, _, _, _, 1] => RPC_T_GAP_REMOTE_ADDR_TYPE::RPC_GAP_REMOTE_ADDR_LE_RANDOM,
+            _ => return (false, RPC_T_LE_KEY_ENTRY::default()),
+        };
+        let mut address = [0u8; 6];
+        address.copy_from_slice(&p_data[..6]);
+        let entry = self.add_bond(
+            &address,
+            address_type,
+            RPC_T_GAP_LOCAL_ADDR_TYPE::RPC_GAP_LOCAL_ADDR_LE_PUBLIC,
+        );
+        (true, entry)
+    }
+
+    fn rpc_le_get_dev_bond_info<'p_data>(
+        &mut self,
+        p_entry: &RPC_T_LE_KEY_ENTRY,
+        p_data: &'p_data mut [u8],
+    ) -> (&'p_data [u8], bool) {
+        let entry = match self.bonds.get(p_entry.idx as usize) {
+            Some(entry) if entry.is_used => entry,
+            _ => return (&[], false),
+        };
+        let mut info = [0u8; 7];
+        info[..6].copy_from_slice(&entry.remote_bd.addr);
+        info[6] = entry.remote_bd.remote_bd_type;
+        let (info, cause) = copy_value(&info, p_data);
+        (info, cause == RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS)
+    }
+}
+
+/// Procedures run on the GATT database of the peers and complete at once, reporting their results as events.
+impl rpc_gatt_client::Irpc_gatt_client for Simulator {
+    fn rpc_ble_client_init(&mut self, _num: u8) -> bool {
+        true
+    }
+
+    fn rpc_ble_add_client(&mut self, _app_id: u8, _link_num: u8) -> u8 {
+        if self.client_count == INVALID_ID {
+            return INVALID_ID;
+        }
+        self.client_count += 1;
+        self.client_count - 1
+    }
+
+    fn rpc_client_init(&mut self, _client_num: u8) {}
+
+    fn rpc_client_all_primary_srv_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_services(conn_id, client_id, |_| true)
+    }
+
+    fn rpc_client_by_uuid_srv_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        uuid16: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_services(conn_id, client_id, |service| service.uuid16 == uuid16)
+    }
+
+    fn rpc_client_by_uuid128_srv_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        _p_uuid128: &[u8; 16],
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_nothing(conn_id, client_id)
+    }
+
+    fn rpc_client_relationship_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        _start_handle: u16,
+        _end_handle: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_nothing(conn_id, client_id)
+    }
+
+    fn rpc_client_all_char_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        start_handle: u16,
+        end_handle: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_characteristics(conn_id, client_id, start_handle, end_handle, |_| true)
+    }
+
+    fn rpc_client_by_uuid_char_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        start_handle: u16,
+        end_handle: u16,
+        uuid16: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_characteristics(
+            conn_id,
+            client_id,
+            start_handle,
+            end_handle,
+            |characteristic| characteristic.uuid16 == uuid16,
+        )
+    }
+
+    fn rpc_client_by_uuid128_char_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        _start_handle: u16,
+        _end_handle: u16,
+        _p_uuid128: &[u8; 16],
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_nothing(conn_id, client_id)
+    }
+
+    fn rpc_client_all_char_descriptor_discovery(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        _start_handle: u16,
+        _end_handle: u16,
+    ) -> RPC_T_GAP_CAUSE {
+        self.discover_nothing(conn_id, client_id)
+    }
+
+    fn rpc_client_attr_read(&mut self, conn_id: u8, client_id: u8, handle: u16) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_client(conn_id, client_id);
+        if cause == RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            self.read_result(conn_id, client_id, Some(handle));
+        }
+        cause
+    }
+
+    /// Reads the first characteristic with the 16-bit UUID `uuid16` between `start_handle` and `end_handle`.
+    fn rpc_client_attr_read_using_uuid(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        start_handle: u16,
+        end_handle: u16,
+        uuid16: u16,
+        _p_uuid128: &[u8; 16],
+    ) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_client(conn_id, client_id);
+        if cause == RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            let handle = self
+                .peer_services
+                .iter()
+                .flat_map(|service| service.characteristics.iter())
+                .find(|characteristic| {
+                    characteristic.uuid16 == uuid16
+                        && (start_handle..=end_handle).contains(&characteristic.value_handle)
+                })
+                .map(|characteristic| characteristic.value_handle);
+            self.read_result(conn_id, client_id, handle);
+        }
+        cause
+    }
+
+    /// Writes the value, reporting the result of write requests only.
+    fn rpc_client_attr_write(
+        &mut self,
+        conn_id: u8,
+        client_id: u8,
+        write_type: RPC_T_GATT_WRITE_TYPE,
+        handle: u16,
+        data: &[u8],
+    ) -> RPC_T_GAP_CAUSE {
+        let cause = self.check_client(conn_id, client_id);
+        if cause != RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS {
+            return cause;
+        }
+        if data.len() > MAX_VALUE_LENGTH {
+            return RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PDU_SIZE;
+        }
+        let result = match self.peer_characteristic(handle) {
+            Some(characteristic) => {
+                characteristic.value = truncated(data);
+                RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS
+            }
+            None => RPC_T_APP_RESULT::RPC_APP_RESULT_ATTR_NOT_FOUND,
+        };
+        if write_type == RPC_T_GATT_WRITE_TYPE::RPC_GATT_WRITE_TYPE_REQ {
+            self.emit(Event::WriteResult {
+                conn_id,
+                client_id,
+                handle,
+                cause: result,
+            });
+        }
+        cause
+    }
+
+    fn rpc_client_attr_ind_confirm(&mut self, conn_id: u8) -> RPC_T_GAP_CAUSE {
+        self.check_connected(conn_id)
+    }
+}
+
+impl rpc_gatt_server::Irpc_gatt_server for Simulator {
+    fn rpc_ble_server_init(&mut self, num: u8) -> bool {
+        num as usize <= MAX_SERVICES
+    }
+
+    fn rpc_ble_create_service(&mut self, uuid: &[u8; 16], uuid_length: u8, is_primary: bool) -> u8 {
+        let service = LocalService {
+            uuid: *uuid,
+            uuid_length,
+            is_primary,
+            started: false,
+            attributes: Vec::new(),
+        };
+        match self.local_services.push(Some(service)) {
+            Ok(()) => self.local_services.len() as u8 - 1,
+            Err(_) => INVALID_ID,
+        }
+    }
+
+    /// Deletes the service, its id is not reused.
+    fn rpc_ble_delete_service(&mut self, app_id: u8) -> bool {
+        match self.local_services.get_mut(app_id as usize) {
+            Some(service) => service.take().is_some(),
+            None => false,
+        }
+    }
+
+    fn rpc_ble_service_start(&mut self, app_id: u8) -> u8 {
+        match self.local_services.get_mut(app_id as usize) {
+            Some(Some(service)) => {
+                service.started = true;
+                app_id
+            }
+            _ => INVALID_ID,
+        }
+    }
+
+    fn rpc_ble_get_servie_handle(&mut self, app_id: u8) -> u8 {
+        match self.local_service(app_id) {
+            Some(service) if service.started => app_id,
+            _ => INVALID_ID,
+        }
+    }
+
+    /// Adds the characteristic to the service `app_id` and returns its attribute index.
+    fn rpc_ble_create_char(
+        &mut self,
+        app_id: u8,
+        uuid: &[u8; 16],
+        uuid_length: u8,
+        properties: u8,
+        permissions: u32,
+    ) -> u16 {
+        let attribute = LocalAttribute {
+            uuid: *uuid,
+            uuid_length,
+            properties,
+            permissions,
+            value: Vec::new(),
+        };
+        add_attribute(&mut self.local_services, app_id, attribute)
+    }
+
+    /// Adds the descriptor to the service `app_id` and returns its attribute index.
+    fn rpc_ble_create_desc(
+        &mut self,
+        app_id: u8,
+        _char_handle: u16,
+        uuid: &[u8; 16],
+        uuid_length: u8,
+        flags: u8,
+        permissions: u32,
+        _value_length: u16,
+        p_value: &[u8],
+    ) -> u16 {
+        let attribute = LocalAttribute {
+            uuid: *uuid,
+            uuid_length,
+            properties: flags,
+            permissions,
+            value: truncated(p_value),
+        };
+        add_attribute(&mut self.local_services, app_id, attribute)
+    }
+
+    /// Records the data in [`Simulator::sent_data`], dropping the oldest if [`MAX_EVENTS`] are recorded.
+    fn rpc_server_send_data(
+        &mut self,
+        conn_id: u8,
+        service_id: u8,
+        attrib_index: u16,
+        data: &[u8],
+        pdu_type: RPC_T_GATT_PDU_TYPE,
+    ) -> bool {
+        if !self.is_connected(conn_id)
+            || data.len() > MAX_VALUE_LENGTH
+            || self.local_attribute(service_id, attrib_index).is_none()
+        {
+            return false;
+        }
+        if self.sent_data.is_full() {
+            self.sent_data.pop_front();
+        }
+        let _ = self.sent_data.push_back(SentData {
+            conn_id,
+            service_id,
+            attrib_index,
+            pdu_type,
+            data: truncated(data),
+        });
+        true
+    }
+
+    fn rpc_ble_server_get_attr_value<'value>(
+        &mut self,
+        app_id: u8,
+        attr_handle: u16,
+        value: &'value mut [u8],
+    ) -> &'value [u8] {
+        match self.local_attribute(app_id, attr_handle) {
+            Some(attribute) => copy_value(&attribute.value, value).0,
+            None => &[],
+        }
+    }
+
+    fn rpc_server_exec_write_confirm(&mut self, conn_id: u8, _cause: u16, _handle: u16) -> bool {
+        self.is_connected(conn_id)
+    }
+
+    fn rpc_server_attr_write_confirm(
+        &mut self,
+        conn_id: u8,
+        _service_id: u8,
+        _attrib_index: u16,
+        _cause: RPC_T_APP_RESULT,
+    ) -> bool {
+        self.is_connected(conn_id)
+    }
+
+    /// Completes a read left pending by the host, storing the value in the attribute.
+    fn rpc_server_attr_read_confirm(
+        &mut self,
+        conn_id: u8,
+        service_id: u8,
+        attrib_index: u16,
+        data: &[u8],
+        cause: RPC_T_APP_RESULT,
+    ) -> bool {
+        if !self.is_connected(conn_id) {
+            return false;
+        }
+        match self.local_attribute(service_id, attrib_index) {
+            Some(attribute) => {
+                if cause == RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS {
+                    attribute.value = truncated(data);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn add_attribute(
+    services: &mut Vec<Option<LocalService>, MAX_SERVICES>,
+    app_id: u8,
+    attribute: LocalAttribute,
+) -> u16 {
+    let service = match services.get_mut(app_id as usize) {
+        Some(Some(service)) if !service.started => service,
+        _ => return INVALID_INDEX,
+    };
+    match service.attributes.push(attribute) {
+        Ok(()) => service.attributes.len() as u16 - 1,
+        Err(_) => INVALID_INDEX,
+    }
+}
+
+type Shared<'simulator> = &'simulator RefCell<Simulator>;
+
+/// Number of services of [`SimulatorServices`].
+pub const SERVICE_COUNT: usize = 11;
+/// Number of services of [`SimulatorServices`] decoding strings and binaries, which share the scratch buffer.
+const SCRATCH_SERVICES: usize = 9;
+
+/// The generated `rpc_ble_api` services, all dispatching to one shared [`Simulator`].
+///
+/// Register them with a `SimpleServer` or a `TransportArbitrator`, see the [module documentation](self).
+pub struct SimulatorServices<'simulator> {
+    rpc_ble_host: rpc_ble_host::Service<'simulator, Shared<'simulator>>,
+    rpc_gap: rpc_gap::Service<'simulator, Shared<'simulator>>,
+    rpc_gap_bone: rpc_gap_bone::Service<'simulator, Shared<'simulator>>,
+    rpc_gap_le: rpc_gap_le::Service<'simulator, Shared<'simulator>>,
+    rpc_gap_config: rpc_gap_config::Service<'simulator, Shared<'simulator>>,
+    rpc_gap_adv: rpc_gap_adv::Service<'simulator, Shared<'simulator>>,
+    rpc_gap_scan: rpc_gap_scan::Service<'simulator, Shared<'simulator>>,
+    rpc_gap_conn: rpc_gap_conn::Service<'simulator, Shared<'simulator>>,
+    rpc_gap_storage: rpc_gap_storage::Service<'simulator, Shared<'simulator>>,
+    rpc_gatt_client: rpc_gatt_client::Service<'simulator, Shared<'simulator>>,
+    rpc_gatt_server: rpc_gatt_server::Service<'simulator, Shared<'simulator>>,
+}
+
+impl<'simulator> SimulatorServices<'simulator> {
+    /// Creates the services of `simulator`.
+    ///
+    /// The services taking strings and binaries each get an even part of `scratch`, into which they decode them
+    /// and which they also split to receive the values read.
+    pub fn new(simulator: &'simulator RefCell<Simulator>, scratch: &'simulator mut [u8]) -> Self {
+        let length = scratch.len() / SCRATCH_SERVICES;
+        let mut parts = scratch.chunks_mut(length.max(1));
+        let mut scratch = || parts.next().unwrap_or_default();
+        Self {
+            rpc_ble_host: rpc_ble_host::Service::new(simulator),
+            rpc_gap: rpc_gap::Service::with_scratch(simulator, scratch()),
+            rpc_gap_bone: rpc_gap_bone::Service::with_scratch(simulator, scratch()),
+            rpc_gap_le: rpc_gap_le::Service::with_scratch(simulator, scratch()),
+            rpc_gap_config: rpc_gap_config::Service::new(simulator),
+            rpc_gap_adv: rpc_gap_adv::Service::with_scratch(simulator, scratch()),
+            rpc_gap_scan: rpc_gap_scan::Service::with_scratch(simulator, scratch()),
+            rpc_gap_conn: rpc_gap_conn::Service::with_scratch(simulator, scratch()),
+            rpc_gap_storage: rpc_gap_storage::Service::with_scratch(simulator, scratch()),
+            rpc_gatt_client: rpc_gatt_client::Service::with_scratch(simulator, scratch()),
+            rpc_gatt_server: rpc_gatt_server::Service::with_scratch(simulator, scratch()),
+        }
+    }
+
+    /// The services, to pass to `add_service`.
+    pub fn services<'object, CursorType, CodecType>(
+        &mut self,
+    ) -> [&mut (dyn rpc::Service<CursorType, CodecType> + 'object); SERVICE_COUNT]
+    where
+        'simulator: 'object,
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        [
+            &mut self.rpc_ble_host,
+            &mut self.rpc_gap,
+            &mut self.rpc_gap_bone,
+            &mut self.rpc_gap_le,
+            &mut self.rpc_gap_config,
+            &mut self.rpc_gap_adv,
+            &mut self.rpc_gap_scan,
+            &mut self.rpc_gap_conn,
+            &mut self.rpc_gap_storage,
+            &mut self.rpc_gatt_client,
+            &mut self.rpc_gatt_server,
+        ]
+    }
+}
+
+/// Delivers the pending events of `simulator`, such as those caused by the requests just served or by the
+/// `peer_*` methods of [`Simulator`], by calling `rpc_ble_callback` through `callbacks`.
+///
+/// Over a single link, `callbacks` is the `TransportArbitrator` serving the requests, which serves the requests
+/// of the host received while a callback is pending.
+pub fn deliver_events<Error, CursorType, CodecType, CallerType>(
+    simulator: &RefCell<Simulator>,
+    callbacks: &mut CallerType,
+) -> Result<(), RequestResponseError<Error>>
+where
+    CallerType: Caller<Error, CursorType, CodecType>,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    loop {
+        // The simulator is not borrowed during the call, as the arbitrator may serve requests meanwhile.
+        let event = simulator.borrow_mut().next_event();
+        match event {
+            Some(event) => deliver(simulator, callbacks, event)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Calls the callback reporting `event`. The result returned by the host is ignored,
+/// except the value returned for [`Event::AttributeRead`].
+fn deliver<Error, CursorType, CodecType, CallerType>(
+    simulator: &RefCell<Simulator>,
+    callbacks: &mut CallerType,
+    event: Event,
+) -> Result<(), RequestResponseError<Error>>
+where
+    CallerType: Caller<Error, CursorType, CodecType>,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    let mut callbacks = rpc_ble_callback::Client::new(callbacks);
+    let mut data = [0u8; 10 + MAX_ADVERTISING_DATA_LENGTH];
+    match event {
+        Event::DeviceState { state, cause } => {
+            let param = [state.to_byte(), 0, cause as u8, (cause >> 8) as u8];
+            callbacks.rpc_ble_handle_gap_msg(&gap_msg(GAP_MSG_LE_DEV_STATE_CHANGE, param))?;
+        }
+        Event::ConnectionState {
+            conn_id,
+            state,
+            disc_cause,
+        } => {
+            let cause = disc_cause.to_le_bytes();
+            let param = [conn_id, state as u8, cause[0], cause[1]];
+            callbacks.rpc_ble_handle_gap_msg(&gap_msg(GAP_MSG_LE_CONN_STATE_CHANGE, param))?;
+        }
+        Event::AuthenticationState {
+            conn_id,
+            state,
+            cause,
+        } => {
+            let cause = cause.to_le_bytes();
+            let param = [conn_id, state, cause[0], cause[1]];
+            callbacks.rpc_ble_handle_gap_msg(&gap_msg(GAP_MSG_LE_AUTHEN_STATE_CHANGE, param))?;
+        }
+        Event::ScanInfo {
+            address,
+            address_type,
+            rssi,
+            data: advertising_data,
+        } => {
+            /// `GAP_ADV_EVT_TYPE_UNDIRECTED` of the BLE stack.
+            const ADV_EVT_TYPE_UNDIRECTED: u8 = 0;
+            data[..6].copy_from_slice(&address);
+            data[6] = address_type as u8;
+            data[7] = ADV_EVT_TYPE_UNDIRECTED;
+            data[8] = rssi as u8;
+            data[9] = advertising_data.len() as u8;
+            data[10..10 + advertising_data.len()].copy_from_slice(&advertising_data);
+            callbacks.rpc_ble_gap_callback(GAP_MSG_LE_SCAN_INFO, &data)?;
+        }
+        Event::ServiceFound {
+            conn_id,
+            client_id,
+            start_handle,
+            end_handle,
+            uuid16,
+        } => {
+            let cb_data = gattc_data(
+                &mut data,
+                GATTC_SERVICE_FOUND,
+                &[start_handle, end_handle, uuid16],
+            );
+            callbacks.rpc_ble_gattc_callback(client_id, conn_id, cb_data, &[])?;
+        }
+        Event::CharacteristicFound {
+            conn_id,
+            client_id,
+            declaration_handle,
+            properties,
+            value_handle,
+            uuid16,
+        } => {
+            let cb_data = gattc_data(
+                &mut data,
+                GATTC_CHARACTERISTIC_FOUND,
+                &[declaration_handle, properties.into(), value_handle, uuid16],
+            );
+            callbacks.rpc_ble_gattc_callback(client_id, conn_id, cb_data, &[])?;
+        }
+        Event::DiscoveryComplete { conn_id, client_id } => {
+            let cb_data = gattc_data(&mut data, GATTC_DISCOVERY_COMPLETE, &[]);
+            callbacks.rpc_ble_gattc_callback(client_id, conn_id, cb_data, &[])?;
+        }
+        Event::ReadResult {
+            conn_id,
+            client_id,
+            handle,
+            cause,
+            value,
+        } => {
+            let cb_data = gattc_data(&mut data, GATTC_READ_RESULT, &[handle, cause as u16]);
+            callbacks.rpc_ble_gattc_callback(client_id, conn_id, cb_data, &value)?;
+        }
+        Event::WriteResult {
+            conn_id,
+            client_id,
+            handle,
+            cause,
+        } => {
+            let cb_data = gattc_data(&mut data, GATTC_WRITE_RESULT, &[handle, cause as u16]);
+            callbacks.rpc_ble_gattc_callback(client_id, conn_id, cb_data, &[])?;
+        }
+        Event::Notification {
+            conn_id,
+            client_id,
+            handle,
+            value,
+        } => {
+            let cb_data = gattc_data(&mut data, GATTC_NOTIFICATION, &[handle]);
+            callbacks.rpc_ble_gattc_callback(client_id, conn_id, cb_data, &value)?;
+        }
+        Event::CccdWritten {
+            conn_id,
+            service_id,
+            attrib_index,
+            cccd,
+        } => {
+            callbacks.rpc_ble_gatts_callback(
+                service_id,
+                conn_id,
+                attrib_index,
+                RPC_T_SERVICE_CALLBACK_TYPE::RPC_SERVICE_CALLBACK_TYPE_INDIFICATION_NOTIFICATION,
+                0,
+                &mut [],
+                &cccd.to_le_bytes(),
+                &[],
+            )?;
+        }
+        Event::AttributeRead {
+            conn_id,
+            service_id,
+            attrib_index,
+        } => {
+            let mut value = [0u8; MAX_VALUE_LENGTH];
+            let (value, result) = callbacks.rpc_ble_gatts_callback(
+                service_id,
+                conn_id,
+                attrib_index,
+                RPC_T_SERVICE_CALLBACK_TYPE::RPC_SERVICE_CALLBACK_TYPE_READ_CHAR_VALUE,
+                0,
+                &mut value,
+                &[],
+                &[],
+            )?;
+            if result == RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS {
+                if let Some(attribute) = simulator
+                    .borrow_mut()
+                    .local_attribute(service_id, attrib_index)
+                {
+                    attribute.value = truncated(value);
+                }
+            }
+        }
+        Event::AttributeWritten {
+            conn_id,
+            service_id,
+            attrib_index,
+            value,
+        } => {
+            callbacks.rpc_ble_gatts_callback(
+                service_id,
+                conn_id,
+                attrib_index,
+                RPC_T_SERVICE_CALLBACK_TYPE::RPC_SERVICE_CALLBACK_TYPE_WRITE_CHAR_VALUE,
+                0,
+                &mut [],
+                &value,
+                &[],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// `T_IO_MSG` of the GAP message `subtype` with the parameter `param`.
+fn gap_msg(subtype: u16, param: [u8; 4]) -> [u8; 8] {
+    let mut msg = [0u8; 8];
+    msg[..2].copy_from_slice(&IO_MSG_TYPE_BT_STATUS.to_le_bytes());
+    msg[2..4].copy_from_slice(&subtype.to_le_bytes());
+    msg[4..].copy_from_slice(&param);
+    msg
+}
+
+/// `cb_data` of `rpc_ble_gattc_callback`, the kind followed by `fields`.
+fn gattc_data<'data>(data: &'data mut [u8], kind: u8, fields: &[u16]) -> &'data [u8] {
+    data[0] = kind;
+    for (index, field) in fields.iter().enumerate() {
+        data[1 + 2 * index..3 + 2 * index].copy_from_slice(&field.to_le_bytes());
+    }
+    &data[..1 + 2 * fields.len()]
+}
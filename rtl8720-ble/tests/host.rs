@@ -0,0 +1,221 @@
+use rtl8720_ble::host::{AdvertisingParameters, BleError, BleHost};
+use rtl8720_ble::rpc_ble_api::*;
+use rust_erpc::arbitrator::TransportArbitrator;
+use rust_erpc::codec::BasicCodecFactory;
+use rust_erpc::framed_transport::{FramedTransport, FramedTransportError};
+use rust_erpc::rpc;
+use std::collections::VecDeque;
+
+/// Records sent frames and answers each with the next prepared reply payload.
+///
+/// The prepared requests of the RTL8720 are received before the next reply.
+#[derive(Default)]
+struct MockTransport {
+    sent: Vec<Vec<u8>>,
+    replies: VecDeque<Vec<u8>>,
+    requests: VecDeque<Vec<u8>>,
+}
+
+impl FramedTransport<()> for MockTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sent.push(data.to_vec());
+        Ok(())
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        if let Some(request) = self.requests.pop_front() {
+            buffer[..request.len()].copy_from_slice(&request);
+            return Ok(&buffer[..request.len()]);
+        }
+        let payload = self
+            .replies
+            .pop_front()
+            .ok_or(FramedTransportError::UnderlyingError(()))?;
+        // The reply header repeats the header of the last invocation, with the reply message type.
+        let request = self.sent.iter().rev().find(|sent| sent[0] == 0).unwrap();
+        let reply = message(
+            2,
+            u32::from(request[2]),
+            u32::from(request[1]),
+            u32::from_le_bytes([request[4], request[5], request[6], request[7]]),
+            &payload,
+        );
+        buffer[..reply.len()].copy_from_slice(&reply);
+        Ok(&buffer[..reply.len()])
+    }
+}
+
+fn message(
+    message_type: u32,
+    service: u32,
+    request: u32,
+    sequence: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let header = (1u32 << 24) | (service << 16) | (request << 8) | message_type;
+    let mut message = header.to_le_bytes().to_vec();
+    message.extend_from_slice(&sequence.to_le_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+const SUCCESS: [u8; 4] = [0; 4];
+
+#[test]
+fn parameters_are_encoded() {
+    let mut transport = MockTransport::default();
+    for _ in 0..7 {
+        transport.replies.push_back(SUCCESS.to_vec());
+    }
+    let mut buffer = [0u8; 128];
+    let mut host = BleHost::new(rpc::Client::new(
+        transport,
+        &mut buffer,
+        BasicCodecFactory::new(),
+    ));
+    host.set_device_name("Wio").unwrap();
+    host.set_appearance(0x0080).unwrap();
+    host.set_advertising_parameters(&AdvertisingParameters::default())
+        .unwrap();
+
+    let (transport, _, _) = host.release().release();
+    let sent = transport.sent;
+    assert_eq!(
+        sent[0],
+        message(
+            0,
+            4,
+            4,
+            1,
+            &[0x22, 2, 0, 0, 4, 0, 0, 0, b'W', b'i', b'o', 0]
+        )
+    );
+    assert_eq!(
+        sent[1],
+        message(0, 4, 4, 2, &[0x21, 2, 0, 0, 2, 0, 0, 0, 0x80, 0])
+    );
+    assert_eq!(
+        sent[2],
+        message(0, 6, 1, 3, &[0x63, 2, 0, 0, 1, 0, 0, 0, 0])
+    );
+    assert_eq!(
+        sent[3],
+        message(0, 6, 1, 4, &[0x66, 2, 0, 0, 1, 0, 0, 0, 7])
+    );
+    assert_eq!(
+        sent[6],
+        message(0, 6, 1, 7, &[0x69, 2, 0, 0, 2, 0, 0, 0, 0xa0, 0])
+    );
+    assert_eq!(sent.len(), 7);
+}
+
+#[test]
+fn results_are_decoded() {
+    let mut transport = MockTransport::default();
+    transport.replies.push_back(vec![2, 0, 0, 0]);
+    transport
+        .replies
+        .push_back(vec![6, 0, 0, 0, 1, 2, 3, 4, 5, 6, 0, 0, 0, 0]);
+    transport.replies.push_back(vec![0; 16]);
+    transport.replies.push_back(vec![0]);
+    let mut buffer = [0u8; 128];
+    let mut host = BleHost::new(rpc::Client::new(
+        transport,
+        &mut buffer,
+        BasicCodecFactory::new(),
+    ));
+
+    assert_eq!(
+        host.start_advertising(),
+        Err(BleError::Gap(RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_STATE))
+    );
+    assert_eq!(host.address(), Ok([1, 2, 3, 4, 5, 6]));
+    assert_eq!(host.connection_info(0), Err(BleError::Failed));
+    assert_eq!(host.init(), Err(BleError::Failed));
+    assert_eq!(
+        host.set_device_name(core::str::from_utf8(&[b'a'; 40]).unwrap()),
+        Err(BleError::InvalidArgument)
+    );
+    assert_eq!(
+        host.set_advertising_data(&[0; 32]),
+        Err(BleError::InvalidArgument)
+    );
+
+    let (transport, _, _) = host.release().release();
+    assert_eq!(transport.sent.len(), 4);
+    assert_eq!(transport.sent[1], message(0, 2, 2, 2, &[0, 2, 0, 0]));
+}
+
+/// Records the GAP messages.
+#[derive(Default)]
+struct GapMessages(Vec<Vec<u8>>);
+
+impl rpc_ble_callback::Irpc_ble_callback for GapMessages {
+    fn rpc_ble_handle_gap_msg(&mut self, gap_msg: &[u8]) -> RPC_T_APP_RESULT {
+        self.0.push(gap_msg.to_vec());
+        RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS
+    }
+
+    fn rpc_ble_gap_callback(&mut self, _cb_type: u8, _cb_data: &[u8]) -> RPC_T_APP_RESULT {
+        RPC_T_APP_RESULT::RPC_APP_RESULT_REJECT
+    }
+
+    fn rpc_ble_gattc_callback(
+        &mut self,
+        _gatt_if: u8,
+        _conn_id: u8,
+        _cb_data: &[u8],
+        _extra_data: &[u8],
+    ) -> RPC_T_APP_RESULT {
+        RPC_T_APP_RESULT::RPC_APP_RESULT_REJECT
+    }
+
+    fn rpc_ble_gatts_callback<'read_cb_data>(
+        &mut self,
+        _gatt_if: u8,
+        _conn_id: u8,
+        _attrib_index: u16,
+        _event: RPC_T_SERVICE_CALLBACK_TYPE,
+        _property: u16,
+        read_cb_data: &'read_cb_data mut [u8],
+        _write_cb_data: &[u8],
+        _app_cb_data: &[u8],
+    ) -> (&'read_cb_data [u8], RPC_T_APP_RESULT) {
+        (&read_cb_data[..0], RPC_T_APP_RESULT::RPC_APP_RESULT_REJECT)
+    }
+}
+
+#[test]
+fn callbacks_are_handled_during_calls_over_an_arbitrator() {
+    let mut transport = MockTransport::default();
+    transport
+        .requests
+        .push_back(message(0, 12, 1, 100, &[2, 0, 0, 0, 0xaa, 0xbb]));
+    transport.replies.push_back(SUCCESS.to_vec());
+    let mut scratch = [0u8; 32];
+    let mut callbacks =
+        rpc_ble_callback::Service::with_scratch(GapMessages::default(), &mut scratch);
+    let mut receive_buffer = [0u8; 128];
+    let mut send_buffer = [0u8; 128];
+    let mut arbitrator: TransportArbitrator<_, _, _, 1> = TransportArbitrator::new(
+        transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        BasicCodecFactory::new(),
+    );
+    assert!(arbitrator.add_service(&mut callbacks).is_ok());
+
+    let mut host = BleHost::new(arbitrator);
+    assert_eq!(host.start_advertising(), Ok(()));
+
+    let (transport, _, _, _) = host.release().release();
+    assert_eq!(transport.sent.len(), 2);
+    assert_eq!(transport.sent[0], message(0, 6, 3, 1, &[]));
+    assert_eq!(transport.sent[1], message(2, 12, 1, 100, &[0, 0, 0, 0]));
+    assert_eq!(callbacks.release().0 .0, [vec![0xaa, 0xbb]]);
+}
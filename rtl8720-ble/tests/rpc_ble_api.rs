@@ -0,0 +1,220 @@
+use rtl8720_ble::rpc_ble_api::*;
+use rust_erpc::codec::{BasicCodecFactory, CodecError};
+use rust_erpc::cursor::CursorError;
+use rust_erpc::framed_transport::{FramedTransport, FramedTransportError};
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc;
+use std::collections::VecDeque;
+
+/// Records sent frames and returns prepared replies.
+#[derive(Default)]
+struct MockTransport {
+    sent: Vec<Vec<u8>>,
+    replies: VecDeque<Vec<u8>>,
+}
+
+impl FramedTransport<()> for MockTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sent.push(data.to_vec());
+        Ok(())
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        let reply = self
+            .replies
+            .pop_front()
+            .ok_or(FramedTransportError::UnderlyingError(()))?;
+        buffer[..reply.len()].copy_from_slice(&reply);
+        Ok(&buffer[..reply.len()])
+    }
+}
+
+fn message(
+    message_type: u32,
+    service: u32,
+    request: u32,
+    sequence: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let header = (1u32 << 24) | (service << 16) | (request << 8) | message_type;
+    let mut message = header.to_le_bytes().to_vec();
+    message.extend_from_slice(&sequence.to_le_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+#[test]
+fn ids_match_erpcgen() {
+    assert_eq!(rpc_ble_host::SERVICE_ID, 1);
+    assert_eq!(rpc_gap::SERVICE_ID, 2);
+    assert_eq!(rpc_gap_bone::SERVICE_ID, 3);
+    assert_eq!(rpc_gap_le::SERVICE_ID, 4);
+    assert_eq!(rpc_gap_config::SERVICE_ID, 5);
+    assert_eq!(rpc_gap_adv::SERVICE_ID, 6);
+    assert_eq!(rpc_gap_scan::SERVICE_ID, 7);
+    assert_eq!(rpc_gap_conn::SERVICE_ID, 8);
+    assert_eq!(rpc_gap_storage::SERVICE_ID, 9);
+    assert_eq!(rpc_gatt_client::SERVICE_ID, 10);
+    assert_eq!(rpc_gatt_server::SERVICE_ID, 11);
+
+    assert_eq!(rpc_ble_host::RPC_BLE_DEINIT_ID, 3);
+    assert_eq!(rpc_gap_bone::RPC_LE_BOND_GET_SEC_LEVEL_ID, 14);
+    assert_eq!(rpc_gap_conn::RPC_LE_UPDATE_CONN_PARAM_ID, 13);
+    assert_eq!(rpc_gap_storage::RPC_LE_GET_DEV_BOND_INFO_ID, 16);
+    assert_eq!(rpc_gatt_client::RPC_CLIENT_ATTR_IND_CONFIRM_ID, 15);
+    assert_eq!(rpc_gatt_server::RPC_SERVER_ATTR_READ_CONFIRM_ID, 12);
+
+    assert_eq!(RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_ERROR_UNKNOWN as i32, 255);
+    assert_eq!(
+        RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_DEVICE_NAME as i32,
+        0x222
+    );
+    assert_eq!(
+        RPC_T_APP_RESULT::RPC_APP_RESULT_PROC_ALREADY_IN_PROGRESS as i32,
+        0x4fe
+    );
+}
+
+#[test]
+fn calls_match_python_shim() {
+    let mut transport = MockTransport::default();
+    transport.replies.push_back(message(2, 4, 4, 1, &[0; 4]));
+    transport
+        .replies
+        .push_back(message(2, 6, 2, 2, &[2, 0, 0, 0, 0x20, 0x00, 3, 0, 0, 0]));
+    transport.replies.push_back(message(2, 1, 2, 3, &[]));
+    let mut buffer = [0u8; 256];
+    let mut client = rpc::Client::new(transport, &mut buffer, BasicCodecFactory::new());
+
+    assert_eq!(
+        rpc_gap_le::Client::new(&mut client)
+            .rpc_le_set_gap_param(RPC_T_GAP_LE_PARAM_TYPE::RPC_GAP_PARAM_DEVICE_NAME, b"Wio\0"),
+        Ok(RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS)
+    );
+    let mut value = [0u8; 2];
+    assert_eq!(
+        rpc_gap_adv::Client::new(&mut client).rpc_le_adv_get_param(
+            RPC_T_LE_ADV_PARAM_TYPE::RPC_GAP_PARAM_ADV_INTERVAL_MIN,
+            &mut value
+        ),
+        Ok((
+            &[0x20u8, 0x00][..],
+            RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_PARAM
+        ))
+    );
+    assert_eq!(
+        rpc_ble_host::Client::new(&mut client).rpc_ble_start(),
+        Ok(())
+    );
+
+    let (transport, _, _) = client.release();
+    assert_eq!(
+        transport.sent[0],
+        message(
+            0,
+            4,
+            4,
+            1,
+            &[0x22, 0x02, 0, 0, 4, 0, 0, 0, b'W', b'i', b'o', 0]
+        )
+    );
+    assert_eq!(transport.sent[1], message(0, 6, 2, 2, &[0x68, 0x02, 0, 0]));
+    assert_eq!(transport.sent[2], message(0, 1, 2, 3, &[]));
+}
+
+#[test]
+fn structs_match_python_shim() {
+    let entry = RPC_T_LE_KEY_ENTRY {
+        is_used: true,
+        idx: 2,
+        flags: 0x1234,
+        local_bd_type: 0,
+        app_data: 0xaa,
+        reserved: [0; 2],
+        remote_bd: RPC_T_LE_REMOTE_BD {
+            addr: [1, 2, 3, 4, 5, 6],
+            remote_bd_type: 1,
+            bond_flags: 0x0f,
+        },
+        resolved_remote_bd: RPC_T_LE_REMOTE_BD::default(),
+    };
+    let mut entry_bytes = vec![1, 2, 0x34, 0x12, 0, 0xaa, 0, 0];
+    entry_bytes.extend_from_slice(&[1, 2, 3, 4, 5, 6, 1, 0x0f]);
+    entry_bytes.extend_from_slice(&[0; 8]);
+
+    let mut transport = MockTransport::default();
+    transport.replies.push_back(message(
+        2,
+        8,
+        2,
+        1,
+        &[2, 0, 0, 0, 2, 0, 0, 0, 6, 5, 4, 3, 2, 1, 0, 1],
+    ));
+    transport
+        .replies
+        .push_back(message(2, 9, 5, 2, &entry_bytes));
+    transport
+        .replies
+        .push_back(message(2, 9, 11, 3, &[6, 5, 4, 3, 2, 1, 1, 0, 0, 0, 1]));
+    transport.replies.push_back(message(2, 9, 11, 4, &[0; 6]));
+    let mut buffer = [0u8; 256];
+    let mut client = rpc::Client::new(transport, &mut buffer, BasicCodecFactory::new());
+
+    assert_eq!(
+        rpc_gap_conn::Client::new(&mut client).rpc_le_get_conn_info(0),
+        Ok((
+            RPC_T_GAP_CONN_INFO {
+                conn_state: RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_CONNECTED,
+                role: RPC_T_GAP_ROLE::RPC_GAP_LINK_ROLE_SLAVE,
+                remote_bd: [6, 5, 4, 3, 2, 1],
+                remote_bd_type: 0,
+            },
+            true
+        ))
+    );
+    let mut storage = rpc_gap_storage::Client::new(&mut client);
+    assert_eq!(
+        storage.rpc_le_find_key_entry(
+            &[1, 2, 3, 4, 5, 6],
+            RPC_T_GAP_REMOTE_ADDR_TYPE::RPC_GAP_REMOTE_ADDR_LE_RANDOM
+        ),
+        Ok(entry)
+    );
+    assert_eq!(
+        storage.rpc_le_resolve_random_address(
+            &[0xc1; 6],
+            &[0; 6],
+            RPC_T_GAP_IDENT_ADDR_TYPE::RPC_GAP_IDENT_ADDR_PUBLIC
+        ),
+        Ok((
+            [6, 5, 4, 3, 2, 1],
+            RPC_T_GAP_IDENT_ADDR_TYPE::RPC_GAP_IDENT_ADDR_RAND,
+            true
+        ))
+    );
+    assert_eq!(
+        storage.rpc_le_resolve_random_address(
+            &[0xc1; 6],
+            &[0; 6],
+            RPC_T_GAP_IDENT_ADDR_TYPE::RPC_GAP_IDENT_ADDR_PUBLIC
+        ),
+        Err(RequestResponseError::CodecError(CodecError::Cursor(
+            CursorError::NotEnoughData
+        )))
+    );
+
+    let (transport, _, _) = client.release();
+    assert_eq!(transport.sent[0], message(0, 8, 2, 1, &[0]));
+    assert_eq!(
+        transport.sent[1],
+        message(0, 9, 5, 2, &[1, 2, 3, 4, 5, 6, 1, 0, 0, 0])
+    );
+    let mut payload = vec![0xc1; 6];
+    payload.extend_from_slice(&[0; 10]);
+    assert_eq!(transport.sent[2], message(0, 9, 11, 3, &payload));
+}
@@ -0,0 +1,201 @@
+use rtl8720_ble::callback::CallbackServer;
+use rtl8720_ble::rpc_ble_api::rpc_ble_callback::{self, Irpc_ble_callback};
+use rtl8720_ble::rpc_ble_api::{RPC_T_APP_RESULT, RPC_T_SERVICE_CALLBACK_TYPE};
+use rust_erpc::framed_transport::{FramedTransport, FramedTransportError};
+use rust_erpc::request::RequestResponseError;
+use std::collections::VecDeque;
+
+/// Returns prepared requests and records sent replies.
+#[derive(Default)]
+struct MockTransport {
+    sent: Vec<Vec<u8>>,
+    requests: VecDeque<Vec<u8>>,
+}
+
+impl FramedTransport<()> for MockTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sent.push(data.to_vec());
+        Ok(())
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        let request = self
+            .requests
+            .pop_front()
+            .ok_or(FramedTransportError::UnderlyingError(()))?;
+        buffer[..request.len()].copy_from_slice(&request);
+        Ok(&buffer[..request.len()])
+    }
+}
+
+fn message(
+    message_type: u32,
+    service: u32,
+    request: u32,
+    sequence: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let header = (1u32 << 24) | (service << 16) | (request << 8) | message_type;
+    let mut message = header.to_le_bytes().to_vec();
+    message.extend_from_slice(&sequence.to_le_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Records the events and answers reads of attribute 3 with its value.
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+}
+
+impl Irpc_ble_callback for Recorder {
+    fn rpc_ble_handle_gap_msg(&mut self, gap_msg: &[u8]) -> RPC_T_APP_RESULT {
+        self.events.push(format!("gap_msg {:?}", gap_msg));
+        RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS
+    }
+    fn rpc_ble_gap_callback(&mut self, cb_type: u8, cb_data: &[u8]) -> RPC_T_APP_RESULT {
+        self.events.push(format!("gap {} {:?}", cb_type, cb_data));
+        RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS
+    }
+    fn rpc_ble_gattc_callback(
+        &mut self,
+        gatt_if: u8,
+        conn_id: u8,
+        cb_data: &[u8],
+        extra_data: &[u8],
+    ) -> RPC_T_APP_RESULT {
+        self.events.push(format!(
+            "gattc {} {} {:?} {:?}",
+            gatt_if, conn_id, cb_data, extra_data
+        ));
+        RPC_T_APP_RESULT::RPC_APP_RESULT_PENDING
+    }
+    fn rpc_ble_gatts_callback<'read_cb_data>(
+        &mut self,
+        gatt_if: u8,
+        conn_id: u8,
+        attrib_index: u16,
+        event: RPC_T_SERVICE_CALLBACK_TYPE,
+        property: u16,
+        read_cb_data: &'read_cb_data mut [u8],
+        write_cb_data: &[u8],
+        app_cb_data: &[u8],
+    ) -> (&'read_cb_data [u8], RPC_T_APP_RESULT) {
+        self.events.push(format!(
+            "gatts {} {} {} {:?} {} {:?} {:?}",
+            gatt_if, conn_id, attrib_index, event, property, write_cb_data, app_cb_data
+        ));
+        if attrib_index != 3 {
+            return (&[], RPC_T_APP_RESULT::RPC_APP_RESULT_ATTR_NOT_FOUND);
+        }
+        read_cb_data[..2].copy_from_slice(&[0x12, 0x34]);
+        (&read_cb_data[..2], RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS)
+    }
+}
+
+#[test]
+fn callbacks_match_python_shim() {
+    let mut transport = MockTransport::default();
+    transport
+        .requests
+        .push_back(message(0, 12, 1, 1, &[2, 0, 0, 0, 0xaa, 0xbb]));
+    transport
+        .requests
+        .push_back(message(0, 12, 2, 2, &[5, 1, 0, 0, 0, 9]));
+    transport
+        .requests
+        .push_back(message(0, 12, 3, 3, &[1, 0, 1, 0, 0, 0, 7, 0, 0, 0, 0]));
+    transport.requests.push_back(message(
+        0,
+        12,
+        4,
+        4,
+        &[2, 0, 3, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    ));
+    transport.requests.push_back(message(
+        0,
+        12,
+        4,
+        5,
+        &[2, 0, 4, 0, 3, 0, 0, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0, 0],
+    ));
+    transport.requests.push_back(message(0, 12, 5, 6, &[]));
+    transport.requests.push_back(message(0, 4, 1, 7, &[]));
+
+    let mut receive_buffer = [0u8; 64];
+    let mut send_buffer = [0u8; 64];
+    let mut scratch = [0u8; 16];
+    let mut server = CallbackServer::new(
+        transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        &mut scratch,
+        Recorder::default(),
+    );
+    for _ in 0..5 {
+        server.run_once().unwrap();
+    }
+    assert_eq!(server.run_once(), Err(RequestResponseError::InvalidRequest));
+    assert_eq!(server.run_once(), Err(RequestResponseError::InvalidRequest));
+
+    let (transport, _, _, _, recorder) = server.release();
+    assert_eq!(
+        recorder.events,
+        [
+            "gap_msg [170, 187]",
+            "gap 5 [9]",
+            "gattc 1 0 [7] []",
+            "gatts 2 0 3 RPC_SERVICE_CALLBACK_TYPE_READ_CHAR_VALUE 0 [] []",
+            "gatts 2 0 4 RPC_SERVICE_CALLBACK_TYPE_WRITE_CHAR_VALUE 0 [5] []",
+        ]
+    );
+    assert_eq!(transport.sent[0], message(2, 12, 1, 1, &[0; 4]));
+    assert_eq!(transport.sent[1], message(2, 12, 2, 2, &[0; 4]));
+    assert_eq!(transport.sent[2], message(2, 12, 3, 3, &[0x01, 0x0d, 0, 0]));
+    assert_eq!(
+        transport.sent[3],
+        message(2, 12, 4, 4, &[2, 0, 0, 0, 0x12, 0x34, 0, 0, 0, 0])
+    );
+    assert_eq!(
+        transport.sent[4],
+        message(2, 12, 4, 5, &[0, 0, 0, 0, 0x0a, 0x04, 0, 0])
+    );
+    assert_eq!(transport.sent.len(), 5);
+    assert_eq!(rpc_ble_callback::SERVICE_ID, 12);
+}
+
+#[test]
+fn run_drops_invalid_requests() {
+    let mut transport = MockTransport::default();
+    transport.requests.push_back(message(0, 12, 5, 1, &[]));
+    transport.requests.push_back(message(0, 4, 1, 2, &[]));
+    transport
+        .requests
+        .push_back(message(0, 12, 1, 3, &[1, 0, 0, 0, 0xcc]));
+
+    let mut receive_buffer = [0u8; 64];
+    let mut send_buffer = [0u8; 64];
+    let mut scratch = [0u8; 16];
+    let mut server = CallbackServer::new(
+        transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        &mut scratch,
+        Recorder::default(),
+    );
+    assert_eq!(
+        server.run(),
+        Err(RequestResponseError::FramedTransportError(
+            FramedTransportError::UnderlyingError(())
+        ))
+    );
+
+    let (transport, _, _, _, recorder) = server.release();
+    assert_eq!(recorder.events, ["gap_msg [204]"]);
+    assert_eq!(transport.sent, [message(2, 12, 1, 3, &[0; 4])]);
+}
@@ -0,0 +1,630 @@
+use core::cell::RefCell;
+use rtl8720_ble::callback::CallbackServer;
+use rtl8720_ble::host::{AdvertisingParameters, AdvertisingType, BleError, BleHost};
+use rtl8720_ble::rpc_ble_api::rpc_gap_adv::Irpc_gap_adv;
+use rtl8720_ble::rpc_ble_api::rpc_gap_bone::Irpc_gap_bone;
+use rtl8720_ble::rpc_ble_api::rpc_gap_conn::Irpc_gap_conn;
+use rtl8720_ble::rpc_ble_api::rpc_gap_storage::Irpc_gap_storage;
+use rtl8720_ble::rpc_ble_api::rpc_gatt_server::Irpc_gatt_server;
+use rtl8720_ble::rpc_ble_api::*;
+use rtl8720_ble::simulator::*;
+use rust_erpc::arbitrator::TransportArbitrator;
+use rust_erpc::codec::BasicCodecFactory;
+use rust_erpc::cursor::BufferCursor;
+use rust_erpc::framed_transport::{
+    BasicFramedTransport, FramedTransport, FramedTransportError, UnderlyingTransport,
+};
+use rust_erpc::rpc;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+/// In-memory pipe, one end of a pair made by `pipe`.
+struct ChannelTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl FramedTransport<()> for ChannelTransport {
+    fn get_max_message_size(&self) -> usize {
+        256
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<()>> {
+        self.sender
+            .send(data.to_vec())
+            .map_err(|_| FramedTransportError::UnderlyingError(()))
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<()>> {
+        let message = self
+            .receiver
+            .recv()
+            .map_err(|_| FramedTransportError::UnderlyingError(()))?;
+        buffer[..message.len()].copy_from_slice(&message);
+        Ok(&buffer[..message.len()])
+    }
+}
+
+fn pipe() -> (ChannelTransport, ChannelTransport) {
+    let (first_sender, second_receiver) = channel();
+    let (second_sender, first_receiver) = channel();
+    (
+        ChannelTransport {
+            sender: first_sender,
+            receiver: first_receiver,
+        },
+        ChannelTransport {
+            sender: second_sender,
+            receiver: second_receiver,
+        },
+    )
+}
+
+struct TcpTransport(TcpStream);
+
+impl UnderlyingTransport for TcpTransport {
+    type Error = std::io::ErrorKind;
+    fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.read_exact(data).map_err(|error| error.kind())
+    }
+    fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.0.write_all(data).map_err(|error| error.kind())
+    }
+    fn read_available(&mut self, data: &mut [u8]) -> Result<usize, Self::Error> {
+        self.0.set_nonblocking(true).map_err(|error| error.kind())?;
+        let result = match self.0.read(data) {
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => Ok(0),
+            result => result.map_err(|error| error.kind()),
+        };
+        self.0
+            .set_nonblocking(false)
+            .map_err(|error| error.kind())?;
+        result
+    }
+}
+
+/// Both ends of a TCP loopback connection.
+fn tcp_loopback() -> (
+    BasicFramedTransport<TcpTransport>,
+    BasicFramedTransport<TcpTransport>,
+) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    (
+        BasicFramedTransport::new(TcpTransport(client)),
+        BasicFramedTransport::new(TcpTransport(server)),
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Callback {
+    GapMsg(Vec<u8>),
+    Gap(u8, Vec<u8>),
+    Gattc {
+        client_id: u8,
+        conn_id: u8,
+        data: Vec<u8>,
+        extra: Vec<u8>,
+    },
+    Gatts {
+        service_id: u8,
+        attrib_index: u16,
+        event: RPC_T_SERVICE_CALLBACK_TYPE,
+        data: Vec<u8>,
+    },
+}
+
+/// Records the callbacks and answers reads with `READ_VALUE`.
+#[derive(Default)]
+struct Recorder {
+    callbacks: Vec<Callback>,
+}
+
+const READ_VALUE: &[u8] = b"from host";
+
+impl rpc_ble_callback::Irpc_ble_callback for Recorder {
+    fn rpc_ble_handle_gap_msg(&mut self, gap_msg: &[u8]) -> RPC_T_APP_RESULT {
+        self.callbacks.push(Callback::GapMsg(gap_msg.to_vec()));
+        RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS
+    }
+
+    fn rpc_ble_gap_callback(&mut self, cb_type: u8, cb_data: &[u8]) -> RPC_T_APP_RESULT {
+        self.callbacks
+            .push(Callback::Gap(cb_type, cb_data.to_vec()));
+        RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS
+    }
+
+    fn rpc_ble_gattc_callback(
+        &mut self,
+        gatt_if: u8,
+        conn_id: u8,
+        cb_data: &[u8],
+        extra_data: &[u8],
+    ) -> RPC_T_APP_RESULT {
+        self.callbacks.push(Callback::Gattc {
+            client_id: gatt_if,
+            conn_id,
+            data: cb_data.to_vec(),
+            extra: extra_data.to_vec(),
+        });
+        RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS
+    }
+
+    fn rpc_ble_gatts_callback<'read_cb_data>(
+        &mut self,
+        gatt_if: u8,
+        _conn_id: u8,
+        attrib_index: u16,
+        event: RPC_T_SERVICE_CALLBACK_TYPE,
+        _property: u16,
+        read_cb_data: &'read_cb_data mut [u8],
+        write_cb_data: &[u8],
+        _app_cb_data: &[u8],
+    ) -> (&'read_cb_data [u8], RPC_T_APP_RESULT) {
+        self.callbacks.push(Callback::Gatts {
+            service_id: gatt_if,
+            attrib_index,
+            event,
+            data: write_cb_data.to_vec(),
+        });
+        let length = match event {
+            RPC_T_SERVICE_CALLBACK_TYPE::RPC_SERVICE_CALLBACK_TYPE_READ_CHAR_VALUE => {
+                READ_VALUE.len()
+            }
+            _ => 0,
+        };
+        let value = &mut read_cb_data[..length];
+        value.copy_from_slice(&READ_VALUE[..length]);
+        (value, RPC_T_APP_RESULT::RPC_APP_RESULT_SUCCESS)
+    }
+}
+
+type HostClient<'buffer, Transport> =
+    rpc::Client<'buffer, Transport, BasicCodecFactory<BufferCursor<&'buffer mut [u8]>>>;
+
+/// Serves `simulator` on one thread and records its callbacks on another, while `host` drives it.
+///
+/// The pending events of the simulator are delivered first. Returns the simulator and the callbacks
+/// once `host` returns.
+fn simulate<Error, HostTransport, SimulatorTransport, CallbackTransport, HostCallbackTransport>(
+    (host_transport, simulator_transport): (HostTransport, SimulatorTransport),
+    (callback_transport, host_callback_transport): (CallbackTransport, HostCallbackTransport),
+    simulator: Simulator,
+    host: impl FnOnce(&mut BleHost<HostClient<'_, HostTransport>>),
+) -> (Simulator, Vec<Callback>)
+where
+    HostTransport: FramedTransport<Error>,
+    SimulatorTransport: FramedTransport<Error> + Send + 'static,
+    CallbackTransport: FramedTransport<Error> + Send + 'static,
+    HostCallbackTransport: FramedTransport<Error> + Send + 'static,
+{
+    let simulator = thread::spawn(move || {
+        let simulator = RefCell::new(simulator);
+        let mut scratch = [0u8; 1024];
+        let mut services = SimulatorServices::new(&simulator, &mut scratch);
+        let mut receive_buffer = [0u8; 256];
+        let mut send_buffer = [0u8; 256];
+        let mut server = rpc::SimpleServer::<_, _, _, SERVICE_COUNT>::new(
+            simulator_transport,
+            &mut receive_buffer,
+            &mut send_buffer,
+            BasicCodecFactory::new(),
+        );
+        for service in services.services() {
+            assert!(server.add_service(service).is_ok());
+        }
+        let mut callback_buffer = [0u8; 256];
+        let mut callbacks = rpc::Client::new(
+            callback_transport,
+            &mut callback_buffer,
+            BasicCodecFactory::new(),
+        );
+        assert!(deliver_events(&simulator, &mut callbacks).is_ok());
+        // Ends when the host closes its transport.
+        loop {
+            match server.run_once() {
+                Err(err) if err.is_underlying_error() => break,
+                _ => assert!(deliver_events(&simulator, &mut callbacks).is_ok()),
+            }
+        }
+        drop(server);
+        simulator.into_inner()
+    });
+    let callbacks = thread::spawn(move || {
+        let mut receive_buffer = [0u8; 256];
+        let mut send_buffer = [0u8; 256];
+        let mut scratch = [0u8; 64];
+        let mut server = CallbackServer::new(
+            host_callback_transport,
+            &mut receive_buffer,
+            &mut send_buffer,
+            &mut scratch,
+            Recorder::default(),
+        );
+        while server.run_once().is_ok() {}
+        server.release().4.callbacks
+    });
+
+    let mut buffer = [0u8; 256];
+    let mut ble_host = BleHost::new(rpc::Client::new(
+        host_transport,
+        &mut buffer,
+        BasicCodecFactory::new(),
+    ));
+    host(&mut ble_host);
+    drop(ble_host);
+    (simulator.join().unwrap(), callbacks.join().unwrap())
+}
+
+const ADDRESS: [u8; 6] = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+const PEER_ADDRESS: [u8; 6] = [0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xc6];
+
+fn device_state_msg(state: DeviceState) -> Callback {
+    Callback::GapMsg(vec![0, 0, 1, 0, state.to_byte(), 0, 0, 0])
+}
+
+fn conn_state_msg(conn_id: u8, state: RPC_T_GAP_CONN_STATE, cause: u16) -> Callback {
+    let cause = cause.to_le_bytes();
+    Callback::GapMsg(vec![0, 0, 2, 0, conn_id, state as u8, cause[0], cause[1]])
+}
+
+#[test]
+fn advertising_is_configured_and_reported() {
+    let parameters = AdvertisingParameters {
+        advertising_type: AdvertisingType::ScannableUndirected,
+        interval_min: 0x30,
+        interval_max: 0x60,
+        ..AdvertisingParameters::default()
+    };
+    let (simulator, callbacks) = simulate(pipe(), pipe(), Simulator::new(ADDRESS), |host| {
+        assert_eq!(
+            host.start_advertising(),
+            Err(BleError::Gap(RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_INVALID_STATE))
+        );
+        host.init().unwrap();
+        host.start().unwrap();
+        assert_eq!(host.address().unwrap(), ADDRESS);
+        host.set_device_name("Wio Terminal").unwrap();
+        host.set_appearance(0x0340).unwrap();
+        host.set_advertising_parameters(&parameters).unwrap();
+        host.set_advertising_data(&[0x02, 0x01, 0x06]).unwrap();
+        host.start_advertising().unwrap();
+        host.stop_advertising().unwrap();
+    });
+
+    assert_eq!(simulator.device_name(), b"Wio Terminal");
+    assert_eq!(simulator.appearance(), 0x0340);
+    assert_eq!(simulator.advertising_parameters(), Some(parameters));
+    assert_eq!(simulator.advertising_data(), &[0x02, 0x01, 0x06]);
+    assert!(!simulator.device_state().advertising);
+
+    let ready = DeviceState {
+        ready: true,
+        ..DeviceState::default()
+    };
+    let advertising = DeviceState {
+        advertising: true,
+        ..ready
+    };
+    assert_eq!(
+        callbacks,
+        vec![
+            device_state_msg(ready),
+            device_state_msg(advertising),
+            device_state_msg(ready),
+        ]
+    );
+}
+
+#[test]
+fn central_connects_pairs_and_discovers() {
+    let mut simulator = Simulator::new(ADDRESS);
+    simulator
+        .add_peer(Peer::new(PEER_ADDRESS, &[0x02, 0x01, 0x06]))
+        .unwrap();
+    simulator.add_peer_service(0x180f).unwrap();
+    let level_handle = simulator
+        .add_peer_characteristic(0x2a19, 0x12, &[87])
+        .unwrap();
+
+    let (simulator, callbacks) = simulate(pipe(), pipe(), simulator, |host| {
+        let peer = RPC_T_GAP_REMOTE_ADDR_TYPE::RPC_GAP_REMOTE_ADDR_LE_PUBLIC;
+        host.start().unwrap();
+        host.start_scan().unwrap();
+        host.stop_scan().unwrap();
+        host.connect(
+            &PEER_ADDRESS,
+            peer,
+            &RPC_T_GAP_LE_CONN_REQ_PARAM::default(),
+            1000,
+        )
+        .unwrap();
+        let info = host.connection_info(0).unwrap();
+        assert_eq!(
+            info.conn_state,
+            RPC_T_GAP_CONN_STATE::RPC_GAP_CONN_STATE_CONNECTED
+        );
+        assert_eq!(info.remote_bd, PEER_ADDRESS);
+
+        host.pair(0).unwrap();
+        assert_eq!(
+            host.security_level(0).unwrap(),
+            RPC_T_GAP_SEC_LEVEL::RPC_GAP_SEC_LEVEL_UNAUTHEN
+        );
+        assert_eq!(host.bonded_device_count().unwrap(), 1);
+
+        let client_id = host.add_gatt_client(0, 1).unwrap();
+        host.discover_services(0, client_id).unwrap();
+        host.discover_characteristics(0, client_id, 1, 0xffff)
+            .unwrap();
+        let cause = rpc_gatt_client::Client::new(host.caller())
+            .rpc_client_attr_read(0, client_id, level_handle)
+            .unwrap();
+        assert_eq!(cause, RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS);
+
+        host.disconnect(0).unwrap();
+        assert_eq!(
+            host.disconnect(0),
+            Err(BleError::Gap(RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NON_CONN))
+        );
+    });
+
+    let bonds: Vec<_> = simulator.bonds().collect();
+    assert_eq!(bonds.len(), 1);
+    assert_eq!(bonds[0].remote_bd.addr, PEER_ADDRESS);
+
+    let ready = DeviceState {
+        ready: true,
+        ..DeviceState::default()
+    };
+    let scanning = DeviceState {
+        scanning: true,
+        ..ready
+    };
+    let connecting = DeviceState {
+        connecting: true,
+        ..ready
+    };
+    let mut scan_info = vec![0u8; 41];
+    scan_info[..6].copy_from_slice(&PEER_ADDRESS);
+    scan_info[8] = -60i8 as u8;
+    scan_info[9] = 3;
+    scan_info[10..13].copy_from_slice(&[0x02, 0x01, 0x06]);
+    let gattc = |data: &[u8], extra: &[u8]| Callback::Gattc {
+        client_id: 0,
+        conn_id: 0,
+        data: data.to_vec(),
+        extra: extra.to_vec(),
+    };
+    use RPC_T_GAP_CONN_STATE::*;
+    assert_eq!(
+        callbacks,
+        vec![
+            device_state_msg(ready),
+            device_state_msg(scanning),
+            Callback::Gap(GAP_MSG_LE_SCAN_INFO, scan_info),
+            device_state_msg(ready),
+            device_state_msg(connecting),
+            conn_state_msg(0, RPC_GAP_CONN_STATE_CONNECTING, 0),
+            device_state_msg(ready),
+            conn_state_msg(0, RPC_GAP_CONN_STATE_CONNECTED, 0),
+            Callback::GapMsg(vec![0, 0, 5, 0, 0, GAP_AUTHEN_STATE_STARTED, 0, 0]),
+            Callback::GapMsg(vec![0, 0, 5, 0, 0, GAP_AUTHEN_STATE_COMPLETE, 0, 0]),
+            gattc(&[GATTC_SERVICE_FOUND, 1, 0, 3, 0, 0x0f, 0x18], &[]),
+            gattc(&[GATTC_DISCOVERY_COMPLETE], &[]),
+            gattc(
+                &[GATTC_CHARACTERISTIC_FOUND, 2, 0, 0x12, 0, 3, 0, 0x19, 0x2a],
+                &[]
+            ),
+            gattc(&[GATTC_DISCOVERY_COMPLETE], &[]),
+            gattc(&[GATTC_READ_RESULT, 3, 0, 0, 0], &[87]),
+            conn_state_msg(0, RPC_GAP_CONN_STATE_DISCONNECTING, 0),
+            conn_state_msg(
+                0,
+                RPC_GAP_CONN_STATE_DISCONNECTED,
+                DISCONNECT_CAUSE_LOCAL_HOST_TERMINATED
+            ),
+        ]
+    );
+}
+
+#[test]
+fn peripheral_serves_peer_requests() {
+    let mut simulator = Simulator::new(ADDRESS);
+    simulator.add_peer(Peer::new(PEER_ADDRESS, &[])).unwrap();
+    let service_id = simulator.rpc_ble_create_service(
+        &[0x0f, 0x18, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        2,
+        true,
+    );
+    let level = simulator.rpc_ble_create_char(
+        service_id,
+        &[0x19, 0x2a, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        2,
+        0x1a,
+        1,
+    );
+    simulator.rpc_ble_service_start(service_id);
+    assert_eq!(simulator.accept_connection(0), None);
+    rtl8720_ble::rpc_ble_api::rpc_ble_host::Irpc_ble_host::rpc_ble_start(&mut simulator);
+    assert_eq!(
+        simulator.rpc_le_adv_start(),
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    );
+    assert_eq!(simulator.accept_connection(0), Some(0));
+    assert!(!simulator.device_state().advertising);
+    assert!(simulator.peer_write(0, service_id, level, &[1, 2]));
+    assert!(simulator.peer_read(0, service_id, level));
+    assert!(simulator.peer_subscribe(0, service_id, level, 1));
+    assert!(!simulator.peer_read(1, service_id, level));
+
+    let (simulator, callbacks) = simulate(pipe(), pipe(), simulator, |host| {
+        let mut server = rpc_gatt_server::Client::new(host.caller());
+        let mut value = [0u8; 16];
+        let value = server
+            .rpc_ble_server_get_attr_value(service_id, level, &mut value)
+            .unwrap();
+        assert_eq!(value, READ_VALUE);
+        let sent = server
+            .rpc_server_send_data(
+                0,
+                service_id,
+                level,
+                &[42],
+                RPC_T_GATT_PDU_TYPE::RPC_GATT_PDU_TYPE_NOTIFICATION,
+            )
+            .unwrap();
+        assert!(sent);
+    });
+
+    let sent: Vec<_> = simulator.sent_data().collect();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(&sent[0].data[..], &[42]);
+
+    let gatts = |event, data: &[u8]| Callback::Gatts {
+        service_id,
+        attrib_index: level,
+        event,
+        data: data.to_vec(),
+    };
+    use RPC_T_SERVICE_CALLBACK_TYPE::*;
+    // After the device state changes and the connection.
+    assert_eq!(
+        &callbacks[4..],
+        &[
+            gatts(RPC_SERVICE_CALLBACK_TYPE_WRITE_CHAR_VALUE, &[1, 2]),
+            gatts(RPC_SERVICE_CALLBACK_TYPE_READ_CHAR_VALUE, &[]),
+            gatts(RPC_SERVICE_CALLBACK_TYPE_INDIFICATION_NOTIFICATION, &[1, 0]),
+        ]
+    );
+}
+
+#[test]
+fn bonds_with_the_lowest_priority_are_replaced() {
+    let mut simulator = Simulator::new(ADDRESS);
+    let public = RPC_T_GAP_REMOTE_ADDR_TYPE::RPC_GAP_REMOTE_ADDR_LE_PUBLIC;
+    for index in 0..=MAX_BONDS as u8 {
+        let address = [index; 6];
+        assert!(simulator.rpc_le_gen_bond_dev(
+            &address,
+            public,
+            RPC_T_GAP_LOCAL_ADDR_TYPE::RPC_GAP_LOCAL_ADDR_LE_PUBLIC,
+            &[],
+            RPC_T_LE_KEY_TYPE::default(),
+            &RPC_T_LE_CCCD::default(),
+        ));
+    }
+    assert_eq!(simulator.rpc_le_get_bond_dev_num(), MAX_BONDS as u8);
+    // The first bond was replaced by the last one.
+    assert!(!simulator.rpc_le_find_key_entry(&[0; 6], public).is_used);
+    assert_eq!(
+        simulator.rpc_le_get_high_priority_bond().remote_bd.addr,
+        [MAX_BONDS as u8; 6]
+    );
+    assert_eq!(
+        simulator.rpc_le_get_low_priority_bond().remote_bd.addr,
+        [1; 6]
+    );
+
+    assert!(simulator.rpc_le_set_high_priority_bond(&[1; 6], public));
+    assert_eq!(
+        simulator.rpc_le_get_low_priority_bond().remote_bd.addr,
+        [2; 6]
+    );
+    assert_eq!(
+        simulator.rpc_le_bond_delete_by_bd(&[2; 6], public),
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_SUCCESS
+    );
+    assert_eq!(
+        simulator.rpc_le_bond_delete_by_bd(&[2; 6], public),
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NOT_FIND
+    );
+    assert_eq!(simulator.bonds().count(), MAX_BONDS - 1);
+    assert_eq!(
+        simulator.rpc_le_disconnect(0),
+        RPC_T_GAP_CAUSE::RPC_GAP_CAUSE_NON_CONN
+    );
+}
+
+#[test]
+fn simulator_serves_over_tcp_loopback() {
+    let mut simulator = Simulator::new(ADDRESS);
+    simulator
+        .add_peer(Peer::new(PEER_ADDRESS, &[0x02, 0x01, 0x06]))
+        .unwrap();
+    let (_, callbacks) = simulate(tcp_loopback(), tcp_loopback(), simulator, |host| {
+        host.start().unwrap();
+        host.start_scan().unwrap();
+    });
+    assert!(matches!(
+        &callbacks[2],
+        Callback::Gap(GAP_MSG_LE_SCAN_INFO, data) if data[..6] == PEER_ADDRESS
+    ));
+}
+
+#[test]
+fn host_calls_survive_callbacks_over_a_single_link() {
+    let (host_transport, simulator_transport) = pipe();
+    let simulator = thread::spawn(move || {
+        let simulator = RefCell::new(Simulator::new(ADDRESS));
+        let mut scratch = [0u8; 1024];
+        let mut services = SimulatorServices::new(&simulator, &mut scratch);
+        let mut receive_buffer = [0u8; 256];
+        let mut send_buffer = [0u8; 256];
+        let mut arbitrator = TransportArbitrator::<_, _, _, SERVICE_COUNT>::new(
+            simulator_transport,
+            &mut receive_buffer,
+            &mut send_buffer,
+            BasicCodecFactory::new(),
+        );
+        for service in services.services() {
+            assert!(arbitrator.add_service(service).is_ok());
+        }
+        // Ends when the host closes its transport.
+        loop {
+            match arbitrator.run_once() {
+                Err(err) if err.is_underlying_error() => break,
+                _ => match deliver_events(&simulator, &mut arbitrator) {
+                    Err(err) if err.is_underlying_error() => break,
+                    result => assert!(result.is_ok()),
+                },
+            }
+        }
+        drop(arbitrator);
+        simulator.into_inner()
+    });
+
+    let mut scratch = [0u8; 64];
+    let mut callbacks = rpc_ble_callback::Service::with_scratch(Recorder::default(), &mut scratch);
+    let mut receive_buffer = [0u8; 256];
+    let mut send_buffer = [0u8; 256];
+    let mut arbitrator = TransportArbitrator::<_, _, _, 1>::new(
+        host_transport,
+        &mut receive_buffer,
+        &mut send_buffer,
+        BasicCodecFactory::new(),
+    );
+    assert!(arbitrator.add_service(&mut callbacks).is_ok());
+    let mut host = BleHost::new(arbitrator);
+    host.init().unwrap();
+    // The simulator reports the new state of the device as soon as it replies, so the callback is pending
+    // while the host calls it again.
+    host.start().unwrap();
+    assert_eq!(host.address().unwrap(), ADDRESS);
+    host.set_device_name("Wio Terminal").unwrap();
+    drop(host);
+
+    assert_eq!(simulator.join().unwrap().device_name(), b"Wio Terminal");
+    let ready = DeviceState {
+        ready: true,
+        ..DeviceState::default()
+    };
+    assert_eq!(
+        callbacks.release().0.callbacks,
+        vec![device_state_msg(ready)]
+    );
+}
@@ -0,0 +1,388 @@
+//! Client and server sharing one transport, like the `TransportArbitrator` of erpc.
+//!
+//! On a link where both ends call each other, such as the BLE host calling `rpc_gap` on the RTL8720 while the
+//! RTL8720 calls back `rpc_ble_callback`, a frame received while waiting for a reply may be a request of the
+//! peer. [`TransportArbitrator`] hands such requests to its registered services and replies to them, then keeps
+//! waiting for the reply of the pending call.
+
+use crate::codec::{Codec, CodecError, CodecFactory, MessageHeader};
+use crate::cursor::BufferCursor;
+use crate::framed_transport::FramedTransport;
+use crate::request::{MessageType, RequestResponseError};
+use crate::rpc::{dispatch_request, is_reply_to, BufferService, Caller, Client};
+
+/// What [`TransportArbitrator`] received in one frame.
+enum Received<CodecType, Error> {
+    /// A reply, with the codec positioned after its header.
+    Reply(MessageHeader, CodecType),
+    /// A request, with the error which prevented handling it if any.
+    Request(Result<(), RequestResponseError<Error>>),
+}
+
+pub struct TransportArbitrator<
+    'buffer,
+    'service,
+    Transport,
+    CodecFactoryType,
+    CodecType,
+    const MAX_SERVICES: usize,
+> {
+    /// Client sending the invocations, whose buffer also encodes the replies to the peer.
+    client: Client<'buffer, Transport, CodecFactoryType>,
+    receive_buffer: &'buffer mut [u8],
+    services: heapless::Vec<&'service mut BufferService<'buffer, CodecType>, MAX_SERVICES>,
+}
+
+impl<'buffer, 'service, Transport, CodecFactoryType, CodecType, const MAX_SERVICES: usize>
+    TransportArbitrator<'buffer, 'service, Transport, CodecFactoryType, CodecType, MAX_SERVICES>
+where
+    CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+    CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+{
+    /// Creates an arbitrator receiving every frame into `receive_buffer`,
+    /// and encoding both its requests and its replies in `send_buffer`.
+    pub fn new(
+        transport: Transport,
+        receive_buffer: &'buffer mut [u8],
+        send_buffer: &'buffer mut [u8],
+        codec_factory: CodecFactoryType,
+    ) -> Self {
+        Self {
+            client: Client::new(transport, send_buffer, codec_factory),
+            receive_buffer,
+            services: heapless::Vec::new(),
+        }
+    }
+    pub fn release(
+        self,
+    ) -> (
+        Transport,
+        &'buffer mut [u8],
+        &'buffer mut [u8],
+        CodecFactoryType,
+    ) {
+        let (transport, send_buffer, codec_factory) = self.client.release();
+        (transport, self.receive_buffer, send_buffer, codec_factory)
+    }
+    pub fn get_sequence(&self) -> u32 {
+        self.client.get_sequence()
+    }
+
+    /// Registers `service`. The service is handed back if `MAX_SERVICES` services are already registered.
+    pub fn add_service(
+        &mut self,
+        service: &'service mut BufferService<'buffer, CodecType>,
+    ) -> Result<(), &'service mut BufferService<'buffer, CodecType>> {
+        self.services.push(service)
+    }
+
+    /// Receives one message and handles it if it is a request, as `SimpleServer::run_once`.
+    /// Replies received while no call is pending are discarded.
+    pub fn run_once<Error>(&mut self) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        match self.receive()? {
+            Received::Reply(_, codec) => {
+                self.receive_buffer = codec.detach().release();
+                Ok(())
+            }
+            Received::Request(result) => result,
+        }
+    }
+
+    /// Receives one message. Requests are dispatched to the services and replied to if needed.
+    /// For replies, the caller must put the buffer of the returned codec back into `receive_buffer`.
+    ///
+    /// Fails if the frame cannot be received or its header decoded, or if the reply to a request cannot be sent.
+    fn receive<Error>(&mut self) -> Result<Received<CodecType, Error>, RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        let client = &mut self.client;
+        let length = client.transport.receive(self.receive_buffer)?.len();
+        let cursor =
+            BufferCursor::new_with_length(core::mem::take(&mut self.receive_buffer), length);
+        let mut in_codec = client.codec_factory.from_cursor(cursor);
+        let header = match in_codec.start_read_message() {
+            Ok(header) => header,
+            Err(err) => {
+                self.receive_buffer = in_codec.detach().release();
+                return Err(err.into());
+            }
+        };
+        if header.message_type == MessageType::ReplyMessage {
+            return Ok(Received::Reply(header, in_codec));
+        }
+
+        let cursor = BufferCursor::new(core::mem::take(&mut client.buffer));
+        let mut out_codec = client.codec_factory.from_cursor(cursor);
+        let result = dispatch_request(&mut self.services, &header, &mut in_codec, &mut out_codec);
+        self.receive_buffer = in_codec.detach().release();
+        let cursor = out_codec.detach();
+        let position = cursor.get_position();
+        client.buffer = cursor.release();
+
+        match result {
+            Ok(true) => client.transport.send(&client.buffer[0..position])?,
+            Ok(false) => {}
+            Err(err) => return Ok(Received::Request(Err(err))),
+        }
+        Ok(Received::Request(Ok(())))
+    }
+}
+
+impl<
+        'buffer,
+        'service,
+        Error,
+        Transport,
+        CodecFactoryType,
+        CodecType,
+        const MAX_SERVICES: usize,
+    > Caller<Error, BufferCursor<&'buffer mut [u8]>, CodecType>
+    for TransportArbitrator<'buffer, 'service, Transport, CodecFactoryType, CodecType, MAX_SERVICES>
+where
+    Transport: FramedTransport<Error>,
+    CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+    CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+{
+    /// Sends an invocation and waits for the matching reply, handling the requests received meanwhile.
+    ///
+    /// Replies carrying another sequence number are stale replies of earlier calls and are skipped.
+    /// A reply with the expected sequence number but another service or request is rejected with `InvalidResponse`.
+    /// Requests and notifications which cannot be handled are dropped as by `SimpleServer::run`, while a frame
+    /// which cannot be received or whose header cannot be decoded fails the call, as it may be the reply.
+    fn call<Constructor, Parser, Output>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+        parser: Parser,
+    ) -> Result<Output, RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+        Parser: FnOnce(&mut CodecType) -> Result<Output, CodecError>,
+    {
+        let message_header = self.client.next_message_header(service, request, false);
+        self.client.send(&message_header, constructor)?;
+        loop {
+            let (header, mut codec) = match self.receive() {
+                Ok(Received::Reply(header, codec)) => (header, codec),
+                Ok(Received::Request(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            let result = match is_reply_to(&message_header, &header) {
+                Ok(true) => parser(&mut codec).map_err(RequestResponseError::from),
+                Ok(false) => {
+                    self.receive_buffer = codec.detach().release();
+                    continue;
+                }
+                Err(err) => Err(err),
+            };
+            self.receive_buffer = codec.detach().release();
+            return result;
+        }
+    }
+
+    fn call_oneway<Constructor>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    {
+        Caller::<Error, _, CodecType>::call_oneway(&mut self.client, service, request, constructor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::BasicCodecFactory;
+    use crate::cursor::{Cursor, CursorError, SliceCursor};
+    use crate::framed_transport::{BasicFramedTransport, UnderlyingTransport};
+    use crate::request::{Request, Response};
+    use crate::rpc::{Service, ServiceError};
+
+    struct TestTransport<'a> {
+        rx: SliceCursor<'a>,
+        tx: SliceCursor<'a>,
+    }
+
+    impl<'a> UnderlyingTransport for TestTransport<'a> {
+        type Error = CursorError;
+        fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+            self.rx.read(data)?;
+            Ok(())
+        }
+        fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+            self.tx.write(data)
+        }
+        fn read_available(&mut self, data: &mut [u8]) -> Result<usize, Self::Error> {
+            self.rx.read_available(data)
+        }
+    }
+
+    /// Callback service of the peer, recording the values it is called with.
+    #[derive(Default)]
+    struct CallbackService {
+        values: heapless::Vec<u32, 4>,
+    }
+
+    impl<CursorType: Cursor, CodecType: Codec<CursorType>> Service<CursorType, CodecType>
+        for CallbackService
+    {
+        fn get_service_id(&self) -> u32 {
+            12
+        }
+        fn handle_invocation(
+            &mut self,
+            method_id: u32,
+            in_codec: &mut CodecType,
+            out_codec: &mut CodecType,
+        ) -> Result<(), ServiceError> {
+            if method_id != 1 {
+                return Err(ServiceError::UnknownMethod);
+            }
+            let value = in_codec.read_u32()?;
+            let _ = self.values.push(value);
+            out_codec.write_u32(value + 1)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn nested_callbacks_during_call() -> Result<(), RequestResponseError<CursorError>> {
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        let rx_length = {
+            let mut frame_buffer = [0u8; 64];
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut rx_buffer));
+            let mut send = |message_type, service, request, sequence, value: u32| {
+                let header = MessageHeader {
+                    message_type,
+                    service,
+                    request,
+                    sequence,
+                };
+                crate::request::send_message(
+                    &mut transport,
+                    &mut frame_buffer,
+                    &header,
+                    BasicCodecFactory::new(),
+                    |codec| codec.write_u32(value),
+                )
+            };
+            send(MessageType::InvocationMessage, 12, 1, 100, 10)?;
+            send(MessageType::ReplyMessage, 2, 1, 0, 0xdead)?;
+            send(MessageType::OnewayMessage, 12, 1, 101, 20)?;
+            // Neither a notification nor a request of an unknown service interrupts the call.
+            send(MessageType::NotificationMessage, 12, 1, 102, 0)?;
+            send(MessageType::InvocationMessage, 13, 1, 103, 0)?;
+            send(MessageType::ReplyMessage, 2, 1, 1, 1234)?;
+            send(MessageType::ReplyMessage, 2, 1, 7, 0)?;
+            send(MessageType::OnewayMessage, 12, 1, 104, 30)?;
+            transport.release().get_position()
+        };
+
+        let mut callbacks = CallbackService::default();
+        {
+            let mut receive_buffer = [0u8; 64];
+            let mut send_buffer = [0u8; 64];
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx: SliceCursor::new(&mut rx_buffer[..rx_length]),
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut arbitrator: TransportArbitrator<_, _, _, 2> = TransportArbitrator::new(
+                transport,
+                &mut receive_buffer,
+                &mut send_buffer,
+                BasicCodecFactory::new(),
+            );
+            assert!(arbitrator.add_service(&mut callbacks).is_ok());
+
+            let result = arbitrator.call(
+                2,
+                1,
+                |codec| codec.write_u32(5678),
+                |codec| codec.read_u32(),
+            )?;
+            assert_eq!(result, 1234);
+            arbitrator.run_once()?;
+            arbitrator.run_once()?;
+            assert_eq!(
+                arbitrator.run_once(),
+                Err(RequestResponseError::FramedTransportError(
+                    crate::framed_transport::FramedTransportError::UnderlyingError(
+                        CursorError::NotEnoughData
+                    )
+                ))
+            );
+        }
+        assert_eq!(callbacks.values, [10, 20, 30]);
+
+        let mut frame_buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut tx_buffer));
+        let (request, mut codec) =
+            Request::receive_request(&mut transport, &mut frame_buffer, BasicCodecFactory::new())?;
+        assert_eq!(
+            (request.service, request.request, request.sequence),
+            (2, 1, 1)
+        );
+        assert_eq!(codec.read_u32()?, 5678);
+        let (response, mut codec) = Response::receive_response(
+            &mut transport,
+            &mut frame_buffer,
+            BasicCodecFactory::new(),
+        )?;
+        assert_eq!(
+            (response.service, response.request, response.sequence),
+            (12, 1, 100)
+        );
+        assert_eq!(codec.read_u32()?, 11);
+        // The oneway callbacks are not replied to.
+        assert_eq!(transport.release().get_position(), 2 * 4 + 12 + 12);
+        Ok(())
+    }
+
+    #[test]
+    fn call_fails_on_a_corrupted_reply() -> Result<(), RequestResponseError<CursorError>> {
+        let mut rx_buffer = [0u8; 64];
+        let mut tx_buffer = [0u8; 64];
+        let rx_length = {
+            let mut frame_buffer = [0u8; 64];
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut rx_buffer));
+            Response::new(2, 1, 1, false).send_response(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+                |codec| codec.write_u32(1234),
+            )?;
+            transport.release().get_position()
+        };
+        rx_buffer[rx_length - 1] ^= 0xff;
+
+        let mut receive_buffer = [0u8; 64];
+        let mut send_buffer = [0u8; 64];
+        let transport = BasicFramedTransport::new(TestTransport {
+            rx: SliceCursor::new(&mut rx_buffer[..rx_length]),
+            tx: SliceCursor::new(&mut tx_buffer),
+        });
+        let mut arbitrator: TransportArbitrator<_, _, _, 1> = TransportArbitrator::new(
+            transport,
+            &mut receive_buffer,
+            &mut send_buffer,
+            BasicCodecFactory::new(),
+        );
+        assert_eq!(
+            arbitrator.call(2, 1, |_| Ok(()), |codec| codec.read_u32()),
+            Err(RequestResponseError::FramedTransportError(
+                crate::framed_transport::FramedTransportError::ChecksumError
+            ))
+        );
+        Ok(())
+    }
+}
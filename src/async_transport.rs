@@ -0,0 +1,320 @@
+//! Async counterparts of the traits in [`crate::framed_transport`], for executors such as embassy or tokio.
+//!
+//! [`AsyncBasicFramedTransport`] sends the same frames as `BasicFramedTransport`, so both ends of a link
+//! need not agree on whether they are async. The underlying byte stream can be an
+//! `embedded_io_async::{Read, Write}` implementation wrapped in [`EmbeddedIo`] (feature `embedded-io-async`)
+//! or a `futures::io::{AsyncRead, AsyncWrite}` implementation wrapped in [`FuturesIo`] (feature `futures-io`).
+//!
+//! The futures returned by these traits are not required to be `Send`.
+
+use crate::crc16::{Crc16, Crc16Engine};
+use crate::cursor::{Cursor, CursorError};
+use crate::framed_transport::{
+    decode_header, encode_header, FramedTransportError, DEFAULT_MAX_MESSAGE_SIZE,
+};
+
+#[allow(async_fn_in_trait)]
+pub trait AsyncFramedTransport<UnderlyingError> {
+    fn get_max_message_size(&self) -> usize;
+    async fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<UnderlyingError>>;
+    async fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<UnderlyingError>>;
+}
+
+#[allow(async_fn_in_trait)]
+pub trait AsyncUnderlyingTransport {
+    type Error;
+    async fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Self::Error>;
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Async counterpart of [`BasicFramedTransport`](crate::framed_transport::BasicFramedTransport),
+/// with the same maximum message size and checksum engine.
+pub struct AsyncBasicFramedTransport<
+    Underlying: AsyncUnderlyingTransport,
+    const MAX_MESSAGE_SIZE: usize = DEFAULT_MAX_MESSAGE_SIZE,
+    Engine: Crc16Engine = Crc16,
+> {
+    underlying: Underlying,
+    crc: Engine,
+}
+
+impl<Underlying: AsyncUnderlyingTransport> AsyncBasicFramedTransport<Underlying> {
+    pub fn new(underlying: Underlying) -> Self {
+        Self::new_sized(underlying)
+    }
+}
+
+impl<Underlying: AsyncUnderlyingTransport, const MAX_MESSAGE_SIZE: usize>
+    AsyncBasicFramedTransport<Underlying, MAX_MESSAGE_SIZE>
+{
+    /// Creates a transport with the maximum message size given by the type,
+    /// as in `AsyncBasicFramedTransport::<_, 256>::new_sized(underlying)`.
+    pub fn new_sized(underlying: Underlying) -> Self {
+        Self::with_crc(underlying, Crc16::new())
+    }
+}
+
+impl<Underlying: AsyncUnderlyingTransport, const MAX_MESSAGE_SIZE: usize, Engine: Crc16Engine>
+    AsyncBasicFramedTransport<Underlying, MAX_MESSAGE_SIZE, Engine>
+{
+    /// The maximum message size, to size message buffers.
+    pub const MAX_MESSAGE_SIZE: usize = {
+        assert!(
+            MAX_MESSAGE_SIZE <= DEFAULT_MAX_MESSAGE_SIZE,
+            "the frame header cannot describe messages larger than 65535 bytes"
+        );
+        MAX_MESSAGE_SIZE
+    };
+
+    /// Creates a transport computing checksums with `crc`, for example a hardware CRC unit.
+    /// Pass `&mut crc` to keep the engine once the transport is released.
+    pub fn with_crc(underlying: Underlying, crc: Engine) -> Self {
+        let _ = Self::MAX_MESSAGE_SIZE;
+        Self { underlying, crc }
+    }
+    pub fn release(self) -> Underlying {
+        self.underlying
+    }
+}
+
+impl<Underlying: AsyncUnderlyingTransport, const MAX_MESSAGE_SIZE: usize, Engine: Crc16Engine>
+    AsyncFramedTransport<Underlying::Error>
+    for AsyncBasicFramedTransport<Underlying, MAX_MESSAGE_SIZE, Engine>
+{
+    fn get_max_message_size(&self) -> usize {
+        Self::MAX_MESSAGE_SIZE
+    }
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<Underlying::Error>> {
+        if data.len() > self.get_max_message_size() {
+            return Err(FramedTransportError::DataTooLong);
+        }
+        let header = encode_header(data.len(), self.crc.checksum(data));
+        self.underlying.write_all(&header).await?;
+        self.underlying.write_all(data).await?;
+        Ok(())
+    }
+
+    async fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<Underlying::Error>> {
+        let mut header = [0u8; 4];
+        self.underlying.read_exact(&mut header).await?;
+        let (length, checksum) = decode_header(&mut header);
+
+        if length > self.get_max_message_size() {
+            return Err(FramedTransportError::InvalidHeader);
+        }
+        if buffer.len() < length {
+            // The payload is discarded, so the next frame starts with its header.
+            let mut scratch = [0u8; 16];
+            let mut remaining = length;
+            while remaining > 0 {
+                let count = core::cmp::min(remaining, scratch.len());
+                self.underlying.read_exact(&mut scratch[..count]).await?;
+                remaining -= count;
+            }
+            return Err(FramedTransportError::BufferTooShort);
+        }
+        let buffer_part = &mut buffer[0..length];
+        self.underlying.read_exact(buffer_part).await?;
+        if self.crc.checksum(buffer_part) != checksum {
+            return Err(FramedTransportError::ChecksumError);
+        }
+        Ok(buffer_part)
+    }
+}
+
+impl<CursorType: Cursor> AsyncUnderlyingTransport for CursorType {
+    type Error = CursorError;
+    async fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+        self.read(data)?;
+        Ok(())
+    }
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.write(data)?;
+        Ok(())
+    }
+}
+
+/// Adapts an `embedded_io_async` stream, such as an embassy UART, to [`AsyncUnderlyingTransport`].
+#[cfg(feature = "embedded-io-async")]
+pub struct EmbeddedIo<Io>(pub Io);
+
+#[cfg(feature = "embedded-io-async")]
+impl<Io: embedded_io_async::Read + embedded_io_async::Write> AsyncUnderlyingTransport
+    for EmbeddedIo<Io>
+{
+    /// Write errors are reported as `ReadExactError::Other`.
+    type Error = embedded_io_async::ReadExactError<Io::Error>;
+    async fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+        self.0.read_exact(data).await
+    }
+    async fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.0
+            .write_all(data)
+            .await
+            .map_err(embedded_io_async::ReadExactError::Other)
+    }
+}
+
+/// Adapts a `futures::io` stream, such as a tokio stream through `tokio_util::compat`,
+/// to [`AsyncUnderlyingTransport`].
+///
+/// Reaching the end of the stream in the middle of a frame fails with `ErrorKind::UnexpectedEof`.
+#[cfg(feature = "futures-io")]
+pub struct FuturesIo<Io>(pub Io);
+
+#[cfg(feature = "futures-io")]
+impl<Io: futures_io::AsyncRead + futures_io::AsyncWrite + Unpin> AsyncUnderlyingTransport
+    for FuturesIo<Io>
+{
+    type Error = futures_io::Error;
+    async fn read_exact(&mut self, mut data: &mut [u8]) -> Result<(), Self::Error> {
+        use core::pin::Pin;
+        while !data.is_empty() {
+            let length =
+                core::future::poll_fn(|cx| Pin::new(&mut self.0).poll_read(cx, data)).await?;
+            if length == 0 {
+                return Err(futures_io::ErrorKind::UnexpectedEof.into());
+            }
+            data = &mut data[length..];
+        }
+        Ok(())
+    }
+    async fn write_all(&mut self, mut data: &[u8]) -> Result<(), Self::Error> {
+        use core::pin::Pin;
+        while !data.is_empty() {
+            let length =
+                core::future::poll_fn(|cx| Pin::new(&mut self.0).poll_write(cx, data)).await?;
+            if length == 0 {
+                return Err(futures_io::ErrorKind::WriteZero.into());
+            }
+            data = &data[length..];
+        }
+        core::future::poll_fn(|cx| Pin::new(&mut self.0).poll_flush(cx)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{BasicCodecFactory, Codec, MessageHeader};
+    use crate::cursor::SliceCursor;
+    use crate::framed_transport::{BasicFramedTransport, FramedTransport};
+    use crate::request::{receive_message_async, send_message_async, MessageType};
+    use futures::executor::block_on;
+
+    #[test]
+    fn frames_match_blocking_transport() {
+        let mut buffer = [0u8; 4 + 3 + 4];
+        {
+            let mut transport = AsyncBasicFramedTransport::new(SliceCursor::new(&mut buffer));
+            block_on(transport.send(&[1, 2, 3])).unwrap();
+            block_on(transport.send(&[])).unwrap();
+            assert_eq!(
+                block_on(transport.send(&[0; 65536])),
+                Err(FramedTransportError::DataTooLong)
+            );
+        }
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        let mut frame = [0u8; 8];
+        assert_eq!(transport.receive(&mut frame), Ok(&[1u8, 2, 3][..]));
+        assert_eq!(transport.receive(&mut frame), Ok(&[][..]));
+
+        buffer[5] ^= 0xff;
+        let mut transport = AsyncBasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        assert_eq!(
+            block_on(transport.receive(&mut frame)),
+            Err(FramedTransportError::ChecksumError)
+        );
+        assert_eq!(block_on(transport.receive(&mut frame)), Ok(&[][..]));
+        assert_eq!(
+            block_on(transport.receive(&mut frame)),
+            Err(FramedTransportError::UnderlyingError(
+                CursorError::NotEnoughData
+            ))
+        );
+    }
+
+    #[test]
+    fn frames_too_long_for_the_buffer_are_discarded() {
+        let mut buffer = [0u8; 4 + 20 + 4 + 2];
+        let mut engine = Crc16::new();
+        {
+            let mut transport = AsyncBasicFramedTransport::<_, 32, _>::with_crc(
+                SliceCursor::new(&mut buffer),
+                &mut engine,
+            );
+            block_on(transport.send(&[7; 20])).unwrap();
+            block_on(transport.send(&[1, 2])).unwrap();
+        }
+        let mut transport = AsyncBasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        let mut frame = [0u8; 8];
+        assert_eq!(
+            block_on(transport.receive(&mut frame)),
+            Err(FramedTransportError::BufferTooShort)
+        );
+        assert_eq!(block_on(transport.receive(&mut frame)), Ok(&[1u8, 2][..]));
+    }
+
+    #[test]
+    fn send_and_receive_messages() {
+        let mut buffer = [0u8; 64];
+        let header = MessageHeader {
+            message_type: MessageType::OnewayMessage,
+            service: 2,
+            request: 3,
+            sequence: 4,
+        };
+        {
+            let mut transport = AsyncBasicFramedTransport::new(SliceCursor::new(&mut buffer));
+            let mut message_buffer = [0u8; 32];
+            block_on(send_message_async(
+                &mut transport,
+                &mut message_buffer,
+                &header,
+                BasicCodecFactory::new(),
+                |codec| codec.write_i32(-5),
+            ))
+            .unwrap();
+        }
+        let mut transport = AsyncBasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        let mut message_buffer = [0u8; 32];
+        let (received, mut codec) = block_on(receive_message_async(
+            &mut transport,
+            &mut message_buffer,
+            BasicCodecFactory::new(),
+        ))
+        .unwrap();
+        assert_eq!(received.message_type, MessageType::OnewayMessage);
+        assert_eq!(
+            (received.service, received.request, received.sequence),
+            (2, 3, 4)
+        );
+        assert_eq!(codec.read_i32(), Ok(-5));
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn futures_io_stream() {
+        let mut transport =
+            AsyncBasicFramedTransport::new(FuturesIo(futures::io::Cursor::new(Vec::new())));
+        block_on(transport.send(&[4, 5])).unwrap();
+        let mut stream = transport.release().0;
+        stream.set_position(0);
+        let mut transport = AsyncBasicFramedTransport::new(FuturesIo(stream));
+        let mut frame = [0u8; 8];
+        assert_eq!(block_on(transport.receive(&mut frame)).unwrap(), [4, 5]);
+        match block_on(transport.receive(&mut frame)) {
+            Err(FramedTransportError::UnderlyingError(err)) => {
+                assert_eq!(err.kind(), futures_io::ErrorKind::UnexpectedEof)
+            }
+            _ => panic!("the end of the stream was not reported"),
+        }
+    }
+}
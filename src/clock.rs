@@ -0,0 +1,50 @@
+//! Time source used to bound how long a transport waits for data.
+//!
+//! The crate has no notion of time of its own. Implement [`Clock`] over a hardware timer, `embassy_time` or
+//! `std::time`, and pass a [`ClockDeadline`] or any other [`Deadline`] to
+//! [`FramedTransport::receive_with_deadline`](crate::framed_transport::FramedTransport::receive_with_deadline).
+
+use core::ops::Add;
+
+pub trait Clock {
+    /// A point in time, such as a tick count.
+    type Instant: Copy + Ord + Add<Self::Duration, Output = Self::Instant>;
+    type Duration;
+    fn now(&self) -> Self::Instant;
+}
+
+pub trait Deadline {
+    fn has_expired(&mut self) -> bool;
+    /// Called while waiting for data which has not arrived yet, before checking the deadline again.
+    ///
+    /// The default implementation only hints the processor that it is spinning. Override it to sleep,
+    /// wait for an interrupt or yield to other threads.
+    fn idle(&mut self) {
+        core::hint::spin_loop();
+    }
+}
+
+/// Deadline expiring once a [`Clock`] reaches a given instant.
+pub struct ClockDeadline<'clock, ClockType: Clock> {
+    clock: &'clock ClockType,
+    expires_at: ClockType::Instant,
+}
+
+impl<'clock, ClockType: Clock> ClockDeadline<'clock, ClockType> {
+    pub fn new(clock: &'clock ClockType, expires_at: ClockType::Instant) -> Self {
+        Self { clock, expires_at }
+    }
+    /// Creates a deadline expiring `timeout` from now.
+    pub fn after(clock: &'clock ClockType, timeout: ClockType::Duration) -> Self {
+        Self::new(clock, clock.now() + timeout)
+    }
+    pub fn expires_at(&self) -> ClockType::Instant {
+        self.expires_at
+    }
+}
+
+impl<'clock, ClockType: Clock> Deadline for ClockDeadline<'clock, ClockType> {
+    fn has_expired(&mut self) -> bool {
+        self.clock.now() >= self.expires_at
+    }
+}
@@ -1,14 +1,19 @@
 use num_traits::FromPrimitive;
 
+use core::convert::TryFrom;
 use core::iter::Iterator;
 
 use crate::cursor::*;
+use crate::encode::{decode_prefix, encode_elements, Decode, DecodeUnion, Encode, Union};
 use crate::request::MessageType;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum CodecError {
     InvalidCallback,
     InvalidMessageType,
+    InvalidEnumValue,
+    /// A length read from the message is larger than the buffer to decode into.
+    LengthExceedsBuffer,
     Cursor(CursorError),
     Utf8Error(core::str::Utf8Error),
 }
@@ -25,6 +30,9 @@ impl From<core::str::Utf8Error> for CodecError {
     }
 }
 
+/// Length of the message header written by `start_write_message`.
+pub const MESSAGE_HEADER_SIZE: usize = 8;
+
 #[derive(Copy, Clone)]
 pub struct MessageHeader {
     pub message_type: crate::request::MessageType,
@@ -84,8 +92,106 @@ pub trait Codec<CursorType: Cursor> {
     fn start_read_union(&mut self) -> Result<i32, CodecError>;
     fn read_null_flag(&mut self) -> Result<bool, CodecError>;
     fn read_callback(&mut self, callback_ids: &[usize]) -> Result<usize, CodecError>;
+
+    /// Writes the null flag, then the value if there is one, the encoding of a `@nullable` member.
+    fn write_optional<T: Encode + ?Sized>(&mut self, value: Option<&T>) -> Result<(), CodecError>
+    where
+        Self: Sized,
+    {
+        match value {
+            Some(value) => {
+                self.write_null_flag(false)?;
+                value.encode::<CursorType, Self>(self)
+            }
+            None => self.write_null_flag(true),
+        }
+    }
+    /// Writes the elements as a list, their number first.
+    fn write_list<Elements>(&mut self, elements: Elements) -> Result<(), CodecError>
+    where
+        Self: Sized,
+        Elements: IntoIterator,
+        Elements::Item: Encode,
+        Elements::IntoIter: ExactSizeIterator,
+    {
+        let elements = elements.into_iter();
+        self.start_write_list(elements.len())?;
+        for element in elements {
+            element.encode::<CursorType, Self>(self)?;
+        }
+        Ok(())
+    }
+    /// Writes the elements of a fixed array, without a length.
+    fn write_array<T: Encode, const N: usize>(
+        &mut self,
+        elements: &[T; N],
+    ) -> Result<(), CodecError>
+    where
+        Self: Sized,
+    {
+        encode_elements::<T, CursorType, Self>(elements, self)
+    }
+    /// Writes the discriminator of the active case, then its members.
+    fn write_union<T: Union + ?Sized>(&mut self, value: &T) -> Result<(), CodecError>
+    where
+        Self: Sized,
+    {
+        self.start_write_union(value.discriminator())?;
+        value.encode_case::<CursorType, Self>(self)
+    }
+
+    fn read_optional<T: Decode>(&mut self) -> Result<Option<T>, CodecError>
+    where
+        Self: Sized,
+    {
+        if self.read_null_flag()? {
+            Ok(None)
+        } else {
+            T::decode::<CursorType, Self>(self).map(Some)
+        }
+    }
+    /// Reads a list, failing with `LengthExceedsBuffer` if it has more than `N` elements.
+    fn read_list<T: Decode, const N: usize>(&mut self) -> Result<heapless::Vec<T, N>, CodecError>
+    where
+        Self: Sized,
+    {
+        heapless::Vec::decode::<CursorType, Self>(self)
+    }
+    /// Reads a list into the beginning of `elements` and returns the elements read.
+    fn read_list_into<'buffer, T: Decode>(
+        &mut self,
+        elements: &'buffer mut [T],
+    ) -> Result<&'buffer [T], CodecError>
+    where
+        Self: Sized,
+    {
+        let length = decode_prefix::<T, CursorType, Self>(elements, self)?;
+        Ok(&elements[..length])
+    }
+    /// Reads the elements of a fixed array, without a length.
+    fn read_array<T: Decode + Default, const N: usize>(&mut self) -> Result<[T; N], CodecError>
+    where
+        Self: Sized,
+    {
+        <[T; N]>::decode::<CursorType, Self>(self)
+    }
+    /// Reads the discriminator, then the members of the case it selects.
+    fn read_union<T: DecodeUnion>(&mut self) -> Result<T, CodecError>
+    where
+        Self: Sized,
+    {
+        let discriminator = self.start_read_union()?;
+        T::decode_case::<CursorType, Self>(discriminator, self)
+    }
+}
+/// Codec reading strings and binaries in place, without copying them out of the frame.
+pub trait BorrowingCodec<'frame> {
+    fn read_str_borrowed(&mut self) -> Result<&'frame str, CodecError>;
+    fn read_binary_borrowed(&mut self) -> Result<&'frame [u8], CodecError>;
 }
+
 pub trait CodecFactory<CursorType: Cursor, CodecType: Codec<CursorType>> {
+    #[allow(clippy::wrong_self_convention)]
     fn from_cursor(&mut self, cursor: CursorType) -> CodecType;
 }
 
@@ -118,6 +224,26 @@ impl<CursorType: Cursor> BasicCodec<CursorType> {
         Self { cursor }
     }
 }
+
+/// Codec which only counts the bytes a `BasicCodec` would write, to size a buffer before encoding into it.
+///
+/// ```
+/// use rust_erpc::codec::{Codec, SizeCodec};
+/// use rust_erpc::cursor::CountingCursor;
+///
+/// let mut codec = SizeCodec::new(CountingCursor::new());
+/// codec.write_u32(1).unwrap();
+/// codec.write_str("name").unwrap();
+/// assert_eq!(codec.size(), 4 + 4 + 4);
+/// ```
+pub type SizeCodec = BasicCodec<CountingCursor>;
+
+impl SizeCodec {
+    /// Number of bytes written so far.
+    pub fn size(&self) -> usize {
+        self.cursor.get_position()
+    }
+}
 pub struct BasicCodecFactory<CursorType: Cursor> {
     _marker: core::marker::PhantomData<CursorType>,
 }
@@ -181,18 +307,16 @@ impl<CursorType: Cursor> Codec<CursorType> for BasicCodec<CursorType> {
         callback_ids: &[usize],
         callback_id: usize,
     ) -> Result<(), CodecError> {
-        if callback_ids.is_empty() {
-            if callback_ids[0] == callback_id {
-                Ok(())
-            } else {
-                Err(CodecError::InvalidCallback)
-            }
+        let index = callback_ids
+            .iter()
+            .position(|id| *id == callback_id)
+            .ok_or(CodecError::InvalidCallback)?;
+        // erpcgen does not encode the callback when there is only one to choose from.
+        if callback_ids.len() == 1 {
+            Ok(())
         } else {
-            let index = callback_ids.iter().position(|id| *id == callback_id);
-            match index {
-                Some(index) => self.write_u8(index as u8),
-                None => Err(CodecError::InvalidCallback),
-            }
+            let index = u8::try_from(index).map_err(|_| CodecError::InvalidCallback)?;
+            self.write_u8(index)
         }
     }
 
@@ -228,8 +352,11 @@ impl<CursorType: Cursor> Codec<CursorType> for BasicCodec<CursorType> {
         buffer: &'buffer mut [u8],
     ) -> Result<&'buffer [u8], CodecError> {
         let length = self.read_u32()? as usize;
-        self.cursor.read(&mut buffer[0..length])?;
-        Ok(&buffer[0..length])
+        let buffer = buffer
+            .get_mut(0..length)
+            .ok_or(CodecError::LengthExceedsBuffer)?;
+        self.cursor.read(buffer)?;
+        Ok(buffer)
     }
 
     fn start_read_list(&mut self) -> Result<usize, CodecError> {
@@ -244,12 +371,15 @@ impl<CursorType: Cursor> Codec<CursorType> for BasicCodec<CursorType> {
         Ok(flag != 0)
     }
     fn read_callback(&mut self, callback_ids: &[usize]) -> Result<usize, CodecError> {
-        let index = self.read_u8()? as usize;
-        if index >= callback_ids.len() {
-            Err(CodecError::InvalidCallback)
+        let index = if callback_ids.len() == 1 {
+            0
         } else {
-            Ok(callback_ids[index])
-        }
+            self.read_u8()? as usize
+        };
+        callback_ids
+            .get(index)
+            .copied()
+            .ok_or(CodecError::InvalidCallback)
     }
     fn start_read_message(&mut self) -> Result<MessageHeader, CodecError> {
         let header = self.read_u32()?;
@@ -278,6 +408,19 @@ impl<CursorType: Cursor> Codec<CursorType> for BasicCodec<CursorType> {
     }
 }
 
+impl<'frame, CursorType: BorrowingCursor<'frame>> BorrowingCodec<'frame>
+    for BasicCodec<CursorType>
+{
+    fn read_str_borrowed(&mut self) -> Result<&'frame str, CodecError> {
+        let raw_str = self.read_binary_borrowed()?;
+        Ok(core::str::from_utf8(raw_str)?)
+    }
+    fn read_binary_borrowed(&mut self) -> Result<&'frame [u8], CodecError> {
+        let length = self.read_u32()? as usize;
+        Ok(self.cursor.read_borrowed(length)?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,13 +444,13 @@ mod tests {
             codec.write_f32(1f32)?;
             codec.write_f32(0.5f32)?;
             codec.write_f32(-0.25f32)?;
-            codec.write_f32(core::f32::MIN)?;
-            codec.write_f32(-core::f32::MIN)?;
-            codec.write_f32(core::f32::MAX)?;
-            codec.write_f32(-core::f32::MAX)?;
-            codec.write_f32(core::f32::NAN)?;
-            codec.write_f32(core::f32::INFINITY)?;
-            codec.write_f32(core::f32::NEG_INFINITY)?;
+            codec.write_f32(f32::MIN)?;
+            codec.write_f32(-f32::MIN)?;
+            codec.write_f32(f32::MAX)?;
+            codec.write_f32(-f32::MAX)?;
+            codec.write_f32(f32::NAN)?;
+            codec.write_f32(f32::INFINITY)?;
+            codec.write_f32(f32::NEG_INFINITY)?;
 
             codec.write_str("HogeFugaPiyo🍣")?;
             codec.write_binary(&[0xdeu8, 0xad, 0xbe, 0xef, 0xca, 0xfe, 0x00])?;
@@ -337,13 +480,13 @@ mod tests {
             assert_eq!(codec.read_f32()?, 1f32);
             assert_eq!(codec.read_f32()?, 0.5f32);
             assert_eq!(codec.read_f32()?, -0.25f32);
-            assert_eq!(codec.read_f32()?, core::f32::MIN);
-            assert_eq!(codec.read_f32()?, -core::f32::MIN);
-            assert_eq!(codec.read_f32()?, core::f32::MAX);
-            assert_eq!(codec.read_f32()?, -core::f32::MAX);
+            assert_eq!(codec.read_f32()?, f32::MIN);
+            assert_eq!(codec.read_f32()?, -f32::MIN);
+            assert_eq!(codec.read_f32()?, f32::MAX);
+            assert_eq!(codec.read_f32()?, -f32::MAX);
             assert!(codec.read_f32()?.is_nan());
-            assert_eq!(codec.read_f32()?, core::f32::INFINITY);
-            assert_eq!(codec.read_f32()?, core::f32::NEG_INFINITY);
+            assert_eq!(codec.read_f32()?, f32::INFINITY);
+            assert_eq!(codec.read_f32()?, f32::NEG_INFINITY);
 
             let mut binary_buffer = [0u8; 256];
             assert_eq!(codec.read_str(&mut binary_buffer)?, "HogeFugaPiyo🍣");
@@ -363,4 +506,211 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn reject_malformed_input() {
+        let mut data = [0xff, 0xff, 0xff, 0xff, 3, 0, 0, 0, 1, 2];
+        let mut buffer = [0u8; 8];
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut data));
+        assert_eq!(
+            codec.read_binary(&mut buffer),
+            Err(CodecError::LengthExceedsBuffer)
+        );
+        assert_eq!(
+            codec.read_binary(&mut buffer),
+            Err(CodecError::Cursor(CursorError::NotEnoughData))
+        );
+        assert_eq!(
+            codec.read_u32(),
+            Err(CodecError::Cursor(CursorError::NotEnoughData))
+        );
+        assert_eq!(codec.read_u16(), Ok(0x0201));
+    }
+
+    #[test]
+    fn callbacks_are_only_encoded_when_there_is_a_choice() -> Result<(), CodecError> {
+        let mut buffer = [0u8; 4];
+        let position = {
+            let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+            codec.write_callback(&[7], 7)?;
+            codec.write_callback(&[7, 8], 8)?;
+            assert_eq!(
+                codec.write_callback(&[], 7),
+                Err(CodecError::InvalidCallback)
+            );
+            assert_eq!(
+                codec.write_callback(&[7], 8),
+                Err(CodecError::InvalidCallback)
+            );
+            codec.detach().get_position()
+        };
+        assert_eq!(buffer[..position], [1]);
+
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer[..position]));
+        assert_eq!(codec.read_callback(&[7])?, 7);
+        assert_eq!(codec.read_callback(&[7, 8])?, 8);
+        let mut index = [2];
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut index));
+        assert_eq!(
+            codec.read_callback(&[7, 8]),
+            Err(CodecError::InvalidCallback)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn borrowed_reads_point_into_the_frame() -> Result<(), CodecError> {
+        let frame = [2, 0, 0, 0, b'o', b'k', 3, 0, 0, 0, 7, 8, 9, 5, 0, 0, 0];
+        let (name, data) = {
+            let mut codec = BasicCodec::new(ReadCursor::new(&frame));
+            let name = codec.read_str_borrowed()?;
+            let data = codec.read_binary_borrowed()?;
+            assert_eq!(
+                codec.read_binary_borrowed(),
+                Err(CodecError::Cursor(CursorError::NotEnoughData))
+            );
+            (name, data)
+        };
+        assert_eq!(name, "ok");
+        assert_eq!(data.as_ptr(), frame[10..].as_ptr());
+        assert_eq!(data, [7, 8, 9]);
+        Ok(())
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Setting {
+        Level(u16),
+        Enabled(bool),
+    }
+
+    impl Union for Setting {
+        fn discriminator(&self) -> i32 {
+            match self {
+                Setting::Level(_) => 1,
+                Setting::Enabled(_) => 2,
+            }
+        }
+        fn encode_case<CursorType, CodecType>(
+            &self,
+            codec: &mut CodecType,
+        ) -> Result<(), CodecError>
+        where
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            match self {
+                Setting::Level(level) => codec.write_u16(*level),
+                Setting::Enabled(enabled) => codec.write_bool(*enabled),
+            }
+        }
+    }
+
+    impl DecodeUnion for Setting {
+        fn decode_case<CursorType, CodecType>(
+            discriminator: i32,
+            codec: &mut CodecType,
+        ) -> Result<Self, CodecError>
+        where
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            match discriminator {
+                1 => Ok(Setting::Level(codec.read_u16()?)),
+                2 => Ok(Setting::Enabled(codec.read_bool()?)),
+                _ => Err(CodecError::InvalidEnumValue),
+            }
+        }
+    }
+
+    #[test]
+    fn typed_helpers_encode_like_erpcgen() -> Result<(), CodecError> {
+        let mut buffer = [0u8; 64];
+        let position = {
+            let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+            codec.write_optional(Some(&7u8))?;
+            codec.write_optional::<u8>(None)?;
+            codec.write_list((1u16..=3).map(|value| value * 2))?;
+            codec.write_list([9u8, 8].iter())?;
+            codec.write_array(&[5i8, -5])?;
+            codec.write_union(&Setting::Level(0x1234))?;
+            codec.write_union(&Setting::Enabled(true))?;
+            codec.detach().get_position()
+        };
+        assert_eq!(
+            buffer[..position],
+            [
+                0, 7, 1, 3, 0, 0, 0, 2, 0, 4, 0, 6, 0, 2, 0, 0, 0, 9, 8, 5, 0xfb, 1, 0, 0, 0, 0x34,
+                0x12, 2, 0, 0, 0, 1
+            ]
+        );
+
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer[..position]));
+        assert_eq!(codec.read_optional::<u8>()?, Some(7));
+        assert_eq!(codec.read_optional::<u8>()?, None);
+        assert_eq!(codec.read_list::<u16, 3>()?.as_slice(), [2, 4, 6]);
+        let mut elements = [0u8; 4];
+        assert_eq!(codec.read_list_into(&mut elements)?, [9, 8]);
+        assert_eq!(codec.read_array::<i8, 2>()?, [5, -5]);
+        assert_eq!(codec.read_union::<Setting>()?, Setting::Level(0x1234));
+        assert_eq!(codec.read_union::<Setting>()?, Setting::Enabled(true));
+        Ok(())
+    }
+
+    #[test]
+    fn typed_helpers_reject_what_does_not_fit() {
+        let mut buffer = [3, 0, 0, 0, 1, 2, 3, 3, 0, 0, 0];
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer[..7]));
+        assert_eq!(
+            codec.read_list::<u8, 2>(),
+            Err(CodecError::LengthExceedsBuffer)
+        );
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer[..7]));
+        assert_eq!(
+            codec.read_list_into(&mut [0u8; 2]),
+            Err(CodecError::LengthExceedsBuffer)
+        );
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer[7..]));
+        assert_eq!(
+            codec.read_union::<Setting>(),
+            Err(CodecError::InvalidEnumValue)
+        );
+    }
+
+    /// Decodes `data` with reads chosen by the data itself, like the fuzz targets.
+    fn decode_all(data: &mut [u8]) {
+        let mut codec = BasicCodec::new(SliceCursor::new(data));
+        let mut buffer = [0u8; 16];
+        let _ = codec.start_read_message();
+        while let Ok(operation) = codec.read_u8() {
+            let _ = match operation % 8 {
+                0 => codec.read_u64().map(drop),
+                1 => codec.read_f32().map(drop),
+                2 => codec.read_str(&mut buffer).map(drop),
+                3 => codec.read_binary(&mut buffer).map(drop),
+                4 => codec.start_read_list().map(drop),
+                5 => codec.read_callback(&[1, 2, 3]).map(drop),
+                6 => codec.start_read_message().map(drop),
+                _ => codec.read_null_flag().map(drop),
+            };
+        }
+    }
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        let mut state = 0x2545_f491u32;
+        for length in 0..64 {
+            let data: Vec<u8> = (0..length)
+                .map(|_| {
+                    // xorshift32
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                })
+                .collect();
+            for end in 0..=data.len() {
+                decode_all(&mut data[..end].to_vec());
+            }
+        }
+    }
 }
@@ -0,0 +1,204 @@
+//! CRC16 checksum of the frames sent by `BasicFramedTransport`.
+//!
+//! It is the CRC-16/CCITT polynomial `0x1021`, not reflected, starting from `0xEF4A` as in the erpc C
+//! implementation. [`Crc16`] computes it in software with a table built at compile time, so it needs no
+//! atomics nor initialization at startup. With the `crc16-slicing` feature, it processes 8 bytes at a time
+//! with 4 KiB of additional tables, which suits host builds better than flash constrained targets.
+//!
+//! A hardware CRC unit computing the same checksum can be plugged into `BasicFramedTransport` by
+//! implementing [`Crc16Engine`].
+
+/// Initial value of the checksum.
+pub const CRC16_START: u16 = 0xEF4A;
+/// Generator polynomial of the checksum.
+pub const CRC16_POLY: u16 = 0x1021;
+
+static CRC16_TABLE: [u16; 256] = compute_table(CRC16_POLY);
+
+/// Tables of the slicing-by-8 algorithm, `CRC16_SLICING_TABLES[k][byte]` being the checksum of `byte`
+/// followed by `k` zero bytes.
+#[cfg(feature = "crc16-slicing")]
+static CRC16_SLICING_TABLES: [[u16; 256]; 8] = compute_slicing_tables(&CRC16_TABLE);
+
+const fn compute_table(poly: u16) -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = (index as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if (crc & 0x8000) != 0 {
+                (crc << 1) ^ poly
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+#[cfg(feature = "crc16-slicing")]
+const fn compute_slicing_tables(table: &[u16; 256]) -> [[u16; 256]; 8] {
+    let mut tables = [*table; 8];
+    let mut slice = 1;
+    while slice < 8 {
+        let mut index = 0;
+        while index < 256 {
+            let previous = tables[slice - 1][index];
+            tables[slice][index] = (previous << 8) ^ table[(previous >> 8) as usize];
+            index += 1;
+        }
+        slice += 1;
+    }
+    tables
+}
+
+fn update_bytewise(mut crc: u16, data: &[u8]) -> u16 {
+    for c in data {
+        crc = (crc << 8) ^ CRC16_TABLE[((crc >> 8) ^ (*c as u16)) as usize & 0xff];
+    }
+    crc
+}
+
+#[cfg(feature = "crc16-slicing")]
+fn update_sliced(mut crc: u16, data: &[u8]) -> u16 {
+    let tables = &CRC16_SLICING_TABLES;
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        crc = tables[7][(chunk[0] ^ (crc >> 8) as u8) as usize]
+            ^ tables[6][(chunk[1] ^ crc as u8) as usize]
+            ^ tables[5][chunk[2] as usize]
+            ^ tables[4][chunk[3] as usize]
+            ^ tables[3][chunk[4] as usize]
+            ^ tables[2][chunk[5] as usize]
+            ^ tables[1][chunk[6] as usize]
+            ^ tables[0][chunk[7] as usize];
+    }
+    update_bytewise(crc, chunks.remainder())
+}
+
+/// Computes the checksum of data given in any number of parts.
+///
+/// ```
+/// use rust_erpc::crc16::Crc16;
+///
+/// let mut crc = Crc16::new();
+/// crc.update(b"1234");
+/// crc.update(b"56789");
+/// assert_eq!(crc.finalize(), Crc16::checksum(b"123456789"));
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Crc16 {
+    crc: u16,
+}
+
+impl Default for Crc16 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc16 {
+    pub const fn new() -> Self {
+        Self { crc: CRC16_START }
+    }
+    /// Checksum of `data` as a whole.
+    pub fn checksum(data: &[u8]) -> u16 {
+        let mut crc = Self::new();
+        crc.update(data);
+        crc.finalize()
+    }
+    /// Continues the checksum with `data`, which follows the data given so far.
+    pub fn update(&mut self, data: &[u8]) {
+        #[cfg(feature = "crc16-slicing")]
+        {
+            self.crc = update_sliced(self.crc, data);
+        }
+        #[cfg(not(feature = "crc16-slicing"))]
+        {
+            self.crc = update_bytewise(self.crc, data);
+        }
+    }
+    /// Checksum of the data given so far.
+    pub fn finalize(&self) -> u16 {
+        self.crc
+    }
+}
+
+/// Computes the checksum of frames for `BasicFramedTransport`, to use a hardware CRC unit instead of [`Crc16`].
+///
+/// Implementations must compute the same checksum as [`Crc16`]: the peer only accepts frames whose checksum
+/// matches.
+pub trait Crc16Engine {
+    /// Starts a new checksum from [`CRC16_START`].
+    fn reset(&mut self);
+    /// Continues the checksum with `data`.
+    fn update(&mut self, data: &[u8]);
+    /// Checksum of the data given since the last `reset`.
+    fn finalize(&mut self) -> u16;
+    /// Checksum of `data` as a whole.
+    fn checksum(&mut self, data: &[u8]) -> u16 {
+        self.reset();
+        self.update(data);
+        self.finalize()
+    }
+}
+
+impl Crc16Engine for Crc16 {
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+    fn update(&mut self, data: &[u8]) {
+        Crc16::update(self, data)
+    }
+    fn finalize(&mut self) -> u16 {
+        Crc16::finalize(self)
+    }
+}
+
+/// Lends an engine to a transport, so that the engine can be shared or kept after releasing the transport.
+impl<Engine: Crc16Engine + ?Sized> Crc16Engine for &mut Engine {
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+    fn update(&mut self, data: &[u8]) {
+        (**self).update(data)
+    }
+    fn finalize(&mut self) -> u16 {
+        (**self).finalize()
+    }
+    fn checksum(&mut self, data: &[u8]) -> u16 {
+        (**self).checksum(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_matches_the_bitwise_definition() {
+        assert_eq!(Crc16::checksum(b""), CRC16_START);
+        assert_eq!(Crc16::checksum(b"123456789"), 0x89ac);
+    }
+
+    #[test]
+    fn parts_give_the_checksum_of_the_whole() {
+        let data: [u8; 61] = core::array::from_fn(|index| (index * 37 + 11) as u8);
+        let expected = update_bytewise(CRC16_START, &data);
+        assert_eq!(Crc16::checksum(&data), expected);
+        for split in 0..data.len() {
+            let mut crc = Crc16::new();
+            crc.update(&data[..split]);
+            crc.update(&data[split..]);
+            assert_eq!(crc.finalize(), expected, "split at {}", split);
+        }
+
+        let mut engine = Crc16::new();
+        engine.update(&[1, 2, 3]);
+        assert_eq!(Crc16Engine::checksum(&mut &mut engine, &data), expected);
+    }
+}
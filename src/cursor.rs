@@ -5,6 +5,7 @@ pub enum CursorError {
 }
 
 pub trait Cursor {
+    /// Reads exactly `buffer.len()` bytes, or fails with `NotEnoughData` without consuming anything.
     fn read<'a>(&mut self, buffer: &'a mut [u8]) -> Result<&'a [u8], CursorError>;
     fn write(&mut self, data: &[u8]) -> Result<(), CursorError>;
 }
@@ -12,6 +13,7 @@ pub trait Cursor {
 pub struct BufferCursor<Buffer: AsMut<[u8]>> {
     buffer: Buffer,
     position: usize,
+    length: Option<usize>,
 }
 
 impl<Buffer: AsMut<[u8]>> BufferCursor<Buffer> {
@@ -19,6 +21,16 @@ impl<Buffer: AsMut<[u8]>> BufferCursor<Buffer> {
         Self {
             buffer,
             position: 0,
+            length: None,
+        }
+    }
+    /// Creates a cursor which only exposes the first `length` bytes of `buffer`,
+    /// while `release` still returns the whole buffer.
+    pub fn new_with_length(buffer: Buffer, length: usize) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            length: Some(length),
         }
     }
     pub fn release(self) -> Buffer {
@@ -30,17 +42,26 @@ impl<Buffer: AsMut<[u8]>> BufferCursor<Buffer> {
     pub fn get_position(&self) -> usize {
         self.position
     }
+    fn as_slice(&mut self) -> &mut [u8] {
+        let buffer = self.buffer.as_mut();
+        match self.length {
+            Some(length) if length < buffer.len() => &mut buffer[0..length],
+            _ => buffer,
+        }
+    }
 }
 
 impl<Buffer: AsMut<[u8]>> Cursor for BufferCursor<Buffer> {
     fn read<'a>(&mut self, buffer: &'a mut [u8]) -> Result<&'a [u8], CursorError> {
-        let mut cursor = SliceCursor::new_with_position(self.buffer.as_mut(), self.position);
+        let position = self.position;
+        let mut cursor = SliceCursor::new_with_position(self.as_slice(), position);
         let result = cursor.read(buffer)?;
         self.position = cursor.get_position();
         Ok(result)
     }
     fn write(&mut self, data: &[u8]) -> Result<(), CursorError> {
-        let mut cursor = SliceCursor::new_with_position(self.buffer.as_mut(), self.position);
+        let position = self.position;
+        let mut cursor = SliceCursor::new_with_position(self.as_slice(), position);
         cursor.write(data)?;
         self.position = cursor.get_position();
         Ok(())
@@ -68,21 +89,157 @@ impl<'buffer> SliceCursor<'buffer> {
 }
 impl<'buffer> Cursor for SliceCursor<'buffer> {
     fn read<'a>(&mut self, buffer: &'a mut [u8]) -> Result<&'a [u8], CursorError> {
-        let remaining = self.buffer.len() - self.position;
-        let bytes_to_read = core::cmp::min(remaining, buffer.len());
-        buffer.copy_from_slice(&self.buffer[self.position..self.position + bytes_to_read]);
+        let bytes_to_read = buffer.len();
+        let data = self
+            .buffer
+            .get(self.position..)
+            .and_then(|remaining| remaining.get(..bytes_to_read))
+            .ok_or(CursorError::NotEnoughData)?;
+        buffer.copy_from_slice(data);
         self.position += bytes_to_read;
-        Ok(&buffer[0..bytes_to_read])
+        Ok(buffer)
     }
     fn write(&mut self, data: &[u8]) -> Result<(), CursorError> {
         let bytes_to_write = data.len();
-        let remaining = self.buffer.len() - self.position;
-        if remaining < bytes_to_write {
-            Err(CursorError::InsufficientBuffer)
-        } else {
-            self.buffer[self.position..self.position + bytes_to_write].copy_from_slice(data);
-            self.position += bytes_to_write;
-            Ok(())
+        let destination = self
+            .buffer
+            .get_mut(self.position..)
+            .and_then(|remaining| remaining.get_mut(..bytes_to_write))
+            .ok_or(CursorError::InsufficientBuffer)?;
+        destination.copy_from_slice(data);
+        self.position += bytes_to_write;
+        Ok(())
+    }
+}
+
+/// Cursor whose reads can borrow from its backing buffer for as long as the buffer lives.
+pub trait BorrowingCursor<'frame>: Cursor {
+    /// Returns the next `length` bytes without copying them, or fails with `NotEnoughData`.
+    fn read_borrowed(&mut self, length: usize) -> Result<&'frame [u8], CursorError>;
+}
+
+/// Read-only cursor over a received frame.
+///
+/// Since it only holds a shared reference, the bytes it lends out stay valid after the cursor,
+/// and the codec wrapping it, are gone. Writes fail with `InsufficientBuffer`.
+pub struct ReadCursor<'frame> {
+    buffer: &'frame [u8],
+    position: usize,
+}
+
+impl<'frame> ReadCursor<'frame> {
+    pub fn new(buffer: &'frame [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0,
         }
     }
+    pub fn release(self) -> &'frame [u8] {
+        self.buffer
+    }
+    pub fn reset(&mut self) {
+        self.position = 0;
+    }
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'frame> Cursor for ReadCursor<'frame> {
+    fn read<'a>(&mut self, buffer: &'a mut [u8]) -> Result<&'a [u8], CursorError> {
+        buffer.copy_from_slice(self.read_borrowed(buffer.len())?);
+        Ok(buffer)
+    }
+    fn write(&mut self, _data: &[u8]) -> Result<(), CursorError> {
+        Err(CursorError::InsufficientBuffer)
+    }
+}
+
+impl<'frame> BorrowingCursor<'frame> for ReadCursor<'frame> {
+    fn read_borrowed(&mut self, length: usize) -> Result<&'frame [u8], CursorError> {
+        let buffer: &'frame [u8] = self.buffer;
+        let data = buffer
+            .get(self.position..)
+            .and_then(|remaining| remaining.get(..length))
+            .ok_or(CursorError::NotEnoughData)?;
+        self.position += length;
+        Ok(data)
+    }
+}
+
+/// Write-only cursor which counts the bytes written to it without storing them.
+///
+/// Reads fail with `NotEnoughData`. Wrapped in a codec, it is the [`SizeCodec`](crate::codec::SizeCodec).
+#[derive(Debug, Default)]
+pub struct CountingCursor {
+    position: usize,
+}
+
+impl CountingCursor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn reset(&mut self) {
+        self.position = 0;
+    }
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Cursor for CountingCursor {
+    fn read<'a>(&mut self, _buffer: &'a mut [u8]) -> Result<&'a [u8], CursorError> {
+        Err(CursorError::NotEnoughData)
+    }
+    fn write(&mut self, data: &[u8]) -> Result<(), CursorError> {
+        self.position = self
+            .position
+            .checked_add(data.len())
+            .ok_or(CursorError::InsufficientBuffer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_reads_fail_without_consuming() {
+        let mut data = [1, 2, 3];
+        let mut buffer = [0u8; 2];
+        let mut cursor = BufferCursor::new_with_length(&mut data[..], 2);
+        assert_eq!(cursor.read(&mut buffer[..1]), Ok(&[1][..]));
+        assert_eq!(cursor.read(&mut buffer), Err(CursorError::NotEnoughData));
+        assert_eq!(cursor.get_position(), 1);
+        assert_eq!(cursor.read(&mut buffer[..1]), Ok(&[2][..]));
+        assert_eq!(cursor.write(&[0]), Err(CursorError::InsufficientBuffer));
+    }
+
+    #[test]
+    fn read_cursor_lends_the_frame() {
+        let frame = [1, 2, 3, 4];
+        let (borrowed, rest) = {
+            let mut cursor = ReadCursor::new(&frame);
+            let borrowed = cursor.read_borrowed(2);
+            let mut buffer = [0u8; 1];
+            assert_eq!(cursor.read(&mut buffer), Ok(&[3][..]));
+            assert_eq!(cursor.read_borrowed(2), Err(CursorError::NotEnoughData));
+            assert_eq!(cursor.write(&[0]), Err(CursorError::InsufficientBuffer));
+            (borrowed, cursor.read_borrowed(1))
+        };
+        assert_eq!(borrowed, Ok(&frame[..2]));
+        assert_eq!(rest, Ok(&[4][..]));
+    }
+
+    #[test]
+    fn counting_cursor_only_counts() {
+        let mut cursor = CountingCursor::new();
+        assert_eq!(cursor.write(&[1, 2, 3]), Ok(()));
+        assert_eq!(cursor.write(&[]), Ok(()));
+        assert_eq!(cursor.get_position(), 3);
+        assert_eq!(cursor.read(&mut [0u8; 1]), Err(CursorError::NotEnoughData));
+        cursor.reset();
+        assert_eq!(cursor.get_position(), 0);
+    }
 }
@@ -0,0 +1,401 @@
+//! Types which encode and decode themselves through a [`Codec`].
+//!
+//! Primitive types map to the erpc built-in types, `[T; N]` to fixed arrays, `heapless::Vec<T, N>` to lists
+//! and `heapless::String<N>` to strings. With the `derive` feature, `#[derive(ErpcEncode, ErpcDecode)]`
+//! implements the traits for structs, enums and unions:
+//!
+//! ```ignore
+//! #[derive(ErpcEncode, ErpcDecode)]
+//! struct Entry {
+//!     addr: [u8; 6],
+//!     #[erpc(nullable)]
+//!     name: Option<heapless::String<16>>,
+//!     // Not encoded here: the list below carries it as its length.
+//!     key_len: u8,
+//!     #[erpc(length = key_len)]
+//!     key: [u8; 32],
+//! }
+//! ```
+//!
+//! Field attributes follow the erpc IDL annotations:
+//!
+//! - `#[erpc(nullable)]` on an `Option<T>` writes the null flag before the value, like `@nullable`.
+//! - `#[erpc(length = field)]` on a `[T; N]` encodes the first `field` elements as a list, like `@length(field)`.
+//!   `field` itself is not encoded in place, as erpcgen does, and is set from the list length when decoding.
+//! - `#[erpc(discriminator = field)]` on a union encodes the union with its discriminator in place of `field`,
+//!   which is set from the decoded discriminator with `TryFrom<i32>`.
+//!
+//! Unions are enums whose variants are marked with `#[erpc(case = value)]`, `value` being an `i32` expression
+//! such as `1` or `Kind::A as i32`. The fields of the variant are the members of the case.
+//! The derive implements [`Union`] and [`DecodeUnion`], which [`Codec::write_union`] and [`Codec::read_union`]
+//! dispatch to.
+//!
+//! `#[derive(ErpcMaxEncodedSize)]` implements [`MaxEncodedSize`] for types of bounded size, following the
+//! same attributes.
+//!
+//! Without these traits, the typed helpers of [`Codec`] encode the same shapes in place:
+//! `write_optional`/`read_optional` for nullable values, `write_list`/`read_list`/`read_list_into` for lists
+//! and `write_array`/`read_array` for fixed arrays.
+
+use crate::codec::{Codec, CodecError};
+use crate::cursor::{Cursor, CursorError};
+
+#[cfg(feature = "derive")]
+pub use erpc_derive::{ErpcDecode, ErpcEncode, ErpcMaxEncodedSize};
+
+pub trait Encode {
+    fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>;
+}
+
+pub trait Decode: Sized {
+    fn decode<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>;
+}
+
+/// Union whose encoding starts with the discriminator of the active case, written by [`Codec::write_union`].
+pub trait Union {
+    fn discriminator(&self) -> i32;
+    /// Encodes the members of the active case, without the discriminator.
+    fn encode_case<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>;
+}
+
+/// Union decoded by [`Codec::read_union`], which reads the discriminator first.
+pub trait DecodeUnion: Sized {
+    /// Decodes the members of the case selected by `discriminator`, failing with `InvalidEnumValue` if there is none.
+    fn decode_case<CursorType, CodecType>(
+        discriminator: i32,
+        codec: &mut CodecType,
+    ) -> Result<Self, CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>;
+}
+
+/// Type whose encoding never exceeds `MAX_ENCODED_SIZE` bytes, to size buffers at compile time.
+///
+/// Implemented for the types of fixed layout, bounded lists and strings counting their full capacity,
+/// and `Option<T>` counting the null flag of a `@nullable` member.
+pub trait MaxEncodedSize {
+    const MAX_ENCODED_SIZE: usize;
+}
+
+/// `T::MAX_ENCODED_SIZE`, usable where the trait is not in scope, as in array lengths.
+pub const fn max_encoded_size<T: MaxEncodedSize>() -> usize {
+    T::MAX_ENCODED_SIZE
+}
+
+macro_rules! impl_primitive {
+    ($type:ty, $write:ident, $read:ident) => {
+        impl MaxEncodedSize for $type {
+            const MAX_ENCODED_SIZE: usize = core::mem::size_of::<$type>();
+        }
+        impl Encode for $type {
+            fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+            where
+                CursorType: Cursor,
+                CodecType: Codec<CursorType>,
+            {
+                codec.$write(*self)
+            }
+        }
+        impl Decode for $type {
+            fn decode<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, CodecError>
+            where
+                CursorType: Cursor,
+                CodecType: Codec<CursorType>,
+            {
+                codec.$read()
+            }
+        }
+    };
+}
+
+impl_primitive!(bool, write_bool, read_bool);
+impl_primitive!(i8, write_i8, read_i8);
+impl_primitive!(u8, write_u8, read_u8);
+impl_primitive!(i16, write_i16, read_i16);
+impl_primitive!(u16, write_u16, read_u16);
+impl_primitive!(i32, write_i32, read_i32);
+impl_primitive!(u32, write_u32, read_u32);
+impl_primitive!(i64, write_i64, read_i64);
+impl_primitive!(u64, write_u64, read_u64);
+impl_primitive!(f32, write_f32, read_f32);
+impl_primitive!(f64, write_f64, read_f64);
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        (**self).encode(codec)
+    }
+}
+
+/// Encoded as a string.
+impl Encode for str {
+    fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        codec.write_str(self)
+    }
+}
+
+/// Encoded as a list.
+impl<T: Encode> Encode for [T] {
+    fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        codec.start_write_list(self.len())?;
+        encode_elements(self, codec)
+    }
+}
+
+/// Encoded as a fixed array, without a length.
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        encode_elements(self, codec)
+    }
+}
+
+impl<T: Decode + Default, const N: usize> Decode for [T; N] {
+    fn decode<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        let mut array: [T; N] = core::array::from_fn(|_| T::default());
+        decode_elements(&mut array, codec)?;
+        Ok(array)
+    }
+}
+
+/// Encoded as a list.
+impl<T: Encode, const N: usize> Encode for heapless::Vec<T, N> {
+    fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        self.as_slice().encode(codec)
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for heapless::Vec<T, N> {
+    fn decode<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        let length = codec.start_read_list()?;
+        if length > N {
+            return Err(CodecError::LengthExceedsBuffer);
+        }
+        let mut list = heapless::Vec::new();
+        for _ in 0..length {
+            // The capacity has been checked above.
+            let _ = list.push(T::decode(codec)?);
+        }
+        Ok(list)
+    }
+}
+
+impl<const N: usize> Encode for heapless::String<N> {
+    fn encode<CursorType, CodecType>(&self, codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        codec.write_str(self)
+    }
+}
+
+impl<const N: usize> Decode for heapless::String<N> {
+    fn decode<CursorType, CodecType>(codec: &mut CodecType) -> Result<Self, CodecError>
+    where
+        CursorType: Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        let mut buffer = [0u8; N];
+        let value = codec.read_str(&mut buffer)?;
+        let mut string = heapless::String::new();
+        // `value` fits since it has been read into `buffer`.
+        let _ = string.push_str(value);
+        Ok(string)
+    }
+}
+
+impl<T: MaxEncodedSize + ?Sized> MaxEncodedSize for &T {
+    const MAX_ENCODED_SIZE: usize = T::MAX_ENCODED_SIZE;
+}
+
+impl<T: MaxEncodedSize, const N: usize> MaxEncodedSize for [T; N] {
+    const MAX_ENCODED_SIZE: usize = N * T::MAX_ENCODED_SIZE;
+}
+
+/// The length followed by `N` elements.
+impl<T: MaxEncodedSize, const N: usize> MaxEncodedSize for heapless::Vec<T, N> {
+    const MAX_ENCODED_SIZE: usize = 4 + N * T::MAX_ENCODED_SIZE;
+}
+
+impl<const N: usize> MaxEncodedSize for heapless::String<N> {
+    const MAX_ENCODED_SIZE: usize = 4 + N;
+}
+
+/// The null flag followed by the value.
+impl<T: MaxEncodedSize> MaxEncodedSize for Option<T> {
+    const MAX_ENCODED_SIZE: usize = 1 + T::MAX_ENCODED_SIZE;
+}
+
+/// Encodes `elements` one after another, without a length.
+pub fn encode_elements<T, CursorType, CodecType>(
+    elements: &[T],
+    codec: &mut CodecType,
+) -> Result<(), CodecError>
+where
+    T: Encode,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    for element in elements {
+        element.encode(codec)?;
+    }
+    Ok(())
+}
+
+/// Decodes `elements.len()` values into `elements`.
+pub fn decode_elements<T, CursorType, CodecType>(
+    elements: &mut [T],
+    codec: &mut CodecType,
+) -> Result<(), CodecError>
+where
+    T: Decode,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    for element in elements.iter_mut() {
+        *element = T::decode(codec)?;
+    }
+    Ok(())
+}
+
+/// Encodes the first `length` elements of `elements` as a list, the encoding of an `@length` member.
+pub fn encode_prefix<T, CursorType, CodecType>(
+    elements: &[T],
+    length: usize,
+    codec: &mut CodecType,
+) -> Result<(), CodecError>
+where
+    T: Encode,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    let elements = elements
+        .get(..length)
+        .ok_or(CodecError::Cursor(CursorError::InsufficientBuffer))?;
+    elements.encode(codec)
+}
+
+/// Decodes a list into the beginning of `elements` and returns its length, the decoding of an `@length` member.
+pub fn decode_prefix<T, CursorType, CodecType>(
+    elements: &mut [T],
+    codec: &mut CodecType,
+) -> Result<usize, CodecError>
+where
+    T: Decode,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    let length = codec.start_read_list()?;
+    let elements = elements
+        .get_mut(..length)
+        .ok_or(CodecError::LengthExceedsBuffer)?;
+    decode_elements(elements, codec)?;
+    Ok(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::BasicCodec;
+    use crate::cursor::SliceCursor;
+
+    #[test]
+    fn encode_builtin_types_like_erpcgen() -> Result<(), CodecError> {
+        let mut buffer = [0u8; 64];
+        let position = {
+            let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+            0x1234u16.encode(&mut codec)?;
+            [1u8, 2, 3].encode(&mut codec)?;
+            [-1i16, 2][..].encode(&mut codec)?;
+            "ok".encode(&mut codec)?;
+            encode_prefix(&[7u8, 8, 9], 2, &mut codec)?;
+            assert_eq!(
+                encode_prefix(&[7u8], 2, &mut codec),
+                Err(CodecError::Cursor(CursorError::InsufficientBuffer))
+            );
+            codec.detach().get_position()
+        };
+        assert_eq!(
+            buffer[..position],
+            [
+                0x34, 0x12, 1, 2, 3, 2, 0, 0, 0, 0xff, 0xff, 2, 0, 2, 0, 0, 0, b'o', b'k', 2, 0, 0,
+                0, 7, 8
+            ]
+        );
+
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer[..position]));
+        assert_eq!(u16::decode(&mut codec)?, 0x1234);
+        assert_eq!(<[u8; 3]>::decode(&mut codec)?, [1, 2, 3]);
+        assert_eq!(
+            heapless::Vec::<i16, 2>::decode(&mut codec)?.as_slice(),
+            [-1, 2]
+        );
+        assert_eq!(heapless::String::<4>::decode(&mut codec)?.as_str(), "ok");
+        let mut elements = [0u8; 4];
+        assert_eq!(decode_prefix(&mut elements, &mut codec)?, 2);
+        assert_eq!(elements, [7, 8, 0, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn max_encoded_size_bounds_the_encoding() -> Result<(), CodecError> {
+        type Name = heapless::String<8>;
+        assert_eq!(<[u16; 3]>::MAX_ENCODED_SIZE, 6);
+        assert_eq!(heapless::Vec::<u32, 2>::MAX_ENCODED_SIZE, 12);
+        assert_eq!(Option::<Name>::MAX_ENCODED_SIZE, 13);
+        const SIZE: usize = max_encoded_size::<Name>();
+
+        let name: Name = "12345678".parse().unwrap();
+        let mut codec = crate::codec::SizeCodec::new(crate::cursor::CountingCursor::new());
+        name.encode(&mut codec)?;
+        assert_eq!(codec.size(), SIZE);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_lists_longer_than_capacity() {
+        let mut buffer = [3, 0, 0, 0, 1, 2, 3];
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+        assert_eq!(
+            heapless::Vec::<u8, 2>::decode(&mut codec),
+            Err(CodecError::LengthExceedsBuffer)
+        );
+    }
+}
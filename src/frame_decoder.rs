@@ -0,0 +1,234 @@
+//! Push-style receiver of the frames sent by `BasicFramedTransport`.
+//!
+//! Where `BasicFramedTransport::receive` blocks until a whole frame is read, a [`FrameDecoder`] is fed the bytes
+//! as they arrive, for example from a UART RX interrupt or a DMA completion callback, and yields each frame once
+//! its last byte has been pushed. It does not allocate and never blocks.
+
+use crate::crc16::Crc16;
+
+/// Size of the header preceding each frame, its length and its checksum as little endian `u16`.
+const HEADER_SIZE: usize = 4;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FrameDecoderError {
+    /// The frame is longer than the decoder buffer. Its payload is skipped.
+    BufferTooShort,
+    ChecksumError,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum State {
+    Header,
+    Payload {
+        length: usize,
+        checksum: u16,
+        crc: Crc16,
+    },
+    Skip {
+        remaining: usize,
+    },
+}
+
+pub struct FrameDecoder<'buffer> {
+    buffer: &'buffer mut [u8],
+    header: [u8; HEADER_SIZE],
+    /// Number of bytes of the header or payload received so far.
+    position: usize,
+    state: State,
+}
+
+impl<'buffer> FrameDecoder<'buffer> {
+    /// Creates a decoder storing the payload of each frame in `buffer`.
+    pub fn new(buffer: &'buffer mut [u8]) -> Self {
+        Self {
+            buffer,
+            header: [0; HEADER_SIZE],
+            position: 0,
+            state: State::Header,
+        }
+    }
+
+    pub fn release(self) -> &'buffer mut [u8] {
+        self.buffer
+    }
+
+    /// Drops the partially received frame, if any, and waits for a new header.
+    pub fn reset(&mut self) {
+        self.position = 0;
+        self.state = State::Header;
+    }
+
+    /// Whether the decoder is between frames.
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::Header) && self.position == 0
+    }
+
+    /// Consumes `data` until a frame completes or turns out to be invalid.
+    ///
+    /// Returns the number of bytes consumed and, if a frame ended within them, the frame or its error.
+    /// The bytes after a completed frame are not consumed and should be pushed again once the frame is handled.
+    #[allow(clippy::type_complexity)]
+    pub fn push(&mut self, data: &[u8]) -> (usize, Option<Result<&[u8], FrameDecoderError>>) {
+        let mut consumed = 0;
+        while consumed < data.len() {
+            let rest = &data[consumed..];
+            match self.state {
+                State::Header => {
+                    let count = core::cmp::min(HEADER_SIZE - self.position, rest.len());
+                    self.header[self.position..self.position + count]
+                        .copy_from_slice(&rest[..count]);
+                    self.position += count;
+                    consumed += count;
+                    if self.position < HEADER_SIZE {
+                        continue;
+                    }
+                    self.position = 0;
+                    let length = u16::from_le_bytes([self.header[0], self.header[1]]) as usize;
+                    let checksum = u16::from_le_bytes([self.header[2], self.header[3]]);
+                    if length > self.buffer.len() {
+                        self.state = State::Skip { remaining: length };
+                        return (consumed, Some(Err(FrameDecoderError::BufferTooShort)));
+                    }
+                    self.state = State::Payload {
+                        length,
+                        checksum,
+                        crc: Crc16::new(),
+                    };
+                    if length == 0 {
+                        return (consumed, Some(self.finish(0, Crc16::new(), checksum)));
+                    }
+                }
+                State::Payload {
+                    length,
+                    checksum,
+                    mut crc,
+                } => {
+                    let count = core::cmp::min(length - self.position, rest.len());
+                    let part = &rest[..count];
+                    self.buffer[self.position..self.position + count].copy_from_slice(part);
+                    crc.update(part);
+                    self.position += count;
+                    consumed += count;
+                    if self.position == length {
+                        return (consumed, Some(self.finish(length, crc, checksum)));
+                    }
+                    self.state = State::Payload {
+                        length,
+                        checksum,
+                        crc,
+                    };
+                }
+                State::Skip { remaining } => {
+                    let count = core::cmp::min(remaining, rest.len());
+                    consumed += count;
+                    self.state = if count == remaining {
+                        State::Header
+                    } else {
+                        State::Skip {
+                            remaining: remaining - count,
+                        }
+                    };
+                }
+            }
+        }
+        (consumed, None)
+    }
+
+    /// Pushes a single byte, as received by a UART interrupt.
+    pub fn push_byte(&mut self, byte: u8) -> Option<Result<&[u8], FrameDecoderError>> {
+        self.push(&[byte]).1
+    }
+
+    fn finish(
+        &mut self,
+        length: usize,
+        crc: Crc16,
+        checksum: u16,
+    ) -> Result<&[u8], FrameDecoderError> {
+        self.position = 0;
+        self.state = State::Header;
+        if crc.finalize() == checksum {
+            Ok(&self.buffer[..length])
+        } else {
+            Err(FrameDecoderError::ChecksumError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cursor::SliceCursor;
+    use crate::framed_transport::{BasicFramedTransport, FramedTransport};
+
+    fn frames(payloads: &[&[u8]]) -> Vec<u8> {
+        let mut buffer = [0u8; 256];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        for payload in payloads {
+            transport.send(payload).unwrap();
+        }
+        let length = transport.release().get_position();
+        buffer[..length].to_vec()
+    }
+
+    /// Pushes `data` in chunks of `chunk_size` bytes and collects what the decoder yields.
+    fn decode(
+        decoder: &mut FrameDecoder,
+        data: &[u8],
+        chunk_size: usize,
+    ) -> Vec<Result<Vec<u8>, FrameDecoderError>> {
+        let mut results = Vec::new();
+        for mut chunk in data.chunks(chunk_size) {
+            while !chunk.is_empty() {
+                let (consumed, result) = decoder.push(chunk);
+                if let Some(result) = result {
+                    results.push(result.map(<[u8]>::to_vec));
+                }
+                chunk = &chunk[consumed..];
+            }
+        }
+        results
+    }
+
+    #[test]
+    fn frames_split_across_chunks() {
+        let data = frames(&[&[1, 2, 3], &[], &[4; 10]]);
+        for chunk_size in [1, 3, 5, data.len()] {
+            let mut buffer = [0u8; 16];
+            let mut decoder = FrameDecoder::new(&mut buffer);
+            assert_eq!(
+                decode(&mut decoder, &data, chunk_size),
+                [Ok(vec![1, 2, 3]), Ok(vec![]), Ok(vec![4; 10])]
+            );
+            assert!(decoder.is_idle());
+        }
+    }
+
+    #[test]
+    fn report_errors_and_recover() {
+        let mut data = frames(&[&[1, 2, 3], &[5; 20], &[6, 7]]);
+        data[5] ^= 0xff;
+        let mut buffer = [0u8; 16];
+        let mut decoder = FrameDecoder::new(&mut buffer);
+        assert_eq!(
+            decode(&mut decoder, &data, 4),
+            [
+                Err(FrameDecoderError::ChecksumError),
+                Err(FrameDecoderError::BufferTooShort),
+                Ok(vec![6, 7])
+            ]
+        );
+
+        let mut buffer = [0u8; 16];
+        let mut decoder = FrameDecoder::new(&mut buffer);
+        for &byte in &data[..6] {
+            assert_eq!(decoder.push_byte(byte), None);
+        }
+        assert!(!decoder.is_idle());
+        decoder.reset();
+        assert_eq!(
+            decode(&mut decoder, &data[7..], 1),
+            [Err(FrameDecoderError::BufferTooShort), Ok(vec![6, 7])]
+        );
+    }
+}
@@ -1,41 +1,29 @@
+use crate::clock::Deadline;
 use crate::codec::*;
+use crate::crc16::{Crc16, Crc16Engine, CRC16_START};
 use crate::cursor::*;
 use core::fmt::Debug;
 
-use lazy_static::lazy_static;
-static CRC16_START: u16 = 0xEF4A;
-static CRC16_POLY: u16 = 0x1021;
-
-lazy_static! {
-    static ref CRC16_TABLE: [u16; 256] = {
-        let mut table = [0u16; 256];
-        for (i, item) in table.iter_mut().enumerate() {
-            *item = compute_table(i as u8, CRC16_POLY);
-        }
-        table
-    };
-}
-
-fn checksum_crc16(data: &[u8]) -> u16 {
-    let mut crc = CRC16_START;
-    for c in data {
-        crc = (crc << 8) ^ CRC16_TABLE[(((crc >> 8) ^ (*c as u16)) & 0xff) as usize];
+/// Builds the header sent before a payload of `length` bytes with the given checksum.
+/// `length` must already be checked against the maximum message size.
+pub(crate) fn encode_header(length: usize, checksum: u16) -> [u8; 4] {
+    let mut header = [0u8; 4];
+    {
+        let cursor = SliceCursor::new(&mut header);
+        let mut codec = BasicCodec::new(cursor);
+        codec.write_u16(length as u16).unwrap();
+        codec.write_u16(checksum).unwrap();
     }
-    crc
+    header
 }
 
-fn compute_table(index: u8, poly: u16) -> u16 {
-    let mut crc = 0u16;
-    let mut i = (index as u16) << 8;
-    for _ in 0..8 {
-        let t = crc ^ i;
-        crc <<= 1;
-        if (t & 0x8000u16) != 0 {
-            crc ^= poly;
-        }
-        i <<= 1;
-    }
-    crc
+/// Returns the payload length and checksum stored in a frame header.
+pub(crate) fn decode_header(header: &mut [u8; 4]) -> (usize, u16) {
+    let cursor = SliceCursor::new(header);
+    let mut codec = BasicCodec::new(cursor);
+    let length = codec.read_u16().unwrap() as usize;
+    let checksum = codec.read_u16().unwrap();
+    (length, checksum)
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -44,6 +32,10 @@ pub enum FramedTransportError<UnderlyingError> {
     DataTooLong,
     ChecksumError,
     InvalidHeader,
+    /// No valid frame was found within the scan window of a [`ResyncFramedTransport`].
+    SyncLost,
+    /// The deadline passed to `receive_with_deadline` expired before a whole frame was received.
+    Timeout,
     UnderlyingError(UnderlyingError),
 }
 
@@ -60,77 +52,530 @@ pub trait FramedTransport<UnderlyingError> {
         &mut self,
         buffer: &'buffer mut [u8],
     ) -> Result<&'buffer [u8], FramedTransportError<UnderlyingError>>;
+    /// Like `receive`, but fails with `FramedTransportError::Timeout` once `deadline` expires.
+    ///
+    /// The default implementation only checks `deadline` before calling `receive`,
+    /// which suits transports that cannot wait for data without blocking.
+    fn receive_with_deadline<'buffer, DeadlineType: Deadline>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+        deadline: &mut DeadlineType,
+    ) -> Result<&'buffer [u8], FramedTransportError<UnderlyingError>>
+    where
+        Self: Sized,
+    {
+        if deadline.has_expired() {
+            return Err(FramedTransportError::Timeout);
+        }
+        self.receive(buffer)
+    }
+}
+
+/// Lends a transport to a client or a server, so that the transport can be kept after releasing them.
+impl<UnderlyingError, Transport: FramedTransport<UnderlyingError>> FramedTransport<UnderlyingError>
+    for &mut Transport
+{
+    fn get_max_message_size(&self) -> usize {
+        (**self).get_max_message_size()
+    }
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<UnderlyingError>> {
+        (**self).send(data)
+    }
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<UnderlyingError>> {
+        (**self).receive(buffer)
+    }
+    fn receive_with_deadline<'buffer, DeadlineType: Deadline>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+        deadline: &mut DeadlineType,
+    ) -> Result<&'buffer [u8], FramedTransportError<UnderlyingError>> {
+        (**self).receive_with_deadline(buffer, deadline)
+    }
 }
 
 pub trait UnderlyingTransport {
     type Error;
     fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Self::Error>;
     fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+    /// Reads the bytes which are available without waiting, at most `data.len()`, and returns their number.
+    ///
+    /// `BasicFramedTransport::receive_with_deadline` calls this until the deadline expires, so an implementation
+    /// must return `Ok(0)` rather than block when no byte has arrived. The default implementation blocks until
+    /// `data` is filled with `read_exact`: the deadline is then only checked before receiving, as by the default
+    /// `FramedTransport::receive_with_deadline`.
+    fn read_available(&mut self, data: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_exact(data)?;
+        Ok(data.len())
+    }
 }
-pub struct BasicFramedTransport<Underlying: UnderlyingTransport> {
+/// Largest payload the 16-bit length of the frame header can describe.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 65535;
+
+/// Framed transport sending and receiving frames of at most `MAX_MESSAGE_SIZE` bytes.
+///
+/// Larger frames are rejected with `DataTooLong` when sending and with `InvalidHeader` when receiving,
+/// before their payload is read. `MAX_MESSAGE_SIZE` cannot exceed [`DEFAULT_MAX_MESSAGE_SIZE`].
+///
+/// Checksums are computed by `Engine`, [`Crc16`] in software unless the transport is created with `with_crc`.
+pub struct BasicFramedTransport<
+    Underlying: UnderlyingTransport,
+    const MAX_MESSAGE_SIZE: usize = DEFAULT_MAX_MESSAGE_SIZE,
+    Engine: Crc16Engine = Crc16,
+> {
     underlying: Underlying,
+    crc: Engine,
+    /// Header of the frame being received when `receive_with_deadline` timed out.
+    header: [u8; 4],
+    /// Number of bytes of that frame already read, header included.
+    received: usize,
 }
 
 impl<Underlying: UnderlyingTransport> BasicFramedTransport<Underlying> {
     pub fn new(underlying: Underlying) -> Self {
-        Self { underlying }
+        Self::new_sized(underlying)
+    }
+}
+
+impl<Underlying: UnderlyingTransport, const MAX_MESSAGE_SIZE: usize>
+    BasicFramedTransport<Underlying, MAX_MESSAGE_SIZE>
+{
+    /// Creates a transport with the maximum message size given by the type,
+    /// as in `BasicFramedTransport::<_, 256>::new_sized(underlying)`.
+    pub fn new_sized(underlying: Underlying) -> Self {
+        Self::with_crc(underlying, Crc16::new())
+    }
+}
+
+impl<Underlying: UnderlyingTransport, const MAX_MESSAGE_SIZE: usize, Engine: Crc16Engine>
+    BasicFramedTransport<Underlying, MAX_MESSAGE_SIZE, Engine>
+{
+    /// The maximum message size, to size message buffers.
+    pub const MAX_MESSAGE_SIZE: usize = {
+        assert!(
+            MAX_MESSAGE_SIZE <= DEFAULT_MAX_MESSAGE_SIZE,
+            "the frame header cannot describe messages larger than 65535 bytes"
+        );
+        MAX_MESSAGE_SIZE
+    };
+
+    /// Creates a transport computing checksums with `crc`, for example a hardware CRC unit.
+    /// Pass `&mut crc` to keep the engine once the transport is released.
+    pub fn with_crc(underlying: Underlying, crc: Engine) -> Self {
+        let _ = Self::MAX_MESSAGE_SIZE;
+        Self {
+            underlying,
+            crc,
+            header: [0; 4],
+            received: 0,
+        }
     }
     pub fn release(self) -> Underlying {
         self.underlying
     }
+
+    /// Sends a frame whose payload `write` writes straight into the underlying transport,
+    /// without a buffer holding the whole frame.
+    ///
+    /// `write` runs twice: first into a cursor which only computes the length and checksum of the payload,
+    /// then, once the header is sent, into the same cursor forwarding to the underlying transport. It must
+    /// write the same bytes both times. If it does not, the payload sent is padded with zeros to the length
+    /// sent in the header, or cut to it, so the receiver drops the frame on its checksum and reads the next
+    /// one; `write`'s error, or else `InvalidHeader`, is returned. A payload longer than the maximum message
+    /// size fails with `DataTooLong` before anything is sent.
+    /// The checksum is computed by `Engine` in both passes, along with the length in the first one.
+    pub fn send_streamed<'transport, Error, Write>(
+        &'transport mut self,
+        mut write: Write,
+    ) -> Result<(), Error>
+    where
+        Error: From<FramedTransportError<Underlying::Error>>,
+        Write: FnMut(&mut StreamCursor<'transport, Underlying, Engine>) -> Result<(), Error>,
+    {
+        let mut cursor = StreamCursor::measure(&mut self.underlying, &mut self.crc);
+        write(&mut cursor)?;
+        let (length, checksum) = (cursor.position, cursor.checksum());
+        if length > Self::MAX_MESSAGE_SIZE {
+            return Err(FramedTransportError::DataTooLong.into());
+        }
+        cursor
+            .stream(&encode_header(length, checksum))
+            .map_err(FramedTransportError::UnderlyingError)?;
+
+        let result = write(&mut cursor);
+        if let Some(error) = cursor.error.take() {
+            return Err(FramedTransportError::UnderlyingError(error).into());
+        }
+        let consistent = cursor.position == length && cursor.checksum() == checksum;
+        cursor
+            .pad()
+            .map_err(FramedTransportError::UnderlyingError)?;
+        result?;
+        if !consistent {
+            return Err(FramedTransportError::InvalidHeader.into());
+        }
+        Ok(())
+    }
 }
 
-impl<Underlying: UnderlyingTransport> FramedTransport<Underlying::Error>
-    for BasicFramedTransport<Underlying>
+/// Cursor receiving the payload of [`BasicFramedTransport::send_streamed`].
+///
+/// It keeps the length and checksum of the bytes written, the checksum being computed by the engine of the
+/// transport. When streaming, it also forwards them to the underlying transport and fails with
+/// `InsufficientBuffer` past the measured length or when the underlying transport fails, whose error is then
+/// returned by `send_streamed`. Reading fails with `NotEnoughData`.
+pub struct StreamCursor<'transport, Underlying: UnderlyingTransport, Engine: Crc16Engine = Crc16> {
+    /// The transport and its engine, `None` once the cursor is taken out with `core::mem::take`.
+    transport: Option<(&'transport mut Underlying, &'transport mut Engine)>,
+    /// Whether the payload is sent, rather than measured.
+    streaming: bool,
+    position: usize,
+    /// Length measured by the first pass, which the second one cannot exceed.
+    length: usize,
+    error: Option<Underlying::Error>,
+}
+
+/// A cursor detached from any transport, which fails every write. It stands in for the cursor while a codec
+/// owns it, see `send_message_streamed`.
+impl<Underlying: UnderlyingTransport, Engine: Crc16Engine> Default
+    for StreamCursor<'_, Underlying, Engine>
+{
+    fn default() -> Self {
+        Self {
+            transport: None,
+            streaming: false,
+            position: 0,
+            length: 0,
+            error: None,
+        }
+    }
+}
+
+impl<'transport, Underlying: UnderlyingTransport, Engine: Crc16Engine>
+    StreamCursor<'transport, Underlying, Engine>
+{
+    fn measure(underlying: &'transport mut Underlying, crc: &'transport mut Engine) -> Self {
+        crc.reset();
+        Self {
+            transport: Some((underlying, crc)),
+            length: usize::MAX,
+            ..Self::default()
+        }
+    }
+    /// Sends `header`, then forwards the payload up to the length measured.
+    fn stream(&mut self, header: &[u8]) -> Result<(), Underlying::Error> {
+        if let Some((underlying, crc)) = self.transport.as_mut() {
+            underlying.write_all(header)?;
+            crc.reset();
+        }
+        self.streaming = true;
+        self.length = self.position;
+        self.position = 0;
+        Ok(())
+    }
+    /// Sends zeros up to the length measured.
+    fn pad(&mut self) -> Result<(), Underlying::Error> {
+        if let Some((underlying, _)) = self.transport.as_mut() {
+            while self.position < self.length {
+                let count = core::cmp::min(self.length - self.position, 16);
+                underlying.write_all(&[0; 16][..count])?;
+                self.position += count;
+            }
+        }
+        Ok(())
+    }
+    fn checksum(&mut self) -> u16 {
+        match self.transport.as_mut() {
+            Some((_, crc)) => crc.finalize(),
+            None => CRC16_START,
+        }
+    }
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<Underlying: UnderlyingTransport, Engine: Crc16Engine> Cursor
+    for StreamCursor<'_, Underlying, Engine>
+{
+    fn read<'a>(&mut self, _buffer: &'a mut [u8]) -> Result<&'a [u8], CursorError> {
+        Err(CursorError::NotEnoughData)
+    }
+    fn write(&mut self, data: &[u8]) -> Result<(), CursorError> {
+        let position = self
+            .position
+            .checked_add(data.len())
+            .filter(|position| *position <= self.length)
+            .ok_or(CursorError::InsufficientBuffer)?;
+        let (underlying, crc) = self
+            .transport
+            .as_mut()
+            .ok_or(CursorError::InsufficientBuffer)?;
+        if self.streaming {
+            if self.error.is_some() {
+                return Err(CursorError::InsufficientBuffer);
+            }
+            if let Err(error) = underlying.write_all(data) {
+                self.error = Some(error);
+                return Err(CursorError::InsufficientBuffer);
+            }
+        }
+        self.position = position;
+        crc.update(data);
+        Ok(())
+    }
+}
+
+impl<Underlying: UnderlyingTransport, const MAX_MESSAGE_SIZE: usize, Engine: Crc16Engine>
+    FramedTransport<Underlying::Error>
+    for BasicFramedTransport<Underlying, MAX_MESSAGE_SIZE, Engine>
 {
     fn get_max_message_size(&self) -> usize {
-        65535
+        Self::MAX_MESSAGE_SIZE
     }
 
     fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<Underlying::Error>> {
-        let length = data.len();
-        if length > self.get_max_message_size() {
+        if data.len() > self.get_max_message_size() {
             return Err(FramedTransportError::DataTooLong);
         }
 
-        let checksum = checksum_crc16(data);
-        let mut header = [0u8; 4];
-        {
-            let cursor = SliceCursor::new(&mut header);
-            let mut codec = BasicCodec::new(cursor);
-            codec.write_u16(length as u16).unwrap();
-            codec.write_u16(checksum).unwrap();
-        }
-        self.underlying.write_all(&header)?;
-        self.underlying.write_all(data)?;
-        Ok(())
+        write_frame(&mut self.underlying, &mut self.crc, data)
     }
 
+    /// Receives a frame.
+    ///
+    /// If the underlying transport fails within the frame, the bytes read so far are kept and the next call
+    /// resumes the frame, as after a timeout of `receive_with_deadline`, so that a read timeout of the
+    /// underlying transport does not lose it. After an error leaving the stream out of sync, create a new
+    /// transport instead.
     fn receive<'buffer>(
         &mut self,
         buffer: &'buffer mut [u8],
     ) -> Result<&'buffer [u8], FramedTransportError<Underlying::Error>> {
-        let mut header = [0u8; 4];
-        self.underlying.read_exact(&mut header)?;
+        self.receive_frame(buffer, |underlying, data| {
+            underlying.read_exact(data)?;
+            Ok(data.len())
+        })
+    }
 
-        let cursor = SliceCursor::new(&mut header);
-        let mut codec = BasicCodec::new(cursor);
-        let length = codec.read_u16().unwrap() as usize;
-        let checksum = codec.read_u16().unwrap();
+    /// Receives a frame, reading only the bytes available until `deadline` expires.
+    ///
+    /// If the deadline expires within a frame, the bytes received so far are kept and the next `receive`
+    /// resumes the header, or discards the rest of the frame if its payload was partially received.
+    /// Between attempts to read, `Deadline::idle` lets the caller sleep or yield instead of spinning.
+    /// The deadline is also checked before receiving, so an underlying transport keeping the default
+    /// `read_available` still times out between frames.
+    fn receive_with_deadline<'buffer, DeadlineType: Deadline>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+        deadline: &mut DeadlineType,
+    ) -> Result<&'buffer [u8], FramedTransportError<Underlying::Error>> {
+        if deadline.has_expired() {
+            return Err(FramedTransportError::Timeout);
+        }
+        self.receive_frame(buffer, |underlying, data| loop {
+            let count = underlying.read_available(data)?;
+            if count > 0 {
+                return Ok(count);
+            }
+            if deadline.has_expired() {
+                return Err(FramedTransportError::Timeout);
+            }
+            deadline.idle();
+        })
+    }
+}
+
+impl<Underlying: UnderlyingTransport, const MAX_MESSAGE_SIZE: usize, Engine: Crc16Engine>
+    BasicFramedTransport<Underlying, MAX_MESSAGE_SIZE, Engine>
+{
+    /// Receives a frame with `read`, which reads at least one byte into its buffer and returns the count.
+    ///
+    /// If `read` fails, the state of the frame is kept in `header` and `received` for the next call.
+    fn receive_frame<'buffer, Read>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+        mut read: Read,
+    ) -> Result<&'buffer [u8], FramedTransportError<Underlying::Error>>
+    where
+        Read: FnMut(
+            &mut Underlying,
+            &mut [u8],
+        ) -> Result<usize, FramedTransportError<Underlying::Error>>,
+    {
+        // The payload of a frame interrupted by a previous call is lost, so the frame is discarded.
+        let mut discard = self.received > 4;
+        loop {
+            while self.received < 4 {
+                self.received += read(&mut self.underlying, &mut self.header[self.received..])?;
+            }
+            let (length, checksum) = decode_header(&mut self.header.clone());
+            if length > self.get_max_message_size() {
+                self.received = 0;
+                return Err(FramedTransportError::InvalidHeader);
+            }
+            let frame_length = 4 + length;
+
+            if discard || buffer.len() < length {
+                let mut scratch = [0u8; 16];
+                while self.received < frame_length {
+                    let count = core::cmp::min(frame_length - self.received, scratch.len());
+                    self.received += read(&mut self.underlying, &mut scratch[..count])?;
+                }
+                self.received = 0;
+                if discard {
+                    discard = false;
+                    continue;
+                }
+                return Err(FramedTransportError::BufferTooShort);
+            }
+
+            let buffer_part = &mut buffer[0..length];
+            while self.received < frame_length {
+                self.received += read(&mut self.underlying, &mut buffer_part[self.received - 4..])?;
+            }
+            self.received = 0;
+            let calculated_checksum = self.crc.checksum(buffer_part);
+            if calculated_checksum != checksum {
+                return Err(FramedTransportError::ChecksumError);
+            }
+            return Ok(buffer_part);
+        }
+    }
+}
+
+fn write_frame<Underlying: UnderlyingTransport, Engine: Crc16Engine>(
+    underlying: &mut Underlying,
+    crc: &mut Engine,
+    data: &[u8],
+) -> Result<(), FramedTransportError<Underlying::Error>> {
+    let header = encode_header(data.len(), crc.checksum(data));
+    underlying.write_all(&header)?;
+    underlying.write_all(data)?;
+    Ok(())
+}
+
+/// Framed transport which finds the next valid frame again after the stream is corrupted.
+///
+/// It sends the same frames as [`BasicFramedTransport`]. When receiving, it looks for a header with a length
+/// not exceeding the maximum message size followed by a payload matching its checksum, discarding one byte
+/// at a time until it finds one. The bytes read to validate a candidate are kept in `window`, so a frame
+/// starting within the payload of a rejected candidate is still found. The maximum message size is the size
+/// of `window` minus the 4 bytes of the header. A smaller window also shortens the wait for the payload of
+/// a header with a corrupted length.
+pub struct ResyncFramedTransport<Underlying: UnderlyingTransport, Buffer: AsMut<[u8]>> {
+    underlying: Underlying,
+    window: Buffer,
+    /// Number of bytes read into `window` and not consumed yet.
+    filled: usize,
+    max_message_size: usize,
+    max_scan: usize,
+    last_discarded: usize,
+    total_discarded: usize,
+}
+
+impl<Underlying: UnderlyingTransport, Buffer: AsMut<[u8]>>
+    ResyncFramedTransport<Underlying, Buffer>
+{
+    /// Creates a transport which gives up with `FramedTransportError::SyncLost` after discarding `max_scan`
+    /// bytes in a single `receive`. The next `receive` resumes the scan.
+    pub fn new(underlying: Underlying, mut window: Buffer, max_scan: usize) -> Self {
+        let max_message_size = core::cmp::min(
+            window.as_mut().len().saturating_sub(4),
+            DEFAULT_MAX_MESSAGE_SIZE,
+        );
+        Self {
+            underlying,
+            window,
+            filled: 0,
+            max_message_size,
+            max_scan,
+            last_discarded: 0,
+            total_discarded: 0,
+        }
+    }
+    pub fn release(self) -> (Underlying, Buffer) {
+        (self.underlying, self.window)
+    }
+    /// Number of bytes discarded by the last `receive`.
+    pub fn last_discarded(&self) -> usize {
+        self.last_discarded
+    }
+    /// Number of bytes discarded since the transport was created.
+    pub fn total_discarded(&self) -> usize {
+        self.total_discarded
+    }
+
+    /// Reads from the underlying transport until `window` holds at least `count` bytes.
+    fn fill(&mut self, count: usize) -> Result<(), Underlying::Error> {
+        if self.filled < count {
+            self.underlying
+                .read_exact(&mut self.window.as_mut()[self.filled..count])?;
+            self.filled = count;
+        }
+        Ok(())
+    }
+
+    fn consume(&mut self, count: usize) {
+        self.window.as_mut().copy_within(count..self.filled, 0);
+        self.filled -= count;
+    }
 
+    /// Returns the payload length of the valid frame at the start of `window`, if any.
+    fn check_frame(&mut self) -> Result<Option<usize>, Underlying::Error> {
+        self.fill(4)?;
+        let mut header = [0u8; 4];
+        header.copy_from_slice(&self.window.as_mut()[..4]);
+        let (length, checksum) = decode_header(&mut header);
         if length > self.get_max_message_size() {
-            return Err(FramedTransportError::InvalidHeader);
+            return Ok(None);
         }
-        if buffer.len() < length {
-            return Err(FramedTransportError::BufferTooShort);
+        self.fill(4 + length)?;
+        let valid = Crc16::checksum(&self.window.as_mut()[4..4 + length]) == checksum;
+        Ok(if valid { Some(length) } else { None })
+    }
+}
+
+impl<Underlying: UnderlyingTransport, Buffer: AsMut<[u8]>> FramedTransport<Underlying::Error>
+    for ResyncFramedTransport<Underlying, Buffer>
+{
+    fn get_max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), FramedTransportError<Underlying::Error>> {
+        if data.len() > self.get_max_message_size() {
+            return Err(FramedTransportError::DataTooLong);
         }
-        let buffer_part = &mut buffer[0..length];
-        self.underlying.read_exact(buffer_part)?;
-        let calculated_checksum = checksum_crc16(buffer_part);
-        if calculated_checksum != checksum {
-            return Err(FramedTransportError::ChecksumError);
+        write_frame(&mut self.underlying, &mut Crc16::new(), data)
+    }
+
+    fn receive<'buffer>(
+        &mut self,
+        buffer: &'buffer mut [u8],
+    ) -> Result<&'buffer [u8], FramedTransportError<Underlying::Error>> {
+        self.last_discarded = 0;
+        loop {
+            if let Some(length) = self.check_frame()? {
+                let result = match buffer.get_mut(..length) {
+                    Some(buffer_part) => {
+                        buffer_part.copy_from_slice(&self.window.as_mut()[4..4 + length]);
+                        Ok(&*buffer_part)
+                    }
+                    None => Err(FramedTransportError::BufferTooShort),
+                };
+                self.consume(4 + length);
+                return result;
+            }
+            if self.last_discarded == self.max_scan {
+                return Err(FramedTransportError::SyncLost);
+            }
+            self.consume(1);
+            self.last_discarded += 1;
+            self.total_discarded += 1;
         }
-        Ok(buffer_part)
     }
 }
 
@@ -144,6 +589,18 @@ impl<CursorType: Cursor> UnderlyingTransport for CursorType {
         self.write(data)?;
         Ok(())
     }
+    /// Cursors only read exact lengths, so the available bytes are read one at a time.
+    fn read_available(&mut self, data: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut count = 0;
+        while count < data.len() {
+            match self.read(&mut data[count..count + 1]) {
+                Ok(_) => count += 1,
+                Err(CursorError::NotEnoughData) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(count)
+    }
 }
 
 #[cfg(test)]
@@ -162,6 +619,7 @@ mod tests {
         );
     }
     #[test]
+    #[allow(clippy::identity_op)]
     fn basic_framed_transport() -> Result<(), FramedTransportError<CursorError>> {
         let mut buffer = [0u8; (4 + 0) + (4 + 16) + (4 + 65535)];
         let data16 = {
@@ -235,4 +693,299 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn resync_after_corruption() -> Result<(), FramedTransportError<CursorError>> {
+        let mut stream = [0u8; 64];
+        {
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut stream[3..]));
+            transport.send(&[1, 2, 3])?;
+            transport.send(&[4; 8])?;
+            transport.send(&[5, 6])?;
+        }
+        stream[..3].copy_from_slice(&[0xff, 0x10, 0x20]); // Line noise before the first frame.
+        stream[10] = 12; // Corrupt the length of the second frame so that it overlaps the third one.
+
+        let mut window = [0u8; 32];
+        let mut transport =
+            ResyncFramedTransport::new(SliceCursor::new(&mut stream), &mut window, 16);
+        assert_eq!(transport.get_max_message_size(), 28);
+        let mut buffer = [0u8; 16];
+        assert_eq!(transport.receive(&mut buffer)?, [1, 2, 3]);
+        assert_eq!(transport.last_discarded(), 3);
+        assert_eq!(transport.receive(&mut buffer)?, [5, 6]);
+        assert_eq!(transport.last_discarded(), 12);
+        assert_eq!(transport.total_discarded(), 15);
+
+        // Only zeroes follow, which never form a valid header.
+        assert_eq!(
+            transport.receive(&mut buffer),
+            Err(FramedTransportError::SyncLost)
+        );
+        assert_eq!(transport.last_discarded(), 16);
+        Ok(())
+    }
+
+    /// Tick counter advancing each time it is read.
+    struct TickClock(core::cell::Cell<u32>);
+
+    impl crate::clock::Clock for TickClock {
+        type Instant = u32;
+        type Duration = u32;
+        fn now(&self) -> u32 {
+            let now = self.0.get();
+            self.0.set(now + 1);
+            now
+        }
+    }
+
+    /// Stream of which only the first `available` bytes have arrived.
+    struct TrickleTransport<'data> {
+        data: &'data [u8],
+        position: usize,
+        available: usize,
+    }
+
+    impl UnderlyingTransport for TrickleTransport<'_> {
+        type Error = ();
+        fn read_exact(&mut self, data: &mut [u8]) -> Result<(), ()> {
+            self.available = self.data.len();
+            let count = self.read_available(data)?;
+            if count < data.len() {
+                return Err(());
+            }
+            Ok(())
+        }
+        fn write_all(&mut self, _data: &[u8]) -> Result<(), ()> {
+            Err(())
+        }
+        fn read_available(&mut self, data: &mut [u8]) -> Result<usize, ()> {
+            let count = core::cmp::min(self.available - self.position, data.len());
+            data[..count].copy_from_slice(&self.data[self.position..self.position + count]);
+            self.position += count;
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn receive_with_deadline() -> Result<(), FramedTransportError<()>> {
+        use crate::clock::ClockDeadline;
+
+        let mut stream = [0u8; 7 + 6 + 5];
+        {
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut stream));
+            transport.send(&[1, 2, 3]).unwrap();
+            transport.send(&[4, 5]).unwrap();
+            transport.send(&[6]).unwrap();
+        }
+        let clock = TickClock(core::cell::Cell::new(0));
+        let mut transport = BasicFramedTransport::new(TrickleTransport {
+            data: &stream,
+            position: 0,
+            available: 6,
+        });
+        let mut buffer = [0u8; 8];
+        assert_eq!(
+            transport.receive_with_deadline(&mut buffer, &mut ClockDeadline::after(&clock, 3)),
+            Err(FramedTransportError::Timeout)
+        );
+
+        // The rest of the interrupted frame is discarded, then the header of the next one is kept.
+        transport.underlying.available = 7 + 2;
+        assert_eq!(
+            transport.receive_with_deadline(&mut buffer, &mut ClockDeadline::after(&clock, 3)),
+            Err(FramedTransportError::Timeout)
+        );
+        transport.underlying.available = stream.len();
+        assert_eq!(
+            transport.receive_with_deadline(&mut buffer, &mut ClockDeadline::after(&clock, 3))?,
+            [4, 5]
+        );
+        assert_eq!(transport.receive(&mut buffer)?, [6]);
+        Ok(())
+    }
+
+    /// Stream failing once to read at `fail_at`, keeping the default `read_available`.
+    struct FlakyTransport<'data> {
+        data: &'data [u8],
+        position: usize,
+        fail_at: Option<usize>,
+    }
+
+    impl UnderlyingTransport for FlakyTransport<'_> {
+        type Error = ();
+        fn read_exact(&mut self, data: &mut [u8]) -> Result<(), ()> {
+            if self.fail_at == Some(self.position) {
+                self.fail_at = None;
+                return Err(());
+            }
+            let end = self.position + data.len();
+            data.copy_from_slice(self.data.get(self.position..end).ok_or(())?);
+            self.position = end;
+            Ok(())
+        }
+        fn write_all(&mut self, _data: &[u8]) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    #[test]
+    fn blocking_reads_resume_the_frame() -> Result<(), FramedTransportError<()>> {
+        use crate::clock::ClockDeadline;
+
+        let mut stream = [0u8; 7 + 6];
+        {
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut stream));
+            transport.send(&[1, 2, 3]).unwrap();
+            transport.send(&[4, 5]).unwrap();
+        }
+        let mut transport = BasicFramedTransport::new(FlakyTransport {
+            data: &stream,
+            position: 0,
+            fail_at: Some(4),
+        });
+        let mut buffer = [0u8; 8];
+        assert_eq!(
+            transport.receive(&mut buffer),
+            Err(FramedTransportError::UnderlyingError(()))
+        );
+        assert_eq!(transport.receive(&mut buffer)?, [1, 2, 3]);
+
+        // Without a non-blocking read, the deadline is checked before receiving.
+        let clock = TickClock(core::cell::Cell::new(0));
+        assert_eq!(
+            transport.receive_with_deadline(&mut buffer, &mut ClockDeadline::after(&clock, 0)),
+            Err(FramedTransportError::Timeout)
+        );
+        assert_eq!(
+            transport.receive_with_deadline(&mut buffer, &mut ClockDeadline::after(&clock, 3))?,
+            [4, 5]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cursors_do_not_block_on_missing_data() {
+        use crate::clock::ClockDeadline;
+
+        let mut stream = [0u8; 7];
+        {
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut stream));
+            transport.send(&[1, 2, 3]).unwrap();
+        }
+        let clock = TickClock(core::cell::Cell::new(0));
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut stream[..5]));
+        let mut buffer = [0u8; 8];
+        assert_eq!(
+            transport.receive_with_deadline(&mut buffer, &mut ClockDeadline::after(&clock, 3)),
+            Err(FramedTransportError::Timeout)
+        );
+    }
+
+    #[test]
+    fn max_message_size() {
+        let mut buffer = [0u8; 64];
+        {
+            let mut transport =
+                BasicFramedTransport::<_, 16>::new_sized(SliceCursor::new(&mut buffer));
+            assert_eq!(transport.get_max_message_size(), 16);
+            assert_eq!(
+                transport.send(&[0; 17]),
+                Err(FramedTransportError::DataTooLong)
+            );
+            assert_eq!(transport.release().get_position(), 0);
+
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+            transport.send(&[0; 17]).unwrap();
+        }
+        let mut transport = BasicFramedTransport::<_, 16>::new_sized(SliceCursor::new(&mut buffer));
+        let mut message = [0u8; BasicFramedTransport::<SliceCursor, 16>::MAX_MESSAGE_SIZE];
+        assert_eq!(
+            transport.receive(&mut message),
+            Err(FramedTransportError::InvalidHeader)
+        );
+        assert_eq!(transport.release().get_position(), 4);
+    }
+
+    /// Engine standing for a hardware CRC unit, counting the bytes it is given.
+    #[derive(Default)]
+    struct CountingEngine {
+        crc: Crc16,
+        count: usize,
+    }
+
+    impl Crc16Engine for CountingEngine {
+        fn reset(&mut self) {
+            self.crc = Crc16::new();
+        }
+        fn update(&mut self, data: &[u8]) {
+            self.count += data.len();
+            self.crc.update(data);
+        }
+        fn finalize(&mut self) -> u16 {
+            self.crc.finalize()
+        }
+    }
+
+    #[test]
+    fn checksums_from_a_plugged_engine() -> Result<(), FramedTransportError<CursorError>> {
+        let mut buffer = [0u8; 32];
+        let mut engine = CountingEngine::default();
+        {
+            let mut transport = BasicFramedTransport::<_, 16, _>::with_crc(
+                SliceCursor::new(&mut buffer),
+                &mut engine,
+            );
+            transport.send(&[1, 2, 3])?;
+        }
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        let mut message = [0u8; 16];
+        assert_eq!(transport.receive(&mut message)?, [1, 2, 3]);
+
+        let mut transport =
+            BasicFramedTransport::<_, 16, _>::with_crc(SliceCursor::new(&mut buffer), &mut engine);
+        assert_eq!(transport.receive(&mut message)?, [1, 2, 3]);
+        assert_eq!(engine.count, 6);
+        Ok(())
+    }
+
+    #[test]
+    fn streamed_frames_keep_their_length() -> Result<(), FramedTransportError<CursorError>> {
+        let mut buffer = [0u8; 32];
+        let mut engine = CountingEngine::default();
+        {
+            let mut transport = BasicFramedTransport::<_, 16, _>::with_crc(
+                SliceCursor::new(&mut buffer),
+                &mut engine,
+            );
+            transport.send_streamed(|cursor| {
+                cursor
+                    .write(&[1, 2, 3])
+                    .map_err(FramedTransportError::UnderlyingError)
+            })?;
+            // The second pass writes less than announced, so the payload is padded.
+            let mut streaming = false;
+            let result = transport.send_streamed(|cursor| {
+                let data: &[u8] = if streaming { &[4] } else { &[4, 5] };
+                streaming = true;
+                cursor
+                    .write(data)
+                    .map_err(FramedTransportError::UnderlyingError)
+            });
+            assert_eq!(result, Err(FramedTransportError::InvalidHeader));
+            transport.send(&[6])?;
+        }
+        // Both passes go through the engine.
+        assert_eq!(engine.count, 3 + 3 + 2 + 1 + 1);
+
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        let mut message = [0u8; 16];
+        assert_eq!(transport.receive(&mut message)?, [1, 2, 3]);
+        assert_eq!(
+            transport.receive(&mut message),
+            Err(FramedTransportError::ChecksumError)
+        );
+        assert_eq!(transport.receive(&mut message)?, [6]);
+        Ok(())
+    }
 }
@@ -1,6 +1,43 @@
 #![cfg_attr(not(test), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod arbitrator;
+#[cfg(feature = "async")]
+pub mod async_transport;
+pub mod clock;
 pub mod codec;
+pub mod crc16;
 pub mod cursor;
+pub mod encode;
+pub mod frame_decoder;
 pub mod framed_transport;
+pub mod message_buffer;
 pub mod request;
+pub mod rpc;
+#[cfg(feature = "serde")]
+pub mod serde_codec;
+
+/// Generates the client proxy and the service dispatcher of a trait-first service definition.
+///
+/// ```ignore
+/// #[rust_erpc::service(id = 2)]
+/// pub trait Calculator {
+///     fn add(&mut self, a: i32, b: i32) -> i32;
+///     #[erpc(id = 5, oneway)]
+///     fn reset(&mut self, value: i32);
+/// }
+/// ```
+///
+/// Methods take `&mut self` followed by arguments implementing [`encode::Encode`] and [`encode::Decode`],
+/// and are numbered in declaration order like erpcgen numbers functions unless marked with `#[erpc(id = value)]`.
+/// Methods marked with `#[erpc(oneway)]` return nothing and are not replied to.
+///
+/// The macro adds a module named after the trait in snake case, here `calculator`, with the `SERVICE_ID` and
+/// `<METHOD>_ID` constants, a `Client` calling through any [`rpc::Caller`] such as [`rpc::Client`], and a
+/// `Service` implementing [`rpc::Service`] by calling a handler implementing the trait, to register with
+/// [`rpc::SimpleServer`] or [`arbitrator::TransportArbitrator`].
+/// Like the code generated by `erpc-codegen`, both are generic over the codec.
+#[cfg(feature = "derive")]
+pub use erpc_derive::service;
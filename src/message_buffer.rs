@@ -0,0 +1,292 @@
+//! Message buffers lent by a pool, as the `MessageBufferFactory` of the erpc C++ implementation.
+//!
+//! A [`MessageBufferPool`] holds `COUNT` buffers of `SIZE` bytes and lends them as [`MessageBuffer`] handles,
+//! which give the buffer back when dropped. The handles implement `AsMut<[u8]>`, so they can be passed to
+//! `send_message` and the other functions taking a buffer, and owned by a codec through its cursor to keep a
+//! message while another one is encoded. Functions, clients and servers creating buffers themselves take a
+//! [`MessageBufferFactory`], which `&MessageBufferPool` and `&StaticMessageBufferPool` implement.
+//!
+//! Neither pool allocates. [`MessageBufferPool`] needs no atomics, but is shared by reference within one
+//! execution context. [`StaticMessageBufferPool`] marks the buffers lent with atomic flags, so it can be declared
+//! `static` and shared between tasks and interrupts, keeping the buffers off the stacks. It needs compare and
+//! swap operations on bytes, which targets such as `thumbv6m` lack.
+
+#[cfg(target_has_atomic = "8")]
+use core::cell::UnsafeCell;
+use core::cell::{RefCell, RefMut};
+use core::ops::{Deref, DerefMut};
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MessageBufferError {
+    /// Every buffer of the pool is in use.
+    Exhausted,
+}
+
+/// Creates the buffers messages are encoded into and received into.
+pub trait MessageBufferFactory {
+    type Buffer: AsMut<[u8]>;
+    fn create(&self) -> Result<Self::Buffer, MessageBufferError>;
+}
+
+/// Pool of `COUNT` buffers of `SIZE` bytes.
+///
+/// ```
+/// use rust_erpc::message_buffer::{MessageBufferError, MessageBufferFactory, MessageBufferPool};
+///
+/// let pool = MessageBufferPool::<64, 2>::new();
+/// let factory = &pool;
+/// let request = factory.create()?;
+/// let mut reply = factory.create()?;
+/// reply[0] = 1;
+/// assert_eq!(factory.create().err(), Some(MessageBufferError::Exhausted));
+/// drop(request);
+/// assert_eq!(pool.available(), 1);
+/// # Ok::<(), MessageBufferError>(())
+/// ```
+pub struct MessageBufferPool<const SIZE: usize, const COUNT: usize> {
+    buffers: [RefCell<[u8; SIZE]>; COUNT],
+}
+
+impl<const SIZE: usize, const COUNT: usize> Default for MessageBufferPool<SIZE, COUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize, const COUNT: usize> MessageBufferPool<SIZE, COUNT> {
+    // Only repeated to initialize the array, so that `new` can be `const`.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const BUFFER: RefCell<[u8; SIZE]> = RefCell::new([0; SIZE]);
+
+    pub const fn new() -> Self {
+        Self {
+            buffers: [Self::BUFFER; COUNT],
+        }
+    }
+    /// Number of buffers not lent.
+    pub fn available(&self) -> usize {
+        self.buffers
+            .iter()
+            .filter(|buffer| buffer.try_borrow_mut().is_ok())
+            .count()
+    }
+}
+
+impl<'pool, const SIZE: usize, const COUNT: usize> MessageBufferFactory
+    for &'pool MessageBufferPool<SIZE, COUNT>
+{
+    type Buffer = MessageBuffer<'pool, SIZE>;
+
+    fn create(&self) -> Result<Self::Buffer, MessageBufferError> {
+        self.buffers
+            .iter()
+            .find_map(|buffer| buffer.try_borrow_mut().ok())
+            .map(MessageBuffer)
+            .ok_or(MessageBufferError::Exhausted)
+    }
+}
+
+/// Buffer lent by a [`MessageBufferPool`], given back when dropped.
+pub struct MessageBuffer<'pool, const SIZE: usize>(RefMut<'pool, [u8; SIZE]>);
+
+impl<const SIZE: usize> Deref for MessageBuffer<'_, SIZE> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.0[..]
+    }
+}
+
+impl<const SIZE: usize> DerefMut for MessageBuffer<'_, SIZE> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0[..]
+    }
+}
+
+impl<const SIZE: usize> AsRef<[u8]> for MessageBuffer<'_, SIZE> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const SIZE: usize> AsMut<[u8]> for MessageBuffer<'_, SIZE> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+/// Pool of `COUNT` buffers of `SIZE` bytes which can be declared `static`.
+///
+/// ```
+/// use rust_erpc::message_buffer::{MessageBufferError, MessageBufferFactory, StaticMessageBufferPool};
+///
+/// static POOL: StaticMessageBufferPool<64, 2> = StaticMessageBufferPool::new();
+///
+/// let factory = &POOL;
+/// let request = factory.create()?;
+/// let reply = factory.create()?;
+/// assert_eq!(factory.create().err(), Some(MessageBufferError::Exhausted));
+/// drop(request);
+/// assert_eq!(POOL.available(), 1);
+/// # Ok::<(), MessageBufferError>(())
+/// ```
+#[cfg(target_has_atomic = "8")]
+pub struct StaticMessageBufferPool<const SIZE: usize, const COUNT: usize> {
+    buffers: [UnsafeCell<[u8; SIZE]>; COUNT],
+    /// Whether each buffer is lent.
+    lent: [AtomicBool; COUNT],
+}
+
+/// Each buffer is only accessed through the [`StaticMessageBuffer`] which set its flag.
+#[cfg(target_has_atomic = "8")]
+unsafe impl<const SIZE: usize, const COUNT: usize> Sync for StaticMessageBufferPool<SIZE, COUNT> {}
+
+#[cfg(target_has_atomic = "8")]
+impl<const SIZE: usize, const COUNT: usize> Default for StaticMessageBufferPool<SIZE, COUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const SIZE: usize, const COUNT: usize> StaticMessageBufferPool<SIZE, COUNT> {
+    // Only repeated to initialize the arrays, so that `new` can be `const`.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const BUFFER: UnsafeCell<[u8; SIZE]> = UnsafeCell::new([0; SIZE]);
+    #[allow(clippy::declare_interior_mutable_const)]
+    const FREE: AtomicBool = AtomicBool::new(false);
+
+    pub const fn new() -> Self {
+        Self {
+            buffers: [Self::BUFFER; COUNT],
+            lent: [Self::FREE; COUNT],
+        }
+    }
+    /// Number of buffers not lent.
+    pub fn available(&self) -> usize {
+        self.lent
+            .iter()
+            .filter(|lent| !lent.load(Ordering::Relaxed))
+            .count()
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<'pool, const SIZE: usize, const COUNT: usize> MessageBufferFactory
+    for &'pool StaticMessageBufferPool<SIZE, COUNT>
+{
+    type Buffer = StaticMessageBuffer<'pool, SIZE>;
+
+    fn create(&self) -> Result<Self::Buffer, MessageBufferError> {
+        let pool: &'pool StaticMessageBufferPool<SIZE, COUNT> = self;
+        pool.lent
+            .iter()
+            .zip(&pool.buffers)
+            .find(|(lent, _)| {
+                lent.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            })
+            .map(|(lent, buffer)| StaticMessageBuffer {
+                // The flag just set gives exclusive access to the buffer until the handle is dropped.
+                buffer: unsafe { &mut *buffer.get() },
+                lent,
+            })
+            .ok_or(MessageBufferError::Exhausted)
+    }
+}
+
+/// Buffer lent by a [`StaticMessageBufferPool`], given back when dropped.
+#[cfg(target_has_atomic = "8")]
+pub struct StaticMessageBuffer<'pool, const SIZE: usize> {
+    buffer: &'pool mut [u8; SIZE],
+    lent: &'pool AtomicBool,
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const SIZE: usize> Drop for StaticMessageBuffer<'_, SIZE> {
+    fn drop(&mut self) {
+        self.lent.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const SIZE: usize> Deref for StaticMessageBuffer<'_, SIZE> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.buffer[..]
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const SIZE: usize> DerefMut for StaticMessageBuffer<'_, SIZE> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.buffer[..]
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const SIZE: usize> AsRef<[u8]> for StaticMessageBuffer<'_, SIZE> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const SIZE: usize> AsMut<[u8]> for StaticMessageBuffer<'_, SIZE> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{BasicCodec, Codec};
+    use crate::cursor::BufferCursor;
+
+    #[test]
+    fn buffers_are_given_back_when_dropped() -> Result<(), MessageBufferError> {
+        let pool = MessageBufferPool::<8, 2>::new();
+        let factory = &pool;
+        let mut codec = BasicCodec::new(BufferCursor::new(factory.create()?));
+        codec.write_u32(7).unwrap();
+        let mut second = factory.create()?;
+        second.fill(1);
+        assert_eq!(pool.available(), 0);
+        assert_eq!(factory.create().err(), Some(MessageBufferError::Exhausted));
+
+        drop(second);
+        let third = factory.create()?;
+        assert_eq!(&third[..], [1; 8]);
+        drop(third);
+
+        // The codec owns its buffer until it is detached and released.
+        let buffer = codec.detach().release();
+        assert_eq!(buffer[..4], [7, 0, 0, 0]);
+        assert_eq!(pool.available(), 1);
+        drop(buffer);
+        assert_eq!(pool.available(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn static_pool_lends_each_buffer_once() {
+        static POOL: StaticMessageBufferPool<8, 2> = StaticMessageBufferPool::new();
+        let threads: [_; 4] = core::array::from_fn(|index| {
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    if let Ok(mut buffer) = (&POOL).create() {
+                        buffer.fill(index as u8);
+                        std::thread::yield_now();
+                        assert_eq!(&buffer[..], [index as u8; 8]);
+                    }
+                }
+            })
+        });
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(POOL.available(), 2);
+    }
+}
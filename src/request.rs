@@ -1,8 +1,14 @@
 use num_derive::FromPrimitive;
 
-use crate::codec::{Codec, CodecError, CodecFactory, MessageHeader};
-use crate::cursor::BufferCursor;
-use crate::framed_transport::{FramedTransport, FramedTransportError};
+#[cfg(feature = "async")]
+use crate::async_transport::AsyncFramedTransport;
+use crate::codec::{BasicCodec, Codec, CodecError, CodecFactory, MessageHeader, SizeCodec};
+use crate::crc16::Crc16Engine;
+use crate::cursor::{BufferCursor, CountingCursor, ReadCursor};
+use crate::framed_transport::{
+    BasicFramedTransport, FramedTransport, FramedTransportError, StreamCursor, UnderlyingTransport,
+};
+use crate::message_buffer::{MessageBufferError, MessageBufferFactory};
 
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, Eq, PartialEq, FromPrimitive)]
@@ -19,6 +25,18 @@ pub enum RequestResponseError<TransportError> {
     InvalidResponse,
     CodecError(CodecError),
     FramedTransportError(FramedTransportError<TransportError>),
+    MessageBufferError(MessageBufferError),
+}
+
+impl<TransportError> RequestResponseError<TransportError> {
+    /// Whether the underlying transport failed, after which the link cannot be used anymore.
+    /// The other errors concern one message, which can be dropped before handling the next one.
+    pub fn is_underlying_error(&self) -> bool {
+        matches!(
+            self,
+            Self::FramedTransportError(FramedTransportError::UnderlyingError(_))
+        )
+    }
 }
 
 impl<FramedTransportError> From<CodecError> for RequestResponseError<FramedTransportError> {
@@ -33,12 +51,17 @@ impl<TransportError> From<FramedTransportError<TransportError>>
         Self::FramedTransportError(err)
     }
 }
+impl<TransportError> From<MessageBufferError> for RequestResponseError<TransportError> {
+    fn from(err: MessageBufferError) -> Self {
+        Self::MessageBufferError(err)
+    }
+}
 
 pub fn send_message<Error, Transport, Constructor, Buffer, CodecType, CodecFactoryType>(
     transport: &mut Transport,
     buffer: Buffer,
     message_header: &MessageHeader,
-    mut codec_factory: CodecFactoryType,
+    codec_factory: CodecFactoryType,
     constructor: Constructor,
 ) -> Result<(), RequestResponseError<Error>>
 where
@@ -48,23 +71,84 @@ where
     CodecType: Codec<BufferCursor<Buffer>>,
     CodecFactoryType: CodecFactory<BufferCursor<Buffer>, CodecType>,
 {
-    let (position, mut buffer) = {
-        let cursor = BufferCursor::new(buffer);
-        let mut codec = codec_factory.from_cursor(cursor);
-
-        codec.start_write_message(message_header)?;
-        constructor(&mut codec)?;
-        let cursor = codec.detach();
-        (cursor.get_position(), cursor.release())
-    };
+    let (position, mut buffer) =
+        encode_message(buffer, message_header, codec_factory, constructor)?;
     transport.send(&buffer.as_mut()[0..position])?;
     Ok(())
 }
 
+/// Async version of [`send_message`].
+#[cfg(feature = "async")]
+pub async fn send_message_async<
+    Error,
+    Transport,
+    Constructor,
+    Buffer,
+    CodecType,
+    CodecFactoryType,
+>(
+    transport: &mut Transport,
+    buffer: Buffer,
+    message_header: &MessageHeader,
+    codec_factory: CodecFactoryType,
+    constructor: Constructor,
+) -> Result<(), RequestResponseError<Error>>
+where
+    Transport: AsyncFramedTransport<Error>,
+    Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    Buffer: AsMut<[u8]>,
+    CodecType: Codec<BufferCursor<Buffer>>,
+    CodecFactoryType: CodecFactory<BufferCursor<Buffer>, CodecType>,
+{
+    let (position, mut buffer) =
+        encode_message(buffer, message_header, codec_factory, constructor)?;
+    transport.send(&buffer.as_mut()[0..position]).await?;
+    Ok(())
+}
+
+/// Encodes a message into `buffer` and returns its length along with the buffer.
+fn encode_message<Constructor, Buffer, CodecType, CodecFactoryType>(
+    buffer: Buffer,
+    message_header: &MessageHeader,
+    mut codec_factory: CodecFactoryType,
+    constructor: Constructor,
+) -> Result<(usize, Buffer), CodecError>
+where
+    Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    Buffer: AsMut<[u8]>,
+    CodecType: Codec<BufferCursor<Buffer>>,
+    CodecFactoryType: CodecFactory<BufferCursor<Buffer>, CodecType>,
+{
+    let cursor = BufferCursor::new(buffer);
+    let mut codec = codec_factory.from_cursor(cursor);
+
+    codec.start_write_message(message_header)?;
+    constructor(&mut codec)?;
+    let cursor = codec.detach();
+    Ok((cursor.get_position(), cursor.release()))
+}
+
+/// Length of the message `send_message` would encode, header included, computed without a buffer.
+///
+/// The constructor receives a [`SizeCodec`]. To run the same encoding through both, write it as a function
+/// generic over the codec and call it from both constructors.
+pub fn encoded_size<Constructor>(
+    message_header: &MessageHeader,
+    constructor: Constructor,
+) -> Result<usize, CodecError>
+where
+    Constructor: FnOnce(&mut SizeCodec) -> Result<(), CodecError>,
+{
+    let mut codec = SizeCodec::new(CountingCursor::new());
+    codec.start_write_message(message_header)?;
+    constructor(&mut codec)?;
+    Ok(codec.size())
+}
+
 pub fn receive_message<'buffer, Error, Transport, CodecType, CodecFactoryType>(
     transport: &mut Transport,
     buffer: &'buffer mut [u8],
-    mut codec_factory: CodecFactoryType,
+    codec_factory: CodecFactoryType,
 ) -> Result<(MessageHeader, CodecType), RequestResponseError<Error>>
 where
     Transport: FramedTransport<Error>,
@@ -72,8 +156,74 @@ where
     CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
 {
     let length = transport.receive(buffer)?.len();
-    let cursor = BufferCursor::new(&mut buffer[0..length]);
-    let mut codec = codec_factory.from_cursor(cursor);
+    decode_message(&mut buffer[0..length], codec_factory)
+}
+
+/// Async version of [`receive_message`].
+#[cfg(feature = "async")]
+pub async fn receive_message_async<'buffer, Error, Transport, CodecType, CodecFactoryType>(
+    transport: &mut Transport,
+    buffer: &'buffer mut [u8],
+    codec_factory: CodecFactoryType,
+) -> Result<(MessageHeader, CodecType), RequestResponseError<Error>>
+where
+    Transport: AsyncFramedTransport<Error>,
+    CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+    CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+{
+    let length = transport.receive(buffer).await?.len();
+    decode_message(&mut buffer[0..length], codec_factory)
+}
+
+fn decode_message<'buffer, Error, CodecType, CodecFactoryType>(
+    message: &'buffer mut [u8],
+    mut codec_factory: CodecFactoryType,
+) -> Result<(MessageHeader, CodecType), RequestResponseError<Error>>
+where
+    CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+    CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+{
+    let mut codec = codec_factory.from_cursor(BufferCursor::new(message));
+    let message_header = codec.start_read_message()?;
+    Ok((message_header, codec))
+}
+
+/// Like [`receive_message`], but the codec reads the frame in place through a [`ReadCursor`],
+/// so that `BorrowingCodec` reads return slices of `buffer` instead of copies.
+pub fn receive_message_borrowed<'frame, Error, Transport, CodecType, CodecFactoryType>(
+    transport: &mut Transport,
+    buffer: &'frame mut [u8],
+    mut codec_factory: CodecFactoryType,
+) -> Result<(MessageHeader, CodecType), RequestResponseError<Error>>
+where
+    Transport: FramedTransport<Error>,
+    CodecType: Codec<ReadCursor<'frame>>,
+    CodecFactoryType: CodecFactory<ReadCursor<'frame>, CodecType>,
+{
+    let frame = transport.receive(buffer)?;
+    let mut codec = codec_factory.from_cursor(ReadCursor::new(frame));
+    let message_header = codec.start_read_message()?;
+    Ok((message_header, codec))
+}
+
+/// Like [`receive_message`], but receives into a buffer created by `factory`.
+///
+/// The returned codec owns the buffer, so the message can be kept while the reply is encoded into another
+/// buffer. The buffer is given back once the codec is dropped, or detached and its cursor released.
+pub fn receive_message_pooled<Error, Transport, Factory, CodecType, CodecFactoryType>(
+    transport: &mut Transport,
+    factory: &Factory,
+    mut codec_factory: CodecFactoryType,
+) -> Result<(MessageHeader, CodecType), RequestResponseError<Error>>
+where
+    Transport: FramedTransport<Error>,
+    Factory: MessageBufferFactory,
+    CodecType: Codec<BufferCursor<Factory::Buffer>>,
+    CodecFactoryType: CodecFactory<BufferCursor<Factory::Buffer>, CodecType>,
+{
+    let mut buffer = factory.create()?;
+    let length = transport.receive(buffer.as_mut())?.len();
+    let mut codec = codec_factory.from_cursor(BufferCursor::new_with_length(buffer, length));
     let message_header = codec.start_read_message()?;
     Ok((message_header, codec))
 }
@@ -85,6 +235,41 @@ pub struct Request {
     pub is_oneway: bool,
 }
 
+/// Like [`send_message`], but encodes the message straight into the underlying transport of `transport`,
+/// without a buffer, see [`BasicFramedTransport::send_streamed`].
+///
+/// `constructor` runs twice, to measure the message and then to send it, and must encode the same values
+/// both times. As with [`encoded_size`], write it as a function generic over the codec to share it.
+/// If it encodes less the second time, the frame is still padded to the length announced in its header,
+/// so the peer drops it on its checksum without losing the frames which follow.
+pub fn send_message_streamed<
+    'transport,
+    Underlying,
+    Engine,
+    Constructor,
+    const MAX_MESSAGE_SIZE: usize,
+>(
+    transport: &'transport mut BasicFramedTransport<Underlying, MAX_MESSAGE_SIZE, Engine>,
+    message_header: &MessageHeader,
+    mut constructor: Constructor,
+) -> Result<(), RequestResponseError<Underlying::Error>>
+where
+    Underlying: UnderlyingTransport,
+    Engine: Crc16Engine,
+    Constructor: FnMut(
+        &mut BasicCodec<StreamCursor<'transport, Underlying, Engine>>,
+    ) -> Result<(), CodecError>,
+{
+    transport.send_streamed(|cursor| {
+        let mut codec = BasicCodec::new(core::mem::take(cursor));
+        let result = codec
+            .start_write_message(message_header)
+            .and_then(|_| constructor(&mut codec));
+        *cursor = codec.detach();
+        Ok(result?)
+    })
+}
+
 impl Request {
     pub fn new(service: u32, request: u32, sequence: u32, is_oneway: bool) -> Self {
         Self {
@@ -109,7 +294,47 @@ impl Request {
         CodecType: Codec<BufferCursor<Buffer>>,
         CodecFactoryType: CodecFactory<BufferCursor<Buffer>, CodecType>,
     {
-        let message_header = MessageHeader {
+        send_message(
+            transport,
+            buffer,
+            &self.message_header(),
+            codec_factory,
+            constructor,
+        )
+    }
+
+    /// Sends the request without a buffer, see [`send_message_streamed`].
+    pub fn send_request_streamed<
+        'transport,
+        Underlying,
+        Engine,
+        Constructor,
+        const MAX_MESSAGE_SIZE: usize,
+    >(
+        &self,
+        transport: &'transport mut BasicFramedTransport<Underlying, MAX_MESSAGE_SIZE, Engine>,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Underlying::Error>>
+    where
+        Underlying: UnderlyingTransport,
+        Engine: Crc16Engine,
+        Constructor: FnMut(
+            &mut BasicCodec<StreamCursor<'transport, Underlying, Engine>>,
+        ) -> Result<(), CodecError>,
+    {
+        send_message_streamed(transport, &self.message_header(), constructor)
+    }
+
+    /// Length of the message `send_request` would encode, see [`encoded_size`].
+    pub fn encoded_size<Constructor>(&self, constructor: Constructor) -> Result<usize, CodecError>
+    where
+        Constructor: FnOnce(&mut SizeCodec) -> Result<(), CodecError>,
+    {
+        encoded_size(&self.message_header(), constructor)
+    }
+
+    fn message_header(&self) -> MessageHeader {
+        MessageHeader {
             message_type: if self.is_oneway {
                 MessageType::OnewayMessage
             } else {
@@ -118,14 +343,7 @@ impl Request {
             service: self.service,
             request: self.request,
             sequence: self.sequence,
-        };
-        send_message(
-            transport,
-            buffer,
-            &message_header,
-            codec_factory,
-            constructor,
-        )
+        }
     }
 
     pub fn receive_request<'buffer, Error, Transport, CodecType, CodecFactoryType>(
@@ -139,18 +357,54 @@ impl Request {
         CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
     {
         let (message_header, codec) = receive_message(transport, buffer, codec_factory)?;
+        Ok((Self::from_header(&message_header)?, codec))
+    }
+
+    /// Like [`Request::receive_request`], reading the request in place with [`receive_message_borrowed`].
+    pub fn receive_request_borrowed<'frame, Error, Transport, CodecType, CodecFactoryType>(
+        transport: &mut Transport,
+        buffer: &'frame mut [u8],
+        codec_factory: CodecFactoryType,
+    ) -> Result<(Request, CodecType), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        CodecType: Codec<ReadCursor<'frame>>,
+        CodecFactoryType: CodecFactory<ReadCursor<'frame>, CodecType>,
+    {
+        let (message_header, codec) = receive_message_borrowed(transport, buffer, codec_factory)?;
+        Ok((Self::from_header(&message_header)?, codec))
+    }
+
+    /// Like [`Request::receive_request`], receiving into a buffer of `factory` with [`receive_message_pooled`].
+    pub fn receive_request_pooled<Error, Transport, Factory, CodecType, CodecFactoryType>(
+        transport: &mut Transport,
+        factory: &Factory,
+        codec_factory: CodecFactoryType,
+    ) -> Result<(Request, CodecType), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        Factory: MessageBufferFactory,
+        CodecType: Codec<BufferCursor<Factory::Buffer>>,
+        CodecFactoryType: CodecFactory<BufferCursor<Factory::Buffer>, CodecType>,
+    {
+        let (message_header, codec) = receive_message_pooled(transport, factory, codec_factory)?;
+        Ok((Self::from_header(&message_header)?, codec))
+    }
+
+    fn from_header<Error>(
+        message_header: &MessageHeader,
+    ) -> Result<Request, RequestResponseError<Error>> {
         let is_oneway = match message_header.message_type {
             MessageType::InvocationMessage => false,
             MessageType::OnewayMessage => true,
             _ => return Err(RequestResponseError::InvalidRequest),
         };
-        let request = Request {
+        Ok(Request {
             is_oneway,
             service: message_header.service,
             request: message_header.request,
             sequence: message_header.sequence,
-        };
-        Ok((request, codec))
+        })
     }
 }
 
@@ -213,27 +467,62 @@ impl Response {
         CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
     {
         let (message_header, codec) = receive_message(transport, buffer, codec_factory)?;
+        Ok((Self::from_header(&message_header)?, codec))
+    }
+
+    /// Like [`Response::receive_response`], reading the response in place with [`receive_message_borrowed`].
+    pub fn receive_response_borrowed<'frame, Error, Transport, CodecType, CodecFactoryType>(
+        transport: &mut Transport,
+        buffer: &'frame mut [u8],
+        codec_factory: CodecFactoryType,
+    ) -> Result<(Response, CodecType), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        CodecType: Codec<ReadCursor<'frame>>,
+        CodecFactoryType: CodecFactory<ReadCursor<'frame>, CodecType>,
+    {
+        let (message_header, codec) = receive_message_borrowed(transport, buffer, codec_factory)?;
+        Ok((Self::from_header(&message_header)?, codec))
+    }
+
+    /// Like [`Response::receive_response`], receiving into a buffer of `factory` with [`receive_message_pooled`].
+    pub fn receive_response_pooled<Error, Transport, Factory, CodecType, CodecFactoryType>(
+        transport: &mut Transport,
+        factory: &Factory,
+        codec_factory: CodecFactoryType,
+    ) -> Result<(Response, CodecType), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        Factory: MessageBufferFactory,
+        CodecType: Codec<BufferCursor<Factory::Buffer>>,
+        CodecFactoryType: CodecFactory<BufferCursor<Factory::Buffer>, CodecType>,
+    {
+        let (message_header, codec) = receive_message_pooled(transport, factory, codec_factory)?;
+        Ok((Self::from_header(&message_header)?, codec))
+    }
+
+    fn from_header<Error>(
+        message_header: &MessageHeader,
+    ) -> Result<Response, RequestResponseError<Error>> {
         let is_notification = match message_header.message_type {
             MessageType::NotificationMessage => true,
             MessageType::ReplyMessage => false,
             _ => return Err(RequestResponseError::InvalidResponse),
         };
-        let response = Response {
+        Ok(Response {
             service: message_header.service,
             request: message_header.request,
             sequence: message_header.sequence,
             is_notification,
-        };
-        Ok((response, codec))
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::codec::BasicCodecFactory;
+    use crate::codec::{BasicCodecFactory, BorrowingCodec};
     use crate::cursor::{CursorError, SliceCursor};
-    use crate::framed_transport::BasicFramedTransport;
 
     fn compare_result_data(expected: &[u8], actual: &[u8]) {
         assert_eq!(
@@ -247,6 +536,7 @@ mod tests {
         );
     }
     #[test]
+    #[allow(clippy::identity_op)]
     fn send_receive() -> Result<(), RequestResponseError<CursorError>> {
         let mut buffer = [0u8; (4 + 0) + (4 + 16) + (4 + 65535)];
         let data16 = {
@@ -295,11 +585,11 @@ mod tests {
             assert_eq!(request.service, 1u32);
             assert_eq!(request.request, 2u32);
             assert_eq!(request.sequence, 0u32);
-            assert_eq!(request.is_oneway, false);
+            assert!(!request.is_oneway);
 
             let mut data_buffer = [0u8; 16];
             let data = codec.read_binary(&mut data_buffer)?;
-            compare_result_data(&data16, &data);
+            compare_result_data(&data16, data);
 
             let (response, mut codec) = Response::receive_response(
                 &mut transport,
@@ -309,13 +599,142 @@ mod tests {
             assert_eq!(response.service, 4u32);
             assert_eq!(response.request, 5u32);
             assert_eq!(response.sequence, 0u32);
-            assert_eq!(response.is_notification, false);
+            assert!(!response.is_notification);
 
             let mut data_buffer = [0u8; 16];
             let data = codec.read_binary(&mut data_buffer)?;
-            compare_result_data(&data16, &data);
+            compare_result_data(&data16, data);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn receive_borrowed() -> Result<(), RequestResponseError<CursorError>> {
+        let mut buffer = [0u8; 128];
+        {
+            let mut frame_buffer = [0u8; 64];
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+            Request::new(1, 2, 3, true).send_request(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+                |codec| {
+                    codec.write_str("name")?;
+                    codec.write_binary(&[1, 2, 3])
+                },
+            )?;
+            Request::new(1, 2, 4, false).send_request(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+                |_| Ok(()),
+            )?;
         }
 
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        let mut frame_buffer = [0u8; 64];
+        let (request, name, data) = {
+            let (request, mut codec) = Request::receive_request_borrowed(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+            )?;
+            let name = codec.read_str_borrowed()?;
+            (request, name, codec.read_binary_borrowed()?)
+        };
+        assert!(request.is_oneway);
+        assert_eq!(request.sequence, 3);
+        assert_eq!(name, "name");
+        assert_eq!(data, [1, 2, 3]);
+
+        let mut frame_buffer = [0u8; 64];
+        let result: Result<(Response, BasicCodec<_>), _> = Response::receive_response_borrowed(
+            &mut transport,
+            &mut frame_buffer,
+            BasicCodecFactory::new(),
+        );
+        assert_eq!(result.err(), Some(RequestResponseError::InvalidResponse));
+        Ok(())
+    }
+
+    /// Arguments encoded by both the sizing and the sending constructor.
+    fn encode_arguments<CursorType, CodecType>(codec: &mut CodecType) -> Result<(), CodecError>
+    where
+        CursorType: crate::cursor::Cursor,
+        CodecType: Codec<CursorType>,
+    {
+        codec.write_str("name")?;
+        codec.write_optional(Some(&7u16))?;
+        codec.write_list([1u8, 2, 3].iter())
+    }
+
+    #[test]
+    fn encoded_size_matches_the_sent_message() -> Result<(), RequestResponseError<CursorError>> {
+        let request = Request::new(1, 2, 3, false);
+        let size = request.encoded_size(encode_arguments)?;
+        assert_eq!(size, 8 + (4 + 4) + (1 + 2) + (4 + 3));
+
+        let mut buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffer));
+        let mut frame_buffer = [0u8; 64];
+        request.send_request(
+            &mut transport,
+            &mut frame_buffer[..size],
+            BasicCodecFactory::new(),
+            encode_arguments,
+        )?;
+        let frame_length = transport.release().get_position() - 4;
+        assert_eq!(frame_length, size);
+        Ok(())
+    }
+
+    #[test]
+    fn streamed_request_matches_the_buffered_one() -> Result<(), RequestResponseError<CursorError>>
+    {
+        let request = Request::new(1, 2, 3, false);
+        let mut buffered = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut buffered));
+        request.send_request(
+            &mut transport,
+            [0u8; 64],
+            BasicCodecFactory::new(),
+            encode_arguments,
+        )?;
+        let length = transport.release().get_position();
+
+        let mut streamed = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut streamed));
+        request.send_request_streamed(&mut transport, encode_arguments)?;
+        assert_eq!(transport.release().get_position(), length);
+        assert_eq!(streamed, buffered);
+
+        // A constructor encoding more the second time is stopped at the length sent in the header.
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut streamed));
+        let mut streaming = false;
+        let result = request.send_request_streamed(&mut transport, |codec| {
+            let count = if streaming { 2 } else { 1 };
+            streaming = true;
+            codec.write_list([7u8; 2][..count].iter())
+        });
+        assert_eq!(
+            result,
+            Err(RequestResponseError::CodecError(CodecError::Cursor(
+                CursorError::InsufficientBuffer
+            )))
+        );
+        // The frame stops at the length announced: the header, the message header and a list of one byte.
+        assert_eq!(transport.release().get_position(), 4 + 8 + 4 + 1);
+
+        // Errors of the underlying transport are reported as such.
+        let mut short = [0u8; 16];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut short));
+        assert_eq!(
+            request.send_request_streamed(&mut transport, encode_arguments),
+            Err(RequestResponseError::FramedTransportError(
+                FramedTransportError::UnderlyingError(CursorError::InsufficientBuffer)
+            ))
+        );
         Ok(())
     }
 }
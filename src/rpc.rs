@@ -0,0 +1,1116 @@
+use crate::clock::Deadline;
+use crate::codec::{Codec, CodecError, CodecFactory, MessageHeader};
+use crate::cursor::{BufferCursor, Cursor};
+use crate::framed_transport::{FramedTransport, FramedTransportError};
+use crate::message_buffer::MessageBufferFactory;
+use crate::request::{MessageType, RequestResponseError};
+
+/// Sends invocations to the services of the peer.
+///
+/// Implemented by [`Client`], [`PooledClient`] and `TransportArbitrator`, which also serves the requests of the
+/// peer while waiting for a reply, so that the generated clients can call through any of them.
+pub trait Caller<Error, CursorType: Cursor, CodecType: Codec<CursorType>> {
+    /// Sends an invocation and waits for the matching reply.
+    ///
+    /// Replies carrying another sequence number are stale replies of earlier calls and are skipped.
+    /// A reply with the expected sequence number but another service or request is rejected with `InvalidResponse`.
+    fn call<Constructor, Parser, Output>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+        parser: Parser,
+    ) -> Result<Output, RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+        Parser: FnOnce(&mut CodecType) -> Result<Output, CodecError>;
+    /// Sends a oneway invocation. No reply is expected, so this returns as soon as the message is sent.
+    fn call_oneway<Constructor>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>;
+}
+
+pub struct Client<'buffer, Transport, CodecFactoryType> {
+    pub(crate) transport: Transport,
+    pub(crate) buffer: &'buffer mut [u8],
+    pub(crate) codec_factory: CodecFactoryType,
+    sequence: u32,
+}
+
+impl<'buffer, Transport, CodecFactoryType> Client<'buffer, Transport, CodecFactoryType> {
+    pub fn new(
+        transport: Transport,
+        buffer: &'buffer mut [u8],
+        codec_factory: CodecFactoryType,
+    ) -> Self {
+        Self {
+            transport,
+            buffer,
+            codec_factory,
+            sequence: 0,
+        }
+    }
+    pub fn release(self) -> (Transport, &'buffer mut [u8], CodecFactoryType) {
+        (self.transport, self.buffer, self.codec_factory)
+    }
+    pub fn get_sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    pub(crate) fn next_message_header(
+        &mut self,
+        service: u32,
+        request: u32,
+        is_oneway: bool,
+    ) -> MessageHeader {
+        next_message_header(&mut self.sequence, service, request, is_oneway)
+    }
+
+    /// Like [`Caller::call`], but fails with `FramedTransportError::Timeout` if the reply has not been received
+    /// when `deadline` expires, see [`FramedTransport::receive_with_deadline`].
+    ///
+    /// A reply arriving after the deadline is skipped by the next call, as its sequence number does not match.
+    pub fn call_with_deadline<Error, CodecType, DeadlineType, Constructor, Parser, Output>(
+        &mut self,
+        service: u32,
+        request: u32,
+        deadline: &mut DeadlineType,
+        constructor: Constructor,
+        parser: Parser,
+    ) -> Result<Output, RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+        CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+        DeadlineType: Deadline,
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+        Parser: FnOnce(&mut CodecType) -> Result<Output, CodecError>,
+    {
+        let message_header = self.next_message_header(service, request, false);
+        self.send(&message_header, constructor)?;
+        self.receive_reply(
+            &message_header,
+            |transport, buffer| Ok(transport.receive_with_deadline(buffer, deadline)?.len()),
+            parser,
+        )
+    }
+
+    pub(crate) fn send<Error, CodecType, Constructor>(
+        &mut self,
+        message_header: &MessageHeader,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+        CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    {
+        let cursor = BufferCursor::new(core::mem::take(&mut self.buffer));
+        let mut codec = self.codec_factory.from_cursor(cursor);
+        let result = codec
+            .start_write_message(message_header)
+            .and_then(|_| constructor(&mut codec));
+        let cursor = codec.detach();
+        let position = cursor.get_position();
+        self.buffer = cursor.release();
+        result?;
+
+        self.transport.send(&self.buffer[0..position])?;
+        Ok(())
+    }
+
+    /// Receives frames with `receive`, which returns the length of the frame, until the reply arrives.
+    fn receive_reply<Error, CodecType, Receive, Parser, Output>(
+        &mut self,
+        expected_header: &MessageHeader,
+        mut receive: Receive,
+        parser: Parser,
+    ) -> Result<Output, RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+        CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+        Receive: FnMut(&mut Transport, &mut [u8]) -> Result<usize, FramedTransportError<Error>>,
+        Parser: FnOnce(&mut CodecType) -> Result<Output, CodecError>,
+    {
+        let mut codec = loop {
+            let length = receive(&mut self.transport, self.buffer)?;
+            let cursor = BufferCursor::new_with_length(core::mem::take(&mut self.buffer), length);
+            let mut codec = self.codec_factory.from_cursor(cursor);
+            let error = match codec.start_read_message() {
+                Ok(header) if header.message_type != MessageType::ReplyMessage => {
+                    Some(RequestResponseError::InvalidResponse)
+                }
+                Ok(header) => match is_reply_to(expected_header, &header) {
+                    Ok(true) => break codec,
+                    Ok(false) => None,
+                    Err(err) => Some(err),
+                },
+                Err(err) => Some(err.into()),
+            };
+            self.buffer = codec.detach().release();
+            if let Some(error) = error {
+                return Err(error);
+            }
+        };
+        let result = parser(&mut codec);
+        self.buffer = codec.detach().release();
+        Ok(result?)
+    }
+}
+
+impl<'buffer, Error, Transport, CodecFactoryType, CodecType>
+    Caller<Error, BufferCursor<&'buffer mut [u8]>, CodecType>
+    for Client<'buffer, Transport, CodecFactoryType>
+where
+    Transport: FramedTransport<Error>,
+    CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+    CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+{
+    fn call<Constructor, Parser, Output>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+        parser: Parser,
+    ) -> Result<Output, RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+        Parser: FnOnce(&mut CodecType) -> Result<Output, CodecError>,
+    {
+        let message_header = self.next_message_header(service, request, false);
+        self.send(&message_header, constructor)?;
+        self.receive_reply(
+            &message_header,
+            |transport, buffer| Ok(transport.receive(buffer)?.len()),
+            parser,
+        )
+    }
+
+    fn call_oneway<Constructor>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    {
+        let message_header = self.next_message_header(service, request, true);
+        self.send(&message_header, constructor)
+    }
+}
+
+/// Client like [`Client`], creating the buffer of each invocation and reply with a [`MessageBufferFactory`].
+///
+/// The buffer of the invocation is given back once sent, before the reply is received into another one, so a
+/// pool can be shared with a [`PooledServer`] instead of reserving a buffer for the client alone.
+pub struct PooledClient<Transport, Factory, CodecFactoryType> {
+    transport: Transport,
+    factory: Factory,
+    codec_factory: CodecFactoryType,
+    sequence: u32,
+}
+
+impl<Transport, Factory, CodecFactoryType> PooledClient<Transport, Factory, CodecFactoryType> {
+    pub fn new(transport: Transport, factory: Factory, codec_factory: CodecFactoryType) -> Self {
+        Self {
+            transport,
+            factory,
+            codec_factory,
+            sequence: 0,
+        }
+    }
+    pub fn release(self) -> (Transport, Factory, CodecFactoryType) {
+        (self.transport, self.factory, self.codec_factory)
+    }
+    pub fn get_sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    fn send<Error, CodecType, Constructor>(
+        &mut self,
+        message_header: &MessageHeader,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+        Factory: MessageBufferFactory,
+        CodecType: Codec<BufferCursor<Factory::Buffer>>,
+        CodecFactoryType: CodecFactory<BufferCursor<Factory::Buffer>, CodecType>,
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    {
+        let cursor = BufferCursor::new(self.factory.create()?);
+        let mut codec = self.codec_factory.from_cursor(cursor);
+        codec.start_write_message(message_header)?;
+        constructor(&mut codec)?;
+        let cursor = codec.detach();
+        let position = cursor.get_position();
+        self.transport
+            .send(&cursor.release().as_mut()[0..position])?;
+        Ok(())
+    }
+}
+
+impl<Error, Transport, Factory, CodecFactoryType, CodecType>
+    Caller<Error, BufferCursor<Factory::Buffer>, CodecType>
+    for PooledClient<Transport, Factory, CodecFactoryType>
+where
+    Transport: FramedTransport<Error>,
+    Factory: MessageBufferFactory,
+    CodecType: Codec<BufferCursor<Factory::Buffer>>,
+    CodecFactoryType: CodecFactory<BufferCursor<Factory::Buffer>, CodecType>,
+{
+    fn call<Constructor, Parser, Output>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+        parser: Parser,
+    ) -> Result<Output, RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+        Parser: FnOnce(&mut CodecType) -> Result<Output, CodecError>,
+    {
+        let message_header = next_message_header(&mut self.sequence, service, request, false);
+        self.send(&message_header, constructor)?;
+        loop {
+            let mut buffer = self.factory.create()?;
+            let length = self.transport.receive(buffer.as_mut())?.len();
+            let cursor = BufferCursor::new_with_length(buffer, length);
+            let mut codec = self.codec_factory.from_cursor(cursor);
+            let header = codec.start_read_message()?;
+            if header.message_type != MessageType::ReplyMessage {
+                return Err(RequestResponseError::InvalidResponse);
+            }
+            if is_reply_to(&message_header, &header)? {
+                return Ok(parser(&mut codec)?);
+            }
+        }
+    }
+
+    fn call_oneway<Constructor>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    {
+        let message_header = next_message_header(&mut self.sequence, service, request, true);
+        self.send(&message_header, constructor)
+    }
+}
+
+/// Increments `sequence` and returns the header of the invocation carrying it.
+fn next_message_header(
+    sequence: &mut u32,
+    service: u32,
+    request: u32,
+    is_oneway: bool,
+) -> MessageHeader {
+    *sequence = sequence.wrapping_add(1);
+    MessageHeader {
+        message_type: if is_oneway {
+            MessageType::OnewayMessage
+        } else {
+            MessageType::InvocationMessage
+        },
+        service,
+        request,
+        sequence: *sequence,
+    }
+}
+
+/// Whether the reply `header` answers the request `expected_header`.
+///
+/// A reply carrying another sequence number is a stale reply of an earlier call, while a reply with the expected
+/// sequence number but another service or request is an `InvalidResponse`.
+pub(crate) fn is_reply_to<Error>(
+    expected_header: &MessageHeader,
+    header: &MessageHeader,
+) -> Result<bool, RequestResponseError<Error>> {
+    if header.sequence != expected_header.sequence {
+        Ok(false)
+    } else if header.service != expected_header.service || header.request != expected_header.request
+    {
+        Err(RequestResponseError::InvalidResponse)
+    } else {
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ServiceError {
+    UnknownMethod,
+    CodecError(CodecError),
+}
+
+impl From<CodecError> for ServiceError {
+    fn from(err: CodecError) -> Self {
+        Self::CodecError(err)
+    }
+}
+
+impl<TransportError> From<ServiceError> for RequestResponseError<TransportError> {
+    fn from(err: ServiceError) -> Self {
+        match err {
+            ServiceError::UnknownMethod => Self::InvalidRequest,
+            ServiceError::CodecError(err) => Self::CodecError(err),
+        }
+    }
+}
+
+pub trait Service<CursorType: Cursor, CodecType: Codec<CursorType>> {
+    fn get_service_id(&self) -> u32;
+    /// Reads the arguments of `method_id` from `in_codec` and writes the out parameters and the return value to `out_codec`.
+    /// The reply header has already been written to `out_codec` by the server, unless the request is oneway.
+    fn handle_invocation(
+        &mut self,
+        method_id: u32,
+        in_codec: &mut CodecType,
+        out_codec: &mut CodecType,
+    ) -> Result<(), ServiceError>;
+}
+
+pub type BufferService<'buffer, CodecType> =
+    dyn Service<BufferCursor<&'buffer mut [u8]>, CodecType> + 'buffer;
+
+pub struct SimpleServer<
+    'buffer,
+    'service,
+    Transport,
+    CodecFactoryType,
+    CodecType,
+    const MAX_SERVICES: usize,
+> {
+    transport: Transport,
+    receive_buffer: &'buffer mut [u8],
+    send_buffer: &'buffer mut [u8],
+    codec_factory: CodecFactoryType,
+    services: heapless::Vec<&'service mut BufferService<'buffer, CodecType>, MAX_SERVICES>,
+}
+
+impl<'buffer, 'service, Transport, CodecFactoryType, CodecType, const MAX_SERVICES: usize>
+    SimpleServer<'buffer, 'service, Transport, CodecFactoryType, CodecType, MAX_SERVICES>
+where
+    CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+    CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+{
+    pub fn new(
+        transport: Transport,
+        receive_buffer: &'buffer mut [u8],
+        send_buffer: &'buffer mut [u8],
+        codec_factory: CodecFactoryType,
+    ) -> Self {
+        Self {
+            transport,
+            receive_buffer,
+            send_buffer,
+            codec_factory,
+            services: heapless::Vec::new(),
+        }
+    }
+    pub fn release(
+        self,
+    ) -> (
+        Transport,
+        &'buffer mut [u8],
+        &'buffer mut [u8],
+        CodecFactoryType,
+    ) {
+        (
+            self.transport,
+            self.receive_buffer,
+            self.send_buffer,
+            self.codec_factory,
+        )
+    }
+
+    /// Registers `service`. The service is handed back if `MAX_SERVICES` services are already registered.
+    pub fn add_service(
+        &mut self,
+        service: &'service mut BufferService<'buffer, CodecType>,
+    ) -> Result<(), &'service mut BufferService<'buffer, CodecType>> {
+        self.services.push(service)
+    }
+
+    /// Receives one request and dispatches it to the registered service.
+    ///
+    /// A reply is sent only for `InvocationMessage`; `OnewayMessage` requests are handled silently.
+    /// A request which cannot be decoded or dispatched is dropped without reply and its error returned.
+    pub fn run_once<Error>(&mut self) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        let length = self.transport.receive(self.receive_buffer)?.len();
+        let cursor =
+            BufferCursor::new_with_length(core::mem::take(&mut self.receive_buffer), length);
+        let mut in_codec = self.codec_factory.from_cursor(cursor);
+        let cursor = BufferCursor::new(core::mem::take(&mut self.send_buffer));
+        let mut out_codec = self.codec_factory.from_cursor(cursor);
+
+        let result = Self::dispatch(&mut self.services, &mut in_codec, &mut out_codec);
+
+        self.receive_buffer = in_codec.detach().release();
+        let cursor = out_codec.detach();
+        let position = cursor.get_position();
+        self.send_buffer = cursor.release();
+
+        if result? {
+            self.transport.send(&self.send_buffer[0..position])?;
+        }
+        Ok(())
+    }
+
+    /// Runs `run_once` repeatedly, dropping the requests which fail, until the underlying transport fails.
+    pub fn run<Error>(&mut self) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        loop {
+            match self.run_once() {
+                Err(err) if err.is_underlying_error() => return Err(err),
+                _ => {}
+            }
+        }
+    }
+
+    fn dispatch<Error>(
+        services: &mut [&'service mut BufferService<'buffer, CodecType>],
+        in_codec: &mut CodecType,
+        out_codec: &mut CodecType,
+    ) -> Result<bool, RequestResponseError<Error>> {
+        let request_header = in_codec.start_read_message()?;
+        dispatch_request(services, &request_header, in_codec, out_codec)
+    }
+}
+
+/// Service of a [`PooledServer`], whose codecs own buffers of type `Buffer`.
+pub type PooledService<'service, Buffer, CodecType> =
+    dyn Service<BufferCursor<PooledBuffer<Buffer>>, CodecType> + 'service;
+
+/// Buffer of the codecs of a [`PooledServer`]: a buffer created by its factory, or none for the reply to a
+/// oneway request, which is never sent and so is empty.
+pub struct PooledBuffer<Buffer>(Option<Buffer>);
+
+impl<Buffer: AsMut<[u8]>> AsMut<[u8]> for PooledBuffer<Buffer> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        match &mut self.0 {
+            Some(buffer) => buffer.as_mut(),
+            None => &mut [],
+        }
+    }
+}
+
+/// Server like [`SimpleServer`], creating the buffer of each request and reply with a [`MessageBufferFactory`].
+///
+/// Both buffers are given back once the request is handled, so a pool can be shared with clients instead of
+/// reserving a receive and a send buffer for the server alone. Both are created before receiving, so that
+/// every request received can be replied to: if the factory cannot create them, `run_once` fails with
+/// `MessageBufferError` and leaves the request in the transport. The reply buffer of a oneway request is given
+/// back as soon as its header is read, before the request is handled.
+pub struct PooledServer<
+    'service,
+    Transport,
+    Factory: MessageBufferFactory,
+    CodecFactoryType,
+    CodecType,
+    const MAX_SERVICES: usize,
+> {
+    transport: Transport,
+    factory: Factory,
+    codec_factory: CodecFactoryType,
+    services: heapless::Vec<
+        &'service mut PooledService<'service, Factory::Buffer, CodecType>,
+        MAX_SERVICES,
+    >,
+}
+
+impl<'service, Transport, Factory, CodecFactoryType, CodecType, const MAX_SERVICES: usize>
+    PooledServer<'service, Transport, Factory, CodecFactoryType, CodecType, MAX_SERVICES>
+where
+    Factory: MessageBufferFactory,
+    CodecType: Codec<BufferCursor<PooledBuffer<Factory::Buffer>>>,
+    CodecFactoryType: CodecFactory<BufferCursor<PooledBuffer<Factory::Buffer>>, CodecType>,
+{
+    pub fn new(transport: Transport, factory: Factory, codec_factory: CodecFactoryType) -> Self {
+        Self {
+            transport,
+            factory,
+            codec_factory,
+            services: heapless::Vec::new(),
+        }
+    }
+    pub fn release(self) -> (Transport, Factory, CodecFactoryType) {
+        (self.transport, self.factory, self.codec_factory)
+    }
+
+    /// Registers `service`. The service is handed back if `MAX_SERVICES` services are already registered.
+    pub fn add_service(
+        &mut self,
+        service: &'service mut PooledService<'service, Factory::Buffer, CodecType>,
+    ) -> Result<(), &'service mut PooledService<'service, Factory::Buffer, CodecType>> {
+        self.services.push(service)
+    }
+
+    /// Receives one request and dispatches it to the registered service, as [`SimpleServer::run_once`].
+    pub fn run_once<Error>(&mut self) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        let mut receive_buffer = self.factory.create()?;
+        let mut send_buffer = PooledBuffer(Some(self.factory.create()?));
+        let length = self.transport.receive(receive_buffer.as_mut())?.len();
+        let cursor = BufferCursor::new_with_length(PooledBuffer(Some(receive_buffer)), length);
+        let mut in_codec = self.codec_factory.from_cursor(cursor);
+        let request_header = in_codec.start_read_message()?;
+        if request_header.message_type == MessageType::OnewayMessage {
+            send_buffer = PooledBuffer(None);
+        }
+        let mut out_codec = self
+            .codec_factory
+            .from_cursor(BufferCursor::new(send_buffer));
+
+        if dispatch_request(
+            &mut self.services,
+            &request_header,
+            &mut in_codec,
+            &mut out_codec,
+        )? {
+            let cursor = out_codec.detach();
+            let position = cursor.get_position();
+            self.transport
+                .send(&cursor.release().as_mut()[0..position])?;
+        }
+        Ok(())
+    }
+
+    /// Runs `run_once` repeatedly, dropping the requests which fail, until the underlying transport fails or
+    /// the factory has no buffer left, which receiving more cannot change.
+    pub fn run<Error>(&mut self) -> Result<(), RequestResponseError<Error>>
+    where
+        Transport: FramedTransport<Error>,
+    {
+        loop {
+            match self.run_once() {
+                Err(err @ RequestResponseError::MessageBufferError(_)) => return Err(err),
+                Err(err) if err.is_underlying_error() => return Err(err),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Calls the service `request_header` is addressed to and writes the reply to `out_codec`.
+/// Returns whether the reply must be sent, that is whether the request is not oneway.
+pub(crate) fn dispatch_request<'service, 'object, Error, CursorType, CodecType>(
+    services: &mut [&'service mut (dyn Service<CursorType, CodecType> + 'object)],
+    request_header: &MessageHeader,
+    in_codec: &mut CodecType,
+    out_codec: &mut CodecType,
+) -> Result<bool, RequestResponseError<Error>>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    let is_oneway = match request_header.message_type {
+        MessageType::InvocationMessage => false,
+        MessageType::OnewayMessage => true,
+        _ => return Err(RequestResponseError::InvalidRequest),
+    };
+    let service = services
+        .iter_mut()
+        .find(|service| service.get_service_id() == request_header.service)
+        .ok_or(RequestResponseError::InvalidRequest)?;
+
+    if !is_oneway {
+        out_codec.start_write_message(&MessageHeader {
+            message_type: MessageType::ReplyMessage,
+            ..*request_header
+        })?;
+    }
+    service.handle_invocation(request_header.request, in_codec, out_codec)?;
+    Ok(!is_oneway)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{BasicCodec, BasicCodecFactory};
+    use crate::cursor::{Cursor, CursorError, SliceCursor};
+    use crate::framed_transport::{BasicFramedTransport, UnderlyingTransport};
+    use crate::message_buffer::{MessageBufferError, MessageBufferPool};
+    use crate::request::{Request, Response};
+
+    struct TestTransport<'a> {
+        rx: SliceCursor<'a>,
+        tx: SliceCursor<'a>,
+    }
+
+    impl<'a> UnderlyingTransport for TestTransport<'a> {
+        type Error = CursorError;
+        fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+            let length = data.len();
+            if self.rx.read(data)?.len() < length {
+                Err(CursorError::NotEnoughData)
+            } else {
+                Ok(())
+            }
+        }
+        fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+            self.tx.write(data)
+        }
+        fn read_available(&mut self, data: &mut [u8]) -> Result<usize, Self::Error> {
+            self.rx.read_available(data)
+        }
+    }
+
+    fn prepare_responses(buffer: &mut [u8], responses: &[(u32, u32, u32, u32)]) {
+        let mut frame_buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(buffer));
+        for (service, request, sequence, value) in responses {
+            Response::new(*service, *request, *sequence, false)
+                .send_response(
+                    &mut transport,
+                    &mut frame_buffer,
+                    BasicCodecFactory::new(),
+                    |codec| codec.write_u32(*value),
+                )
+                .unwrap();
+        }
+    }
+
+    /// Deadline expiring the `n`th time it is checked, `Attempts(n)`.
+    struct Attempts(u32);
+
+    impl Deadline for Attempts {
+        fn has_expired(&mut self) -> bool {
+            self.0 = self.0.saturating_sub(1);
+            self.0 == 0
+        }
+    }
+
+    #[test]
+    fn call_with_deadline_times_out() -> Result<(), RequestResponseError<CursorError>> {
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        prepare_responses(&mut rx_buffer, &[(2, 1, 1, 1234)]);
+
+        let mut frame_buffer = [0u8; 64];
+        let transport = BasicFramedTransport::new(TestTransport {
+            rx: SliceCursor::new(&mut rx_buffer[..4 + 8 + 4]),
+            tx: SliceCursor::new(&mut tx_buffer),
+        });
+        let mut client = Client::new(transport, &mut frame_buffer, BasicCodecFactory::new());
+        let result = client.call_with_deadline(
+            2,
+            1,
+            &mut Attempts(3),
+            |codec| codec.write_u32(5678),
+            |codec| codec.read_u32(),
+        )?;
+        assert_eq!(result, 1234);
+        assert_eq!(
+            client.call_with_deadline(
+                2,
+                1,
+                &mut Attempts(3),
+                |codec| codec.write_u32(5678),
+                |codec| codec.read_u32(),
+            ),
+            Err(RequestResponseError::FramedTransportError(
+                FramedTransportError::Timeout
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn call_skips_stale_replies() -> Result<(), RequestResponseError<CursorError>> {
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        prepare_responses(&mut rx_buffer, &[(2, 1, 0, 0xdead), (2, 1, 1, 1234)]);
+
+        {
+            let mut frame_buffer = [0u8; 64];
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx: SliceCursor::new(&mut rx_buffer),
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut client = Client::new(transport, &mut frame_buffer, BasicCodecFactory::new());
+            let result = client.call(
+                2,
+                1,
+                |codec| codec.write_u32(5678),
+                |codec| codec.read_u32(),
+            )?;
+            assert_eq!(result, 1234);
+            assert_eq!(client.get_sequence(), 1);
+        }
+
+        let mut frame_buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut tx_buffer));
+        let (request, mut codec) =
+            Request::receive_request(&mut transport, &mut frame_buffer, BasicCodecFactory::new())?;
+        assert_eq!(request.service, 2);
+        assert_eq!(request.request, 1);
+        assert_eq!(request.sequence, 1);
+        assert!(!request.is_oneway);
+        assert_eq!(codec.read_u32()?, 5678);
+        Ok(())
+    }
+
+    #[test]
+    fn call_rejects_mismatched_reply() {
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        prepare_responses(&mut rx_buffer, &[(3, 1, 1, 0), (2, 1, 2, 42)]);
+
+        let mut frame_buffer = [0u8; 64];
+        let transport = BasicFramedTransport::new(TestTransport {
+            rx: SliceCursor::new(&mut rx_buffer),
+            tx: SliceCursor::new(&mut tx_buffer),
+        });
+        let mut client = Client::new(transport, &mut frame_buffer, BasicCodecFactory::new());
+        assert_eq!(
+            client.call(2, 1, |_| Ok(()), |codec| codec.read_u32()),
+            Err(RequestResponseError::InvalidResponse)
+        );
+        // The frame buffer must still be usable after a failed call.
+        assert_eq!(
+            client.call(2, 1, |_| Ok(()), |codec| codec.read_u32()),
+            Ok(42)
+        );
+    }
+
+    #[test]
+    fn call_oneway_does_not_wait() -> Result<(), RequestResponseError<CursorError>> {
+        let mut rx_buffer = [0u8; 0];
+        let mut tx_buffer = [0u8; 256];
+        {
+            let mut frame_buffer = [0u8; 64];
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx: SliceCursor::new(&mut rx_buffer),
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut client = Client::new(transport, &mut frame_buffer, BasicCodecFactory::new());
+            client.call_oneway(4, 2, |codec| codec.write_u8(1))?;
+        }
+
+        let mut frame_buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut tx_buffer));
+        let (request, _) =
+            Request::receive_request(&mut transport, &mut frame_buffer, BasicCodecFactory::new())?;
+        assert_eq!(request.service, 4);
+        assert_eq!(request.request, 2);
+        assert!(request.is_oneway);
+        Ok(())
+    }
+
+    struct CalculatorService {
+        last_oneway_value: u32,
+    }
+
+    impl<CursorType: Cursor, CodecType: Codec<CursorType>> Service<CursorType, CodecType>
+        for CalculatorService
+    {
+        fn get_service_id(&self) -> u32 {
+            2
+        }
+        fn handle_invocation(
+            &mut self,
+            method_id: u32,
+            in_codec: &mut CodecType,
+            out_codec: &mut CodecType,
+        ) -> Result<(), ServiceError> {
+            match method_id {
+                1 => {
+                    let lhs = in_codec.read_u32()?;
+                    let rhs = in_codec.read_u32()?;
+                    out_codec.write_u32(lhs + rhs)?;
+                }
+                2 => {
+                    self.last_oneway_value = in_codec.read_u32()?;
+                }
+                _ => return Err(ServiceError::UnknownMethod),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn server_replies_to_invocations_only() -> Result<(), RequestResponseError<CursorError>> {
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        {
+            let mut frame_buffer = [0u8; 64];
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut rx_buffer));
+            Request::new(2, 2, 1, true).send_request(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+                |codec| codec.write_u32(7),
+            )?;
+            Request::new(2, 1, 2, false).send_request(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+                |codec| {
+                    codec.write_u32(3)?;
+                    codec.write_u32(4)
+                },
+            )?;
+            Request::new(5, 1, 3, false).send_request(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+                |_| Ok(()),
+            )?;
+        }
+
+        let mut calculator = CalculatorService {
+            last_oneway_value: 0,
+        };
+        {
+            let mut receive_buffer = [0u8; 64];
+            let mut send_buffer = [0u8; 64];
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx: SliceCursor::new(&mut rx_buffer),
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut server: SimpleServer<_, _, _, 4> = SimpleServer::new(
+                transport,
+                &mut receive_buffer,
+                &mut send_buffer,
+                BasicCodecFactory::new(),
+            );
+            assert!(server.add_service(&mut calculator).is_ok());
+
+            server.run_once()?;
+            let (transport, _, _, _) = server.release();
+            assert_eq!(transport.release().tx.get_position(), 0);
+        }
+        assert_eq!(calculator.last_oneway_value, 7);
+
+        {
+            let mut receive_buffer = [0u8; 64];
+            let mut send_buffer = [0u8; 64];
+            // Skip the oneway request which has already been handled.
+            let rx = SliceCursor::new_with_position(&mut rx_buffer, 4 + 8 + 4);
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx,
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut server: SimpleServer<_, _, _, 4> = SimpleServer::new(
+                transport,
+                &mut receive_buffer,
+                &mut send_buffer,
+                BasicCodecFactory::new(),
+            );
+            assert!(server.add_service(&mut calculator).is_ok());
+
+            server.run_once()?;
+            assert_eq!(server.run_once(), Err(RequestResponseError::InvalidRequest));
+        }
+
+        let mut frame_buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut tx_buffer));
+        let (response, mut codec) = Response::receive_response(
+            &mut transport,
+            &mut frame_buffer,
+            BasicCodecFactory::new(),
+        )?;
+        assert_eq!(response.service, 2);
+        assert_eq!(response.request, 1);
+        assert_eq!(response.sequence, 2);
+        assert!(!response.is_notification);
+        assert_eq!(codec.read_u32()?, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn run_drops_failing_requests() -> Result<(), RequestResponseError<CursorError>> {
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        {
+            let mut frame_buffer = [0u8; 64];
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut rx_buffer));
+            // Unknown service, unknown method, missing argument, then a valid request.
+            for (service, request, sequence) in [(5, 1, 1), (2, 9, 2), (2, 1, 3)] {
+                Request::new(service, request, sequence, false).send_request(
+                    &mut transport,
+                    &mut frame_buffer,
+                    BasicCodecFactory::new(),
+                    |codec| codec.write_u32(3),
+                )?;
+            }
+            Request::new(2, 1, 4, false).send_request(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+                |codec| {
+                    codec.write_u32(3)?;
+                    codec.write_u32(4)
+                },
+            )?;
+        }
+
+        let mut calculator = CalculatorService {
+            last_oneway_value: 0,
+        };
+        {
+            let mut receive_buffer = [0u8; 64];
+            let mut send_buffer = [0u8; 64];
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx: SliceCursor::new(&mut rx_buffer),
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut server: SimpleServer<_, _, _, 1> = SimpleServer::new(
+                transport,
+                &mut receive_buffer,
+                &mut send_buffer,
+                BasicCodecFactory::new(),
+            );
+            assert!(server.add_service(&mut calculator).is_ok());
+            // Only zeroes follow the requests, which fail the checksum, until the receive buffer runs out.
+            let result = server.run();
+            assert!(result.is_err_and(|err| err.is_underlying_error()));
+        }
+
+        let mut frame_buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut tx_buffer));
+        let (response, mut codec) = Response::receive_response(
+            &mut transport,
+            &mut frame_buffer,
+            BasicCodecFactory::new(),
+        )?;
+        assert_eq!(response.sequence, 4);
+        assert_eq!(codec.read_u32()?, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn pooled_server_creates_its_buffers() -> Result<(), RequestResponseError<CursorError>> {
+        let pool = MessageBufferPool::<64, 2>::new();
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        {
+            let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut rx_buffer));
+            // Two invocations, a oneway request, then an unknown method and a valid invocation.
+            for (request, sequence, is_oneway) in [
+                (1, 1, false),
+                (1, 2, false),
+                (2, 3, true),
+                (9, 4, false),
+                (1, 5, false),
+            ] {
+                Request::new(2, request, sequence, is_oneway).send_request(
+                    &mut transport,
+                    (&pool).create()?,
+                    BasicCodecFactory::new(),
+                    |codec| {
+                        codec.write_u32(sequence)?;
+                        codec.write_u32(4)
+                    },
+                )?;
+            }
+        }
+
+        let mut calculator = CalculatorService {
+            last_oneway_value: 0,
+        };
+        {
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx: SliceCursor::new(&mut rx_buffer),
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut server: PooledServer<_, _, _, _, 1> =
+                PooledServer::new(transport, &pool, BasicCodecFactory::new());
+            assert!(server.add_service(&mut calculator).is_ok());
+            server.run_once()?;
+            assert_eq!(pool.available(), 2);
+
+            // A buffer kept elsewhere leaves none for the reply, so the request is left unreceived.
+            let kept = (&pool).create()?;
+            assert_eq!(
+                server.run(),
+                Err(RequestResponseError::MessageBufferError(
+                    MessageBufferError::Exhausted
+                ))
+            );
+            drop(kept);
+
+            // The unknown method is dropped until the receive buffer runs out.
+            let result = server.run();
+            assert!(result.is_err_and(|err| err.is_underlying_error()));
+        }
+        assert_eq!(calculator.last_oneway_value, 3);
+
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut tx_buffer));
+        for (sequence, value) in [(1, 5), (2, 6), (5, 9)] {
+            let (response, mut codec) = Response::receive_response_pooled(
+                &mut transport,
+                &&pool,
+                BasicCodecFactory::new(),
+            )?;
+            assert_eq!(response.sequence, sequence);
+            assert_eq!(codec.read_u32()?, value);
+            assert_eq!(pool.available(), 1);
+        }
+        // Nothing else was sent, and the buffer of a failed receive is given back too.
+        let result: Result<(Response, BasicCodec<_>), _> =
+            Response::receive_response_pooled(&mut transport, &&pool, BasicCodecFactory::new());
+        assert!(result.is_err());
+        assert_eq!(pool.available(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn pooled_client_creates_its_buffers() -> Result<(), RequestResponseError<CursorError>> {
+        // The invocation is sent before the reply is received, so one buffer is enough.
+        let pool = MessageBufferPool::<64, 1>::new();
+        let mut rx_buffer = [0u8; 256];
+        let mut tx_buffer = [0u8; 256];
+        prepare_responses(&mut rx_buffer, &[(2, 1, 0, 0xdead), (2, 1, 1, 1234)]);
+        {
+            let transport = BasicFramedTransport::new(TestTransport {
+                rx: SliceCursor::new(&mut rx_buffer),
+                tx: SliceCursor::new(&mut tx_buffer),
+            });
+            let mut client = PooledClient::new(transport, &pool, BasicCodecFactory::new());
+            let result = client.call(
+                2,
+                1,
+                |codec| codec.write_u32(5678),
+                |codec| codec.read_u32(),
+            )?;
+            assert_eq!(result, 1234);
+            client.call_oneway(2, 2, |codec| codec.write_u32(7))?;
+            assert_eq!(client.get_sequence(), 2);
+            assert_eq!(pool.available(), 1);
+        }
+
+        let mut frame_buffer = [0u8; 64];
+        let mut transport = BasicFramedTransport::new(SliceCursor::new(&mut tx_buffer));
+        for (request, sequence, is_oneway, value) in [(1, 1, false, 5678), (2, 2, true, 7)] {
+            let (received, mut codec) = Request::receive_request(
+                &mut transport,
+                &mut frame_buffer,
+                BasicCodecFactory::new(),
+            )?;
+            assert_eq!(received.request, request);
+            assert_eq!(received.sequence, sequence);
+            assert_eq!(received.is_oneway, is_oneway);
+            assert_eq!(codec.read_u32()?, value);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,671 @@
+//! `serde` support on top of any [`Codec`], enabled with the `serde` feature.
+//!
+//! Types deriving `Serialize` and `Deserialize` map to the erpc encoding as follows:
+//!
+//! - Structs and tuples encode their fields one after another, so `[T; N]` is a fixed array.
+//! - Sequences such as `Vec<T>` or `&[T]` are lists, and maps are lists of key and value pairs.
+//! - `Option<T>` is preceded by the null flag, like an `@nullable` member.
+//! - Enum variants are union cases, whose discriminator is the index of the variant.
+//! - `&str` and `String` are strings and bytes, for example with `serde_bytes`, are binaries.
+//!
+//! Since the encoding does not describe itself, `deserialize_any` is not supported. Strings and binaries are
+//! read into a scratch buffer given to the [`Deserializer`]. The `alloc` feature enables the `alloc` support of
+//! `serde`, for `Vec<T>` and `String` among others, and messages in custom errors.
+
+#[cfg(feature = "alloc")]
+use alloc::string::{String, ToString};
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::codec::{Codec, CodecError};
+use crate::cursor::Cursor;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Codec(CodecError),
+    /// Sequences and maps need a known length to be encoded as a list.
+    LengthRequired,
+    /// The type cannot be decoded without a description in the encoding.
+    Unsupported,
+    #[cfg(feature = "alloc")]
+    Custom(String),
+    #[cfg(not(feature = "alloc"))]
+    Custom,
+}
+
+impl From<CodecError> for Error {
+    fn from(error: CodecError) -> Self {
+        Error::Codec(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Codec(error) => write!(f, "codec error: {:?}", error),
+            Error::LengthRequired => f.write_str("sequences and maps need a length"),
+            Error::Unsupported => f.write_str("type not supported by the erpc encoding"),
+            #[cfg(feature = "alloc")]
+            Error::Custom(message) => f.write_str(message),
+            #[cfg(not(feature = "alloc"))]
+            Error::Custom => f.write_str("serde error"),
+        }
+    }
+}
+
+impl ser::StdError for Error {}
+
+impl ser::Error for Error {
+    #[allow(unused_variables)]
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        #[cfg(feature = "alloc")]
+        return Error::Custom(message.to_string());
+        #[cfg(not(feature = "alloc"))]
+        return Error::Custom;
+    }
+}
+
+impl de::Error for Error {
+    #[allow(unused_variables)]
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        <Self as ser::Error>::custom(message)
+    }
+}
+
+/// Encodes `value` into `codec`.
+pub fn to_codec<T, CursorType, CodecType>(value: &T, codec: &mut CodecType) -> Result<(), Error>
+where
+    T: Serialize + ?Sized,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    value.serialize(&mut Serializer::new(codec))
+}
+
+/// Decodes a value from `codec`, reading strings and binaries into `scratch`.
+pub fn from_codec<T, CursorType, CodecType>(
+    codec: &mut CodecType,
+    scratch: &mut [u8],
+) -> Result<T, Error>
+where
+    T: de::DeserializeOwned,
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    T::deserialize(&mut Deserializer::new(codec, scratch))
+}
+
+pub struct Serializer<'codec, CursorType, CodecType> {
+    codec: &'codec mut CodecType,
+    _cursor: PhantomData<CursorType>,
+}
+
+impl<'codec, CursorType, CodecType> Serializer<'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    pub fn new(codec: &'codec mut CodecType) -> Self {
+        Self {
+            codec,
+            _cursor: PhantomData,
+        }
+    }
+
+    fn start_write_variant(&mut self, variant_index: u32) -> Result<(), Error> {
+        let discriminator = i32::try_from(variant_index).map_err(|_| Error::Unsupported)?;
+        Ok(self.codec.start_write_union(discriminator)?)
+    }
+}
+
+impl<'a, 'codec, CursorType, CodecType> ser::Serializer
+    for &'a mut Serializer<'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        Ok(self.codec.write_bool(value)?)
+    }
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        Ok(self.codec.write_i8(value)?)
+    }
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        Ok(self.codec.write_i16(value)?)
+    }
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        Ok(self.codec.write_i32(value)?)
+    }
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        Ok(self.codec.write_i64(value)?)
+    }
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        Ok(self.codec.write_u8(value)?)
+    }
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        Ok(self.codec.write_u16(value)?)
+    }
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        Ok(self.codec.write_u32(value)?)
+    }
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        Ok(self.codec.write_u64(value)?)
+    }
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        Ok(self.codec.write_f32(value)?)
+    }
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        Ok(self.codec.write_f64(value)?)
+    }
+    /// Encoded as a string, erpc having no character type.
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        let mut buffer = [0u8; 4];
+        Ok(self.codec.write_str(value.encode_utf8(&mut buffer))?)
+    }
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        Ok(self.codec.write_str(value)?)
+    }
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Error> {
+        Ok(self.codec.write_binary(value)?)
+    }
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(self.codec.write_null_flag(true)?)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        self.codec.write_null_flag(false)?;
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), Error> {
+        self.start_write_variant(variant_index)
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.start_write_variant(variant_index)?;
+        value.serialize(self)
+    }
+    fn serialize_seq(self, length: Option<usize>) -> Result<Self, Error> {
+        let length = length.ok_or(Error::LengthRequired)?;
+        self.codec.start_write_list(length)?;
+        Ok(self)
+    }
+    fn serialize_tuple(self, _length: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, _length: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _length: usize,
+    ) -> Result<Self, Error> {
+        self.start_write_variant(variant_index)?;
+        Ok(self)
+    }
+    fn serialize_map(self, length: Option<usize>) -> Result<Self, Error> {
+        self.serialize_seq(length)
+    }
+    fn serialize_struct(self, _name: &'static str, _length: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _length: usize,
+    ) -> Result<Self, Error> {
+        self.start_write_variant(variant_index)?;
+        Ok(self)
+    }
+}
+
+macro_rules! impl_serialize_compound {
+    ($trait:ident, $method:ident $(, $key:ident)?) => {
+        impl<'a, 'codec, CursorType, CodecType> ser::$trait
+            for &'a mut Serializer<'codec, CursorType, CodecType>
+        where
+            CursorType: Cursor,
+            CodecType: Codec<CursorType>,
+        {
+            type Ok = ();
+            type Error = Error;
+
+            fn $method<T: Serialize + ?Sized>(
+                &mut self,
+                $($key: &'static str,)?
+                value: &T,
+            ) -> Result<(), Error> {
+                value.serialize(&mut **self)
+            }
+
+            fn end(self) -> Result<(), Error> {
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_serialize_compound!(SerializeSeq, serialize_element);
+impl_serialize_compound!(SerializeTuple, serialize_element);
+impl_serialize_compound!(SerializeTupleStruct, serialize_field);
+impl_serialize_compound!(SerializeTupleVariant, serialize_field);
+impl_serialize_compound!(SerializeStruct, serialize_field, _key);
+impl_serialize_compound!(SerializeStructVariant, serialize_field, _key);
+
+impl<'a, 'codec, CursorType, CodecType> ser::SerializeMap
+    for &'a mut Serializer<'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        key.serialize(&mut **self)
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+pub struct Deserializer<'codec, CursorType, CodecType> {
+    codec: &'codec mut CodecType,
+    scratch: &'codec mut [u8],
+    _cursor: PhantomData<CursorType>,
+}
+
+impl<'codec, CursorType, CodecType> Deserializer<'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    /// Creates a deserializer reading strings and binaries into `scratch`, which must hold the longest of them.
+    pub fn new(codec: &'codec mut CodecType, scratch: &'codec mut [u8]) -> Self {
+        Self {
+            codec,
+            scratch,
+            _cursor: PhantomData,
+        }
+    }
+}
+
+macro_rules! define_deserialize {
+    ($method:ident, $read:ident, $visit:ident) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.$visit(self.codec.$read()?)
+        }
+    };
+}
+
+impl<'de, 'a, 'codec, CursorType, CodecType> de::Deserializer<'de>
+    for &'a mut Deserializer<'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    type Error = Error;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::Unsupported)
+    }
+
+    define_deserialize!(deserialize_bool, read_bool, visit_bool);
+    define_deserialize!(deserialize_i8, read_i8, visit_i8);
+    define_deserialize!(deserialize_i16, read_i16, visit_i16);
+    define_deserialize!(deserialize_i32, read_i32, visit_i32);
+    define_deserialize!(deserialize_i64, read_i64, visit_i64);
+    define_deserialize!(deserialize_u8, read_u8, visit_u8);
+    define_deserialize!(deserialize_u16, read_u16, visit_u16);
+    define_deserialize!(deserialize_u32, read_u32, visit_u32);
+    define_deserialize!(deserialize_u64, read_u64, visit_u64);
+    define_deserialize!(deserialize_f32, read_f32, visit_f32);
+    define_deserialize!(deserialize_f64, read_f64, visit_f64);
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut buffer = [0u8; 4];
+        let mut chars = self.codec.read_str(&mut buffer)?.chars();
+        match (chars.next(), chars.next()) {
+            (Some(value), None) => visitor.visit_char(value),
+            _ => Err(de::Error::invalid_length(0, &"a single character")),
+        }
+    }
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_str(self.codec.read_str(self.scratch)?)
+    }
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_bytes(self.codec.read_binary(self.scratch)?)
+    }
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.codec.read_null_flag()? {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let length = self.codec.start_read_list()?;
+        visitor.visit_seq(Elements {
+            deserializer: self,
+            remaining: length,
+        })
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        length: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_seq(Elements {
+            deserializer: self,
+            remaining: length,
+        })
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        length: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(length, visitor)
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let length = self.codec.start_read_list()?;
+        visitor.visit_map(Elements {
+            deserializer: self,
+            remaining: length,
+        })
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self)
+    }
+    fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::Unsupported)
+    }
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+        Err(Error::Unsupported)
+    }
+}
+
+/// Elements of a sequence, tuple or struct, or entries of a map.
+struct Elements<'a, 'codec, CursorType, CodecType> {
+    deserializer: &'a mut Deserializer<'codec, CursorType, CodecType>,
+    remaining: usize,
+}
+
+impl<'de, 'a, 'codec, CursorType, CodecType> de::SeqAccess<'de>
+    for Elements<'a, 'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        // The length comes from the peer, do not let it preallocate.
+        None
+    }
+}
+
+impl<'de, 'a, 'codec, CursorType, CodecType> de::MapAccess<'de>
+    for Elements<'a, 'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(&mut *self.deserializer)
+    }
+}
+
+impl<'de, 'a, 'codec, CursorType, CodecType> de::EnumAccess<'de>
+    for &'a mut Deserializer<'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let discriminator = self.codec.start_read_union()?;
+        let index = u32::try_from(discriminator).map_err(|_| CodecError::InvalidEnumValue)?;
+        let value = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(index))?;
+        Ok((value, self))
+    }
+}
+
+impl<'de, 'a, 'codec, CursorType, CodecType> de::VariantAccess<'de>
+    for &'a mut Deserializer<'codec, CursorType, CodecType>
+where
+    CursorType: Cursor,
+    CodecType: Codec<CursorType>,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, length: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_tuple(self, length, visitor)
+    }
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::BasicCodec;
+    use crate::cursor::SliceCursor;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Device {
+        addr: [u8; 6],
+        handle: u16,
+        name: String,
+        alias: Option<String>,
+        handles: Vec<u16>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        Reset,
+        Connected(u16),
+        Moved { x: i8, y: i8 },
+    }
+
+    fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut buffer = [0u8; 128];
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+        to_codec(value, &mut codec).unwrap();
+        let position = codec.detach().get_position();
+        buffer[..position].to_vec()
+    }
+
+    fn decode<T: de::DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+        let mut bytes = bytes.to_vec();
+        let mut scratch = [0u8; 8];
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut bytes));
+        from_codec(&mut codec, &mut scratch)
+    }
+
+    #[test]
+    fn structs_lists_and_options_like_erpcgen() {
+        let device = Device {
+            addr: [1, 2, 3, 4, 5, 6],
+            handle: 0x1234,
+            name: "ab".to_string(),
+            alias: None,
+            handles: vec![7, 8],
+        };
+        let bytes = encode(&device);
+        assert_eq!(
+            bytes,
+            [1, 2, 3, 4, 5, 6, 0x34, 0x12, 2, 0, 0, 0, b'a', b'b', 1, 2, 0, 0, 0, 7, 0, 8, 0]
+        );
+        assert_eq!(decode::<Device>(&bytes), Ok(device));
+
+        let alias = Some("c".to_string());
+        assert_eq!(encode(&alias), [0, 1, 0, 0, 0, b'c']);
+        assert_eq!(decode::<Option<String>>(&[0, 1, 0, 0, 0, b'c']), Ok(alias));
+    }
+
+    #[test]
+    fn enums_are_unions() {
+        assert_eq!(encode(&Event::Reset), [0, 0, 0, 0]);
+        assert_eq!(encode(&Event::Connected(1)), [1, 0, 0, 0, 1, 0]);
+        assert_eq!(encode(&Event::Moved { x: -1, y: 2 }), [2, 0, 0, 0, 0xff, 2]);
+        assert_eq!(
+            decode::<Event>(&[2, 0, 0, 0, 0xff, 2]),
+            Ok(Event::Moved { x: -1, y: 2 })
+        );
+        assert!(decode::<Event>(&[3, 0, 0, 0]).is_err());
+        assert_eq!(
+            decode::<Event>(&[0xff, 0xff, 0xff, 0xff]),
+            Err(Error::Codec(CodecError::InvalidEnumValue))
+        );
+    }
+
+    #[test]
+    fn maps_bytes_and_errors() {
+        let mut map = BTreeMap::new();
+        map.insert(1u8, 'x');
+        assert_eq!(encode(&map), [1, 0, 0, 0, 1, 1, 0, 0, 0, b'x']);
+        assert_eq!(decode::<BTreeMap<u8, char>>(&encode(&map)), Ok(map));
+
+        let mut buffer = [0u8; 8];
+        let mut codec = BasicCodec::new(SliceCursor::new(&mut buffer));
+        let mut serializer = Serializer::new(&mut codec);
+        ser::Serializer::serialize_bytes(&mut serializer, &[1, 2]).unwrap();
+        assert_eq!(
+            ser::Serializer::serialize_seq(&mut serializer, None).err(),
+            Some(Error::LengthRequired)
+        );
+
+        assert_eq!(
+            decode::<String>(&[9, 0, 0, 0]),
+            Err(Error::Codec(CodecError::LengthExceedsBuffer))
+        );
+    }
+}
@@ -0,0 +1,11 @@
+use std::path::Path;
+
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    erpc_codegen::compile(
+        "examples/rpc_gap.erpc",
+        Path::new(&out_dir).join("rpc_gap.rs"),
+        &erpc_codegen::Options::default(),
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
+}
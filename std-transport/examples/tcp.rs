@@ -1,12 +1,72 @@
-use std::{net::TcpStream, time::Duration};
+use std::{
+    net::TcpStream,
+    time::{Duration, Instant},
+};
 
+use rust_erpc::clock::{Clock, ClockDeadline};
+use rust_erpc::codec::{BasicCodecFactory, Codec, CodecError, CodecFactory};
+use rust_erpc::cursor::BufferCursor;
+use rust_erpc::framed_transport::{BasicFramedTransport, FramedTransport};
+use rust_erpc::request::RequestResponseError;
+use rust_erpc::rpc::{Caller, Client};
 use std_transport::IoTransport;
-use rust_erpc::framed_transport::{BasicFramedTransport, FramedTransportError};
-use rust_erpc::request::{Request, Response};
-use rust_erpc::{
-    codec::{BasicCodecFactory, Codec},
-    request::RequestResponseError,
-};
+
+include!(concat!(env!("OUT_DIR"), "/rpc_gap.rs"));
+
+struct StdClock;
+
+impl Clock for StdClock {
+    type Instant = Instant;
+    type Duration = Duration;
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Caller waiting up to `timeout` for each reply, so that a slow reply is not mistaken for a lost one.
+///
+/// The read timeout of the socket only bounds each poll of `IoTransport::read_available`.
+struct DeadlineCaller<'buffer, Transport, CodecFactoryType> {
+    client: Client<'buffer, Transport, CodecFactoryType>,
+    timeout: Duration,
+}
+
+impl<'buffer, Error, Transport, CodecFactoryType, CodecType>
+    Caller<Error, BufferCursor<&'buffer mut [u8]>, CodecType>
+    for DeadlineCaller<'buffer, Transport, CodecFactoryType>
+where
+    Transport: FramedTransport<Error>,
+    CodecType: Codec<BufferCursor<&'buffer mut [u8]>>,
+    CodecFactoryType: CodecFactory<BufferCursor<&'buffer mut [u8]>, CodecType>,
+{
+    fn call<Constructor, Parser, Output>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+        parser: Parser,
+    ) -> Result<Output, RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+        Parser: FnOnce(&mut CodecType) -> Result<Output, CodecError>,
+    {
+        let mut deadline = ClockDeadline::after(&StdClock, self.timeout);
+        self.client
+            .call_with_deadline(service, request, &mut deadline, constructor, parser)
+    }
+
+    fn call_oneway<Constructor>(
+        &mut self,
+        service: u32,
+        request: u32,
+        constructor: Constructor,
+    ) -> Result<(), RequestResponseError<Error>>
+    where
+        Constructor: FnOnce(&mut CodecType) -> Result<(), CodecError>,
+    {
+        self.client.call_oneway(service, request, constructor)
+    }
+}
 
 fn main() {
     let remote = "127.0.0.1:5555"
@@ -15,72 +75,30 @@ fn main() {
     let stream =
         TcpStream::connect_timeout(&remote, Duration::from_secs(1)).expect("Could not connect.");
     stream
-        .set_read_timeout(Some(Duration::from_secs(2)))
+        .set_read_timeout(Some(Duration::from_millis(10)))
         .unwrap();
     stream
         .set_write_timeout(Some(Duration::from_secs(2)))
         .unwrap();
 
     let io_transport = IoTransport::new(stream);
-    let mut transport = BasicFramedTransport::new(io_transport);
+    let transport = BasicFramedTransport::new(io_transport);
     let mut frame_buffer = [0u8; 1024];
+    let mut caller = DeadlineCaller {
+        client: Client::new(transport, &mut frame_buffer, BasicCodecFactory::new()),
+        timeout: Duration::from_secs(2),
+    };
 
-    'main: loop {
-        print!("Sending request... ");
-        let request = Request::new(2, 1, 0, false);
-        let result = request.send_request(
-            &mut transport,
-            &mut frame_buffer,
-            BasicCodecFactory::new(),
-            |codec| {
-                codec.write_u32(1234)?;
-                codec.write_binary(&[0x5a; 128])?;
-                Ok(())
-            },
-        );
-        if let Err(err) = result {
-            println!("Error: {:?}", err);
-            continue 'main;
-        }
-        println!("Ok.");
-
-        print!("Receiving response... ");
-        loop {
-            let result = Response::receive_response(
-                &mut transport,
-                &mut frame_buffer,
-                BasicCodecFactory::new(),
-            );
-            match result {
-                Ok((response, mut codec)) => {
-                    println!(
-                        "Ok. response = {}, {}, {}, {}",
-                        response.service,
-                        response.request,
-                        response.sequence,
-                        response.is_notification
-                    );
-                    if let Ok(result) = codec.read_u32() {
-                        println!("\tresult = {}", result);
-                    } else {
-                        println!("\tno result...");
-                    }
-                    break;
-                }
-                Err(err) => {
-                    if let RequestResponseError::FramedTransportError(
-                        FramedTransportError::UnderlyingError(underlying_error),
-                    ) = &err
-                    {
-                        if underlying_error.kind() == std::io::ErrorKind::WouldBlock {
-                            continue;
-                        }
-                    } else {
-                    }
-                    println!("Error: {:?}", err);
-                    continue 'main;
-                }
-            }
+    loop {
+        print!("Calling rpc_gap_set_param... ");
+        let mut gap = rpc_gap::Client::new(&mut caller);
+        match gap.rpc_gap_set_param(1234, &[0x5a; 128]) {
+            Ok(result) => println!(
+                "Ok. sequence = {}, result = {}",
+                caller.client.get_sequence(),
+                result
+            ),
+            Err(err) => println!("Error: {:?}", err),
         }
     }
-}
\ No newline at end of file
+}
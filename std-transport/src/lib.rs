@@ -1,5 +1,5 @@
 use rust_erpc::framed_transport::UnderlyingTransport;
-use std::io::{Error, Read, Write};
+use std::io::{Error, ErrorKind, Read, Write};
 
 pub struct IoTransport<Io: Read + Write> {
     io: Io,
@@ -19,4 +19,11 @@ impl<Io: Read + Write> UnderlyingTransport for IoTransport<Io> {
     fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
         self.io.write_all(data)
     }
+    /// Expects `io` to be non-blocking or to have a short read timeout.
+    fn read_available(&mut self, data: &mut [u8]) -> Result<usize, Self::Error> {
+        match self.io.read(data) {
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(0),
+            result => result,
+        }
+    }
 }